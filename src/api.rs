@@ -0,0 +1,117 @@
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+use std::str::FromStr;
+
+use crate::{BotError, Result};
+
+const API_NAME: &str = "degen.fund";
+const ANTIBOT_URL: &str = "https://www.degen.fund/api/antibot";
+const SEARCH_URL: &str = "https://www.degen.fund/api/tokens/search";
+
+// A token from the degen.fund listing
+pub struct Listing {
+    pub mint: Pubkey,
+    pub symbol: String,
+    pub name: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub market_cap_usd: Option<f64>,
+}
+
+// Fetch an unsigned buy transaction for `amount` SOL of `mint`
+pub async fn fetch_buy_transaction(
+    mint: &str,
+    amount: f64,
+    buyer: &str,
+) -> Result<VersionedTransaction> {
+    let url = format!(
+        "{}/{}?&buy-amount={}&buyer={}",
+        ANTIBOT_URL, mint, amount, buyer
+    );
+    fetch_transaction(&url).await
+}
+
+// Fetch an unsigned sell transaction for `amount` raw units of `mint`
+pub async fn fetch_sell_transaction(
+    mint: &str,
+    amount: u64,
+    seller: &str,
+) -> Result<VersionedTransaction> {
+    let url = format!(
+        "{}/{}?&sell-amount={}&seller={}",
+        ANTIBOT_URL, mint, amount, seller
+    );
+    fetch_transaction(&url).await
+}
+
+async fn fetch_transaction(url: &str) -> Result<VersionedTransaction> {
+    let response = reqwest::get(url).await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(BotError::api(API_NAME, Some(status.as_u16()), body.trim()));
+    }
+    let byte_tx = base64::engine::general_purpose::STANDARD
+        .decode(body.trim())
+        .map_err(|e| BotError::api(API_NAME, None, format!("not a transaction: {}", e)))?;
+    // Handles both legacy and v0 messages
+    bincode::deserialize(&byte_tx)
+        .map_err(|e| BotError::api(API_NAME, None, format!("not a transaction: {}", e)))
+}
+
+// Tokens whose ticker or name matches `query`, newest first
+pub async fn search_tokens(query: &str) -> Result<Vec<Listing>> {
+    let response: Value = reqwest::Client::new()
+        .get(SEARCH_URL)
+        .query(&[("q", query)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    // Either a bare array or wrapped in `tokens`/`data`
+    let tokens = response
+        .as_array()
+        .or_else(|| response["tokens"].as_array())
+        .or_else(|| response["data"].as_array())
+        .cloned()
+        .unwrap_or_default();
+    let mut listings: Vec<Listing> = tokens.iter().filter_map(listing).collect();
+    listings.sort_by_key(|listing| std::cmp::Reverse(listing.created_at));
+    Ok(listings)
+}
+
+fn listing(token: &Value) -> Option<Listing> {
+    let text = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| token[*key].as_str())
+            .map(str::to_string)
+    };
+    let number = |keys: &[&str]| {
+        keys.iter().find_map(|key| match &token[*key] {
+            Value::String(value) => value.parse().ok(),
+            value => value.as_f64(),
+        })
+    };
+    let mint = Pubkey::from_str(&text(&["mint", "address"])?).ok()?;
+    // Creation time as RFC 3339 or a Unix timestamp in seconds or milliseconds
+    let created_at = text(&["createdAt", "created_at"])
+        .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|| {
+            let timestamp = number(&["createdAt", "created_timestamp"])? as i64;
+            if timestamp > 1_000_000_000_000 {
+                DateTime::from_timestamp_millis(timestamp)
+            } else {
+                DateTime::from_timestamp(timestamp, 0)
+            }
+        });
+    Some(Listing {
+        mint,
+        symbol: text(&["symbol", "ticker"]).unwrap_or_default(),
+        name: text(&["name"]).unwrap_or_default(),
+        created_at,
+        market_cap_usd: number(&["marketCap", "market_cap", "usd_market_cap"]),
+    })
+}
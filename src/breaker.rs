@@ -0,0 +1,93 @@
+use chrono::Local;
+use colored::*;
+use log::{info, warn};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::prepare::Trade;
+use crate::{BotError, Result};
+
+pub const DEFAULT_BREAKER_WINDOW: Duration = Duration::from_secs(300);
+pub const DEFAULT_BREAKER_COOLOFF: Duration = Duration::from_secs(300);
+
+// Pauses buying after `failures` sends in a row failed or reverted within
+// `window`, instead of paying fees into a broken RPC or a changed program
+#[derive(Clone, Copy, Debug)]
+pub struct CircuitBreaker {
+    pub failures: u32,
+    pub window: Duration,
+    pub cooloff: Duration,
+}
+
+// Why the breaker tripped, for the alert
+pub struct Trip {
+    pub failures: u32,
+    pub resumes_at: String,
+}
+
+#[derive(Default)]
+struct State {
+    // When each failure since the last landed trade happened
+    failures: Vec<Instant>,
+    paused_until: Option<Instant>,
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(Default::default)
+}
+
+impl CircuitBreaker {
+    // Count a failed send, tripping the breaker on the one too many
+    pub fn record_failure(&self) -> Option<Trip> {
+        let mut state = state().lock().unwrap();
+        state.failures.retain(|at| at.elapsed() < self.window);
+        state.failures.push(Instant::now());
+        if (state.failures.len() as u32) < self.failures {
+            return None;
+        }
+        state.failures.clear();
+        state.paused_until = Some(Instant::now() + self.cooloff);
+        let resumes_at = (Local::now() + self.cooloff).format("%H:%M:%S").to_string();
+        warn!(
+            "{}",
+            format!(
+                "{} sends failed in a row, pausing buys until {}",
+                self.failures, resumes_at
+            )
+            .red()
+            .bold()
+        );
+        Some(Trip {
+            failures: self.failures,
+            resumes_at,
+        })
+    }
+}
+
+// A trade landed, the failures before it were not in a row
+pub fn record_success() {
+    state().lock().unwrap().failures.clear();
+}
+
+// Refuse to buy while the breaker is tripped. Sells still go through, to get
+// out of positions.
+pub fn check(trade: &Trade) -> Result<()> {
+    if !matches!(trade, Trade::Buy { .. }) {
+        return Ok(());
+    }
+    let mut state = state().lock().unwrap();
+    let Some(until) = state.paused_until else {
+        return Ok(());
+    };
+    let left = until.saturating_duration_since(Instant::now());
+    if left.is_zero() {
+        state.paused_until = None;
+        info!("{}", "Circuit breaker cooled off, buying again".green());
+        return Ok(());
+    }
+    Err(BotError::policy(format!(
+        "Circuit breaker tripped by repeated failures, buying paused for another {}s",
+        left.as_secs() + 1
+    )))
+}
@@ -0,0 +1,946 @@
+use chrono::{DateTime, Utc};
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+use crate::commands::twap::Side;
+use crate::fees::FeeOracle;
+use crate::logging::LogFormat;
+use crate::market::PriceProviderKind;
+use crate::monitor::{BuyRung, ExitRules, MigrationAction, SellRung};
+use crate::output::OutputFormat;
+use crate::price::PriceOracle;
+use crate::rugcheck::RugCheckMode;
+use crate::source::TxSource;
+use crate::transaction::{Commitment, SendMode};
+use crate::venue::VenueKind;
+
+#[derive(Parser)]
+#[command(
+    name = "degen-fund-bot",
+    version,
+    about = "Trade degen.fund tokens from the terminal"
+)]
+pub struct Cli {
+    /// Path to a TOML config file, defaults to ./config.toml when present
+    #[arg(long, env = "DEGEN_CONFIG", global = true)]
+    pub config: Option<String>,
+
+    /// Named [profiles.<name>] table of the config file to apply
+    #[arg(long, env = "DEGEN_PROFILE", global = true)]
+    pub profile: Option<String>,
+
+    /// Solana RPC endpoints, comma separated in failover order
+    #[arg(long, env = "SOLANA_RPC_URL", global = true, value_delimiter = ',')]
+    pub rpc_url: Vec<String>,
+
+    /// Base58 encoded private key of the trading wallet
+    #[arg(
+        long,
+        env = "PRIVATE_KEY_BASE58",
+        global = true,
+        hide_env_values = true
+    )]
+    pub private_key: Option<String>,
+
+    /// Keypair file in the solana-keygen JSON format, e.g. ~/.config/solana/id.json
+    #[arg(long, env = "KEYPAIR_PATH", global = true)]
+    pub keypair: Option<String>,
+
+    /// BIP39 seed phrase of the trading wallet
+    #[arg(long, env = "MNEMONIC", global = true, hide_env_values = true)]
+    pub mnemonic: Option<String>,
+
+    /// Derivation path used with --mnemonic, defaults to m/44'/501'/0'/0'
+    #[arg(long, env = "DERIVATION_PATH", global = true)]
+    pub derivation_path: Option<String>,
+
+    /// Encrypted keystore created with import-key
+    #[arg(long, env = "KEYSTORE_PATH", global = true)]
+    pub keystore: Option<String>,
+
+    /// Passphrase of the keystore, asked for interactively when not set
+    #[arg(long, env = "KEY_PASSWORD", global = true, hide_env_values = true)]
+    pub key_password: Option<String>,
+
+    /// Name of an OS keychain entry holding the private key
+    #[arg(long, env = "KEYRING_ENTRY", global = true)]
+    pub keyring_entry: Option<String>,
+
+    /// Sign on a Ledger instead of with a private key, e.g. usb://ledger?key=0
+    #[arg(long, env = "LEDGER", global = true)]
+    pub ledger: Option<String>,
+
+    /// Comma separated base58 private keys, buys are fired from all of them
+    #[arg(
+        long,
+        env = "PRIVATE_KEYS",
+        global = true,
+        hide_env_values = true,
+        value_delimiter = ','
+    )]
+    pub private_keys: Vec<String>,
+
+    /// How to submit signed transactions
+    #[arg(long, env = "SEND_MODE", global = true, value_enum)]
+    pub send_mode: Option<SendMode>,
+
+    /// Send to RPC endpoints without simulating first, saving a round trip
+    #[arg(long, env = "SKIP_PREFLIGHT", global = true)]
+    pub skip_preflight: bool,
+
+    /// Commitment the RPC preflight simulates against
+    #[arg(long, env = "PREFLIGHT_COMMITMENT", global = true, value_enum)]
+    pub preflight_commitment: Option<Commitment>,
+
+    /// Commitment a sent transaction must reach to count as landed
+    #[arg(long, env = "CONFIRM_COMMITMENT", global = true, value_enum)]
+    pub confirm_commitment: Option<Commitment>,
+
+    /// Include a Jito bundle in race send mode
+    #[arg(long, env = "RACE_JITO", global = true)]
+    pub race_jito: bool,
+
+    /// Tip paid to Jito validators in jito send mode
+    #[arg(long, env = "JITO_TIP_LAMPORTS", global = true)]
+    pub jito_tip_lamports: Option<u64>,
+
+    /// Tip this percentile of recently landed Jito tips instead, with
+    /// jito_tip_lamports as the minimum
+    #[arg(long, env = "JITO_TIP_PERCENTILE", global = true, value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub jito_tip_percentile: Option<u8>,
+
+    /// Most a percentile based Jito tip may be
+    #[arg(long, env = "JITO_TIP_CAP_LAMPORTS", global = true)]
+    pub jito_tip_cap_lamports: Option<u64>,
+
+    /// Jito block engine to submit bundles to
+    #[arg(long, env = "JITO_BLOCK_ENGINE_URL", global = true)]
+    pub jito_block_engine_url: Option<String>,
+
+    /// Priority fee in micro-lamports per compute unit
+    #[arg(long, env = "COMPUTE_UNIT_PRICE", global = true)]
+    pub compute_unit_price: Option<u64>,
+
+    /// Compute unit limit requested for the transaction
+    #[arg(long, env = "COMPUTE_UNIT_LIMIT", global = true)]
+    pub compute_unit_limit: Option<u32>,
+
+    /// Set the unit price to this percentile (0-100) of the fees recently paid
+    /// to write lock the same curve accounts
+    #[arg(long, env = "PRIORITY_FEE_PERCENTILE", global = true, value_parser = clap::value_parser!(u8).range(0..=100))]
+    pub priority_fee_percentile: Option<u8>,
+
+    /// Source of the dynamic unit price, rpc by default once a percentile is set
+    #[arg(long, env = "FEE_ORACLE", global = true, value_enum)]
+    pub fee_oracle: Option<FeeOracle>,
+
+    /// Helius RPC URL with the api key for the helius fee oracle, defaults
+    /// to the first RPC endpoint
+    #[arg(long, env = "HELIUS_URL", global = true)]
+    pub helius_url: Option<String>,
+
+    /// Source of the SOL/USD price used for USD amounts and valuations,
+    /// coingecko by default
+    #[arg(long, env = "PRICE_ORACLE", global = true, value_enum)]
+    pub price_oracle: Option<PriceOracle>,
+
+    /// text logs only, or json to also print a result object with every
+    /// trade's signature, slot, status, price and fees on stdout
+    #[arg(
+        long,
+        env = "OUTPUT_FORMAT",
+        global = true,
+        value_enum,
+        default_value = "text"
+    )]
+    pub output: OutputFormat,
+
+    /// Colored text log lines, or json for one object per line with the
+    /// timestamp, level, target and message
+    #[arg(
+        long,
+        env = "LOG_FORMAT",
+        global = true,
+        value_enum,
+        default_value = "text"
+    )]
+    pub log_format: LogFormat,
+
+    /// Also write log lines, without colors, to this file. It is rotated by
+    /// size and kept as FILE.1 to FILE.N, see log_max_mb in the config file
+    #[arg(long, env = "LOG_FILE", global = true)]
+    pub log_file: Option<String>,
+
+    /// Export spans of every trade's fetch, sign, send and confirm steps to
+    /// this OTLP gRPC collector, e.g. http://localhost:4317
+    #[arg(long, env = "OTEL_EXPORTER_OTLP_ENDPOINT", global = true)]
+    pub otlp_endpoint: Option<String>,
+
+    /// Market price of tokens that left the bonding curve, for monitoring
+    /// after the migration and PnL, raydium by default
+    #[arg(long, env = "PRICE_PROVIDER", global = true, value_enum)]
+    pub price_provider: Option<PriceProviderKind>,
+
+    /// API key of the birdeye price provider
+    #[arg(long, env = "BIRDEYE_API_KEY", global = true, hide_env_values = true)]
+    pub birdeye_api_key: Option<String>,
+
+    /// Resend a transaction that hasn't landed with its unit price raised by
+    /// this many micro-lamports
+    #[arg(long, env = "PRIORITY_FEE_STEP", global = true)]
+    pub priority_fee_step: Option<u64>,
+
+    /// Highest unit price in micro-lamports the resends go to
+    #[arg(long, env = "PRIORITY_FEE_CAP", global = true)]
+    pub priority_fee_cap: Option<u64>,
+
+    /// Address of the degen.fund bonding curve program
+    #[arg(long, env = "DEGEN_FUND_PROGRAM_ID", global = true)]
+    pub program_id: Option<String>,
+
+    /// WebSocket RPC endpoint, derived from the RPC endpoint when not set
+    #[arg(long, env = "SOLANA_WS_URL", global = true)]
+    pub ws_url: Option<String>,
+
+    /// Yellowstone gRPC endpoint, used instead of the WebSocket to detect
+    /// launches and migrations
+    #[arg(long, env = "GEYSER_URL", global = true)]
+    pub geyser_url: Option<String>,
+
+    /// Access token sent as x-token to the Geyser endpoint
+    #[arg(long, env = "GEYSER_X_TOKEN", global = true)]
+    pub geyser_x_token: Option<String>,
+
+    /// Follow slots over the WebSocket endpoint and time launch sends and
+    /// rebroadcasts by them instead of the wall clock
+    #[arg(long, env = "SLOT_CLOCK", global = true)]
+    pub slot_clock: bool,
+
+    /// Fetch, sign and simulate transactions without broadcasting them
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Where trade transactions are built, the other source is used as a fallback
+    #[arg(long, env = "TX_SOURCE", global = true, value_enum)]
+    pub tx_source: Option<TxSource>,
+
+    /// Launchpad the token trades on, only degen.fund has the API source
+    #[arg(long, env = "VENUE", global = true, value_enum)]
+    pub venue: Option<VenueKind>,
+
+    /// Maximum slippage in basis points, transactions allowing worse
+    /// execution against the curve are refused
+    #[arg(long, env = "SLIPPAGE_BPS", global = true, value_parser = clap::value_parser!(u64).range(0..=10_000))]
+    pub slippage_bps: Option<u64>,
+
+    /// Tighten a too loose slippage limit instead of refusing the transaction
+    #[arg(long, env = "PATCH_SLIPPAGE", global = true)]
+    pub patch_slippage: bool,
+
+    /// SOL in lamports a trade may take beyond its amount when simulated,
+    /// covering fees and rent
+    #[arg(long, env = "DEBIT_TOLERANCE_LAMPORTS", global = true)]
+    pub debit_tolerance_lamports: Option<u64>,
+
+    /// Durable nonce account to sign trades against, so they don't expire
+    /// while waiting, e.g. for a --at launch
+    #[arg(long, env = "NONCE_ACCOUNT", global = true)]
+    pub nonce_account: Option<String>,
+
+    /// Print the decoded instructions of every transaction before signing it
+    #[arg(long, env = "SHOW_TX", global = true)]
+    pub show_tx: bool,
+
+    /// Safety checks of the mint before buying
+    #[arg(long, env = "RUG_CHECK", global = true, value_enum)]
+    pub rug_check: Option<RugCheckMode>,
+
+    /// Refuse buys of tokens whose top holders own more than this percent of the supply
+    #[arg(long, env = "MAX_TOP_HOLDERS_PCT", global = true)]
+    pub max_top_holders_pct: Option<f64>,
+
+    /// Refuse buys of tokens whose creator's earlier launches score below this (0-100)
+    #[arg(long, env = "MIN_CREATOR_SCORE", global = true)]
+    pub min_creator_score: Option<u32>,
+
+    /// Refuse buys spending more SOL than this at once
+    #[arg(long, env = "MAX_TRADE_SOL", global = true)]
+    pub max_trade_sol: Option<f64>,
+
+    /// Refuse buys once this much SOL was spent today (UTC)
+    #[arg(long, env = "MAX_DAILY_SOL", global = true)]
+    pub max_daily_sol: Option<f64>,
+
+    /// Refuse buys of a new token while this many positions are open
+    #[arg(long, env = "MAX_OPEN_POSITIONS", global = true)]
+    pub max_open_positions: Option<usize>,
+
+    /// Refuse buys taking the SOL committed to one token over this, across wallets
+    #[arg(long, env = "MAX_POSITION_SOL", global = true)]
+    pub max_position_sol: Option<f64>,
+
+    /// Seconds after a buy before another token may be bought
+    #[arg(long, env = "BUY_COOLDOWN_SECS", global = true)]
+    pub buy_cooldown_secs: Option<u64>,
+
+    /// Seconds after a buy before another token of the same creator may be bought
+    #[arg(long, env = "CREATOR_COOLDOWN_SECS", global = true)]
+    pub creator_cooldown_secs: Option<u64>,
+
+    /// Pause trading after this many sends in a row fail or revert
+    #[arg(long, env = "CIRCUIT_BREAKER_FAILURES", global = true)]
+    pub circuit_breaker_failures: Option<u32>,
+
+    /// Refuse every buy while this file exists
+    #[arg(long, env = "KILL_SWITCH_FILE", global = true)]
+    pub kill_switch_file: Option<PathBuf>,
+
+    /// Refuse every buy while this URL answers with a body other than empty,
+    /// 0, false or off (404 clears it, an unreachable URL engages it)
+    #[arg(long, env = "KILL_SWITCH_URL", global = true)]
+    pub kill_switch_url: Option<String>,
+
+    /// Sell every position when the kill switch engages under a daemon or watcher
+    #[arg(long, env = "KILL_SWITCH_FLATTEN", global = true)]
+    pub kill_switch_flatten: bool,
+
+    /// NTP server the launch countdown is synced against
+    #[arg(long, env = "NTP_SERVER", global = true)]
+    pub ntp_server: Option<String>,
+
+    /// Telegram bot token used to send trade notifications
+    #[arg(
+        long,
+        env = "TELEGRAM_BOT_TOKEN",
+        global = true,
+        hide_env_values = true
+    )]
+    pub telegram_bot_token: Option<String>,
+
+    /// Telegram chat that receives trade notifications
+    #[arg(long, env = "TELEGRAM_CHAT_ID", global = true)]
+    pub telegram_chat_id: Option<String>,
+
+    /// Discord webhook that receives trade notifications
+    #[arg(
+        long,
+        env = "DISCORD_WEBHOOK_URL",
+        global = true,
+        hide_env_values = true
+    )]
+    pub discord_webhook_url: Option<String>,
+
+    /// SQLite database every trade is recorded in
+    #[arg(long, env = "DEGEN_JOURNAL", global = true)]
+    pub journal: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Buy a token through the degen.fund antibot API
+    Buy {
+        /// Mint address or degen.fund URL of the token to buy, `-` reads it from stdin
+        #[arg(env = "TOKEN_TO_BUY", required_unless_present_any = ["tokens", "clipboard", "symbol"])]
+        mint: Option<String>,
+
+        /// Take the mint from the system clipboard
+        #[arg(long, conflicts_with_all = ["mint", "tokens"])]
+        clipboard: bool,
+
+        /// Search degen.fund for tokens with this ticker and pick one
+        #[arg(long, conflicts_with_all = ["mint", "tokens", "clipboard"])]
+        symbol: Option<String>,
+
+        /// Buy every token listed in this file instead, one mint per line
+        /// optionally followed by its own SOL amount
+        #[arg(long, conflicts_with_all = ["mint", "take_profit", "stop_loss", "trailing_stop", "ladder", "sell_at", "at"])]
+        tokens: Option<String>,
+
+        /// Buys of the token list run at the same time, 1 buys them one by one
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+        concurrency: u16,
+
+        /// Amount of SOL to spend, defaults to buy_amount from the config file
+        #[arg(long, env = "BUY_AMOUNT")]
+        amount: Option<f64>,
+
+        /// Spend this many USD instead, converted to SOL at the current price as
+        /// the buy is sent
+        #[arg(long, env = "BUY_AMOUNT_USD", conflicts_with = "amount")]
+        amount_usd: Option<f64>,
+
+        /// Spend this percentage of each wallet's SOL, less fees and the
+        /// sol_reserve from the config file
+        #[arg(long, env = "BUY_AMOUNT_PCT", conflicts_with_all = ["amount", "amount_usd", "tokens"])]
+        amount_pct: Option<f64>,
+
+        #[command(flatten)]
+        exit: ExitArgs,
+
+        /// Prepare and sign ahead of time and send exactly at this UTC time,
+        /// e.g. 2024-09-01T18:00:00Z
+        #[arg(long, env = "LAUNCH_AT")]
+        at: Option<DateTime<Utc>>,
+
+        /// Skip the token name, socials and curve summary shown before buying
+        #[arg(long, env = "NO_PREVIEW")]
+        no_preview: bool,
+
+        /// Send without asking for confirmation, for unattended runs
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+
+        /// Buy even from wallets the journal shows already bought the token
+        #[arg(long)]
+        force: bool,
+    },
+    /// Resume monitoring a position held by the wallets, from the entry price
+    /// in the journal and the sell ladder steps already taken
+    Monitor {
+        /// Mint address or degen.fund URL of the token
+        mint: String,
+
+        #[command(flatten)]
+        exit: ExitArgs,
+    },
+    /// Sell a percentage of a token held by the wallet
+    Sell {
+        /// Mint address or degen.fund URL of the token to sell
+        #[arg(env = "TOKEN_TO_SELL")]
+        mint: String,
+
+        /// Percentage of the held balance to sell, defaults to sell_percent
+        /// from the config file or 100
+        #[arg(long, visible_alias = "pct", env = "SELL_PERCENT", value_parser = sell_pct)]
+        percent: Option<f64>,
+
+        /// Number of tokens to sell, converted with the mint's decimals
+        #[arg(long, conflicts_with = "percent")]
+        tokens: Option<f64>,
+    },
+    /// Sell every degen.fund position of every wallet at once with a high
+    /// priority fee
+    Panic {
+        /// Compute unit price in micro-lamports to pay at least, defaults to
+        /// panic_unit_price from the config file or 1000000
+        #[arg(long)]
+        unit_price: Option<u64>,
+
+        /// Sell without asking for confirmation
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Watch the degen.fund program and buy new launches as they are created
+    Snipe {
+        /// Amount of SOL to spend per launch, defaults to buy_amount from the config file
+        #[arg(long, env = "BUY_AMOUNT")]
+        amount: Option<f64>,
+
+        /// Only buy launches from this creator wallet, can be repeated
+        #[arg(long = "creator")]
+        creators: Vec<String>,
+
+        /// Skip launches whose market cap in SOL is already above this
+        #[arg(long)]
+        max_market_cap: Option<f64>,
+
+        /// Stop after this many buys
+        #[arg(long)]
+        max_buys: Option<usize>,
+    },
+    /// Buy a token in equal tranches at a fixed interval, resuming the plan
+    /// from the journal when run again
+    Dca {
+        /// Mint address or degen.fund URL of the token to buy
+        mint: String,
+
+        /// SOL to spend over all tranches
+        #[arg(long)]
+        total: f64,
+
+        /// Number of tranches the total is split in
+        #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+        tranches: u32,
+
+        /// Seconds between tranches
+        #[arg(long, default_value_t = 30)]
+        interval_secs: u64,
+
+        /// Skip tranches while the curve price per token is above this many SOL
+        #[arg(long)]
+        max_price: Option<f64>,
+
+        /// Discard the unfinished plan of this mint and start over
+        #[arg(long)]
+        restart: bool,
+
+        /// Start without asking for confirmation, for unattended runs
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Buy or sell in randomized child orders spread evenly over a duration,
+    /// keeping the price impact of a large order low
+    Twap {
+        /// Mint address or degen.fund URL of the token
+        mint: String,
+
+        /// Whether to buy or sell
+        #[arg(long, value_enum, default_value = "buy")]
+        side: Side,
+
+        /// SOL to buy over all orders, defaults to buy_amount from the config file
+        #[arg(long, conflicts_with = "percent")]
+        amount: Option<f64>,
+
+        /// Percentage of the held balance to sell over all orders, defaults to
+        /// sell_percent from the config file or 100
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=100))]
+        percent: Option<u8>,
+
+        /// Number of child orders
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        slices: u32,
+
+        /// Seconds the orders are spread over
+        #[arg(long)]
+        duration_secs: u64,
+
+        /// Most a child order may differ from an even split, in percent
+        #[arg(long, default_value_t = 30.0)]
+        jitter: f64,
+
+        /// Start without asking for confirmation, for unattended runs
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Place, list or cancel limit buys filled once a token's curve price or
+    /// market cap drops below a limit, and watch them
+    Orders {
+        #[command(subcommand)]
+        action: OrdersAction,
+    },
+    /// Mirror the degen.fund buys of other wallets as they happen
+    Copy {
+        /// Wallet to copy, can be repeated
+        #[arg(long = "target", required = true)]
+        targets: Vec<String>,
+
+        /// Spend this much SOL per copied buy, defaults to buy_amount from the
+        /// config file when --ratio is not given
+        #[arg(long, env = "BUY_AMOUNT", conflicts_with = "ratio")]
+        amount: Option<f64>,
+
+        /// Spend this fraction of the SOL the target spent, e.g. 0.5
+        #[arg(long)]
+        ratio: Option<f64>,
+
+        /// Never spend more than this much SOL on a single copy
+        #[arg(long)]
+        max_amount: Option<f64>,
+    },
+    /// Stay running and trade off a watchlist file, new launches, copied
+    /// wallets and Telegram commands, restarting any feed that drops
+    Daemon {
+        /// File of mints to buy once each, one `MINT [AMOUNT]` per line,
+        /// re-read as it changes
+        #[arg(long, env = "WATCHLIST")]
+        watchlist: Option<String>,
+
+        /// Buy new launches like `snipe`
+        #[arg(long)]
+        snipe: bool,
+
+        /// Only snipe launches from this creator wallet, can be repeated
+        #[arg(long = "creator", requires = "snipe")]
+        creators: Vec<String>,
+
+        /// Skip launches whose market cap in SOL is already above this
+        #[arg(long, requires = "snipe")]
+        max_market_cap: Option<f64>,
+
+        /// Mirror the buys of this wallet like `copy`, can be repeated
+        #[arg(long = "copy-target")]
+        copy_targets: Vec<String>,
+
+        /// Spend this fraction of the SOL a copied wallet spent instead of
+        /// the buy amount
+        #[arg(long)]
+        copy_ratio: Option<f64>,
+
+        /// Never spend more than this much SOL on a single copy
+        #[arg(long)]
+        max_copy_amount: Option<f64>,
+
+        /// Take commands from the configured Telegram chat as well
+        #[arg(long)]
+        telegram: bool,
+
+        /// Fill the open limit orders like `orders watch`
+        #[arg(long)]
+        orders: bool,
+
+        /// Serve Prometheus metrics on http://ADDR/metrics, e.g. 127.0.0.1:9100
+        #[arg(long, env = "METRICS_ADDR")]
+        metrics_addr: Option<SocketAddr>,
+
+        /// Serve the web dashboard, and the HTTP API of `serve`, on
+        /// http://ADDR, e.g. 127.0.0.1:8080
+        #[arg(long, env = "DASHBOARD_ADDR", requires = "api_token")]
+        dashboard_addr: Option<SocketAddr>,
+
+        /// Token the dashboard asks for
+        #[arg(long, env = "API_TOKEN", hide_env_values = true)]
+        api_token: Option<String>,
+
+        /// Amount of SOL per buy, defaults to buy_amount from the config file
+        #[arg(long, env = "BUY_AMOUNT")]
+        amount: Option<f64>,
+    },
+    /// Run a Telegram bot taking /buy, /sell, /positions and /panic commands
+    /// from the configured chat
+    Telegram,
+    /// Serve an HTTP API taking POST /buy and /sell and answering GET
+    /// /positions and /health, for other tools to trade through the bot
+    Serve {
+        /// Address to listen on
+        #[arg(long, env = "SERVE_ADDR", default_value = "127.0.0.1:8080")]
+        listen: SocketAddr,
+
+        /// Also serve the gRPC service of proto/control.proto on this address,
+        /// which streams trade events and position prices as well
+        #[arg(long, env = "GRPC_ADDR")]
+        grpc_listen: Option<SocketAddr>,
+
+        /// Token callers must send as `Authorization: Bearer <token>`
+        #[arg(long, env = "API_TOKEN", hide_env_values = true)]
+        token: String,
+    },
+    /// Full screen view of the open positions with live prices, pending
+    /// limit orders, recent trades and the log
+    Tui {
+        /// Fill the open limit orders like `orders watch`
+        #[arg(long)]
+        orders: bool,
+    },
+    /// Realized and unrealized profit per token from the trade journal
+    Pnl,
+    /// List every token the wallets hold with its name and estimated USD value
+    Portfolio,
+    /// Encrypt the configured (or a pasted) private key into a keystore file
+    ImportKey {
+        /// Keystore file to create
+        #[arg(long, default_value = "keystore.json")]
+        output: String,
+    },
+    /// Manage the private key kept in the OS keychain
+    Keyring {
+        #[command(subcommand)]
+        action: KeyringAction,
+    },
+    /// Check a token's mint and freeze authorities, metadata and supply
+    Check {
+        /// Mint address or degen.fund URL of the token
+        mint: String,
+    },
+    /// List the largest holders of a token, excluding the bonding curve
+    Holders {
+        /// Mint address or degen.fund URL of the token
+        mint: String,
+
+        /// Number of holders to show, defaults to top_holders from the config file
+        #[arg(long)]
+        top: Option<usize>,
+    },
+    /// Create, inspect, advance or close a durable nonce account
+    Nonce {
+        #[command(subcommand)]
+        action: NonceAction,
+    },
+    /// Split SOL from the wallet across the sub-wallets of the wallet list, or
+    /// the given addresses, in as few transactions as fit
+    #[command(group(ArgGroup::new("size").required(true)))]
+    Disperse {
+        /// Address to fund instead of the wallet list, can be repeated
+        #[arg(long = "to")]
+        targets: Vec<String>,
+        /// SOL to send each wallet
+        #[arg(long, group = "size")]
+        amount: Option<f64>,
+        /// SOL to split across the wallets
+        #[arg(long, group = "size")]
+        total: Option<f64>,
+        /// Vary each amount randomly by up to this percent
+        #[arg(long, default_value_t = 0.0)]
+        jitter: f64,
+        /// Send without asking for confirmation
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Move every token and the SOL of the sub-wallets of the wallet list back
+    /// to the trading wallet, or the given address
+    Sweep {
+        /// Address to sweep into instead of the trading wallet
+        #[arg(long)]
+        to: Option<String>,
+        /// SOL to leave in each sub-wallet for fees, sweep_keep_sol from the
+        /// config file or 0.002
+        #[arg(long)]
+        keep_sol: Option<f64>,
+        /// Sweep without asking for confirmation
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Close the empty token accounts of the wallets, reclaiming their rent
+    Cleanup {
+        /// Also burn balances worth less than this many USD, or without a
+        /// price, and close their accounts, after a review of them
+        #[arg(long)]
+        burn_below_usd: Option<f64>,
+        /// Close without asking for confirmation
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Send SOL, or a token with --mint, from the wallet to another address
+    Transfer {
+        /// Address to send to
+        to: String,
+        /// SOL to send, or whole tokens with --mint
+        amount: f64,
+        /// Mint address of the token to send instead of SOL
+        #[arg(long)]
+        mint: Option<String>,
+        /// Send without asking for confirmation
+        #[arg(short, long, env = "ASSUME_YES")]
+        yes: bool,
+    },
+    /// Wrap SOL of the wallet into wrapped SOL (WSOL)
+    Wrap {
+        /// SOL to wrap
+        amount: f64,
+    },
+    /// Unwrap all of the wallet's wrapped SOL, closing its account
+    Unwrap,
+    /// Show the wallet's SOL balance, and its token balance when a mint is given
+    Balance {
+        /// Mint address of a token to show the balance of
+        mint: Option<String>,
+    },
+}
+
+// How a position is monitored once bought, on top of the config file
+#[derive(Args)]
+pub struct ExitArgs {
+    /// Keep running and sell everything once the price rises this many percent
+    #[arg(long, env = "TAKE_PROFIT_PCT", value_parser = take_profit_pct)]
+    pub take_profit: Option<f64>,
+
+    /// Keep running and sell everything once the price falls this many percent
+    #[arg(long, env = "STOP_LOSS_PCT", value_parser = sell_pct)]
+    pub stop_loss: Option<f64>,
+
+    /// Keep running and sell everything once the price falls this many
+    /// percent from the highest price seen since the entry
+    #[arg(long, env = "TRAILING_STOP_PCT", value_parser = sell_pct)]
+    pub trailing_stop: Option<f64>,
+
+    /// What to do when the token migrates to Raydium while monitored
+    #[arg(long, env = "ON_MIGRATION", value_enum)]
+    pub on_migration: Option<MigrationAction>,
+
+    /// Keep running and buy AMOUNT more SOL per wallet once the price falls
+    /// DROP_PCT below the entry, as DROP_PCT:AMOUNT, can be repeated
+    #[arg(long = "ladder", value_name = "DROP_PCT:AMOUNT")]
+    pub ladder: Vec<BuyRung>,
+
+    /// Keep running and sell PCT of the position once the price reaches
+    /// MULTIPLE times the entry, as MULTIPLE:PCT (e.g. 2x:25), can be repeated
+    #[arg(long = "sell-at", value_name = "MULTIPLE:PCT")]
+    pub sell_at: Vec<SellRung>,
+
+    /// Keep this percentage of the position when take profit, trailing stop
+    /// or sell ladder exits fire, stop losses still sell everything
+    #[arg(long, env = "MOON_BAG_PCT", value_parser = moon_bag_pct)]
+    pub moon_bag: Option<f64>,
+
+    /// Keep running and sell this percentage of the position the moment the
+    /// token's creator sells any of theirs, 100 for a full exit
+    #[arg(long, env = "CREATOR_SELL_PCT", value_parser = sell_pct)]
+    pub on_creator_sell: Option<f64>,
+
+    /// Keep running and sell everything once this percentage of the curve's
+    /// tokens is sold, getting out ahead of the migration
+    #[arg(long, env = "PROGRESS_EXIT_PCT", value_parser = sell_pct)]
+    pub sell_at_progress: Option<f64>,
+
+    /// Keep running and notify, without trading, once the price is this many
+    /// percent from the entry (negative below it), can be repeated
+    #[arg(long = "alert", value_name = "PCT", allow_negative_numbers = true)]
+    pub alerts: Vec<f64>,
+
+    /// Keep running and notify on new highs since the entry, at most once a minute
+    #[arg(long)]
+    pub alert_high: bool,
+}
+
+impl ExitArgs {
+    // Flags win over the config file, a ladder given replaces the file's one
+    pub fn rules(&self, file: &ExitRules) -> ExitRules {
+        ExitRules {
+            take_profit_pct: self.take_profit.or(file.take_profit_pct),
+            stop_loss_pct: self.stop_loss.or(file.stop_loss_pct),
+            trailing_stop_pct: self.trailing_stop.or(file.trailing_stop_pct),
+            on_migration: self.on_migration.or(file.on_migration),
+            buy_ladder: if self.ladder.is_empty() {
+                file.buy_ladder.clone()
+            } else {
+                self.ladder.clone()
+            },
+            sell_ladder: if self.sell_at.is_empty() {
+                file.sell_ladder.clone()
+            } else {
+                self.sell_at.clone()
+            },
+            moon_bag_pct: self.moon_bag.or(file.moon_bag_pct),
+            creator_sell_pct: self.on_creator_sell.or(file.creator_sell_pct),
+            progress_exit_pct: self.sell_at_progress.or(file.progress_exit_pct),
+            price_alerts: if self.alerts.is_empty() {
+                file.price_alerts.clone()
+            } else {
+                self.alerts.clone()
+            },
+            alert_new_high: self.alert_high || file.alert_new_high,
+        }
+    }
+}
+
+fn sell_pct(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(pct) if pct > 0.0 && pct <= 100.0 => Ok(pct),
+        _ => Err("must be a percentage above 0 and up to 100".to_string()),
+    }
+}
+
+fn take_profit_pct(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(pct) if pct > 0.0 => Ok(pct),
+        _ => Err("must be a percentage above 0".to_string()),
+    }
+}
+
+fn moon_bag_pct(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(pct) if (0.0..100.0).contains(&pct) => Ok(pct),
+        _ => Err("must be a percentage from 0 up to 100".to_string()),
+    }
+}
+
+impl Command {
+    // Commands that send trades, which a Ctrl-C lets finish
+    pub fn trades(&self) -> bool {
+        matches!(
+            self,
+            Command::Buy { .. }
+                | Command::Monitor { .. }
+                | Command::Sell { .. }
+                | Command::Panic { .. }
+                | Command::Snipe { .. }
+                | Command::Dca { .. }
+                | Command::Twap { .. }
+                | Command::Orders {
+                    action: OrdersAction::Watch
+                }
+                | Command::Copy { .. }
+                | Command::Daemon { .. }
+                | Command::Telegram
+                | Command::Serve { .. }
+                | Command::Tui { .. }
+        )
+    }
+
+    // Commands that keep trading until stopped, of which only one may run
+    // per wallet. A buy with exit rules stays on as the position's monitor.
+    pub fn locks_wallets(&self, file_rules: &ExitRules) -> bool {
+        if let Command::Buy {
+            tokens: None, exit, ..
+        } = self
+        {
+            return !exit.rules(file_rules).is_empty();
+        }
+        matches!(
+            self,
+            Command::Monitor { .. }
+                | Command::Snipe { .. }
+                | Command::Dca { .. }
+                | Command::Twap { .. }
+                | Command::Orders {
+                    action: OrdersAction::Watch
+                }
+                | Command::Copy { .. }
+                | Command::Daemon { .. }
+                | Command::Telegram
+                | Command::Serve { .. }
+                | Command::Tui { orders: true }
+        )
+    }
+}
+
+#[derive(Subcommand)]
+pub enum KeyringAction {
+    /// Save the configured (or a pasted) private key into the keychain
+    Store,
+    /// Print the wallet address held in the keychain
+    Show,
+    /// Remove the key from the keychain
+    Delete,
+}
+
+#[derive(Subcommand)]
+pub enum OrdersAction {
+    /// Place a limit buy, filled by `orders watch` or `daemon --orders`
+    #[command(group(ArgGroup::new("limit").required(true)))]
+    Add {
+        /// Mint address or degen.fund URL of the token to buy
+        mint: String,
+
+        /// Amount of SOL to buy, defaults to buy_amount from the config file
+        #[arg(long)]
+        amount: Option<f64>,
+
+        /// Buy once the curve price per token drops below this many SOL
+        #[arg(long, group = "limit")]
+        below_price: Option<f64>,
+
+        /// Buy once the market cap drops below this many SOL
+        #[arg(long, group = "limit")]
+        below_market_cap: Option<f64>,
+    },
+    /// List the open orders
+    List {
+        /// Include filled, failed and cancelled orders
+        #[arg(long)]
+        all: bool,
+    },
+    /// Cancel an open order
+    Cancel {
+        /// Order number, as listed
+        id: i64,
+    },
+    /// Poll the curves of the open orders and buy when a limit is met
+    Watch,
+}
+
+#[derive(Subcommand)]
+pub enum NonceAction {
+    /// Create and fund a nonce account controlled by the wallet
+    Create,
+    /// Print the stored nonce and authority of the nonce account
+    Show,
+    /// Advance the nonce, invalidating transactions signed against it
+    Advance,
+    /// Withdraw the nonce account's balance back to the wallet
+    Close,
+}
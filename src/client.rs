@@ -0,0 +1,156 @@
+use solana_sdk::{
+    clock::Slot, native_token::sol_to_lamports, pubkey::Pubkey, signature::Signature,
+    signer::Signer, transaction::VersionedTransaction,
+};
+use tracing::instrument;
+
+use crate::config::Config;
+use crate::prepare::{prepare_transaction, Trade};
+use crate::signer::WalletSigner;
+use crate::transaction::Sender;
+use crate::{source, Result};
+
+// The bot's trading pipeline for embedding in other programs: fetch a trade
+// transaction, sign it, send it and wait for it to land. Sends and
+// confirmations go through the configured send mode, journal and notifiers
+// exactly as the binary's do.
+pub struct DegenFundClient {
+    config: Config,
+    sender: Sender,
+    signer: WalletSigner,
+}
+
+impl DegenFundClient {
+    pub fn new(config: Config, signer: WalletSigner) -> Result<DegenFundClient> {
+        let sender = config.sender()?;
+        Ok(DegenFundClient {
+            config,
+            sender,
+            signer,
+        })
+    }
+
+    // Trade with the wallet the config points at
+    pub fn from_config(config: Config) -> Result<DegenFundClient> {
+        let signer = config.signer()?;
+        DegenFundClient::new(config, signer)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
+
+    // Unsigned buy of `amount_sol` worth of `mint`, from the configured source
+    pub async fn fetch_buy_tx(
+        &self,
+        mint: &Pubkey,
+        amount_sol: f64,
+    ) -> Result<(VersionedTransaction, Trade)> {
+        let tx = source::buy_transaction(
+            &self.config,
+            self.sender.client(),
+            &self.pubkey(),
+            mint,
+            amount_sol,
+        )
+        .await?;
+        let trade = Trade::Buy {
+            mint: *mint,
+            lamports: sol_to_lamports(amount_sol),
+        };
+        Ok((tx, trade))
+    }
+
+    // Unsigned sell of `token_amount` raw units of `mint`
+    pub async fn fetch_sell_tx(
+        &self,
+        mint: &Pubkey,
+        token_amount: u64,
+    ) -> Result<(VersionedTransaction, Trade)> {
+        let tx = source::sell_transaction(
+            &self.config,
+            self.sender.client(),
+            &self.pubkey(),
+            mint,
+            token_amount,
+        )
+        .await?;
+        let trade = Trade::Sell {
+            mint: *mint,
+            token_amount,
+        };
+        Ok((tx, trade))
+    }
+
+    // Apply the compute budget and slippage checks, check the balance and sign
+    pub async fn sign(&self, tx: &mut VersionedTransaction, trade: &Trade) -> Result<()> {
+        prepare_transaction(&self.config, self.sender.client(), tx, &self.signer, trade).await
+    }
+
+    // Broadcast without waiting. In dry run mode the transaction is only simulated.
+    pub async fn send(&self, tx: &VersionedTransaction, trade: &Trade) -> Result<Signature> {
+        if self.sender.is_dry_run() {
+            self.sender.dry_run(tx, &self.signer).await?;
+            return Ok(tx.signatures[0]);
+        }
+        match self.sender.send(tx, &self.signer).await {
+            Ok(signature) => {
+                self.sender.on_sent(trade, &self.signer, &signature).await;
+                Ok(signature)
+            }
+            Err(e) => {
+                self.sender
+                    .on_failed(trade, &self.signer, &tx.signatures[0], e.to_string())
+                    .await;
+                Err(e)
+            }
+        }
+    }
+
+    // Wait for a sent transaction to land, returning its slot
+    pub async fn confirm(
+        &self,
+        tx: &VersionedTransaction,
+        trade: &Trade,
+        signature: &Signature,
+    ) -> Result<Slot> {
+        match self.sender.confirm(tx, &self.signer, signature).await {
+            Ok(slot) => {
+                self.sender
+                    .on_confirmed(trade, &self.signer, signature, slot)
+                    .await;
+                Ok(slot)
+            }
+            Err(e) => {
+                self.sender
+                    .on_failed(trade, &self.signer, signature, e.to_string())
+                    .await;
+                Err(e)
+            }
+        }
+    }
+
+    // Fetch, sign, send and confirm a buy in one go
+    #[instrument(name = "buy", skip_all, fields(mint = %mint, wallet = %self.pubkey()))]
+    pub async fn buy(&self, mint: &Pubkey, amount_sol: f64) -> Result<(Signature, Slot)> {
+        let (mut tx, trade) = self.fetch_buy_tx(mint, amount_sol).await?;
+        self.sign(&mut tx, &trade).await?;
+        self.sender
+            .send_and_confirm(&tx, &self.signer, &trade)
+            .await
+    }
+
+    // Fetch, sign, send and confirm a sell in one go
+    #[instrument(name = "sell", skip_all, fields(mint = %mint, wallet = %self.pubkey()))]
+    pub async fn sell(&self, mint: &Pubkey, token_amount: u64) -> Result<(Signature, Slot)> {
+        let (mut tx, trade) = self.fetch_sell_tx(mint, token_amount).await?;
+        self.sign(&mut tx, &trade).await?;
+        self.sender
+            .send_and_confirm(&tx, &self.signer, &trade)
+            .await
+    }
+}
@@ -0,0 +1,33 @@
+use colored::*;
+use log::info;
+use solana_sdk::{native_token::lamports_to_sol, signature::Signer};
+
+use crate::config::Config;
+use crate::{wallet, Result};
+
+pub async fn run(config: &Config, mint: Option<&str>) -> Result<()> {
+    let keypair = config.signer()?;
+    let client = config.rpc_client()?;
+    let owner = keypair.pubkey();
+
+    let lamports = client.get_balance(&owner).await?;
+    info!(
+        "Wallet {} holds {} SOL",
+        owner.to_string().bright_green(),
+        lamports_to_sol(lamports).to_string().yellow()
+    );
+
+    if let Some(mint) = mint {
+        let mint_pubkey = wallet::parse_mint(mint)?;
+        let (amount, decimals) = wallet::token_balance(&client, &owner, &mint_pubkey).await?;
+        let ui_amount = amount as f64 / 10f64.powi(decimals as i32);
+        info!(
+            "Token {} balance: {} ({} raw units)",
+            mint.bright_cyan(),
+            ui_amount.to_string().yellow(),
+            amount
+        );
+    }
+
+    Ok(())
+}
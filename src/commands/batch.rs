@@ -0,0 +1,126 @@
+use colored::*;
+use futures_util::{stream, StreamExt};
+use log::{error, info};
+
+use super::buy::{self, BuyAmount, Prompt};
+use crate::config::Config;
+use crate::monitor::ExitRules;
+use crate::token_list::{self, Entry};
+use crate::{shutdown, Result};
+
+// Buy every token of a `MINT [AMOUNT]` list, `concurrency` at a time, and
+// report how each went once all are done
+pub async fn run(
+    config: &Config,
+    path: &str,
+    amount: Option<f64>,
+    concurrency: usize,
+    confirm: bool,
+    force: bool,
+) -> Result<()> {
+    let entries = token_list::load(path)?;
+    if entries.is_empty() {
+        return Err(format!("No tokens listed in {}", path)
+            .red()
+            .to_string()
+            .into());
+    }
+    let mut total = 0.0;
+    for entry in &entries {
+        let amount = entry.amount.or(amount).or(config.buy_amount).ok_or_else(|| {
+            format!(
+                "No amount for {}, give one on its line or with --amount, BUY_AMOUNT or buy_amount in the config file",
+                entry.mint
+            )
+            .red()
+            .to_string()
+        })?;
+        total += amount;
+    }
+    if confirm {
+        let wallets = config.signers()?.len();
+        let spend = if wallets == 1 {
+            format!("{} SOL", total)
+        } else {
+            format!("{} SOL from each of {} wallets", total, wallets)
+        };
+        buy::ask_to_proceed(format!(
+            "You are about to spend {} on {} tokens from {}. Proceed?",
+            spend,
+            entries.len(),
+            path
+        ))?;
+    }
+
+    info!(
+        "Buying {} tokens, {} at a time",
+        entries.len().to_string().yellow(),
+        concurrency.to_string().yellow()
+    );
+    let prompt = Prompt {
+        preview: false,
+        confirm: false,
+        force,
+    };
+    let results: Vec<(Entry, Option<Result<()>>)> = stream::iter(entries)
+        .map(|entry| async move {
+            // Buys not started yet are skipped once a shutdown is requested
+            if shutdown::requested() {
+                return (entry, None);
+            }
+            let mint = entry.mint.to_string();
+            let result = buy::run(
+                config,
+                &mint,
+                entry.amount.or(amount).map(BuyAmount::Sol),
+                ExitRules::default(),
+                None,
+                prompt,
+            )
+            .await;
+            if let Err(e) = &result {
+                error!("Buy of {} failed: {}", mint, e);
+            }
+            (entry, Some(result))
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    info!("{}", "Batch summary".bold());
+    let mut failed = 0;
+    for (entry, result) in &results {
+        let amount = entry
+            .amount
+            .or(amount)
+            .or(config.buy_amount)
+            .unwrap_or_default();
+        let outcome = match result {
+            Some(Ok(())) => "bought".green().to_string(),
+            Some(Err(e)) => {
+                failed += 1;
+                format!("{} {}", "failed".red(), e.to_string().dimmed())
+            }
+            None => {
+                failed += 1;
+                "skipped".yellow().to_string()
+            }
+        };
+        info!(
+            "  {} {} SOL  {}",
+            format!("{:<44}", entry.mint).bright_cyan(),
+            format!("{:>12}", amount).yellow(),
+            outcome
+        );
+    }
+    if failed > 0 {
+        return Err(
+            format!("{} of {} buys did not go through", failed, results.len())
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+    info!("All {} buys went through", results.len());
+    Ok(())
+}
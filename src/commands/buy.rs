@@ -0,0 +1,530 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use colored::*;
+use dialoguer::Confirm;
+use log::{error, info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::task::JoinSet;
+use tracing::instrument;
+
+use super::position;
+use crate::config::Config;
+use crate::curve;
+use crate::journal::Journal;
+use crate::metadata::{OffChainMetadata, TokenMetadata};
+use crate::monitor::ExitRules;
+use crate::prepare::{prepare_for_launch, prepare_transaction, Trade};
+use crate::schedule::Launch;
+use crate::signer::WalletSigner;
+use crate::transaction::{report_landed, Sender};
+use crate::{creator, holders, price, rugcheck, source, wallet, BotError, Result};
+
+// How long before its launch a buy sized in USD is converted and signed
+const USD_SIZING_LEAD: TimeDelta = TimeDelta::seconds(30);
+
+// What a buy started by hand shows and asks before sending, automated buys
+// use the default and do neither
+#[derive(Clone, Copy, Default)]
+pub struct Prompt {
+    pub preview: bool,
+    pub confirm: bool,
+    // Buy again from wallets that already bought the token
+    pub force: bool,
+}
+
+// How much SOL each wallet spends
+#[derive(Clone, Copy)]
+pub enum BuyAmount {
+    Sol(f64),
+    // Percent of the wallet's spendable SOL, its balance less fees and the
+    // configured reserve
+    BalancePct(f64),
+    // SOL worth this many USD, converted as the buy is sent
+    Usd(f64),
+}
+
+// What the wallets spend, a USD amount staying in USD until it is sized
+enum Spend {
+    // SOL of each wallet
+    Sol(Vec<f64>),
+    // USD from every wallet
+    Usd(f64),
+}
+
+pub async fn run(
+    config: &Config,
+    mint: &str,
+    amount: Option<BuyAmount>,
+    rules: ExitRules,
+    at: Option<DateTime<Utc>>,
+    prompt: Prompt,
+) -> Result<()> {
+    let amount = amount
+        .or(config.buy_amount.map(BuyAmount::Sol))
+        .ok_or_else(|| {
+            "A buy amount must be given with --amount, BUY_AMOUNT or buy_amount in the config file"
+                .red()
+                .to_string()
+        })?;
+    let sender = Arc::new(config.sender()?);
+    let mint_pubkey = wallet::parse_mint(mint)?;
+    let (signers, _claim) = if prompt.force {
+        (config.signers()?, None)
+    } else {
+        let (signers, claim) = first_buys(config, config.signers()?, &mint_pubkey)?;
+        (signers, Some(claim))
+    };
+    let (mut keypairs, spend) = wallet_amounts(config, sender.client(), signers, amount).await?;
+    rugcheck::gate(sender.client(), &mint_pubkey, config.rug_check).await?;
+    holders::gate(config, sender.client(), &mint_pubkey).await?;
+    creator::gate(config, sender.client(), &mint_pubkey).await?;
+    let symbol = if prompt.preview {
+        preview(config, &mint_pubkey).await
+    } else {
+        None
+    };
+    if prompt.confirm {
+        confirm(
+            sender.client(),
+            &mint_pubkey,
+            symbol,
+            &spend,
+            keypairs.len(),
+        )
+        .await?;
+    }
+    let launch = match at {
+        Some(at) => Some(Arc::new(Launch::new(at, &config.ntp_server).await?)),
+        None => None,
+    };
+    let mut amounts = size(
+        config,
+        sender.client(),
+        spend,
+        keypairs.len(),
+        launch.as_deref(),
+    )
+    .await?;
+
+    // Entry price for the position monitor, read just before buying
+    let monitor = if rules.is_empty() {
+        None
+    } else {
+        let decimals = curve::mint_decimals(sender.client(), &mint_pubkey).await?;
+        let curve = config
+            .venue()?
+            .curve(sender.client(), &mint_pubkey, decimals)
+            .await?;
+        Some((decimals, curve.price_sol))
+    };
+
+    let holders = if keypairs.len() == 1 {
+        let keypair = keypairs.remove(0);
+        let amount = amounts.remove(0);
+        let buyer = keypair.pubkey();
+
+        info!(
+            "Buying {} SOL of tokens using wallet {}",
+            amount.to_string().yellow(),
+            buyer.to_string().bright_green()
+        );
+
+        let signature = buy_with_wallet(
+            config,
+            &sender,
+            &keypair,
+            &mint_pubkey,
+            amount,
+            launch.as_deref().map(|launch| (launch, true)),
+        )
+        .await?;
+        if !sender.is_dry_run() {
+            report_landed(&signature);
+        }
+        vec![keypair]
+    } else {
+        buy_with_wallets(config, &sender, keypairs, amounts, &mint_pubkey, launch).await?
+    };
+
+    let Some((decimals, entry_price)) = monitor else {
+        return Ok(());
+    };
+    if config.dry_run {
+        info!("Dry run, not monitoring the position");
+        return Ok(());
+    }
+    // A new position starts its sell ladder and moon bag over
+    Journal::open(&config.journal_path)?.clear_position(&mint_pubkey)?;
+    position::manage(
+        config,
+        &sender,
+        &holders,
+        &mint_pubkey,
+        decimals,
+        entry_price,
+        &rules,
+    )
+    .await
+}
+
+fn in_flight() -> &'static Mutex<HashSet<(Pubkey, Pubkey)>> {
+    static IN_FLIGHT: OnceLock<Mutex<HashSet<(Pubkey, Pubkey)>>> = OnceLock::new();
+    IN_FLIGHT.get_or_init(Default::default)
+}
+
+// Wallets and mint of buys under way, released when the buy is over
+struct Claim {
+    keys: Vec<(Pubkey, Pubkey)>,
+}
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        let mut in_flight = in_flight().lock().unwrap();
+        for key in &self.keys {
+            in_flight.remove(key);
+        }
+    }
+}
+
+// The wallets that never bought `mint`, per the journal, and aren't buying it
+// right now, so a signal firing twice or a re-run after an unclear failure
+// doesn't buy it twice
+fn first_buys(
+    config: &Config,
+    signers: Vec<WalletSigner>,
+    mint: &Pubkey,
+) -> Result<(Vec<WalletSigner>, Claim)> {
+    let journal = Journal::open(&config.journal_path)?;
+    // Declared first to be dropped last, as releasing it takes the lock
+    let mut claim = Claim { keys: Vec::new() };
+    let mut in_flight = in_flight().lock().unwrap();
+    let mut kept = Vec::new();
+    for signer in signers {
+        let key = (signer.pubkey(), *mint);
+        if in_flight.contains(&key) || journal.has_bought_with(&key.0, mint)? {
+            warn!(
+                "{} already bought {}, skipping it (--force buys again)",
+                key.0.to_string().bright_green(),
+                mint
+            );
+            continue;
+        }
+        in_flight.insert(key);
+        claim.keys.push(key);
+        kept.push(signer);
+    }
+    if kept.is_empty() {
+        return Err(BotError::policy(format!(
+            "Every wallet already bought {}, pass --force to buy it again",
+            mint
+        )));
+    }
+    Ok((kept, claim))
+}
+
+// Show what the mint is before buying it, so a wrong paste stands out, and
+// return its symbol. Any part that can't be fetched is skipped with a warning.
+async fn preview(config: &Config, mint: &Pubkey) -> Option<String> {
+    let client = match config.rpc_client() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!("Could not preview the token: {}", e);
+            return None;
+        }
+    };
+
+    let mut symbol = None;
+    match TokenMetadata::fetch_many(&client, &[*mint]).await {
+        Ok(mut metadata) => match metadata.remove(0) {
+            Some(metadata) => {
+                info!(
+                    "Token {} ({}) {}",
+                    metadata.name.bold(),
+                    metadata.symbol.yellow(),
+                    mint.to_string().bright_cyan()
+                );
+                match OffChainMetadata::fetch(&metadata.uri).await {
+                    Ok(details) => {
+                        if let Some(image) = &details.image {
+                            info!("  Image: {}", image);
+                        }
+                        if let Some(description) = &details.description {
+                            info!("  Description: {}", description);
+                        }
+                        for (name, link) in &details.socials {
+                            info!("  {}: {}", name, link);
+                        }
+                    }
+                    Err(e) => warn!("Could not fetch {}: {}", metadata.uri, e),
+                }
+                symbol = Some(metadata.symbol);
+            }
+            None => warn!("{} has no metadata account", mint),
+        },
+        Err(e) => warn!("Could not fetch the token metadata: {}", e),
+    }
+
+    let Ok(venue) = config.venue() else {
+        return symbol;
+    };
+    let curve = async {
+        let decimals = curve::mint_decimals(&client, mint).await?;
+        let initial = venue.initial_curve_tokens(&client).await?;
+        let curve = venue.curve(&client, mint, decimals).await?;
+        Result::Ok((initial, curve))
+    };
+    match curve.await {
+        Ok((Some(initial), curve)) => info!(
+            "  Curve {:.1}% sold, market cap {:.2} SOL",
+            curve.progress_pct(initial),
+            curve.market_cap_sol
+        ),
+        Ok((None, curve)) => info!("  Market cap {:.2} SOL", curve.market_cap_sol),
+        Err(e) => warn!("Could not read the bonding curve: {}", e),
+    }
+    symbol
+}
+
+// What each wallet spends, wallets with nothing to spend at the balance
+// percentage being left out
+async fn wallet_amounts(
+    config: &Config,
+    client: &RpcClient,
+    keypairs: Vec<WalletSigner>,
+    amount: BuyAmount,
+) -> Result<(Vec<WalletSigner>, Spend)> {
+    let pct = match amount {
+        BuyAmount::Sol(amount) | BuyAmount::Usd(amount) if amount <= 0.0 => {
+            return Err("The buy amount must be positive".red().to_string().into());
+        }
+        BuyAmount::Sol(amount) => {
+            let amounts = vec![amount; keypairs.len()];
+            return Ok((keypairs, Spend::Sol(amounts)));
+        }
+        BuyAmount::Usd(usd) => return Ok((keypairs, Spend::Usd(usd))),
+        BuyAmount::BalancePct(pct) => pct,
+    };
+    if pct <= 0.0 || pct > 100.0 {
+        return Err("The balance percentage must be above 0 and at most 100"
+            .red()
+            .to_string()
+            .into());
+    }
+
+    // Fees and token account rent stay available, as the simulation allows them
+    let reserve = config.sol_reserve_lamports + config.debit_tolerance_lamports;
+    let mut spending = (Vec::new(), Vec::new());
+    for keypair in keypairs {
+        let balance = client.get_balance(&keypair.pubkey()).await?;
+        let lamports = (balance.saturating_sub(reserve) as f64 * pct / 100.0) as u64;
+        if lamports == 0 {
+            warn!(
+                "Skipping wallet {}, its {} SOL are all reserved",
+                keypair.pubkey(),
+                lamports_to_sol(balance)
+            );
+            continue;
+        }
+        spending.0.push(keypair);
+        spending.1.push(lamports_to_sol(lamports));
+    }
+    if spending.0.is_empty() {
+        return Err(format!(
+            "No wallet has SOL to spend above the {} SOL reserve",
+            lamports_to_sol(reserve)
+        )
+        .red()
+        .to_string()
+        .into());
+    }
+    Ok((spending.0, Spend::Sol(spending.1)))
+}
+
+// The SOL each of the `wallets` spends, USD being converted at the current
+// SOL price. A launch is sized shortly before it fires rather than when it was
+// scheduled, its buys being signed from then on.
+async fn size(
+    config: &Config,
+    client: &RpcClient,
+    spend: Spend,
+    wallets: usize,
+    launch: Option<&Launch>,
+) -> Result<Vec<f64>> {
+    let usd = match spend {
+        Spend::Sol(amounts) => return Ok(amounts),
+        Spend::Usd(usd) => usd,
+    };
+    if let Some(launch) = launch {
+        launch.approach(USD_SIZING_LEAD).await?;
+    }
+    let sol = price::usd_to_sol(config.price_oracle, client, usd).await?;
+    Ok(vec![sol; wallets])
+}
+
+// Ask before spending, the symbol is looked up when the preview was skipped
+async fn confirm(
+    client: &RpcClient,
+    mint: &Pubkey,
+    symbol: Option<String>,
+    spend: &Spend,
+    wallets: usize,
+) -> Result<()> {
+    let symbol = match symbol {
+        Some(symbol) => symbol,
+        None => TokenMetadata::fetch_many(client, &[*mint])
+            .await
+            .ok()
+            .and_then(|mut metadata| metadata.remove(0))
+            .map_or_else(
+                || "an unknown token".to_string(),
+                |metadata| metadata.symbol,
+            ),
+    };
+    let spend = match spend {
+        Spend::Usd(usd) if wallets == 1 => format!("${}", usd),
+        Spend::Usd(usd) => format!("${} from each of {} wallets", usd, wallets),
+        Spend::Sol(amounts) => match amounts.as_slice() {
+            [amount] => format!("{} SOL", amount),
+            [first, ..] if amounts.iter().all(|amount| amount == first) => {
+                format!("{} SOL from each of {} wallets", first, amounts.len())
+            }
+            _ => format!(
+                "{} SOL across {} wallets",
+                amounts.iter().sum::<f64>(),
+                amounts.len()
+            ),
+        },
+    };
+    ask_to_proceed(format!(
+        "You are about to spend {} on {} ({}). Proceed?",
+        spend, symbol, mint
+    ))
+}
+
+// A yes/no question defaulting to no, answering no cancels the buy
+pub fn ask_to_proceed(question: String) -> Result<()> {
+    let proceed = Confirm::new()
+        .with_prompt(question)
+        .default(false)
+        .interact()
+        .map_err(|e| {
+            format!(
+                "Could not ask for confirmation ({}), pass --yes to skip it",
+                e
+            )
+            .red()
+            .to_string()
+        })?;
+    if !proceed {
+        return Err("Buy cancelled".red().to_string().into());
+    }
+    Ok(())
+}
+
+// Fire the buy from every wallet concurrently and report each result,
+// returning the wallets whose buy landed
+async fn buy_with_wallets(
+    config: &Config,
+    sender: &Arc<Sender>,
+    keypairs: Vec<WalletSigner>,
+    amounts: Vec<f64>,
+    mint: &Pubkey,
+    launch: Option<Arc<Launch>>,
+) -> Result<Vec<WalletSigner>> {
+    info!(
+        "Buying {} SOL of tokens with {} wallets",
+        amounts.iter().sum::<f64>().to_string().yellow(),
+        keypairs.len().to_string().yellow()
+    );
+
+    // One task per wallet, each fetching its own transaction
+    let config = Arc::new(config.clone());
+    let mut tasks = JoinSet::new();
+    for (index, (keypair, amount)) in keypairs.into_iter().zip(amounts).enumerate() {
+        let config = config.clone();
+        let sender = sender.clone();
+        let mint = *mint;
+        let launch = launch.clone();
+        tasks.spawn(async move {
+            // Only the first wallet draws the countdown
+            let launch = launch.as_deref().map(|launch| (launch, index == 0));
+            let result = buy_with_wallet(&config, &sender, &keypair, &mint, amount, launch).await;
+            (index, keypair, result)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        results.push(joined?);
+    }
+    results.sort_by_key(|(index, _, _)| *index);
+
+    // Per-wallet report
+    let total = results.len();
+    let mut holders = Vec::new();
+    let mut first_error = None;
+    for (_, keypair, result) in results {
+        let buyer = keypair.pubkey().to_string();
+        match result {
+            Ok(signature) => {
+                info!(
+                    "{} {} -> https://solscan.io/tx/{}",
+                    "✔".green(),
+                    buyer.bright_green(),
+                    signature
+                );
+                holders.push(keypair);
+            }
+            Err(e) => {
+                error!("{} {} -> {}", "✘".red(), buyer.bright_green(), e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    info!(
+        "{} of {} buys confirmed, using RPC {}",
+        holders.len().to_string().green(),
+        total,
+        sender.rpc().endpoint().bright_blue()
+    );
+
+    // The first wallet's error tells scripts why, as with a single wallet
+    if let (true, Some(e)) = (holders.is_empty(), first_error) {
+        error!("All buys failed");
+        return Err(e);
+    }
+    Ok(holders)
+}
+
+#[instrument(name = "buy", skip_all, fields(mint = %mint, wallet = %keypair.pubkey()))]
+pub(super) async fn buy_with_wallet(
+    config: &Config,
+    sender: &Sender,
+    keypair: &WalletSigner,
+    mint: &Pubkey,
+    amount: f64,
+    launch: Option<(&Launch, bool)>,
+) -> Result<Signature> {
+    let mut tx =
+        source::buy_transaction(config, sender.client(), &keypair.pubkey(), mint, amount).await?;
+    let trade = Trade::Buy {
+        mint: *mint,
+        lamports: sol_to_lamports(amount),
+    };
+    match launch {
+        Some((launch, countdown)) => {
+            prepare_for_launch(config, sender.client(), &mut tx, keypair, &trade).await?;
+            launch
+                .hold(config, sender, &mut tx, keypair, &trade, countdown)
+                .await?;
+        }
+        None => prepare_transaction(config, sender.client(), &mut tx, keypair, &trade).await?,
+    }
+    Ok(sender.send_and_confirm(&tx, keypair, &trade).await?.0)
+}
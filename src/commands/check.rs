@@ -0,0 +1,28 @@
+use colored::*;
+use log::{info, warn};
+
+use crate::config::Config;
+use crate::{creator, rugcheck, wallet, Result};
+
+// Print the pre-buy safety report of a token
+pub async fn run(config: &Config, mint: &str) -> Result<()> {
+    let mint = wallet::parse_mint(mint)?;
+    let client = config.rpc_client()?;
+    let report = rugcheck::check(&client, &mint).await?;
+    info!("Safety checks of {}", mint.to_string().bright_cyan());
+    report.print();
+    match report.risks().count() {
+        0 => info!("{}", "No risks found".green()),
+        risks => info!("{}", format!("{} risk(s) found", risks).red()),
+    }
+
+    // The creator's track record needs the program to recognize launches
+    match config.program_id() {
+        Ok(program_id) => match creator::history(&client, &program_id, &mint).await {
+            Ok(history) => history.print(),
+            Err(e) => warn!("Could not read the creator history: {}", e),
+        },
+        Err(_) => info!("Set program_id to include the creator history"),
+    }
+    Ok(())
+}
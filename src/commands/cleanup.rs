@@ -0,0 +1,292 @@
+use colored::*;
+use dialoguer::MultiSelect;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, native_token::lamports_to_sol, signature::Signer};
+
+use super::{buy, portfolio};
+use crate::config::Config;
+use crate::jupiter::WSOL_MINT;
+use crate::metadata::TokenMetadata;
+use crate::signer::WalletSigner;
+use crate::transaction::send_instructions;
+use crate::wallet::{self, Holding, OwnedTokenAccount};
+use crate::{price, Result};
+
+// Closes per transaction, well within the packet size limit
+const CLOSES_PER_TX: usize = 20;
+// A burn and its close touch the mint too
+const BURNS_PER_TX: usize = 10;
+
+// A balance worth too little to sell, burned so its account can be closed
+struct Dust {
+    account: OwnedTokenAccount,
+    label: String,
+    usd: Option<f64>,
+}
+
+impl Dust {
+    // `PEPE 1200.5 of <mint>, ~$0.03`
+    fn describe(&self, owner: &str) -> String {
+        let value = match self.usd {
+            Some(usd) => format!("~${:.2}", usd),
+            None => "no price".to_string(),
+        };
+        let ui_amount = self.account.amount as f64 / 10f64.powi(self.account.decimals as i32);
+        format!(
+            "{} {} {} of {}, {}",
+            &owner[..8],
+            self.label,
+            ui_amount,
+            self.account.mint,
+            value
+        )
+    }
+}
+
+struct Cleanup {
+    signer: WalletSigner,
+    empty: Vec<OwnedTokenAccount>,
+    dust: Vec<Dust>,
+}
+
+// Close every empty token account of the trading wallets, giving their rent
+// back to the wallet that owns them. With `burn_below_usd`, balances worth
+// less are burned and their accounts closed as well, after a review of them.
+pub async fn run(config: &Config, burn_below_usd: Option<f64>, confirm: bool) -> Result<()> {
+    let client = config.rpc_client()?;
+    let sol_usd = match burn_below_usd {
+        Some(_) => Some(price::sol_usd(config.price_oracle, &client).await?),
+        None => None,
+    };
+    let program_id = config.program_id().ok();
+
+    let mut cleanups = Vec::new();
+    for signer in config.signers()? {
+        // Wrapped SOL is never burned, closing its account unwraps it instead
+        let (empty, held): (Vec<OwnedTokenAccount>, Vec<OwnedTokenAccount>) =
+            wallet::token_accounts(&client, &signer.pubkey())
+                .await?
+                .into_iter()
+                .filter(|account| account.amount == 0 || account.mint != WSOL_MINT)
+                .partition(|account| account.amount == 0);
+        let mut dust = Vec::new();
+        if let Some(below) = burn_below_usd {
+            let holdings: Vec<Holding> = held
+                .iter()
+                .map(|account| Holding {
+                    mint: account.mint,
+                    amount: account.amount,
+                    decimals: account.decimals,
+                })
+                .collect();
+            let prices =
+                portfolio::usd_prices(&client, program_id.as_ref(), sol_usd, &holdings).await;
+            let mints: Vec<_> = held.iter().map(|account| account.mint).collect();
+            let metadata = TokenMetadata::fetch_many(&client, &mints).await?;
+            for ((account, holding), metadata) in held.into_iter().zip(&holdings).zip(metadata) {
+                let usd = prices
+                    .get(&account.mint)
+                    .map(|price| holding.ui_amount() * price);
+                // A token without any price is listed, it may well be dead
+                if usd.is_some_and(|usd| usd >= below) {
+                    continue;
+                }
+                dust.push(Dust {
+                    account,
+                    label: metadata.map_or_else(|| "unknown token".to_string(), |m| m.symbol),
+                    usd,
+                });
+            }
+        }
+        if !empty.is_empty() || !dust.is_empty() {
+            cleanups.push(Cleanup {
+                signer,
+                empty,
+                dust,
+            });
+        }
+    }
+    if cleanups.iter().any(|cleanup| !cleanup.dust.is_empty()) {
+        review(&mut cleanups, confirm)?;
+    }
+    cleanups.retain(|cleanup| !cleanup.empty.is_empty() || !cleanup.dust.is_empty());
+    if cleanups.is_empty() {
+        info!("No token accounts to close");
+        return Ok(());
+    }
+
+    let closes: usize = cleanups
+        .iter()
+        .map(|cleanup| cleanup.empty.len() + cleanup.dust.len())
+        .sum();
+    let burns: usize = cleanups.iter().map(|cleanup| cleanup.dust.len()).sum();
+    let rent: u64 = cleanups.iter().map(rent_of).sum();
+    info!(
+        "{} token account(s) to close, {} of them after a burn, holding {} SOL of rent",
+        closes,
+        burns,
+        lamports_to_sol(rent).to_string().yellow()
+    );
+    for cleanup in &cleanups {
+        info!(
+            "  {} {} account(s), {} SOL",
+            cleanup.signer.pubkey().to_string().bright_cyan(),
+            cleanup.empty.len() + cleanup.dust.len(),
+            lamports_to_sol(rent_of(cleanup))
+        );
+    }
+    if confirm {
+        buy::ask_to_proceed(format!(
+            "Close {} token account(s) to reclaim {} SOL?",
+            closes,
+            lamports_to_sol(rent)
+        ))?;
+    }
+
+    for cleanup in &cleanups {
+        let owner = cleanup.signer.pubkey();
+        for batch in cleanup.empty.chunks(CLOSES_PER_TX) {
+            let instructions: Vec<_> = batch
+                .iter()
+                .map(|account| {
+                    wallet::close_token_account(
+                        &account.token_program,
+                        &account.address,
+                        &owner,
+                        &owner,
+                    )
+                })
+                .collect();
+            send(
+                &client,
+                &cleanup.signer,
+                &instructions,
+                batch.len(),
+                "Closed",
+                config.dry_run,
+            )
+            .await?;
+        }
+        for batch in cleanup.dust.chunks(BURNS_PER_TX) {
+            let instructions: Vec<Instruction> = batch
+                .iter()
+                .flat_map(|dust| {
+                    let account = &dust.account;
+                    [
+                        wallet::burn_checked(
+                            &account.token_program,
+                            &account.address,
+                            &account.mint,
+                            &owner,
+                            account.amount,
+                            account.decimals,
+                        ),
+                        wallet::close_token_account(
+                            &account.token_program,
+                            &account.address,
+                            &owner,
+                            &owner,
+                        ),
+                    ]
+                })
+                .collect();
+            send(
+                &client,
+                &cleanup.signer,
+                &instructions,
+                batch.len(),
+                "Burned and closed",
+                config.dry_run,
+            )
+            .await?;
+        }
+    }
+    info!(
+        "{}",
+        format!("Reclaimed {} SOL of rent", lamports_to_sol(rent))
+            .green()
+            .bold()
+    );
+    Ok(())
+}
+
+fn rent_of(cleanup: &Cleanup) -> u64 {
+    cleanup
+        .empty
+        .iter()
+        .chain(cleanup.dust.iter().map(|dust| &dust.account))
+        .map(|account| account.lamports)
+        .sum()
+}
+
+// Let the user pick the balances to burn. Priced dust is picked to start
+// with, tokens without a price have to be picked by hand. Without a review
+// only the priced dust is burned.
+fn review(cleanups: &mut [Cleanup], confirm: bool) -> Result<()> {
+    if !confirm {
+        for cleanup in cleanups.iter_mut() {
+            cleanup.dust.retain(|dust| dust.usd.is_some());
+        }
+        return Ok(());
+    }
+    let mut items = Vec::new();
+    let mut defaults = Vec::new();
+    for cleanup in cleanups.iter() {
+        let owner = cleanup.signer.pubkey().to_string();
+        for dust in &cleanup.dust {
+            items.push(dust.describe(&owner));
+            defaults.push(dust.usd.is_some());
+        }
+    }
+    let picked = MultiSelect::new()
+        .with_prompt("Balances to burn (space toggles, enter accepts)")
+        .items(&items)
+        .defaults(&defaults)
+        .interact_opt()
+        .map_err(|e| {
+            format!(
+                "Could not ask which balances to burn ({}), pass --yes to burn the priced ones",
+                e
+            )
+            .red()
+            .to_string()
+        })?;
+    let Some(picked) = picked else {
+        return Err("Cleanup cancelled".red().to_string().into());
+    };
+    let mut index = 0;
+    for cleanup in cleanups.iter_mut() {
+        cleanup.dust.retain(|_| {
+            let keep = picked.contains(&index);
+            index += 1;
+            keep
+        });
+    }
+    if picked.is_empty() {
+        warn!("No balances picked, only closing the empty accounts");
+    }
+    Ok(())
+}
+
+async fn send(
+    client: &RpcClient,
+    signer: &WalletSigner,
+    instructions: &[Instruction],
+    accounts: usize,
+    done: &str,
+    dry_run: bool,
+) -> Result<()> {
+    let owner = signer.pubkey();
+    match send_instructions(client, signer, instructions, dry_run).await? {
+        Some(signature) => info!(
+            "{} {} account(s) of {}: {}",
+            done.green(),
+            accounts,
+            owner,
+            signature
+        ),
+        None => info!("Dry run, {} account(s) of {} simulated", accounts, owner),
+    }
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use colored::*;
+use futures_util::{stream::select_all, StreamExt};
+use log::{error, info, warn};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, native_token::lamports_to_sol, pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+use super::buy::{BuyAmount, Prompt};
+use crate::config::Config;
+use crate::curve::{BondingCurve, BUY_DISCRIMINATOR};
+use crate::monitor::ExitRules;
+use crate::transaction::Sender;
+use crate::{shutdown, Result};
+
+const BUY_LOG: &str = "Program log: Instruction: Buy";
+// Account positions in the curve buy instruction
+const BUY_MINT_INDEX: usize = 2;
+const BUY_USER_INDEX: usize = 6;
+
+// How much to spend when mirroring a buy
+#[derive(Clone, Copy)]
+pub enum CopySizing {
+    /// The same amount of SOL every time
+    Fixed(f64),
+    /// This fraction of the SOL the target spent
+    Ratio(f64),
+}
+
+// A curve buy made by a watched wallet
+struct TargetBuy {
+    target: Pubkey,
+    mint: Pubkey,
+    lamports: u64,
+}
+
+pub async fn run(
+    config: Arc<Config>,
+    targets: Vec<Pubkey>,
+    sizing: CopySizing,
+    max_amount: Option<f64>,
+) -> Result<()> {
+    if targets.is_empty() {
+        return Err("At least one --target wallet must be given"
+            .red()
+            .to_string()
+            .into());
+    }
+    let program_id = config.program_id()?;
+    let sender = config.sender()?;
+    let ws_url = config.ws_url()?;
+
+    // logsSubscribe takes a single address, so one subscription per target
+    let pubsub = PubsubClient::new(&ws_url).await?;
+    let mut subscriptions = Vec::new();
+    let mut _unsubscribes = Vec::new();
+    for target in &targets {
+        let (logs, unsubscribe) = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![target.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?;
+        subscriptions.push(logs);
+        _unsubscribes.push(unsubscribe);
+    }
+    let mut logs = select_all(subscriptions);
+
+    info!(
+        "Copying degen.fund buys of {} wallets",
+        targets.len().to_string().yellow()
+    );
+
+    let mut seen = HashSet::new();
+    let mut tasks = JoinSet::new();
+    while let Some(response) = tokio::select! {
+        response = logs.next() => response,
+        _ = shutdown::reached(shutdown::State::Draining) => None,
+    } {
+        let response = response.value;
+        if response.err.is_some()
+            || !response.logs.iter().any(|log| log.starts_with(BUY_LOG))
+            || !seen.insert(response.signature.clone())
+        {
+            continue;
+        }
+
+        let buys = match find_buys(&sender, &program_id, &targets, &response.signature).await {
+            Ok(buys) => buys,
+            Err(e) => {
+                warn!("Could not inspect {}: {}", response.signature, e);
+                continue;
+            }
+        };
+        for buy in buys {
+            let their_amount = lamports_to_sol(buy.lamports);
+            let mut amount = match sizing {
+                CopySizing::Fixed(amount) => amount,
+                CopySizing::Ratio(ratio) => their_amount * ratio,
+            };
+            if let Some(max) = max_amount {
+                amount = amount.min(max);
+            }
+            info!(
+                "{} bought {} for {:.4} SOL, copying with {:.4} SOL",
+                buy.target.to_string().bright_green(),
+                buy.mint.to_string().bright_cyan(),
+                their_amount,
+                amount
+            );
+            if amount <= 0.0 {
+                continue;
+            }
+
+            let config = config.clone();
+            let mint = buy.mint.to_string();
+            tasks.spawn(async move {
+                if let Err(e) = super::buy::run(
+                    &config,
+                    &mint,
+                    Some(BuyAmount::Sol(amount)),
+                    ExitRules::default(),
+                    None,
+                    Prompt::default(),
+                )
+                .await
+                {
+                    error!("Copy buy of {} failed: {}", mint, e);
+                }
+            });
+        }
+
+        // Reap finished copies so the task set doesn't grow forever
+        while tasks.try_join_next().is_some() {}
+    }
+
+    while tasks.join_next().await.is_some() {}
+    Ok(())
+}
+
+// Curve buys in the transaction signed by one of the targets, with the SOL
+// each one paid into the curve
+async fn find_buys(
+    sender: &Sender,
+    program_id: &Pubkey,
+    targets: &[Pubkey],
+    signature: &str,
+) -> Result<Vec<TargetBuy>> {
+    let signature = Signature::from_str(signature)?;
+    let tx = sender
+        .client()
+        .get_transaction_with_config(
+            &signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let (Some(decoded), Some(meta)) = (tx.transaction.transaction.decode(), tx.transaction.meta)
+    else {
+        return Ok(Vec::new());
+    };
+
+    // Account list as indexed by instructions and balances: static keys, then
+    // writable and readonly lookup table addresses
+    let mut keys = decoded.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for address in loaded.writable.iter().chain(&loaded.readonly) {
+            keys.push(Pubkey::from_str(address)?);
+        }
+    }
+
+    let mut buys = Vec::new();
+    for ix in decoded.message.instructions() {
+        if keys.get(ix.program_id_index as usize) != Some(program_id)
+            || !ix.data.starts_with(&BUY_DISCRIMINATOR)
+        {
+            continue;
+        }
+        let account = |position: usize| {
+            ix.accounts
+                .get(position)
+                .and_then(|index| keys.get(*index as usize))
+        };
+        let (Some(mint), Some(user)) = (account(BUY_MINT_INDEX), account(BUY_USER_INDEX)) else {
+            continue;
+        };
+        if !targets.contains(user) {
+            continue;
+        }
+
+        let curve = BondingCurve::address(program_id, mint);
+        let Some(curve_index) = keys.iter().position(|key| *key == curve) else {
+            continue;
+        };
+        let (Some(pre), Some(post)) = (
+            meta.pre_balances.get(curve_index),
+            meta.post_balances.get(curve_index),
+        ) else {
+            continue;
+        };
+        buys.push(TargetBuy {
+            target: *user,
+            mint: *mint,
+            lamports: post.saturating_sub(*pre),
+        });
+    }
+    Ok(buys)
+}
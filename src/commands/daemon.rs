@@ -0,0 +1,208 @@
+use colored::*;
+use futures_util::future::{join_all, LocalBoxFuture};
+use futures_util::FutureExt;
+use log::{error, info, warn};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use super::buy::{BuyAmount, Prompt};
+use super::copy::CopySizing;
+use super::snipe::SnipeFilters;
+use crate::config::Config;
+use crate::journal::Journal;
+use crate::token_list::{self, Entry};
+use crate::{metrics, shutdown, Result};
+
+const WATCHLIST_POLL: Duration = Duration::from_secs(5);
+// Wait before restarting a feed that failed, e.g. on a dropped WebSocket
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+pub struct CopyFeed {
+    pub targets: Vec<Pubkey>,
+    pub sizing: CopySizing,
+    pub max_amount: Option<f64>,
+}
+
+// What the daemon watches, any combination of them
+pub struct Feeds {
+    /// File of mints to buy, re-read as it changes
+    pub watchlist: Option<String>,
+    /// Buy new launches passing these filters
+    pub snipe: Option<SnipeFilters>,
+    /// Mirror the buys of these wallets
+    pub copy: Option<CopyFeed>,
+    /// Take commands from the configured Telegram chat
+    pub telegram: bool,
+    /// Fill the open limit orders
+    pub orders: bool,
+}
+
+// Stay resident and trade off every configured feed until shut down
+pub async fn run(
+    config: Arc<Config>,
+    amount: Option<f64>,
+    feeds: Feeds,
+    metrics_addr: Option<SocketAddr>,
+    dashboard: Option<(SocketAddr, String)>,
+) -> Result<()> {
+    let mut running: Vec<LocalBoxFuture<()>> = Vec::new();
+    if let Some(path) = feeds.watchlist {
+        let config = config.clone();
+        running.push(
+            supervise("watchlist", move || {
+                watchlist(config.clone(), path.clone(), amount)
+            })
+            .boxed_local(),
+        );
+    }
+    if let Some(filters) = feeds.snipe {
+        let config = config.clone();
+        running.push(
+            supervise("snipe", move || {
+                super::snipe::run(config.clone(), amount, filters.clone())
+            })
+            .boxed_local(),
+        );
+    }
+    if let Some(copy) = feeds.copy {
+        let config = config.clone();
+        running.push(
+            supervise("copy", move || {
+                super::copy::run(
+                    config.clone(),
+                    copy.targets.clone(),
+                    copy.sizing,
+                    copy.max_amount,
+                )
+            })
+            .boxed_local(),
+        );
+    }
+    if feeds.telegram {
+        let config = config.clone();
+        running.push(
+            supervise("telegram", move || super::telegram::run(config.clone())).boxed_local(),
+        );
+    }
+    if feeds.orders {
+        let config = config.clone();
+        running
+            .push(supervise("orders", move || super::orders::watch(config.clone())).boxed_local());
+    }
+    if running.is_empty() {
+        return Err(
+            "Nothing to watch, give --watchlist, --snipe, --copy-target, --telegram or --orders"
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+
+    info!("Daemon running {} feed(s)", running.len());
+    if let Some(addr) = metrics_addr {
+        running.push(
+            async move {
+                if let Err(e) = metrics::serve(addr).await {
+                    error!("Metrics stopped: {}", e);
+                }
+            }
+            .boxed_local(),
+        );
+    }
+    if let Some((addr, token)) = dashboard {
+        let config = config.clone();
+        running.push(
+            async move {
+                if let Err(e) = super::serve::dashboard(config, addr, token).await {
+                    error!("Dashboard stopped: {}", e);
+                }
+            }
+            .boxed_local(),
+        );
+    }
+    join_all(running).await;
+    info!("Daemon stopped");
+    Ok(())
+}
+
+// Keep a feed running, restarting it when it fails or ends, until shutdown
+async fn supervise<F, Fut>(name: &'static str, feed: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    while !shutdown::requested() {
+        match feed().await {
+            Ok(()) if shutdown::requested() => break,
+            Ok(()) => warn!("The {} feed ended, restarting it", name),
+            Err(e) => error!("The {} feed failed, restarting it: {}", name, e),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(RESTART_DELAY) => {}
+            _ = shutdown::reached(shutdown::State::Draining) => {}
+        }
+    }
+}
+
+// Buy every mint listed in the file once, one `MINT [AMOUNT]` per line with `#`
+// starting a comment. Mints bought before, according to the journal, are skipped.
+async fn watchlist(config: Arc<Config>, path: String, amount: Option<f64>) -> Result<()> {
+    let journal = Journal::open(&config.journal_path)?;
+    let mut handled = HashSet::new();
+    let mut tasks = JoinSet::new();
+    info!("Watching {} for tokens to buy", path.bright_cyan());
+    while !shutdown::requested() {
+        let entries = match token_list::load(&path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Skipping the watchlist for now: {}", e);
+                Vec::new()
+            }
+        };
+        for Entry {
+            mint,
+            amount: entry_amount,
+        } in entries
+        {
+            if !handled.insert(mint) {
+                continue;
+            }
+            if journal.has_bought(&mint)? {
+                info!("{} was bought before, skipping it", mint);
+                continue;
+            }
+            info!("Watchlist buy of {}", mint.to_string().bright_cyan());
+            let config = config.clone();
+            let amount = entry_amount.or(amount);
+            tasks.spawn(async move {
+                if let Err(e) = super::buy::run(
+                    &config,
+                    &mint.to_string(),
+                    amount.map(BuyAmount::Sol),
+                    config.exit_rules.clone(),
+                    None,
+                    Prompt::default(),
+                )
+                .await
+                {
+                    error!("Watchlist buy of {} failed: {}", mint, e);
+                }
+            });
+        }
+
+        // Reap finished buys so the task set doesn't grow forever
+        while tasks.try_join_next().is_some() {}
+        tokio::select! {
+            _ = tokio::time::sleep(WATCHLIST_POLL) => {}
+            _ = shutdown::reached(shutdown::State::Draining) => {}
+        }
+    }
+
+    while tasks.join_next().await.is_some() {}
+    Ok(())
+}
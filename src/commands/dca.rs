@@ -0,0 +1,178 @@
+use chrono::Utc;
+use colored::*;
+use log::{error, info, warn};
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+};
+use std::time::Duration;
+
+use super::buy::{self, BuyAmount, Prompt};
+use crate::config::Config;
+use crate::curve::{self, BondingCurve};
+use crate::journal::{DcaPlan, Journal};
+use crate::monitor::ExitRules;
+use crate::{shutdown, wallet, Result};
+
+// What `dca` was asked to buy, used when no plan for the mint is under way
+pub struct DcaOrder {
+    pub total: f64,
+    pub tranches: u32,
+    pub interval: Duration,
+    pub max_price: Option<f64>,
+}
+
+// Buy `total` SOL of a token in equal tranches, one per interval. Progress is
+// kept in the journal, so running it again for the same mint resumes the plan
+// unless `restart` discards it.
+pub async fn run(
+    config: &Config,
+    mint: &str,
+    order: DcaOrder,
+    restart: bool,
+    confirm: bool,
+) -> Result<()> {
+    let mint_pubkey = wallet::parse_mint(mint)?;
+    // Dry runs keep their plan in memory, like their trades out of the journal
+    let journal = if config.dry_run {
+        Journal::open(":memory:")?
+    } else {
+        Journal::open(&config.journal_path)?
+    };
+    let mut plan = match journal.active_dca(&mint_pubkey)? {
+        Some(plan) if !restart => {
+            info!(
+                "Resuming the DCA plan for {}, {} of {} tranches bought",
+                mint_pubkey.to_string().bright_cyan(),
+                plan.done.to_string().yellow(),
+                plan.tranches
+            );
+            plan
+        }
+        previous => {
+            if let Some(plan) = previous {
+                journal.cancel_dca(&plan)?;
+                info!(
+                    "Discarded the DCA plan with {} of {} tranches bought",
+                    plan.done, plan.tranches
+                );
+            }
+            start(&journal, &mint_pubkey, order, confirm)?
+        }
+    };
+
+    let tranche = lamports_to_sol(plan.tranche_lamports);
+    while plan.done < plan.tranches {
+        // A resumed plan keeps its pace from the last tranche bought
+        if let Some(last) = plan.last_tranche_at {
+            let elapsed = (Utc::now() - last).to_std().unwrap_or_default();
+            if let Some(wait) = plan.interval.checked_sub(elapsed) {
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = shutdown::reached(shutdown::State::Draining) => {}
+                }
+            }
+        }
+        if shutdown::requested() {
+            return Err(format!(
+                "Shutting down with {} of {} tranches bought, run dca again to resume",
+                plan.done, plan.tranches
+            )
+            .yellow()
+            .to_string()
+            .into());
+        }
+
+        if let Err(e) = check_price(config, &plan).await {
+            warn!("Skipping this tranche: {}", e);
+        } else {
+            info!(
+                "DCA tranche {} of {}: {} SOL",
+                (plan.done + 1).to_string().yellow(),
+                plan.tranches,
+                tranche
+            );
+            let result = buy::run(
+                config,
+                &plan.mint,
+                Some(BuyAmount::Sol(tranche)),
+                ExitRules::default(),
+                None,
+                // Every tranche buys the token again
+                Prompt {
+                    force: true,
+                    ..Prompt::default()
+                },
+            )
+            .await;
+            match result {
+                Ok(()) => {
+                    journal.record_tranche(&mut plan)?;
+                    continue;
+                }
+                Err(e) => error!("DCA tranche failed, retrying next interval: {}", e),
+            }
+        }
+        // Skipped or failed tranches are tried again an interval later
+        tokio::select! {
+            _ = tokio::time::sleep(plan.interval) => {}
+            _ = shutdown::reached(shutdown::State::Draining) => {}
+        }
+    }
+    info!(
+        "{} all {} tranches of {} bought",
+        "DCA done,".green(),
+        plan.tranches,
+        plan.mint.bright_cyan()
+    );
+    Ok(())
+}
+
+fn start(journal: &Journal, mint: &Pubkey, order: DcaOrder, confirm: bool) -> Result<DcaPlan> {
+    if order.total <= 0.0 || order.tranches == 0 {
+        return Err("The DCA total and tranches must be positive"
+            .red()
+            .to_string()
+            .into());
+    }
+    let tranche = order.total / order.tranches as f64;
+    if confirm {
+        buy::ask_to_proceed(format!(
+            "You are about to spend {} SOL on {} in {} tranches of {} SOL every {}s. Proceed?",
+            order.total,
+            mint,
+            order.tranches,
+            tranche,
+            order.interval.as_secs()
+        ))?;
+    }
+    journal.start_dca(
+        mint,
+        sol_to_lamports(tranche),
+        order.tranches,
+        order.interval,
+        order.max_price,
+    )
+}
+
+// Refuse the tranche while the curve price is above the plan's limit
+async fn check_price(config: &Config, plan: &DcaPlan) -> Result<()> {
+    let Some(max_price) = plan.max_price else {
+        return Ok(());
+    };
+    let client = config.rpc_client()?;
+    let mint = wallet::parse_mint(&plan.mint)?;
+    let program_id = config.program_id()?;
+    let decimals = curve::mint_decimals(&client, &mint).await?;
+    let price = BondingCurve::fetch(&client, &program_id, &mint)
+        .await?
+        .price_sol(decimals);
+    if price > max_price {
+        return Err(format!(
+            "price {:.10} SOL is above the {:.10} SOL limit",
+            price, max_price
+        )
+        .into());
+    }
+    Ok(())
+}
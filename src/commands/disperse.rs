@@ -0,0 +1,154 @@
+use colored::*;
+use log::info;
+use rand::Rng;
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    signature::Signer,
+    system_instruction,
+};
+use std::collections::HashSet;
+
+use super::{buy, twap};
+use crate::config::Config;
+use crate::transaction::send_instructions;
+use crate::{wallet, BotError, Result};
+
+// Transfers per transaction, well within the packet size limit
+const TRANSFERS_PER_TX: usize = 20;
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+// How much SOL the sub-wallets get
+#[derive(Clone, Copy)]
+pub enum DisperseAmount {
+    // Each wallet gets this much
+    Each(f64),
+    // Split across the wallets
+    Total(f64),
+}
+
+// Send SOL from the trading wallet to each of `targets`, or the wallet list
+// when none are given, batching the transfers into as few transactions as fit
+pub async fn run(
+    config: &Config,
+    targets: &[String],
+    amount: DisperseAmount,
+    jitter_pct: f64,
+    confirm: bool,
+) -> Result<()> {
+    let funder = config.signer()?;
+    let client = config.rpc_client()?;
+    let mut recipients = if targets.is_empty() {
+        config
+            .wallets
+            .iter()
+            .map(|key| Ok(wallet::load_keypair(Some(key))?.pubkey()))
+            .collect::<Result<Vec<Pubkey>>>()?
+    } else {
+        targets
+            .iter()
+            .map(|address| wallet::parse_pubkey(address))
+            .collect::<Result<Vec<Pubkey>>>()?
+    };
+    let mut seen = HashSet::new();
+    recipients.retain(|recipient| *recipient != funder.pubkey() && seen.insert(*recipient));
+    if recipients.is_empty() {
+        return Err(
+            "No wallets to fund, give them with --to or the wallets list of the config file"
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+
+    let amounts = amounts(amount, recipients.len(), jitter_pct);
+    // A wallet that doesn't exist yet must receive at least the rent exemption
+    let rent = client.get_minimum_balance_for_rent_exemption(0).await?;
+    let accounts = client.get_multiple_accounts(&recipients).await?;
+    for ((recipient, lamports), account) in recipients.iter().zip(&amounts).zip(&accounts) {
+        if account.is_none() && *lamports < rent {
+            return Err(format!(
+                "{} SOL is too little to fund the empty wallet {}, it needs at least {} SOL",
+                lamports_to_sol(*lamports),
+                recipient,
+                lamports_to_sol(rent)
+            )
+            .red()
+            .to_string()
+            .into());
+        }
+    }
+
+    let transactions = recipients.len().div_ceil(TRANSFERS_PER_TX);
+    let total: u64 = amounts.iter().sum();
+    let needed = total + transactions as u64 * SIGNATURE_FEE_LAMPORTS;
+    let available = client.get_balance(&funder.pubkey()).await?;
+    if needed > available {
+        return Err(BotError::InsufficientFunds {
+            wallet: funder.pubkey(),
+            needed,
+            available,
+        });
+    }
+
+    info!(
+        "Dispersing {} SOL from {} to {} wallet(s) in {} transaction(s)",
+        lamports_to_sol(total).to_string().yellow(),
+        funder.pubkey().to_string().bright_green(),
+        recipients.len(),
+        transactions
+    );
+    for ((recipient, lamports), account) in recipients.iter().zip(&amounts).zip(&accounts) {
+        info!(
+            "  {} {} SOL (holds {} SOL)",
+            recipient.to_string().bright_cyan(),
+            lamports_to_sol(*lamports),
+            lamports_to_sol(account.as_ref().map_or(0, |account| account.lamports))
+        );
+    }
+    if confirm {
+        buy::ask_to_proceed(format!(
+            "Send {} SOL to {} wallet(s)?",
+            lamports_to_sol(total),
+            recipients.len()
+        ))?;
+    }
+
+    let transfers: Vec<_> = recipients
+        .iter()
+        .zip(&amounts)
+        .map(|(recipient, lamports)| {
+            system_instruction::transfer(&funder.pubkey(), recipient, *lamports)
+        })
+        .collect();
+    for (i, batch) in transfers.chunks(TRANSFERS_PER_TX).enumerate() {
+        match send_instructions(&client, &funder, batch, config.dry_run).await? {
+            Some(signature) => info!(
+                "{} {} transfer(s): {}",
+                "Sent".green(),
+                batch.len(),
+                signature
+            ),
+            None => info!("Dry run, transaction {} simulated and not sent", i + 1),
+        }
+    }
+    info!("{}", "Every wallet funded".green().bold());
+    Ok(())
+}
+
+// Lamports per wallet, each within `jitter_pct` of the even amount
+fn amounts(amount: DisperseAmount, wallets: usize, jitter_pct: f64) -> Vec<u64> {
+    match amount {
+        DisperseAmount::Each(sol) => {
+            let jitter = (jitter_pct / 100.0).clamp(0.0, 0.99);
+            let mut rng = rand::thread_rng();
+            (0..wallets)
+                .map(|_| sol_to_lamports(sol * (1.0 + rng.gen_range(-jitter..=jitter))))
+                .collect()
+        }
+        DisperseAmount::Total(sol) => twap::shares(wallets as u32, jitter_pct)
+            .into_iter()
+            .map(|share| sol_to_lamports(sol * share))
+            .collect(),
+    }
+}
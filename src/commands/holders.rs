@@ -0,0 +1,32 @@
+use colored::*;
+use log::info;
+
+use crate::config::Config;
+use crate::{holders, wallet, Result};
+
+// List the largest holders of a token and their share of the supply
+pub async fn run(config: &Config, mint: &str, top: Option<usize>) -> Result<()> {
+    let mint = wallet::parse_mint(mint)?;
+    let top = top.unwrap_or(config.top_holders);
+    let client = config.rpc_client()?;
+    let concentration =
+        holders::fetch(&client, &mint, &holders::excluded_owners(config, &mint)).await?;
+
+    for (rank, (owner, amount)) in concentration.holders.iter().take(top).enumerate() {
+        info!(
+            "{:>2}. {} {} ({:.2}%)",
+            rank + 1,
+            owner.to_string().bright_green(),
+            (*amount as f64 / 10f64.powi(concentration.decimals as i32))
+                .to_string()
+                .yellow(),
+            *amount as f64 / concentration.supply.max(1) as f64 * 100.0
+        );
+    }
+    info!(
+        "Top {} holders, excluding the curve, own {}",
+        top,
+        format!("{:.1}%", concentration.top_pct(top)).bold()
+    );
+    Ok(())
+}
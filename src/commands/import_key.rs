@@ -0,0 +1,40 @@
+use colored::*;
+use log::info;
+use solana_sdk::signature::Signer;
+
+use crate::config::Config;
+use crate::keystore::{self, Keystore};
+use crate::{wallet, Result};
+
+// Encrypt the configured wallet, or a pasted private key, into a keystore file
+pub fn run(config: &Config, output: &str) -> Result<()> {
+    let keypair = if config.has_plaintext_key() {
+        config.keypair()?
+    } else {
+        let private_key = rpassword::prompt_password("Private key (base58): ")?;
+        wallet::load_keypair(Some(private_key.trim()))?
+    };
+
+    let passphrase = match config.key_password.as_deref() {
+        Some(passphrase) => passphrase.to_string(),
+        None => {
+            let passphrase = keystore::passphrase(None, "New keystore passphrase: ")?;
+            if keystore::passphrase(None, "Repeat the passphrase: ")? != passphrase {
+                return Err("Passphrases do not match".red().to_string().into());
+            }
+            passphrase
+        }
+    };
+    if passphrase.is_empty() {
+        return Err("The passphrase must not be empty".red().to_string().into());
+    }
+
+    Keystore::encrypt(&keypair, &passphrase)?.write(output)?;
+    info!(
+        "Wallet {} encrypted into {}",
+        keypair.pubkey().to_string().bright_green(),
+        output.bright_cyan()
+    );
+    info!("Point keystore_path (or --keystore) at it and remove the plaintext key");
+    Ok(())
+}
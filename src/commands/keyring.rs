@@ -0,0 +1,40 @@
+use colored::*;
+use log::info;
+use solana_sdk::signature::Signer;
+
+use crate::cli::KeyringAction;
+use crate::config::Config;
+use crate::{keychain, wallet, Result};
+
+pub fn run(config: &Config, action: &KeyringAction, entry: &str) -> Result<()> {
+    match action {
+        KeyringAction::Store => {
+            let keypair = if config.has_plaintext_key() {
+                config.keypair()?
+            } else {
+                let private_key = rpassword::prompt_password("Private key (base58): ")?;
+                wallet::load_keypair(Some(private_key.trim()))?
+            };
+            keychain::store(entry, &keypair)?;
+            info!(
+                "Wallet {} stored in the OS keychain as '{}'",
+                keypair.pubkey().to_string().bright_green(),
+                entry.bright_cyan()
+            );
+            info!("Set keyring_entry (or --keyring-entry) and remove the plaintext key");
+        }
+        KeyringAction::Show => {
+            let keypair = keychain::load(entry)?;
+            info!(
+                "Keychain entry '{}' holds wallet {}",
+                entry.bright_cyan(),
+                keypair.pubkey().to_string().bright_green()
+            );
+        }
+        KeyringAction::Delete => {
+            keychain::delete(entry)?;
+            info!("Keychain entry '{}' deleted", entry.bright_cyan());
+        }
+    }
+    Ok(())
+}
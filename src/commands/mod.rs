@@ -0,0 +1,28 @@
+pub mod balance;
+pub mod batch;
+pub mod buy;
+pub mod check;
+pub mod cleanup;
+pub mod copy;
+pub mod daemon;
+pub mod dca;
+pub mod disperse;
+pub mod holders;
+pub mod import_key;
+pub mod keyring;
+pub mod monitor;
+pub mod nonce;
+pub mod orders;
+pub mod panic;
+pub mod pnl;
+pub mod portfolio;
+pub mod position;
+pub mod sell;
+pub mod serve;
+pub mod snipe;
+pub mod sweep;
+pub mod telegram;
+pub mod transfer;
+pub mod tui;
+pub mod twap;
+pub mod wrap;
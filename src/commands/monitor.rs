@@ -0,0 +1,69 @@
+use colored::*;
+use log::{info, warn};
+use solana_sdk::signer::Signer;
+
+use super::position;
+use crate::config::Config;
+use crate::curve;
+use crate::journal::Journal;
+use crate::monitor::ExitRules;
+use crate::{wallet, Result};
+
+// Pick a held position back up, after a restart for instance: the entry is
+// the average price of its confirmed buys in the journal
+pub async fn run(config: &Config, mint: &str, rules: ExitRules) -> Result<()> {
+    if rules.is_empty() {
+        return Err(
+            "Nothing to monitor, give exit thresholds, a ladder or alerts with flags or the config file"
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+    let mint_pubkey = wallet::parse_mint(mint)?;
+    let sender = config.sender()?;
+
+    let mut holders = Vec::new();
+    for keypair in config.signers()? {
+        let (balance, _) =
+            wallet::token_balance(sender.client(), &keypair.pubkey(), &mint_pubkey).await?;
+        if balance > 0 {
+            holders.push(keypair);
+        }
+    }
+    if holders.is_empty() {
+        return Err(format!("No wallet holds {}", mint_pubkey)
+            .red()
+            .to_string()
+            .into());
+    }
+
+    let decimals = curve::mint_decimals(sender.client(), &mint_pubkey).await?;
+    let entry_price = match Journal::open(&config.journal_path)?.entry_price(&mint_pubkey)? {
+        Some(price) => price,
+        None => {
+            let curve = config
+                .venue()?
+                .curve(sender.client(), &mint_pubkey, decimals)
+                .await?;
+            warn!(
+                "No confirmed buy of this token in the journal, using the current price as entry"
+            );
+            curve.price_sol
+        }
+    };
+    info!(
+        "Monitoring the position of {} wallets",
+        holders.len().to_string().yellow()
+    );
+    position::manage(
+        config,
+        &sender,
+        &holders,
+        &mint_pubkey,
+        decimals,
+        entry_price,
+        &rules,
+    )
+    .await
+}
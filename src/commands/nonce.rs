@@ -0,0 +1,63 @@
+use colored::*;
+use log::info;
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signature::Signer};
+
+use crate::cli::NonceAction;
+use crate::config::Config;
+use crate::{nonce, Result};
+
+pub async fn run(config: &Config, action: &NonceAction) -> Result<()> {
+    let keypair = config.signer()?;
+    let client = config.rpc_client()?;
+    match action {
+        NonceAction::Create => {
+            let account = nonce::create(&client, &keypair).await?;
+            info!(
+                "Nonce account {} created for wallet {}",
+                account.to_string().bright_cyan(),
+                keypair.pubkey().to_string().bright_green()
+            );
+            info!("Set nonce_account (or --nonce-account) to sign trades against it");
+        }
+        NonceAction::Show => {
+            let account = configured(config)?;
+            let nonce = nonce::fetch(&client, &account).await?;
+            info!(
+                "Nonce account {} holds nonce {}, authority {}, {} SOL",
+                account.to_string().bright_cyan(),
+                nonce.blockhash.to_string().yellow(),
+                nonce.authority.to_string().bright_green(),
+                lamports_to_sol(nonce.lamports)
+            );
+        }
+        NonceAction::Advance => {
+            let account = configured(config)?;
+            let blockhash = nonce::advance(&client, &keypair, &account).await?;
+            info!(
+                "Nonce account {} advanced to {}",
+                account.to_string().bright_cyan(),
+                blockhash.to_string().yellow()
+            );
+        }
+        NonceAction::Close => {
+            let account = configured(config)?;
+            let lamports = nonce::close(&client, &keypair, &account).await?;
+            info!(
+                "Nonce account {} closed, {} SOL returned to {}",
+                account.to_string().bright_cyan(),
+                lamports_to_sol(lamports).to_string().yellow(),
+                keypair.pubkey().to_string().bright_green()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn configured(config: &Config) -> Result<Pubkey> {
+    config.nonce_account()?.ok_or_else(|| {
+        "A nonce account must be given with --nonce-account, NONCE_ACCOUNT or nonce_account in the config file"
+            .red()
+            .to_string()
+            .into()
+    })
+}
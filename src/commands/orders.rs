@@ -0,0 +1,189 @@
+use colored::*;
+use log::{error, info, warn};
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use super::buy::{self, BuyAmount, Prompt};
+use crate::cli::OrdersAction;
+use crate::config::Config;
+use crate::curve::{self, BondingCurve};
+use crate::journal::{Journal, LimitOrder};
+use crate::{shutdown, wallet, Result};
+
+pub async fn run(config: Arc<Config>, action: &OrdersAction) -> Result<()> {
+    let journal = Journal::open(&config.journal_path)?;
+    match action {
+        OrdersAction::Add {
+            mint,
+            amount,
+            below_price,
+            below_market_cap,
+        } => {
+            let mint = wallet::parse_mint(mint)?;
+            let amount = amount.or(config.buy_amount).ok_or_else(|| {
+                "A buy amount must be given with --amount or buy_amount in the config file"
+                    .red()
+                    .to_string()
+            })?;
+            if amount <= 0.0 {
+                return Err("The buy amount must be positive".red().to_string().into());
+            }
+            let id = journal.add_limit_order(
+                &mint,
+                sol_to_lamports(amount),
+                *below_price,
+                *below_market_cap,
+            )?;
+            info!(
+                "Order {} placed, run `orders watch` (or `daemon --orders`) to execute it",
+                format!("#{}", id).bright_cyan()
+            );
+        }
+        OrdersAction::List { all } => {
+            let orders = journal.limit_orders(*all)?;
+            if orders.is_empty() {
+                info!("No orders");
+            }
+            for order in &orders {
+                info!("{}", describe(order));
+            }
+        }
+        OrdersAction::Cancel { id } => {
+            if !journal.move_limit_order(*id, "open", "cancelled", None)? {
+                return Err(format!("Order #{} is not open", id)
+                    .red()
+                    .to_string()
+                    .into());
+            }
+            info!("Order #{} cancelled", id);
+        }
+        OrdersAction::Watch => watch(config).await?,
+    }
+    Ok(())
+}
+
+// Check the open orders against their curves every price poll and buy those
+// whose limit is crossed, until shut down
+pub async fn watch(config: Arc<Config>) -> Result<()> {
+    let journal = Journal::open(&config.journal_path)?;
+    let client = config.rpc_client()?;
+    let program_id = config.program_id()?;
+    let mut decimals = HashMap::new();
+    // Dry runs leave the orders open, remembering which ones they bought
+    let mut dry_filled = HashSet::new();
+    info!("Watching limit orders");
+    while !shutdown::requested() {
+        // Orders are re-read each round, picking up ones placed meanwhile
+        for order in journal.limit_orders(false)? {
+            if dry_filled.contains(&order.id) {
+                continue;
+            }
+            let mint = wallet::parse_mint(&order.mint)?;
+            let mint_decimals = match decimals.get(&mint) {
+                Some(decimals) => *decimals,
+                None => match curve::mint_decimals(&client, &mint).await {
+                    Ok(found) => *decimals.entry(mint).or_insert(found),
+                    Err(e) => {
+                        warn!("Order #{}: could not read the mint: {}", order.id, e);
+                        continue;
+                    }
+                },
+            };
+            let curve = match BondingCurve::fetch(&client, &program_id, &mint).await {
+                Ok(curve) => curve,
+                Err(e) => {
+                    warn!("Order #{}: price poll failed: {}", order.id, e);
+                    continue;
+                }
+            };
+            let price = curve.price_sol(mint_decimals);
+            let market_cap = curve.market_cap_sol(mint_decimals);
+            let triggered = order.below_price.is_some_and(|limit| price < limit)
+                || order
+                    .below_market_cap
+                    .is_some_and(|limit| market_cap < limit);
+            if !triggered || curve.complete {
+                continue;
+            }
+            // Claim the order so no other process fills it as well
+            if config.dry_run {
+                dry_filled.insert(order.id);
+            } else if !journal.move_limit_order(order.id, "open", "filling", None)? {
+                continue;
+            }
+            info!(
+                "{} #{} at {:.10} SOL (market cap {:.2} SOL)",
+                "Limit order triggered".green().bold(),
+                order.id,
+                price,
+                market_cap
+            );
+            let result = buy::run(
+                &config,
+                &order.mint,
+                Some(BuyAmount::Sol(lamports_to_sol(order.lamports))),
+                config.exit_rules.clone(),
+                None,
+                // An order is meant to buy, whether the token is held or not
+                Prompt {
+                    force: true,
+                    ..Prompt::default()
+                },
+            )
+            .await;
+            if config.dry_run {
+                continue;
+            }
+            match result {
+                Ok(()) => {
+                    journal.move_limit_order(order.id, "filling", "filled", None)?;
+                }
+                Err(e) => {
+                    error!("Limit order #{} failed: {}", order.id, e);
+                    journal.move_limit_order(
+                        order.id,
+                        "filling",
+                        "failed",
+                        Some(&e.to_string()),
+                    )?;
+                }
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(config.price_poll_interval) => {}
+            _ = shutdown::reached(shutdown::State::Draining) => {}
+        }
+    }
+    Ok(())
+}
+
+// `#3 open: buy 0.5 SOL of <mint> below 0.0000001 SOL, placed 2024-09-01...`
+fn describe(order: &LimitOrder) -> String {
+    let mut limits = Vec::new();
+    if let Some(price) = order.below_price {
+        limits.push(format!("price below {} SOL", price));
+    }
+    if let Some(market_cap) = order.below_market_cap {
+        limits.push(format!("market cap below {} SOL", market_cap));
+    }
+    let status = match order.status.as_str() {
+        "open" => order.status.green(),
+        "filled" => order.status.bright_blue(),
+        "failed" => order.status.red(),
+        _ => order.status.dimmed(),
+    };
+    let mut line = format!(
+        "{} {}: buy {} SOL of {} once the {}, placed {}",
+        format!("#{}", order.id).bright_cyan(),
+        status,
+        lamports_to_sol(order.lamports).to_string().yellow(),
+        order.mint,
+        limits.join(" or "),
+        order.created_at
+    );
+    if let Some(error) = &order.error {
+        line.push_str(&format!(" ({})", error));
+    }
+    line
+}
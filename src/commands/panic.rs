@@ -0,0 +1,125 @@
+use colored::*;
+use futures_util::future::join_all;
+use log::{error, info, warn};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+
+use super::buy;
+use crate::config::Config;
+use crate::curve::BondingCurve;
+use crate::journal::Journal;
+use crate::signer::WalletSigner;
+use crate::transaction::Sender;
+use crate::{wallet, Result};
+
+// A token some wallet holds, to be sold in full
+struct Position {
+    wallet: usize,
+    mint: Pubkey,
+}
+
+// Sell every degen.fund position of every wallet at once, paying at least
+// panic_unit_price for priority
+pub async fn run(config: &Config, unit_price: Option<u64>, confirm: bool) -> Result<()> {
+    let mut config = config.clone();
+    let floor = unit_price.unwrap_or(config.panic_unit_price);
+    config.compute_budget.unit_price =
+        Some(config.compute_budget.unit_price.unwrap_or(0).max(floor));
+    let sender = config.sender()?;
+    let signers = config.signers()?;
+    let positions = positions(&config, &sender, &signers).await?;
+    if positions.is_empty() {
+        info!("No degen.fund positions to sell");
+        return Ok(());
+    }
+    if confirm {
+        buy::ask_to_proceed(format!(
+            "You are about to sell all of {} position(s) across {} wallet(s). Proceed?",
+            positions.len(),
+            signers.len()
+        ))?;
+    }
+
+    let results = sell_all(&config, &sender, &signers, &positions).await;
+    let mut failed = 0;
+    for (position, result) in positions.iter().zip(results) {
+        let owner = signers[position.wallet].pubkey();
+        match result {
+            Ok(signature) => info!(
+                "{} {} from {}: {}",
+                "Sold".green(),
+                position.mint.to_string().bright_cyan(),
+                owner,
+                signature
+            ),
+            Err(e) => {
+                failed += 1;
+                error!(
+                    "Selling {} from {} failed: {}",
+                    position.mint.to_string().bright_cyan(),
+                    owner,
+                    e
+                );
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(
+            format!("{} of {} panic sells failed", failed, positions.len())
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+    info!("{}", "Every position sold".green().bold());
+    Ok(())
+}
+
+// Fire a 100% sell of each position concurrently, results in the same order
+async fn sell_all(
+    config: &Config,
+    sender: &Sender,
+    signers: &[WalletSigner],
+    positions: &[Position],
+) -> Vec<Result<Signature>> {
+    join_all(positions.iter().map(|position| {
+        let mint = position.mint.to_string();
+        async move {
+            super::sell::sell_with_wallet(config, sender, &signers[position.wallet], &mint, 100)
+                .await
+        }
+    }))
+    .await
+}
+
+// Held tokens with a degen.fund bonding curve, or bought through the journal
+// when no program_id is configured to look the curves up
+async fn positions(
+    config: &Config,
+    sender: &Sender,
+    signers: &[WalletSigner],
+) -> Result<Vec<Position>> {
+    let program_id = config.program_id().ok();
+    let journal = Journal::open(&config.journal_path)?;
+    if program_id.is_none() {
+        warn!("No program_id configured, only selling tokens the journal has buys for");
+    }
+    let mut positions = Vec::new();
+    for (index, keypair) in signers.iter().enumerate() {
+        let holdings = wallet::holdings(sender.client(), &keypair.pubkey()).await?;
+        for holding in holdings.into_iter().filter(|h| h.amount > 0) {
+            let is_position = match &program_id {
+                Some(program_id) => BondingCurve::fetch(sender.client(), program_id, &holding.mint)
+                    .await
+                    .is_ok(),
+                None => journal.has_bought(&holding.mint)?,
+            };
+            if is_position {
+                positions.push(Position {
+                    wallet: index,
+                    mint: holding.mint,
+                });
+            }
+        }
+    }
+    Ok(positions)
+}
@@ -0,0 +1,211 @@
+use colored::*;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::native_token::lamports_to_sol;
+
+use crate::config::Config;
+use crate::curve::{self, BondingCurve};
+use crate::journal::Journal;
+use crate::{price, wallet, Result};
+
+// Running average cost position of one mint
+#[derive(Default)]
+struct Position {
+    mint: String,
+    tokens: f64,
+    cost_sol: f64,
+    realized_sol: f64,
+}
+
+impl Position {
+    fn buy(&mut self, tokens: f64, cost_sol: f64) {
+        self.tokens += tokens;
+        self.cost_sol += cost_sol;
+    }
+
+    fn sell(&mut self, tokens: f64, proceeds_sol: f64) {
+        // Tokens bought outside the bot have no known cost
+        let sold = tokens.min(self.tokens);
+        let cost = if self.tokens > 0.0 {
+            self.cost_sol * sold / self.tokens
+        } else {
+            0.0
+        };
+        self.realized_sol += proceeds_sol - cost;
+        self.tokens -= sold;
+        self.cost_sol -= cost;
+    }
+}
+
+// Profit of one mint from the journal's fills, open tokens valued at the
+// current price when there is one
+pub struct MintPnl {
+    pub mint: String,
+    pub realized_sol: f64,
+    pub tokens: f64,
+    pub cost_sol: f64,
+    pub value_sol: Option<f64>,
+    pub moon_bag_pct: Option<f64>,
+}
+
+impl MintPnl {
+    pub fn unrealized_sol(&self) -> Option<f64> {
+        self.value_sol.map(|value| value - self.cost_sol)
+    }
+}
+
+pub async fn run(config: &Config) -> Result<()> {
+    let client = config.rpc_client()?;
+    let positions = compute(config, &client).await?;
+    if positions.is_empty() {
+        info!("No confirmed trades in {}", config.journal_path);
+        return Ok(());
+    }
+
+    let sol_usd = match price::sol_usd(config.price_oracle, &client).await {
+        Ok(price) => Some(price),
+        Err(e) => {
+            warn!("Could not fetch the SOL price, showing SOL only: {}", e);
+            None
+        }
+    };
+    let usd = |sol: f64| match sol_usd {
+        Some(price) => format!(" (${:.2})", sol * price),
+        None => String::new(),
+    };
+
+    let mut total_realized = 0.0;
+    let mut total_unrealized = 0.0;
+    for position in &positions {
+        total_realized += position.realized_sol;
+        info!("{}", position.mint.bright_cyan());
+        info!(
+            "  realized {}{}",
+            signed(position.realized_sol),
+            usd(position.realized_sol)
+        );
+        if position.tokens <= 0.0 {
+            continue;
+        }
+        let bag = match position.moon_bag_pct {
+            Some(pct) => format!(", a {}% moon bag", pct).yellow().to_string(),
+            None => String::new(),
+        };
+        match (position.value_sol, position.unrealized_sol()) {
+            (Some(value), Some(unrealized)) => {
+                total_unrealized += unrealized;
+                info!(
+                    "  holding {:.2} tokens{} worth {:.4} SOL, unrealized {}{}",
+                    position.tokens,
+                    bag,
+                    value,
+                    signed(unrealized),
+                    usd(unrealized)
+                );
+            }
+            _ => info!(
+                "  holding {:.2} tokens{}, no price available",
+                position.tokens, bag
+            ),
+        }
+    }
+
+    info!(
+        "Total realized {}{}, unrealized {}{}",
+        signed(total_realized),
+        usd(total_realized),
+        signed(total_unrealized),
+        usd(total_unrealized)
+    );
+    Ok(())
+}
+
+// Average cost positions of every mint in the journal
+pub async fn compute(config: &Config, client: &RpcClient) -> Result<Vec<MintPnl>> {
+    let journal = Journal::open(&config.journal_path)?;
+    let mut positions: Vec<Position> = Vec::new();
+    for trade in journal.filled_trades()? {
+        let index = match positions.iter().position(|p| p.mint == trade.mint) {
+            Some(index) => index,
+            None => {
+                positions.push(Position {
+                    mint: trade.mint.clone(),
+                    ..Position::default()
+                });
+                positions.len() - 1
+            }
+        };
+        let fee = lamports_to_sol(trade.fee_lamports);
+        match trade.side.as_str() {
+            "buy" => positions[index].buy(trade.token_amount, trade.sol_amount + fee),
+            _ => positions[index].sell(trade.token_amount, trade.sol_amount - fee),
+        }
+    }
+    if positions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let program_id = config.program_id().ok();
+    let market = match config.price_provider() {
+        Ok(market) => Some(market),
+        Err(e) => {
+            warn!("{}", e);
+            None
+        }
+    };
+    let mut pnl = Vec::new();
+    for position in positions {
+        let mut value_sol = None;
+        let mut moon_bag_pct = None;
+        if position.tokens > 0.0 {
+            moon_bag_pct = journal.moon_bag(&position.mint)?;
+
+            // Open positions are marked at the current curve price, or at the
+            // market price once the token left the curve
+            let mint = wallet::parse_mint(&position.mint)?;
+            let curve_value = match &program_id {
+                Some(program_id) => {
+                    match (
+                        curve::mint_decimals(client, &mint).await,
+                        BondingCurve::fetch(client, program_id, &mint).await,
+                    ) {
+                        (Ok(decimals), Ok(curve)) if !curve.complete => {
+                            Some(position.tokens * curve.price_sol(decimals))
+                        }
+                        _ => None,
+                    }
+                }
+                None => None,
+            };
+            value_sol = match (curve_value, &market) {
+                (Some(value), _) => Some(value),
+                (None, Some(market)) => match market.price_sol(&mint).await {
+                    Ok(price) => Some(position.tokens * price),
+                    Err(e) => {
+                        warn!("No {} price for {}: {}", market.name(), position.mint, e);
+                        None
+                    }
+                },
+                (None, None) => None,
+            };
+        }
+        pnl.push(MintPnl {
+            mint: position.mint,
+            realized_sol: position.realized_sol,
+            tokens: position.tokens,
+            cost_sol: position.cost_sol,
+            value_sol,
+            moon_bag_pct,
+        });
+    }
+    Ok(pnl)
+}
+
+fn signed(sol: f64) -> ColoredString {
+    let text = format!("{:+.4} SOL", sol);
+    if sol >= 0.0 {
+        text.green()
+    } else {
+        text.red()
+    }
+}
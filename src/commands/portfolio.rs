@@ -0,0 +1,106 @@
+use colored::*;
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey, signature::Signer};
+use std::collections::HashMap;
+
+use crate::config::Config;
+use crate::curve::BondingCurve;
+use crate::metadata::TokenMetadata;
+use crate::wallet::Holding;
+use crate::{price, wallet, Result};
+
+// Every token each wallet holds, with its metadata and estimated USD value
+pub async fn run(config: &Config) -> Result<()> {
+    let client = config.rpc_client()?;
+    let program_id = config.program_id().ok();
+    let sol_usd = match price::sol_usd(config.price_oracle, &client).await {
+        Ok(price) => Some(price),
+        Err(e) => {
+            warn!("Could not fetch the SOL price: {}", e);
+            None
+        }
+    };
+
+    let mut total_usd = 0.0;
+    for keypair in config.signers()? {
+        let owner = keypair.pubkey();
+        let lamports = client.get_balance(&owner).await?;
+        let sol = lamports_to_sol(lamports);
+        total_usd += sol * sol_usd.unwrap_or(0.0);
+        info!(
+            "Wallet {} holds {} SOL",
+            owner.to_string().bright_green(),
+            sol.to_string().yellow()
+        );
+
+        let holdings = wallet::holdings(&client, &owner).await?;
+        if holdings.is_empty() {
+            continue;
+        }
+        let mints: Vec<_> = holdings.iter().map(|holding| holding.mint).collect();
+        let metadata = TokenMetadata::fetch_many(&client, &mints).await?;
+        let prices = usd_prices(&client, program_id.as_ref(), sol_usd, &holdings).await;
+
+        for (holding, metadata) in holdings.iter().zip(metadata) {
+            let label = match &metadata {
+                Some(metadata) => format!("{} ({})", metadata.symbol, metadata.name),
+                None => "unknown token".to_string(),
+            };
+            let value = match prices.get(&holding.mint) {
+                Some(usd_price) => {
+                    let value = holding.ui_amount() * usd_price;
+                    total_usd += value;
+                    format!("~${:.2}", value).green().to_string()
+                }
+                None => "no price".dimmed().to_string(),
+            };
+            info!(
+                "  {} {} {}: {} {}",
+                label.bold(),
+                holding.mint.to_string().bright_cyan(),
+                "balance".dimmed(),
+                holding.ui_amount().to_string().yellow(),
+                value
+            );
+        }
+    }
+
+    match sol_usd {
+        Some(_) => info!("Estimated total {}", format!("${:.2}", total_usd).green()),
+        None => info!(
+            "Estimated total of priced tokens {}",
+            format!("${:.2}", total_usd).green()
+        ),
+    }
+    Ok(())
+}
+
+// USD price of each holding. Tokens still on a degen.fund curve are priced off
+// the curve, everything else from the market, and tokens without either are
+// left out.
+pub async fn usd_prices(
+    client: &RpcClient,
+    program_id: Option<&Pubkey>,
+    sol_usd: Option<f64>,
+    holdings: &[Holding],
+) -> HashMap<Pubkey, f64> {
+    let mints: Vec<_> = holdings.iter().map(|holding| holding.mint).collect();
+    let mut prices = match price::token_usd(&mints).await {
+        Ok(prices) => prices,
+        Err(e) => {
+            warn!("Could not fetch token prices: {}", e);
+            Default::default()
+        }
+    };
+    if let (Some(program_id), Some(sol_usd)) = (program_id, sol_usd) {
+        for holding in holdings {
+            if let Ok(curve) = BondingCurve::fetch(client, program_id, &holding.mint).await {
+                if !curve.complete {
+                    prices.insert(holding.mint, curve.price_sol(holding.decimals) * sol_usd);
+                }
+            }
+        }
+    }
+    prices
+}
@@ -0,0 +1,592 @@
+use colored::*;
+use log::{error, info, warn};
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use super::buy::buy_with_wallet;
+use crate::config::Config;
+use crate::events::{self, Update};
+use crate::journal::Journal;
+use crate::monitor::{
+    Alerts, BuyRung, Exit, ExitRules, MigrationAction, PriceWatch, SellRung, Thresholds, Tick,
+};
+use crate::notify::Event;
+use crate::signer::WalletSigner;
+use crate::transaction::Sender;
+use crate::{creator, metrics, wallet, Result};
+
+// How often the holders' balances are read to notice sells made by hand
+const BALANCE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+// Follow the price of a position from its entry, buying the ladder rungs it
+// falls to from every holder, and sell it all once an exit threshold is hit
+#[allow(clippy::too_many_arguments)]
+pub async fn manage(
+    config: &Config,
+    sender: &Sender,
+    holders: &[WalletSigner],
+    mint: &Pubkey,
+    decimals: u8,
+    entry_price: f64,
+    rules: &ExitRules,
+) -> Result<()> {
+    let _open = metrics::OpenPosition::open();
+    let (exit, ladder_sold_pct) =
+        match watch(config, sender, holders, mint, decimals, entry_price, rules).await? {
+            Some(found) => found,
+            None => return Ok(()),
+        };
+    sender
+        .notifier()
+        .notify(Event::Exit { mint: *mint, exit })
+        .await;
+    match exit {
+        Exit::TakeProfit(price) => {
+            info!("{} at {:.10} SOL", "Take profit hit".green().bold(), price)
+        }
+        Exit::StopLoss(price) => info!("{} at {:.10} SOL", "Stop loss hit".red().bold(), price),
+        Exit::TrailingStop(price) => {
+            info!("{} at {:.10} SOL", "Trailing stop hit".red().bold(), price)
+        }
+        Exit::CreatorSold(price) => {
+            info!("{} at {:.10} SOL", "Creator sold".red().bold(), price)
+        }
+        Exit::NearMigration(price) => {
+            info!(
+                "{} at {:.10} SOL",
+                "Curve near migration".yellow().bold(),
+                price
+            )
+        }
+        Exit::Migrated(price) => {
+            info!(
+                "{}, last curve price {:.10} SOL",
+                "Token migrated to Raydium".yellow().bold(),
+                price
+            );
+            if rules.on_migration == Some(MigrationAction::Alert) {
+                return Ok(());
+            }
+        }
+    }
+
+    // Profit exits leave the moon bag held, stop losses and migrations don't
+    let bag = match exit {
+        Exit::TakeProfit(_) | Exit::TrailingStop(_) => moon_bag(rules),
+        _ => 0.0,
+    };
+    let journal = open_journal(config)?;
+    let mint_address = mint.to_string();
+    let percent = if bag > 0.0 {
+        if journal.moon_bag(&mint_address)?.is_some() {
+            info!("Only the moon bag is left, keeping it");
+            return Ok(());
+        }
+        let Some(percent) = bag_exit_share(ladder_sold_pct, bag) else {
+            return Ok(());
+        };
+        info!(
+            "Keeping {}% of the position as a moon bag",
+            bag.to_string().yellow()
+        );
+        percent
+    } else {
+        100.0
+    };
+    for keypair in holders {
+        if let Err(e) =
+            super::sell::sell_portion(config, sender, keypair, &mint_address, percent).await
+        {
+            error!("Exit sell from {} failed: {}", keypair.pubkey(), e);
+        }
+    }
+    if bag > 0.0 {
+        journal.record_moon_bag(mint, bag)?;
+    }
+    Ok(())
+}
+
+// Sells of the token by its creator, None when they can't be watched
+async fn watch_creator(
+    config: &Config,
+    sender: &Sender,
+    mint: &Pubkey,
+) -> Option<mpsc::UnboundedReceiver<Signature>> {
+    let watched: Result<_> = async {
+        let program_id = config
+            .venue_program_id()
+            .ok_or("no program_id to find the launch with")?;
+        let launch = creator::find_launch(sender.client(), &program_id, mint).await?;
+        let sells = creator::watch_sells(config, &program_id, &launch).await?;
+        Ok((launch.creator, sells))
+    }
+    .await;
+    match watched {
+        Ok((creator, sells)) => {
+            info!(
+                "Watching creator {} for sells",
+                creator.to_string().bright_green()
+            );
+            Some(sells)
+        }
+        Err(e) => {
+            warn!("Not watching the creator for sells: {}", e);
+            None
+        }
+    }
+}
+
+// Percent of the position profit exits keep
+fn moon_bag(rules: &ExitRules) -> f64 {
+    rules.moon_bag_pct.unwrap_or(0.0).clamp(0.0, 100.0)
+}
+
+// The share of what is left an exit sells once `sold_pct` of the position is
+// gone, None when no more than the bag is left. The bag is a share of the
+// position as bought, kept out of what is left.
+fn bag_exit_share(sold_pct: f64, bag_pct: f64) -> Option<f64> {
+    let remaining = 100.0 - sold_pct;
+    if remaining <= bag_pct {
+        return None;
+    }
+    Some((remaining - bag_pct) / remaining * 100.0)
+}
+
+// Dry runs keep their fills in memory, like their trades out of the journal
+fn open_journal(config: &Config) -> Result<Journal> {
+    if config.dry_run {
+        Journal::open(":memory:")
+    } else {
+        Journal::open(&config.journal_path)
+    }
+}
+
+// Watch until an exit, returned with the percent of the position the sell
+// ladder sold, nothing being returned when the ladder was all that was left
+// to watch for
+#[allow(clippy::too_many_arguments)]
+async fn watch(
+    config: &Config,
+    sender: &Sender,
+    holders: &[WalletSigner],
+    mint: &Pubkey,
+    decimals: u8,
+    entry_price: f64,
+    rules: &ExitRules,
+) -> Result<Option<(Exit, f64)>> {
+    let mut thresholds = Thresholds::new(rules, entry_price);
+    let mut alerts = Alerts::new(rules, entry_price);
+    info!(
+        "Monitoring {} from entry {:.10} SOL (TP {}, SL {}, trailing {})",
+        mint.to_string().bright_cyan(),
+        entry_price,
+        thresholds
+            .take_profit
+            .map_or("-".to_string(), |p| format!("{:.10}", p))
+            .green(),
+        thresholds
+            .stop_loss
+            .map_or("-".to_string(), |p| format!("{:.10}", p))
+            .red(),
+        rules
+            .trailing_stop_pct
+            .map_or("-".to_string(), |pct| format!("{}%", pct))
+            .red()
+    );
+
+    let geyser = config.geyser();
+    let mut watch = PriceWatch::new(
+        sender,
+        geyser.as_ref(),
+        config.venue()?,
+        mint,
+        decimals,
+        entry_price,
+        rules.on_migration,
+        config.price_poll_interval,
+        config.ws_url().ok().as_deref(),
+        config.price_provider()?,
+    )
+    .await?;
+    let mut ladder = rules.buy_ladder.clone();
+    let mut sell_ladder = SellLadder::load(config, mint, rules)?;
+    let mut balances = Balances::read(sender, holders, mint).await?;
+    let mut creator_sells = match rules.creator_sell_pct {
+        Some(_) if sell_ladder.creator_sold => {
+            info!("The creator already sold and part of the position was sold with them");
+            None
+        }
+        Some(_) => watch_creator(config, sender, mint).await,
+        None => None,
+    };
+    loop {
+        let mut creator_sold = None;
+        let tick = match &mut creator_sells {
+            Some(sells) => tokio::select! {
+                tick = watch.next() => Some(tick?),
+                sold = sells.recv() => {
+                    creator_sold = Some(sold);
+                    None
+                }
+            },
+            None => Some(watch.next().await?),
+        };
+        if let Some(sold) = creator_sold {
+            // Only the first creator sell is acted on
+            creator_sells = None;
+            let Some(signature) = sold else {
+                warn!("Stopped watching the creator's wallet");
+                continue;
+            };
+            let pct = rules.creator_sell_pct.unwrap_or(100.0);
+            let exit = Exit::CreatorSold(watch.last_price());
+            warn!("{} in {}", "The creator sold".red().bold(), signature);
+            if pct >= 100.0 {
+                return Ok(Some((exit, sell_ladder.sold_pct)));
+            }
+            sender
+                .notifier()
+                .notify(Event::Exit { mint: *mint, exit })
+                .await;
+            info!("Selling {}% of the position", pct.to_string().yellow());
+            let mint_address = mint.to_string();
+            for keypair in holders {
+                if let Err(e) =
+                    super::sell::sell_portion(config, sender, keypair, &mint_address, pct).await
+                {
+                    error!("Creator sell exit from {} failed: {}", keypair.pubkey(), e);
+                }
+            }
+            sell_ladder.record_creator_sell(config, mint, pct)?;
+            balances = Balances::read(sender, holders, mint).await?;
+            continue;
+        }
+        let Some(tick) = tick else {
+            continue;
+        };
+        let price = match tick {
+            Tick::Price(price) => price,
+            Tick::Migrated(price) => {
+                return Ok(Some((Exit::Migrated(price), sell_ladder.sold_pct)))
+            }
+        };
+        events::publish(Update::Position {
+            mint: *mint,
+            price,
+            entry_price,
+        });
+        // A sell made by hand, here or elsewhere, cancels the exit orders
+        if balances.sold_elsewhere(sender, holders, mint).await {
+            if balances.is_closed() {
+                info!("The position was sold outside the monitor, no longer monitoring");
+                return Ok(None);
+            }
+            if thresholds.is_armed() {
+                warn!("Part of the position was sold outside the monitor, cancelling its take profit and stops");
+                thresholds.cancel();
+            }
+        }
+        for alert in alerts.check(price) {
+            let change_pct = (price / entry_price - 1.0) * 100.0;
+            info!(
+                "{} {:+.2}% from entry",
+                "Price alert:".cyan().bold(),
+                change_pct
+            );
+            sender
+                .notifier()
+                .notify(Event::Alert {
+                    mint: *mint,
+                    alert,
+                    price,
+                    change_pct,
+                })
+                .await;
+        }
+        if let Some(exit) = thresholds.check(price) {
+            return Ok(Some((exit, sell_ladder.sold_pct)));
+        }
+        if let (Some(limit), Some(progress)) = (rules.progress_exit_pct, watch.progress()) {
+            if progress >= limit {
+                info!("The curve is {:.1}% sold", progress);
+                return Ok(Some((Exit::NearMigration(price), sell_ladder.sold_pct)));
+            }
+        }
+
+        let (reached, left): (Vec<BuyRung>, Vec<BuyRung>) = ladder
+            .into_iter()
+            .partition(|rung| price <= entry_price * (1.0 - rung.drop_pct / 100.0));
+        ladder = left;
+        let traded = !reached.is_empty() || sell_ladder.reached(price, entry_price);
+        for rung in reached {
+            info!(
+                "{} -{}%, buying {} more SOL per wallet",
+                "Ladder rung hit at".cyan().bold(),
+                rung.drop_pct,
+                rung.amount.to_string().yellow()
+            );
+            for keypair in holders {
+                if let Err(e) =
+                    buy_with_wallet(config, sender, keypair, mint, rung.amount, None).await
+                {
+                    error!("Ladder buy from {} failed: {}", keypair.pubkey(), e);
+                }
+            }
+        }
+        if sell_ladder
+            .sell_reached(config, sender, holders, mint, price, entry_price)
+            .await?
+        {
+            info!("The sell ladder sold the position, no longer monitoring");
+            return Ok(None);
+        }
+        if traded {
+            balances = Balances::read(sender, holders, mint).await?;
+        }
+        let watches_exit = thresholds.is_armed()
+            || rules.on_migration.is_some()
+            || rules.progress_exit_pct.is_some()
+            || creator_sells.is_some()
+            || !alerts.is_empty();
+        if ladder.is_empty() && sell_ladder.rungs.is_empty() && !watches_exit {
+            info!("Every ladder rung was filled, no longer monitoring");
+            return Ok(None);
+        }
+    }
+}
+
+// The multiple a creator sell exit is journaled at among the sell ladder
+// fills, below any rung
+const CREATOR_SELL_MULTIPLE: f64 = 0.0;
+
+// The sell rungs left and the part of the position already sold by earlier
+// ones or the creator sell exit, which the journal keeps across restarts.
+// Rungs stop short of the moon bag.
+struct SellLadder {
+    journal: Option<Journal>,
+    rungs: Vec<SellRung>,
+    sold_pct: f64,
+    bag_pct: f64,
+    creator_sold: bool,
+}
+
+impl SellLadder {
+    fn load(config: &Config, mint: &Pubkey, rules: &ExitRules) -> Result<SellLadder> {
+        let bag_pct = moon_bag(rules);
+        if rules.sell_ladder.is_empty() && rules.creator_sell_pct.is_none() {
+            return Ok(SellLadder {
+                journal: None,
+                rungs: Vec::new(),
+                sold_pct: 0.0,
+                bag_pct,
+                creator_sold: false,
+            });
+        }
+        let journal = open_journal(config)?;
+        let fills = journal.sell_ladder_fills(mint)?;
+        let sold_pct = fills.iter().map(|(_, pct)| pct).sum::<f64>();
+        let creator_sold = fills
+            .iter()
+            .any(|(multiple, _)| *multiple == CREATOR_SELL_MULTIPLE);
+        let mut rungs: Vec<SellRung> = rules
+            .sell_ladder
+            .iter()
+            .filter(|rung| !fills.iter().any(|(multiple, _)| *multiple == rung.multiple))
+            .copied()
+            .collect();
+        rungs.sort_by(|a, b| a.multiple.total_cmp(&b.multiple));
+        if !fills.is_empty() {
+            info!(
+                "Sell ladder resumed with {}% of the position already sold",
+                sold_pct
+            );
+        }
+        // Without rungs the journal only keeps the creator sell exit
+        let journal = (!rules.sell_ladder.is_empty()).then_some(journal);
+        Ok(SellLadder {
+            journal,
+            rungs,
+            sold_pct,
+            bag_pct,
+            creator_sold,
+        })
+    }
+
+    // Count a creator sell exit of `pct` of what is left, as a share of the
+    // position as bought like the rungs
+    fn record_creator_sell(&mut self, config: &Config, mint: &Pubkey, pct: f64) -> Result<()> {
+        let sold = (100.0 - self.sold_pct) * pct / 100.0;
+        match &self.journal {
+            Some(journal) => journal.record_sell_ladder_fill(mint, CREATOR_SELL_MULTIPLE, sold)?,
+            None => {
+                open_journal(config)?.record_sell_ladder_fill(mint, CREATOR_SELL_MULTIPLE, sold)?
+            }
+        }
+        self.sold_pct += sold;
+        self.creator_sold = true;
+        Ok(())
+    }
+
+    fn reached(&self, price: f64, entry_price: f64) -> bool {
+        self.journal.is_some()
+            && self
+                .rungs
+                .first()
+                .is_some_and(|rung| price >= entry_price * rung.multiple)
+    }
+
+    // Sell the rungs the price reached, returning whether nothing but the moon
+    // bag is left
+    async fn sell_reached(
+        &mut self,
+        config: &Config,
+        sender: &Sender,
+        holders: &[WalletSigner],
+        mint: &Pubkey,
+        price: f64,
+        entry_price: f64,
+    ) -> Result<bool> {
+        let Some(journal) = &self.journal else {
+            return Ok(false);
+        };
+        while let Some(rung) = self.rungs.first().copied() {
+            if price < entry_price * rung.multiple {
+                break;
+            }
+            self.rungs.remove(0);
+            let Some((pct, share)) = rung_sale(rung.pct, self.sold_pct, self.bag_pct) else {
+                continue;
+            };
+            info!(
+                "{} {}x, selling {}% of the position",
+                "Sell rung hit at".green().bold(),
+                rung.multiple,
+                pct.to_string().yellow()
+            );
+            let mint_address = mint.to_string();
+            for keypair in holders {
+                if let Err(e) =
+                    super::sell::sell_portion(config, sender, keypair, &mint_address, share).await
+                {
+                    error!("Ladder sell from {} failed: {}", keypair.pubkey(), e);
+                }
+            }
+            journal.record_sell_ladder_fill(mint, rung.multiple, pct)?;
+            self.sold_pct += pct;
+        }
+        let done = self.sold_pct >= 100.0 - self.bag_pct;
+        if done && self.bag_pct > 0.0 {
+            journal.record_moon_bag(mint, self.bag_pct)?;
+        }
+        Ok(done)
+    }
+}
+
+// The percent of the position as bought a rung of `rung_pct` sells once
+// `sold_pct` of it is gone, and the share of what is left that makes. Rungs are
+// shares of the position as bought, sold out of what is left, and stop short of
+// the moon bag.
+fn rung_sale(rung_pct: f64, sold_pct: f64, bag_pct: f64) -> Option<(f64, f64)> {
+    let remaining = 100.0 - sold_pct;
+    let pct = rung_pct.min(remaining - bag_pct);
+    if pct <= 0.0 {
+        return None;
+    }
+    Some((pct, pct / remaining * 100.0))
+}
+
+// Token balances of the holders as the monitor last left them, so a sell made
+// by hand shows up as a drop
+struct Balances {
+    amounts: Vec<u64>,
+    checked_at: Instant,
+}
+
+impl Balances {
+    async fn read(sender: &Sender, holders: &[WalletSigner], mint: &Pubkey) -> Result<Balances> {
+        let mut amounts = Vec::new();
+        for keypair in holders {
+            let (amount, _) =
+                wallet::token_balance(sender.client(), &keypair.pubkey(), mint).await?;
+            amounts.push(amount);
+        }
+        Ok(Balances {
+            amounts,
+            checked_at: Instant::now(),
+        })
+    }
+
+    // Whether a balance dropped since the last read, checked every
+    // BALANCE_CHECK_INTERVAL. Failed reads count as no change.
+    async fn sold_elsewhere(
+        &mut self,
+        sender: &Sender,
+        holders: &[WalletSigner],
+        mint: &Pubkey,
+    ) -> bool {
+        if self.checked_at.elapsed() < BALANCE_CHECK_INTERVAL {
+            return false;
+        }
+        let current = match Balances::read(sender, holders, mint).await {
+            Ok(current) => current,
+            Err(e) => {
+                warn!("Could not check the position's balances: {}", e);
+                self.checked_at = Instant::now();
+                return false;
+            }
+        };
+        let dropped = current
+            .amounts
+            .iter()
+            .zip(&self.amounts)
+            .any(|(now, before)| now < before);
+        *self = current;
+        dropped
+    }
+
+    fn is_closed(&self) -> bool {
+        self.amounts.iter().all(|amount| *amount == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_rung_sells_its_share_of_the_whole_position() {
+        assert_eq!(rung_sale(25.0, 0.0, 0.0), Some((25.0, 25.0)));
+    }
+
+    #[test]
+    fn later_rungs_sell_their_share_out_of_what_is_left() {
+        // 25% of the position as bought is a third of the 75% left
+        let (pct, share) = rung_sale(25.0, 25.0, 0.0).unwrap();
+        assert_eq!(pct, 25.0);
+        assert!((share - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(rung_sale(50.0, 50.0, 0.0), Some((50.0, 100.0)));
+    }
+
+    #[test]
+    fn rung_past_what_is_left_sells_the_rest() {
+        assert_eq!(rung_sale(50.0, 75.0, 0.0), Some((25.0, 100.0)));
+        assert_eq!(rung_sale(25.0, 100.0, 0.0), None);
+    }
+
+    #[test]
+    fn rungs_stop_short_of_the_moon_bag() {
+        assert_eq!(rung_sale(50.0, 0.0, 20.0), Some((50.0, 50.0)));
+        assert_eq!(rung_sale(50.0, 50.0, 20.0), Some((30.0, 60.0)));
+        assert_eq!(rung_sale(50.0, 80.0, 20.0), None);
+    }
+
+    #[test]
+    fn exit_keeps_the_moon_bag_of_the_position_as_bought() {
+        assert_eq!(bag_exit_share(0.0, 20.0), Some(80.0));
+        // 20% of the position as bought is half of the 40% left
+        assert_eq!(bag_exit_share(60.0, 20.0), Some(50.0));
+        assert_eq!(bag_exit_share(80.0, 20.0), None);
+        assert_eq!(bag_exit_share(90.0, 20.0), None);
+    }
+}
@@ -0,0 +1,138 @@
+use colored::*;
+use log::info;
+use solana_sdk::signature::{Signature, Signer};
+use tracing::instrument;
+
+use crate::config::Config;
+use crate::prepare::{prepare_transaction, Trade};
+use crate::signer::WalletSigner;
+use crate::transaction::{new_spinner, report_landed, Sender};
+use crate::{source, wallet, Result};
+
+// How much of the held balance a sell takes
+#[derive(Clone, Copy)]
+pub enum SellAmount {
+    Percent(f64),
+    // Whole tokens, converted with the mint's decimals
+    Tokens(f64),
+}
+
+pub async fn run(config: &Config, mint: &str, amount: Option<SellAmount>) -> Result<()> {
+    let amount = amount.unwrap_or(SellAmount::Percent(config.sell_percent as f64));
+    let keypair = config.signer()?;
+    let sender = config.sender()?;
+    sell_amount(config, &sender, &keypair, mint, amount).await?;
+    Ok(())
+}
+
+// Sell `percent` of the wallet's balance of `mint`
+pub async fn sell_with_wallet(
+    config: &Config,
+    sender: &Sender,
+    keypair: &WalletSigner,
+    mint: &str,
+    percent: u8,
+) -> Result<Signature> {
+    sell_portion(config, sender, keypair, mint, percent as f64).await
+}
+
+// Sell a possibly fractional percentage of the wallet's balance
+pub async fn sell_portion(
+    config: &Config,
+    sender: &Sender,
+    keypair: &WalletSigner,
+    mint: &str,
+    percent: f64,
+) -> Result<Signature> {
+    sell_amount(config, sender, keypair, mint, SellAmount::Percent(percent)).await
+}
+
+// Sell a share or a number of tokens of the wallet's balance, worked out in
+// raw units from the token account
+#[instrument(name = "sell", skip_all, fields(mint = %mint, wallet = %keypair.pubkey()))]
+pub async fn sell_amount(
+    config: &Config,
+    sender: &Sender,
+    keypair: &WalletSigner,
+    mint: &str,
+    amount: SellAmount,
+) -> Result<Signature> {
+    let mint_pubkey = wallet::parse_mint(mint)?;
+    let seller = keypair.pubkey().to_string();
+
+    let (balance, decimals) =
+        wallet::token_balance(sender.client(), &keypair.pubkey(), &mint_pubkey).await?;
+    if balance == 0 {
+        return Err("Wallet holds none of this token".red().to_string().into());
+    }
+    let (sell_amount, shown) = match amount {
+        SellAmount::Percent(percent) if percent >= 100.0 => (balance, "100%".to_string()),
+        SellAmount::Percent(percent) => (
+            (balance as f64 * percent / 100.0) as u64,
+            format!("{}%", format!("{:.1}", percent).trim_end_matches(".0")),
+        ),
+        SellAmount::Tokens(tokens) => {
+            let raw = (tokens * 10f64.powi(decimals as i32)).round() as u64;
+            if raw > balance {
+                return Err(format!(
+                    "Wallet holds only {} tokens",
+                    balance as f64 / 10f64.powi(decimals as i32)
+                )
+                .red()
+                .to_string()
+                .into());
+            }
+            (raw, format!("{} tokens", tokens))
+        }
+    };
+    if sell_amount == 0 {
+        return Err("Nothing to sell, the amount rounds down to zero"
+            .red()
+            .to_string()
+            .into());
+    }
+
+    info!(
+        "Selling {} ({} raw units) of {} using wallet {}",
+        shown.yellow(),
+        sell_amount.to_string().yellow(),
+        mint_pubkey.to_string().bright_cyan(),
+        seller.bright_green()
+    );
+
+    let spinner = new_spinner("Preparing transaction...");
+    let mut tx = source::sell_transaction(
+        config,
+        sender.client(),
+        &keypair.pubkey(),
+        &mint_pubkey,
+        sell_amount,
+    )
+    .await?;
+    let trade = Trade::Sell {
+        mint: mint_pubkey,
+        token_amount: sell_amount,
+    };
+    prepare_transaction(config, sender.client(), &mut tx, keypair, &trade).await?;
+    spinner.finish_with_message("Transaction prepared successfully!".green().to_string());
+
+    if sender.is_dry_run() {
+        return Ok(sender.send_and_confirm(&tx, keypair, &trade).await?.0);
+    }
+    let spinner = new_spinner("Sending transaction...");
+    match sender.send_and_confirm(&tx, keypair, &trade).await {
+        Ok((signature, slot)) => {
+            spinner.finish_with_message(
+                format!("Transaction confirmed in slot {}", slot)
+                    .green()
+                    .to_string(),
+            );
+            report_landed(&signature);
+            Ok(signature)
+        }
+        Err(e) => {
+            spinner.finish_with_message("Transaction did not land".red().to_string());
+            Err(e)
+        }
+    }
+}
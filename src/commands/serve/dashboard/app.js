@@ -0,0 +1,169 @@
+// Polls the bot's API and renders positions, PnL and recent trades. The token
+// is kept in this browser only and sent with every call.
+const REFRESH_MS = 5000;
+
+let token = localStorage.getItem("token");
+
+async function call(method, path, body) {
+  const response = await fetch(path, {
+    method,
+    headers: {
+      "Authorization": `Bearer ${token}`,
+      "Content-Type": "application/json",
+    },
+    body: body && JSON.stringify(body),
+  });
+  const answer = await response.json();
+  if (response.status === 401) {
+    showLogin();
+  }
+  if (!response.ok) {
+    throw new Error(answer.error || response.statusText);
+  }
+  return answer;
+}
+
+function cell(row, text, className) {
+  const td = row.insertCell();
+  td.textContent = text;
+  if (className) {
+    td.className = className;
+  }
+  return td;
+}
+
+function short(text) {
+  return text.length > 12 ? `${text.slice(0, 4)}…${text.slice(-4)}` : text;
+}
+
+function sol(value) {
+  return value == null ? "-" : value.toFixed(4);
+}
+
+function signed(row, value) {
+  if (value == null) {
+    return cell(row, "-");
+  }
+  return cell(row, (value >= 0 ? "+" : "") + value.toFixed(4), value >= 0 ? "gain" : "loss");
+}
+
+async function sell(mint, percent) {
+  if (!confirm(`Sell ${percent}% of ${mint} from every wallet?`)) {
+    return;
+  }
+  try {
+    const answer = await call("POST", "/sell", { mint, percent });
+    const failed = answer.wallets.filter((wallet) => wallet.error);
+    alert(failed.length ? failed.map((wallet) => wallet.error).join("\n") : "Sold");
+  } catch (e) {
+    alert(`Sell failed: ${e.message}`);
+  }
+  refresh();
+}
+
+async function renderPositions() {
+  const { wallets } = await call("GET", "/positions");
+  const body = document.getElementById("positions");
+  body.replaceChildren();
+  for (const wallet of wallets) {
+    for (const holding of wallet.tokens) {
+      const row = body.insertRow();
+      cell(row, short(wallet.wallet));
+      cell(row, short(holding.mint)).title = holding.mint;
+      cell(row, holding.amount.toLocaleString());
+      cell(row, sol(holding.value_sol));
+      const actions = row.insertCell();
+      for (const percent of [25, 50, 100]) {
+        const button = document.createElement("button");
+        button.textContent = `Sell ${percent}%`;
+        button.onclick = () => sell(holding.mint, percent);
+        actions.append(button, " ");
+      }
+    }
+  }
+}
+
+async function renderPnl() {
+  const pnl = await call("GET", "/pnl");
+  document.getElementById("pnl-total").textContent =
+    `realized ${sol(pnl.realized_sol)} SOL, unrealized ${sol(pnl.unrealized_sol)} SOL`;
+  const body = document.getElementById("pnl");
+  body.replaceChildren();
+  for (const mint of pnl.mints) {
+    const row = body.insertRow();
+    cell(row, short(mint.mint)).title = mint.mint;
+    signed(row, mint.realized_sol);
+    cell(row, mint.tokens > 0 ? mint.tokens.toLocaleString() : "-");
+    cell(row, mint.tokens > 0 ? sol(mint.cost_sol) : "-");
+    cell(row, sol(mint.value_sol));
+    signed(row, mint.unrealized_sol);
+  }
+}
+
+async function renderTrades() {
+  const { trades } = await call("GET", "/trades");
+  const body = document.getElementById("trades");
+  body.replaceChildren();
+  for (const trade of trades) {
+    const row = body.insertRow();
+    cell(row, new Date(trade.time).toLocaleString());
+    cell(row, trade.side);
+    cell(row, short(trade.mint)).title = trade.mint;
+    cell(row, sol(trade.sol));
+    cell(row, trade.status, trade.status === "failed" ? "failed" : "").title = trade.error || "";
+    const link = document.createElement("a");
+    link.href = `https://solscan.io/tx/${trade.signature}`;
+    link.target = "_blank";
+    link.textContent = short(trade.signature);
+    row.insertCell().append(link);
+  }
+}
+
+async function refresh() {
+  const status = document.getElementById("status");
+  try {
+    await Promise.all([renderPositions(), renderPnl(), renderTrades()]);
+    status.textContent = `Updated ${new Date().toLocaleTimeString()}`;
+  } catch (e) {
+    status.textContent = e.message;
+  }
+}
+
+function showLogin() {
+  document.getElementById("dashboard").hidden = true;
+  document.getElementById("login").hidden = false;
+}
+
+document.getElementById("login").onsubmit = (event) => {
+  event.preventDefault();
+  token = document.getElementById("token").value;
+  localStorage.setItem("token", token);
+  document.getElementById("login").hidden = true;
+  document.getElementById("dashboard").hidden = false;
+  refresh();
+};
+
+document.getElementById("panic").onclick = async () => {
+  if (!confirm("Sell every position of every wallet now?")) {
+    return;
+  }
+  try {
+    await call("POST", "/panic");
+    alert("Every position sold");
+  } catch (e) {
+    alert(`Panic sell failed: ${e.message}`);
+  }
+  refresh();
+};
+
+if (token) {
+  document.getElementById("dashboard").hidden = false;
+  refresh();
+} else {
+  showLogin();
+}
+setInterval(() => {
+  if (!document.getElementById("dashboard").hidden) {
+    refresh();
+  }
+}, REFRESH_MS);
@@ -0,0 +1,434 @@
+use colored::*;
+use futures_util::{stream, Stream};
+use log::{info, warn};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::{empty_body, http, Body, BoxFuture, Context, Poll, Service, StdError};
+use tonic::server::{Grpc, NamedService};
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status};
+
+use super::{Api, ApiError};
+use crate::events::{self, Update};
+use crate::notify::Event;
+use crate::prepare::Trade;
+use crate::{shutdown, Result};
+
+// The messages of proto/control.proto, kept in sync by hand like the geyser ones
+mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Empty {}
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HealthReply {
+        #[prost(string, tag = "1")]
+        pub rpc: String,
+        #[prost(uint64, tag = "2")]
+        pub slot: u64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct BuyRequest {
+        #[prost(string, tag = "1")]
+        pub mint: String,
+        #[prost(double, optional, tag = "2")]
+        pub amount: Option<f64>,
+        #[prost(bool, tag = "3")]
+        pub force: bool,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SellRequest {
+        #[prost(string, tag = "1")]
+        pub mint: String,
+        #[prost(double, optional, tag = "2")]
+        pub percent: Option<f64>,
+        #[prost(double, optional, tag = "3")]
+        pub tokens: Option<f64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SellReply {
+        #[prost(message, repeated, tag = "1")]
+        pub wallets: Vec<WalletResult>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WalletResult {
+        #[prost(string, tag = "1")]
+        pub wallet: String,
+        #[prost(string, optional, tag = "2")]
+        pub signature: Option<String>,
+        #[prost(string, optional, tag = "3")]
+        pub error: Option<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PositionsReply {
+        #[prost(message, repeated, tag = "1")]
+        pub wallets: Vec<WalletPosition>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct WalletPosition {
+        #[prost(string, tag = "1")]
+        pub wallet: String,
+        #[prost(double, tag = "2")]
+        pub sol: f64,
+        #[prost(message, repeated, tag = "3")]
+        pub tokens: Vec<TokenPosition>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TokenPosition {
+        #[prost(string, tag = "1")]
+        pub mint: String,
+        #[prost(double, tag = "2")]
+        pub amount: f64,
+        #[prost(double, optional, tag = "3")]
+        pub value_sol: Option<f64>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Event {
+        #[prost(oneof = "event::Kind", tags = "1, 2, 3")]
+        pub event: Option<event::Kind>,
+    }
+
+    pub mod event {
+        #[derive(Clone, PartialEq, prost::Oneof)]
+        pub enum Kind {
+            #[prost(message, tag = "1")]
+            Trade(super::TradeEvent),
+            #[prost(message, tag = "2")]
+            Position(super::PositionEvent),
+            #[prost(message, tag = "3")]
+            Notice(super::NoticeEvent),
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TradeEvent {
+        #[prost(string, tag = "1")]
+        pub status: String,
+        #[prost(string, tag = "2")]
+        pub side: String,
+        #[prost(string, tag = "3")]
+        pub mint: String,
+        #[prost(uint64, tag = "4")]
+        pub amount: u64,
+        #[prost(string, tag = "5")]
+        pub signature: String,
+        #[prost(uint64, tag = "6")]
+        pub slot: u64,
+        #[prost(double, optional, tag = "7")]
+        pub price: Option<f64>,
+        #[prost(string, tag = "8")]
+        pub error: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct PositionEvent {
+        #[prost(string, tag = "1")]
+        pub mint: String,
+        #[prost(double, tag = "2")]
+        pub price: f64,
+        #[prost(double, tag = "3")]
+        pub entry_price: f64,
+        #[prost(double, tag = "4")]
+        pub change_pct: f64,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct NoticeEvent {
+        #[prost(string, tag = "1")]
+        pub mint: String,
+        #[prost(string, tag = "2")]
+        pub title: String,
+        #[prost(double, tag = "3")]
+        pub price: f64,
+    }
+}
+
+// Serve the degen.Control service until shutdown
+pub(super) async fn serve(api: Arc<Api>, addr: SocketAddr) -> Result<()> {
+    info!("Serving the gRPC API on {}", addr.to_string().bright_blue());
+    Server::builder()
+        .add_service(Control { api })
+        .serve_with_shutdown(addr, shutdown::reached(shutdown::State::Draining))
+        .await
+        .map_err(|e| {
+            format!("Could not serve gRPC on {}: {}", addr, e)
+                .red()
+                .to_string()
+        })?;
+    Ok(())
+}
+
+impl From<ApiError> for Status {
+    fn from(error: ApiError) -> Status {
+        let code = match error.0.as_u16() {
+            400 => Code::InvalidArgument,
+            401 => Code::Unauthenticated,
+            403 => Code::PermissionDenied,
+            422 => Code::FailedPrecondition,
+            502 | 503 => Code::Unavailable,
+            _ => Code::Internal,
+        };
+        Status::new(code, crate::notify::strip_ansi(&error.1))
+    }
+}
+
+fn authorize<T>(api: &Api, request: &Request<T>) -> std::result::Result<(), ApiError> {
+    let given = request
+        .metadata()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    api.authorize(given)
+}
+
+async fn health(api: Arc<Api>, _: Request<proto::Empty>) -> Answer<proto::HealthReply> {
+    let slot = api.health().await?;
+    Ok(Response::new(proto::HealthReply {
+        rpc: api.sender.rpc().endpoint(),
+        slot,
+    }))
+}
+
+async fn buy(api: Arc<Api>, request: Request<proto::BuyRequest>) -> Answer<proto::Empty> {
+    authorize(&api, &request)?;
+    let request = request.into_inner();
+    api.buy(&request.mint, request.amount, request.force)
+        .await?;
+    Ok(Response::new(proto::Empty {}))
+}
+
+async fn sell(api: Arc<Api>, request: Request<proto::SellRequest>) -> Answer<proto::SellReply> {
+    authorize(&api, &request)?;
+    let request = request.into_inner();
+    let results = api
+        .sell(&request.mint, request.percent, request.tokens)
+        .await?;
+    let wallets = results
+        .into_iter()
+        .map(|result| proto::WalletResult {
+            wallet: result.wallet,
+            signature: result.signature,
+            error: result.error,
+        })
+        .collect();
+    Ok(Response::new(proto::SellReply { wallets }))
+}
+
+async fn positions(api: Arc<Api>, request: Request<proto::Empty>) -> Answer<proto::PositionsReply> {
+    authorize(&api, &request)?;
+    let wallets = api
+        .positions()
+        .await?
+        .into_iter()
+        .map(|wallet| proto::WalletPosition {
+            wallet: wallet.wallet,
+            sol: wallet.sol,
+            tokens: wallet
+                .tokens
+                .into_iter()
+                .map(|token| proto::TokenPosition {
+                    mint: token.mint,
+                    amount: token.amount,
+                    value_sol: token.value_sol,
+                })
+                .collect(),
+        })
+        .collect();
+    Ok(Response::new(proto::PositionsReply { wallets }))
+}
+
+type EventStream = Pin<Box<dyn Stream<Item = std::result::Result<proto::Event, Status>> + Send>>;
+
+async fn stream_events(api: Arc<Api>, request: Request<proto::Empty>) -> Answer<EventStream> {
+    authorize(&api, &request)?;
+    let updates = events::subscribe();
+    let events = stream::unfold(updates, |mut updates| async move {
+        loop {
+            match updates.recv().await {
+                Ok(update) => return Some((Ok(event(&update)), updates)),
+                Err(RecvError::Lagged(missed)) => {
+                    warn!(
+                        "A gRPC event stream fell behind and missed {} events",
+                        missed
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(Response::new(Box::pin(events) as EventStream))
+}
+
+fn event(update: &Update) -> proto::Event {
+    use proto::event::Kind;
+    let kind = match update {
+        Update::Position {
+            mint,
+            price,
+            entry_price,
+        } => Kind::Position(proto::PositionEvent {
+            mint: mint.to_string(),
+            price: *price,
+            entry_price: *entry_price,
+            change_pct: (price / entry_price - 1.0) * 100.0,
+        }),
+        Update::Event(event) => match event.as_ref() {
+            Event::Sent { trade, signature } => Kind::Trade(proto::TradeEvent {
+                status: "sent".to_string(),
+                signature: signature.to_string(),
+                ..trade_event(trade)
+            }),
+            Event::Confirmed {
+                trade,
+                signature,
+                slot,
+                fill,
+            } => Kind::Trade(proto::TradeEvent {
+                status: "confirmed".to_string(),
+                signature: signature.to_string(),
+                slot: *slot,
+                price: fill.as_ref().and_then(|fill| fill.price()),
+                ..trade_event(trade)
+            }),
+            Event::Failed { trade, error } => Kind::Trade(proto::TradeEvent {
+                status: "failed".to_string(),
+                error: crate::notify::strip_ansi(error),
+                ..trade_event(trade)
+            }),
+            Event::Refused { trade, reason } => Kind::Trade(proto::TradeEvent {
+                status: "refused".to_string(),
+                error: crate::notify::strip_ansi(reason),
+                ..trade_event(trade)
+            }),
+            Event::Alert { mint, price, .. } => Kind::Notice(proto::NoticeEvent {
+                mint: mint.to_string(),
+                title: event.title(),
+                price: *price,
+            }),
+            Event::Exit { mint, exit } => Kind::Notice(proto::NoticeEvent {
+                mint: mint.to_string(),
+                title: event.title(),
+                price: exit.price(),
+            }),
+            Event::Paused(_) => Kind::Notice(proto::NoticeEvent {
+                title: event.text(),
+                ..Default::default()
+            }),
+        },
+    };
+    proto::Event { event: Some(kind) }
+}
+
+fn trade_event(trade: &Trade) -> proto::TradeEvent {
+    let (side, amount) = match trade {
+        Trade::Buy { lamports, .. } => ("buy", *lamports),
+        Trade::Sell { token_amount, .. } => ("sell", *token_amount),
+    };
+    proto::TradeEvent {
+        side: side.to_string(),
+        mint: trade.mint().to_string(),
+        amount,
+        ..Default::default()
+    }
+}
+
+type Answer<T> = std::result::Result<Response<T>, Status>;
+
+// Hand-written router in place of tonic codegen, dispatching on the method path
+#[derive(Clone)]
+struct Control {
+    api: Arc<Api>,
+}
+
+impl NamedService for Control {
+    const NAME: &'static str = "degen.Control";
+}
+
+// A method handler as the tower service tonic drives
+struct Method<F>(Arc<Api>, F);
+
+impl<F, Fut, Req, Res> Service<Request<Req>> for Method<F>
+where
+    F: Fn(Arc<Api>, Request<Req>) -> Fut,
+    Fut: Future<Output = Answer<Res>>,
+{
+    type Response = Response<Res>;
+    type Error = Status;
+    type Future = Fut;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<std::result::Result<(), Status>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Req>) -> Fut {
+        (self.1)(self.0.clone(), request)
+    }
+}
+
+impl<B> Service<http::Request<B>> for Control
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<std::result::Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let api = self.api.clone();
+        Box::pin(async move {
+            let response = match request.uri().path() {
+                "/degen.Control/Health" => {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    grpc.unary(Method(api, health), request).await
+                }
+                "/degen.Control/Buy" => {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    grpc.unary(Method(api, buy), request).await
+                }
+                "/degen.Control/Sell" => {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    grpc.unary(Method(api, sell), request).await
+                }
+                "/degen.Control/Positions" => {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    grpc.unary(Method(api, positions), request).await
+                }
+                "/degen.Control/StreamEvents" => {
+                    let mut grpc = Grpc::new(ProstCodec::default());
+                    grpc.server_streaming(Method(api, stream_events), request)
+                        .await
+                }
+                _ => {
+                    let mut response = http::Response::new(empty_body());
+                    let headers = response.headers_mut();
+                    headers.insert(Status::GRPC_STATUS, (Code::Unimplemented as i32).into());
+                    headers.insert(
+                        http::header::CONTENT_TYPE,
+                        tonic::metadata::GRPC_CONTENT_TYPE,
+                    );
+                    response
+                }
+            };
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,429 @@
+mod grpc;
+
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use colored::*;
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use solana_sdk::{native_token::lamports_to_sol, signature::Signer};
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use super::buy::{BuyAmount, Prompt};
+use super::sell::SellAmount;
+use crate::config::Config;
+use crate::curve::{BondingCurve, Global};
+use crate::journal::Journal;
+use crate::monitor::ExitRules;
+use crate::notify::strip_ansi;
+use crate::transaction::Sender;
+use crate::{shutdown, wallet, BotError, Result};
+
+// Trades the dashboard lists
+const RECENT_TRADES: usize = 50;
+
+// The dashboard's static assets
+const INDEX_HTML: &str = include_str!("dashboard/index.html");
+const APP_JS: &str = include_str!("dashboard/app.js");
+const STYLE_CSS: &str = include_str!("dashboard/style.css");
+
+// What the HTTP and gRPC routes share
+struct Api {
+    config: Arc<Config>,
+    sender: Sender,
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct BuyBody {
+    mint: String,
+    // SOL per wallet, buy_amount from the config when left out
+    amount: Option<f64>,
+    // Buy again from wallets that already bought the token
+    #[serde(default)]
+    force: bool,
+}
+
+#[derive(Deserialize)]
+struct SellBody {
+    mint: String,
+    // Either a percentage of each wallet's balance, 100 when both are left out
+    percent: Option<f64>,
+    // or whole tokens from each wallet
+    tokens: Option<f64>,
+}
+
+#[derive(Serialize)]
+struct WalletResult {
+    wallet: String,
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct WalletPosition {
+    wallet: String,
+    sol: f64,
+    tokens: Vec<TokenPosition>,
+}
+
+#[derive(Serialize)]
+struct TokenPosition {
+    mint: String,
+    amount: f64,
+    // What the balance would sell for on the curve, while it has one
+    value_sol: Option<f64>,
+}
+
+// An error answered as {"error": "..."} with its status
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn bad_request(message: impl ToString) -> ApiError {
+        ApiError(StatusCode::BAD_REQUEST, message.to_string())
+    }
+
+    fn internal(error: impl ToString) -> ApiError {
+        ApiError(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+    }
+}
+
+impl From<BotError> for ApiError {
+    fn from(error: BotError) -> ApiError {
+        let status = match &error {
+            // The bot is fine, what it talks to is not
+            BotError::Api { .. } | BotError::Rpc(_) => StatusCode::BAD_GATEWAY,
+            BotError::Policy(_) => StatusCode::FORBIDDEN,
+            BotError::InsufficientFunds { .. } | BotError::TransactionFailed { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            BotError::Signing(_) | BotError::Other(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError(status, error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": strip_ansi(&self.1) }))).into_response()
+    }
+}
+
+type Answer = std::result::Result<Json<Value>, ApiError>;
+
+// Serve the HTTP API, and the gRPC service when given an address, until
+// shutdown. Every route but health needs the token as `Authorization: Bearer
+// <token>`.
+pub async fn run(
+    config: Arc<Config>,
+    addr: SocketAddr,
+    grpc_addr: Option<SocketAddr>,
+    token: String,
+) -> Result<()> {
+    let api = api(config, token)?;
+    match grpc_addr {
+        Some(grpc_addr) => {
+            tokio::try_join!(serve_http(api.clone(), addr), grpc::serve(api, grpc_addr))?;
+        }
+        None => serve_http(api, addr).await?,
+    }
+    info!("API stopped");
+    Ok(())
+}
+
+// Serve the HTTP API with its web dashboard until shutdown, for the daemon
+pub async fn dashboard(config: Arc<Config>, addr: SocketAddr, token: String) -> Result<()> {
+    serve_http(api(config, token)?, addr).await
+}
+
+fn api(config: Arc<Config>, token: String) -> Result<Arc<Api>> {
+    if token.is_empty() {
+        return Err("The API token can't be empty".red().to_string().into());
+    }
+    Ok(Arc::new(Api {
+        sender: config.sender()?,
+        config,
+        token,
+    }))
+}
+
+async fn serve_http(api: Arc<Api>, addr: SocketAddr) -> Result<()> {
+    // The page and its assets hold nothing secret, the API calls they make
+    // carry the token
+    let app = Router::new()
+        .route("/", get(|| async { Html(INDEX_HTML) }))
+        .route(
+            "/app.js",
+            get(|| async { ([(header::CONTENT_TYPE, "text/javascript")], APP_JS) }),
+        )
+        .route(
+            "/style.css",
+            get(|| async { ([(header::CONTENT_TYPE, "text/css")], STYLE_CSS) }),
+        )
+        .route("/health", get(health))
+        .route("/buy", post(buy))
+        .route("/sell", post(sell))
+        .route("/panic", post(panic))
+        .route("/positions", get(positions))
+        .route("/trades", get(trades))
+        .route("/pnl", get(pnl))
+        .with_state(api);
+
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+        format!("Could not listen on {}: {}", addr, e)
+            .red()
+            .to_string()
+    })?;
+    info!(
+        "Serving the API on {}",
+        format!("http://{}", addr).bright_blue()
+    );
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown::reached(shutdown::State::Draining))
+        .await?;
+    Ok(())
+}
+
+fn bearer(headers: &HeaderMap) -> &str {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default()
+}
+
+impl Api {
+    fn authorize(&self, given: &str) -> std::result::Result<(), ApiError> {
+        // Compared in constant time so the token can't be guessed byte by byte
+        let matches = given.len() == self.token.len()
+            && given
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0;
+        if !matches {
+            return Err(ApiError(
+                StatusCode::UNAUTHORIZED,
+                "Missing or wrong API token".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Slot of the RPC in use
+    async fn health(&self) -> std::result::Result<u64, ApiError> {
+        self.sender.client().get_slot().await.map_err(|e| {
+            ApiError(
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("RPC unreachable: {}", e),
+            )
+        })
+    }
+
+    async fn buy(
+        &self,
+        mint: &str,
+        amount: Option<f64>,
+        force: bool,
+    ) -> std::result::Result<(), ApiError> {
+        check_mint(mint)?;
+        if amount.is_some_and(|amount| amount <= 0.0) {
+            return Err(ApiError::bad_request("The amount must be above 0"));
+        }
+        info!("API buy of {}", mint.bright_cyan());
+        super::buy::run(
+            &self.config,
+            mint,
+            amount.map(BuyAmount::Sol),
+            ExitRules::default(),
+            None,
+            Prompt {
+                force,
+                ..Prompt::default()
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    // Sell from every wallet, a percentage or whole tokens, all of it when
+    // neither is given
+    async fn sell(
+        &self,
+        mint: &str,
+        percent: Option<f64>,
+        tokens: Option<f64>,
+    ) -> std::result::Result<Vec<WalletResult>, ApiError> {
+        check_mint(mint)?;
+        let amount = match (percent, tokens) {
+            (Some(_), Some(_)) => {
+                return Err(ApiError::bad_request("Give either percent or tokens"));
+            }
+            (Some(percent), None) if percent <= 0.0 || percent > 100.0 => {
+                return Err(ApiError::bad_request(
+                    "The percent must be above 0 and at most 100",
+                ));
+            }
+            (Some(percent), None) => SellAmount::Percent(percent),
+            (None, Some(tokens)) if tokens <= 0.0 => {
+                return Err(ApiError::bad_request("The tokens must be above 0"));
+            }
+            (None, Some(tokens)) => SellAmount::Tokens(tokens),
+            (None, None) => SellAmount::Percent(100.0),
+        };
+        info!("API sell of {}", mint.bright_cyan());
+        let mut results = Vec::new();
+        for keypair in self.config.signers()? {
+            let result =
+                super::sell::sell_amount(&self.config, &self.sender, &keypair, mint, amount).await;
+            results.push(WalletResult {
+                wallet: keypair.pubkey().to_string(),
+                signature: result.as_ref().ok().map(|signature| signature.to_string()),
+                error: result.err().map(|e| strip_ansi(&e.to_string())),
+            });
+        }
+        Ok(results)
+    }
+
+    async fn positions(&self) -> std::result::Result<Vec<WalletPosition>, ApiError> {
+        let client = self.sender.client();
+        // Curve values are only given when the program is known
+        let program = match self.config.program_id() {
+            Ok(program_id) => Some((program_id, Global::fetch(client, &program_id).await?)),
+            Err(_) => None,
+        };
+
+        let mut wallets = Vec::new();
+        for keypair in self.config.signers()? {
+            let owner = keypair.pubkey();
+            let lamports = client
+                .get_balance(&owner)
+                .await
+                .map_err(ApiError::internal)?;
+            let mut tokens = Vec::new();
+            for holding in wallet::holdings(client, &owner).await? {
+                let mut value_sol = None;
+                if let Some((program_id, global)) = &program {
+                    if let Ok(curve) = BondingCurve::fetch(client, program_id, &holding.mint).await
+                    {
+                        let value = curve.sell_quote(holding.amount, global.fee_basis_points);
+                        value_sol = Some(lamports_to_sol(value));
+                    }
+                }
+                tokens.push(TokenPosition {
+                    mint: holding.mint.to_string(),
+                    amount: holding.ui_amount(),
+                    value_sol,
+                });
+            }
+            wallets.push(WalletPosition {
+                wallet: owner.to_string(),
+                sol: lamports_to_sol(lamports),
+                tokens,
+            });
+        }
+        Ok(wallets)
+    }
+}
+
+fn check_mint(mint: &str) -> std::result::Result<(), ApiError> {
+    wallet::parse_mint(mint).map_err(ApiError::bad_request)?;
+    Ok(())
+}
+
+async fn health(State(api): State<Arc<Api>>) -> Answer {
+    let slot = api.health().await?;
+    Ok(Json(json!({
+        "ok": true,
+        "rpc": api.sender.rpc().endpoint(),
+        "slot": slot,
+    })))
+}
+
+async fn buy(State(api): State<Arc<Api>>, headers: HeaderMap, Json(body): Json<BuyBody>) -> Answer {
+    api.authorize(bearer(&headers))?;
+    api.buy(&body.mint, body.amount, body.force).await?;
+    Ok(Json(json!({ "ok": true, "mint": body.mint })))
+}
+
+async fn sell(
+    State(api): State<Arc<Api>>,
+    headers: HeaderMap,
+    Json(body): Json<SellBody>,
+) -> Answer {
+    api.authorize(bearer(&headers))?;
+    let results = api.sell(&body.mint, body.percent, body.tokens).await?;
+    let ok = results.iter().any(|result| result.signature.is_some());
+    Ok(Json(
+        json!({ "ok": ok, "mint": body.mint, "wallets": results }),
+    ))
+}
+
+async fn positions(State(api): State<Arc<Api>>, headers: HeaderMap) -> Answer {
+    api.authorize(bearer(&headers))?;
+    Ok(Json(json!({ "wallets": api.positions().await? })))
+}
+
+async fn panic(State(api): State<Arc<Api>>, headers: HeaderMap) -> Answer {
+    api.authorize(bearer(&headers))?;
+    info!("API panic sell");
+    super::panic::run(&api.config, None, false).await?;
+    Ok(Json(json!({ "ok": true })))
+}
+
+async fn trades(State(api): State<Arc<Api>>, headers: HeaderMap) -> Answer {
+    api.authorize(bearer(&headers))?;
+    let trades: Vec<Value> = Journal::open(&api.config.journal_path)?
+        .recent_trades(RECENT_TRADES)?
+        .into_iter()
+        .map(|trade| {
+            json!({
+                "time": trade.updated_at,
+                "wallet": trade.wallet,
+                "mint": trade.mint,
+                "side": trade.side,
+                "amount": trade.amount,
+                "signature": trade.signature,
+                "status": trade.status,
+                "sol": trade.sol_amount,
+                "price": trade.price,
+                "error": trade.error.map(|e| strip_ansi(&e)),
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "trades": trades })))
+}
+
+async fn pnl(State(api): State<Arc<Api>>, headers: HeaderMap) -> Answer {
+    api.authorize(bearer(&headers))?;
+    let mints = super::pnl::compute(&api.config, api.sender.client()).await?;
+    // Folded from 0 as an empty sum of floats is -0
+    let realized = mints.iter().fold(0.0, |sum, mint| sum + mint.realized_sol);
+    let unrealized = mints
+        .iter()
+        .filter_map(|mint| mint.unrealized_sol())
+        .fold(0.0, |sum, pnl| sum + pnl);
+    let mints: Vec<Value> = mints
+        .iter()
+        .map(|mint| {
+            json!({
+                "mint": mint.mint,
+                "realized_sol": mint.realized_sol,
+                "tokens": mint.tokens,
+                "cost_sol": mint.cost_sol,
+                "value_sol": mint.value_sol,
+                "unrealized_sol": mint.unrealized_sol(),
+                "moon_bag_pct": mint.moon_bag_pct,
+            })
+        })
+        .collect();
+    Ok(Json(json!({
+        "realized_sol": realized,
+        "unrealized_sol": unrealized,
+        "mints": mints,
+    })))
+}
@@ -0,0 +1,173 @@
+use colored::*;
+use futures_util::{stream::LocalBoxStream, StreamExt};
+use log::{error, info, warn};
+use solana_client::{
+    nonblocking::pubsub_client::PubsubClient,
+    rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::task::JoinSet;
+
+use super::buy::{BuyAmount, Prompt};
+use crate::config::Config;
+use crate::creator::{self, Launch, CREATE_LOG};
+use crate::curve::{self, BondingCurve};
+use crate::monitor::ExitRules;
+use crate::transaction::{SendMode, Sender};
+use crate::{shutdown, Result};
+
+// Which launches to buy
+#[derive(Clone)]
+pub struct SnipeFilters {
+    /// Only buy launches from these creator wallets when non-empty
+    pub creators: Vec<Pubkey>,
+    /// Skip launches whose curve is already above this market cap (e.g. big dev buys)
+    pub max_market_cap_sol: Option<f64>,
+    /// Stop after this many buys
+    pub max_buys: Option<usize>,
+}
+
+pub async fn run(config: Arc<Config>, amount: Option<f64>, filters: SnipeFilters) -> Result<()> {
+    let program_id = config.program_id()?;
+    let sender = config.sender()?;
+
+    // Geyser transactions carry their account keys, log notifications need
+    // the transaction fetched to find the mint
+    let pubsub;
+    let mut _unsubscribe = None;
+    let mut launches: LocalBoxStream<Launch> = match config.geyser() {
+        Some(geyser) => {
+            let transactions = geyser.transactions(&program_id).await?;
+            info!(
+                "Watching {} for new launches over Geyser",
+                program_id.to_string().bright_cyan()
+            );
+            transactions
+                .filter_map(|update| async move {
+                    match update {
+                        Ok(update) if update.logs.iter().any(|log| log.starts_with(CREATE_LOG)) => {
+                            creator::launch_from_keys(&program_id, &update.account_keys)
+                        }
+                        Ok(_) => None,
+                        Err(e) => {
+                            warn!("Geyser stream error: {}", e);
+                            None
+                        }
+                    }
+                })
+                .boxed_local()
+        }
+        None => {
+            pubsub = PubsubClient::new(&config.ws_url()?).await?;
+            let (logs, unsubscribe) = pubsub
+                .logs_subscribe(
+                    RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+                    RpcTransactionLogsConfig {
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await?;
+            _unsubscribe = Some(unsubscribe);
+            info!(
+                "Watching {} for new launches",
+                program_id.to_string().bright_cyan()
+            );
+            let sender = &sender;
+            logs.filter_map(move |response| async move {
+                let response = response.value;
+                if response.err.is_some()
+                    || !response.logs.iter().any(|log| log.starts_with(CREATE_LOG))
+                {
+                    return None;
+                }
+                let signature = Signature::from_str(&response.signature).ok()?;
+                match creator::fetch_launch(sender.client(), &program_id, &signature).await {
+                    Ok(launch) => launch,
+                    Err(e) => {
+                        warn!("Could not inspect launch {}: {}", response.signature, e);
+                        None
+                    }
+                }
+            })
+            .boxed_local()
+        }
+    };
+
+    // Connections to the upcoming leaders stay open so a snipe sends at once
+    let warm_up = (config.send_mode == SendMode::Tpu && !config.dry_run).then(|| {
+        let tpu = config.tpu();
+        tokio::spawn(async move { tpu.keep_warm().await })
+    });
+
+    let mut buys = 0;
+    let mut tasks = JoinSet::new();
+    while let Some(launch) = tokio::select! {
+        launch = launches.next() => launch,
+        _ = shutdown::reached(shutdown::State::Draining) => None,
+    } {
+        info!(
+            "New launch {} by {}",
+            launch.mint.to_string().bright_cyan(),
+            launch.creator.to_string().bright_green()
+        );
+
+        if !filters.creators.is_empty() && !filters.creators.contains(&launch.creator) {
+            info!("Skipping, creator is not in the allowed list");
+            continue;
+        }
+        if let Some(max) = filters.max_market_cap_sol {
+            match market_cap(&sender, &program_id, &launch.mint).await {
+                Ok(market_cap) if market_cap > max => {
+                    info!(
+                        "Skipping, market cap {:.2} SOL is above {:.2}",
+                        market_cap, max
+                    );
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!("Skipping, could not read the curve: {}", e);
+                    continue;
+                }
+            }
+        }
+
+        let config = config.clone();
+        let mint = launch.mint.to_string();
+        tasks.spawn(async move {
+            if let Err(e) = super::buy::run(
+                &config,
+                &mint,
+                amount.map(BuyAmount::Sol),
+                ExitRules::default(),
+                None,
+                Prompt::default(),
+            )
+            .await
+            {
+                error!("Snipe of {} failed: {}", mint, e);
+            }
+        });
+
+        buys += 1;
+        if filters.max_buys.is_some_and(|max| buys >= max) {
+            info!("Reached the maximum of {} snipes", buys);
+            break;
+        }
+    }
+
+    // Let in-flight buys finish before exiting
+    while tasks.join_next().await.is_some() {}
+    if let Some(warm_up) = warm_up {
+        warm_up.abort();
+    }
+    Ok(())
+}
+
+async fn market_cap(sender: &Sender, program_id: &Pubkey, mint: &Pubkey) -> Result<f64> {
+    let decimals = curve::mint_decimals(sender.client(), mint).await?;
+    let curve = BondingCurve::fetch(sender.client(), program_id, mint).await?;
+    Ok(curve.market_cap_sol(decimals))
+}
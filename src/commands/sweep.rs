@@ -0,0 +1,183 @@
+use colored::*;
+use log::info;
+use solana_sdk::{
+    instruction::Instruction, native_token::lamports_to_sol, signature::Signer, system_instruction,
+};
+
+use super::buy;
+use crate::config::Config;
+use crate::signer::WalletSigner;
+use crate::transaction::send_instructions;
+use crate::wallet::{self, OwnedTokenAccount};
+use crate::Result;
+
+// Tokens moved per transaction: create, transfer and close take three
+// instructions each
+const TOKENS_PER_TX: usize = 5;
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+// What a sub-wallet gives back
+struct Sweep {
+    signer: WalletSigner,
+    tokens: Vec<OwnedTokenAccount>,
+    lamports: u64,
+}
+
+// Move every token and all but `keep` lamports of the sub-wallets to `to`, or
+// to the trading wallet when none is given. Token accounts are closed after
+// their transfer, so their rent comes back too.
+pub async fn run(config: &Config, to: Option<&str>, keep: u64, confirm: bool) -> Result<()> {
+    let destination = match to {
+        Some(address) => wallet::parse_pubkey(address)?,
+        None => config.signer()?.pubkey(),
+    };
+    if config.wallets.is_empty() {
+        return Err(
+            "No wallets to sweep, list them under wallets in the config file"
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+    let client = config.rpc_client()?;
+    // Any SOL left behind must keep the wallet rent exempt, or its transfer
+    // would fail once the tokens were already moved
+    let rent_exempt = client.get_minimum_balance_for_rent_exemption(0).await?;
+    if keep > 0 && keep < rent_exempt {
+        return Err(format!(
+            "The SOL left in each wallet must be 0 or at least the {} SOL that keeps it rent exempt",
+            lamports_to_sol(rent_exempt)
+        )
+        .red()
+        .to_string()
+        .into());
+    }
+
+    let mut sweeps = Vec::new();
+    for key in &config.wallets {
+        let signer: WalletSigner = wallet::load_keypair(Some(key))?.into();
+        if signer.pubkey() == destination {
+            continue;
+        }
+        let tokens = wallet::token_accounts(&client, &signer.pubkey()).await?;
+        let transactions = tokens.len().div_ceil(TOKENS_PER_TX) as u64 + 1;
+        // An estimate, before the closed accounts refund their rent
+        let balance = client.get_balance(&signer.pubkey()).await?;
+        let lamports = balance.saturating_sub(keep + transactions * SIGNATURE_FEE_LAMPORTS);
+        if tokens.is_empty() && lamports == 0 {
+            continue;
+        }
+        sweeps.push(Sweep {
+            signer,
+            tokens,
+            lamports,
+        });
+    }
+    if sweeps.is_empty() {
+        info!("Nothing to sweep");
+        return Ok(());
+    }
+
+    info!(
+        "Sweeping {} wallet(s) into {}, leaving {} SOL in each",
+        sweeps.len(),
+        destination.to_string().bright_green(),
+        lamports_to_sol(keep)
+    );
+    for sweep in &sweeps {
+        info!(
+            "  {} {} token(s), about {} SOL",
+            sweep.signer.pubkey().to_string().bright_cyan(),
+            sweep.tokens.len(),
+            lamports_to_sol(sweep.lamports)
+        );
+    }
+    if confirm {
+        buy::ask_to_proceed(format!(
+            "Sweep {} wallet(s) into {}?",
+            sweeps.len(),
+            destination
+        ))?;
+    }
+
+    for sweep in &sweeps {
+        let owner = sweep.signer.pubkey();
+        for batch in sweep.tokens.chunks(TOKENS_PER_TX) {
+            let instructions: Vec<Instruction> = batch
+                .iter()
+                .flat_map(|token| {
+                    let mut instructions = Vec::new();
+                    if token.amount > 0 {
+                        instructions.push(
+                            wallet::create_associated_token_account_idempotent_with_program(
+                                &owner,
+                                &destination,
+                                &token.mint,
+                                &token.token_program,
+                            ),
+                        );
+                        instructions.push(wallet::transfer_checked(
+                            &token.token_program,
+                            &token.address,
+                            &token.mint,
+                            &wallet::associated_token_address_with_program(
+                                &destination,
+                                &token.mint,
+                                &token.token_program,
+                            ),
+                            &owner,
+                            token.amount,
+                            token.decimals,
+                        ));
+                    }
+                    instructions.push(wallet::close_token_account(
+                        &token.token_program,
+                        &token.address,
+                        &owner,
+                        &owner,
+                    ));
+                    instructions
+                })
+                .collect();
+            match send_instructions(&client, &sweep.signer, &instructions, config.dry_run).await? {
+                Some(signature) => info!(
+                    "{} {} token(s) from {}: {}",
+                    "Swept".green(),
+                    batch.len(),
+                    owner,
+                    signature
+                ),
+                None => info!("Dry run, {} token(s) from {} simulated", batch.len(), owner),
+            }
+        }
+
+        // Read the balance again, the closed accounts refunded their rent and
+        // new destination accounts were paid for. A dry run changed nothing.
+        let lamports = if config.dry_run {
+            sweep.lamports
+        } else {
+            let balance = client.get_balance(&owner).await?;
+            balance.saturating_sub(keep + SIGNATURE_FEE_LAMPORTS)
+        };
+        if lamports == 0 {
+            continue;
+        }
+        let transfer = system_instruction::transfer(&owner, &destination, lamports);
+        match send_instructions(&client, &sweep.signer, &[transfer], config.dry_run).await? {
+            Some(signature) => info!(
+                "{} {} SOL from {}: {}",
+                "Swept".green(),
+                lamports_to_sol(lamports),
+                owner,
+                signature
+            ),
+            None => info!(
+                "Dry run, {} SOL from {} simulated",
+                lamports_to_sol(lamports),
+                owner
+            ),
+        }
+    }
+    info!("{}", "Every wallet swept".green().bold());
+    Ok(())
+}
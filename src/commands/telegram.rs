@@ -0,0 +1,237 @@
+use colored::*;
+use log::{error, info, warn};
+use solana_sdk::{native_token::lamports_to_sol, signature::Signer};
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use super::buy::{BuyAmount, Prompt};
+use crate::config::Config;
+use crate::curve::{BondingCurve, Global};
+use crate::monitor::ExitRules;
+use crate::notify::{strip_ansi, Telegram};
+use crate::transaction::Sender;
+use crate::{shutdown, wallet, Result};
+
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+// getUpdates holds the request open this long when there is nothing new
+const LONG_POLL_SECS: u64 = 30;
+const HELP: &str = "Commands:\n\
+    /buy <mint> <amount> - buy <amount> SOL of a token\n\
+    /sell <mint> <pct> - sell a percentage of a token from every wallet\n\
+    /positions - balances of every wallet\n\
+    /panic - sell everything every wallet holds";
+
+enum BotCommand {
+    Buy { mint: String, amount: f64 },
+    Sell { mint: String, percent: u8 },
+    Positions,
+    Panic,
+    Help,
+}
+
+impl BotCommand {
+    fn parse(text: &str) -> std::result::Result<BotCommand, String> {
+        let mut words = text.split_whitespace();
+        // Commands sent in groups carry the bot name, /buy@my_bot
+        let command = words
+            .next()
+            .unwrap_or_default()
+            .split('@')
+            .next()
+            .unwrap_or_default();
+        let args: Vec<&str> = words.collect();
+        match (command, args.as_slice()) {
+            ("/buy", [mint, amount]) => Ok(BotCommand::Buy {
+                mint: mint.to_string(),
+                amount: amount
+                    .parse()
+                    .ok()
+                    .filter(|amount: &f64| *amount > 0.0)
+                    .ok_or_else(|| format!("Invalid amount '{}'", amount))?,
+            }),
+            ("/sell", [mint, percent]) => Ok(BotCommand::Sell {
+                mint: mint.to_string(),
+                percent: percent
+                    .trim_end_matches('%')
+                    .parse()
+                    .ok()
+                    .filter(|percent| (1..=100).contains(percent))
+                    .ok_or_else(|| format!("Invalid percentage '{}'", percent))?,
+            }),
+            ("/positions", []) => Ok(BotCommand::Positions),
+            ("/panic", []) => Ok(BotCommand::Panic),
+            ("/start" | "/help", _) => Ok(BotCommand::Help),
+            _ => Err(format!("Unknown command\n{}", HELP)),
+        }
+    }
+}
+
+// Stay resident and execute commands sent from the configured Telegram chat
+pub async fn run(config: Arc<Config>) -> Result<()> {
+    let telegram = config.telegram()?.ok_or_else(|| {
+        "The Telegram bot needs telegram_bot_token and telegram_chat_id"
+            .red()
+            .to_string()
+    })?;
+    let sender = Arc::new(config.sender()?);
+
+    info!(
+        "Telegram bot running, accepting commands from chat {}",
+        telegram.chat_id().bright_cyan()
+    );
+    if let Err(e) = telegram.send(&format!("Bot is online\n{}", HELP)).await {
+        warn!("Could not greet the Telegram chat: {}", e);
+    }
+
+    // Commands sent while the bot was down are dropped, not replayed
+    let mut offset = match telegram.updates(-1, 0).await?.last() {
+        Some(update) => update.update_id + 1,
+        None => 0,
+    };
+    let mut tasks = JoinSet::new();
+    loop {
+        let updates = tokio::select! {
+            updates = telegram.updates(offset, LONG_POLL_SECS) => updates,
+            _ = shutdown::reached(shutdown::State::Draining) => break,
+        };
+        let updates = match updates {
+            Ok(updates) => updates,
+            Err(e) => {
+                warn!("Polling Telegram failed: {}", e);
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        for update in updates {
+            offset = update.update_id + 1;
+            let Some(message) = update.message else {
+                continue;
+            };
+            // Anyone can message a bot, only the owner's chat is obeyed
+            if message.chat.id.to_string() != telegram.chat_id() {
+                warn!("Ignoring Telegram message from chat {}", message.chat.id);
+                continue;
+            }
+            let Some(text) = message.text else {
+                continue;
+            };
+            info!("Telegram command: {}", text.bright_cyan());
+
+            let command = match BotCommand::parse(&text) {
+                Ok(command) => command,
+                Err(e) => {
+                    reply(&telegram, &e).await;
+                    continue;
+                }
+            };
+            let config = config.clone();
+            let sender = sender.clone();
+            let telegram = telegram.clone();
+            tasks.spawn(async move {
+                let answer = match execute(&config, &sender, command).await {
+                    Ok(answer) => answer,
+                    Err(e) => format!("Failed: {}", e),
+                };
+                reply(&telegram, &answer).await;
+            });
+        }
+
+        // Reap finished commands so the task set doesn't grow forever
+        while tasks.try_join_next().is_some() {}
+    }
+
+    // Let running commands finish before exiting
+    while tasks.join_next().await.is_some() {}
+    Ok(())
+}
+
+async fn reply(telegram: &Telegram, text: &str) {
+    if let Err(e) = telegram.send(&strip_ansi(text)).await {
+        error!("Telegram reply failed: {}", e);
+    }
+}
+
+async fn execute(config: &Config, sender: &Sender, command: BotCommand) -> Result<String> {
+    match command {
+        BotCommand::Buy { mint, amount } => {
+            super::buy::run(
+                config,
+                &mint,
+                Some(BuyAmount::Sol(amount)),
+                ExitRules::default(),
+                None,
+                Prompt::default(),
+            )
+            .await?;
+            Ok(format!("Bought {} SOL of {}", amount, mint))
+        }
+        BotCommand::Sell { mint, percent } => sell_everywhere(config, sender, &mint, percent).await,
+        BotCommand::Positions => positions(config, sender).await,
+        BotCommand::Panic => panic_sell(config, sender).await,
+        BotCommand::Help => Ok(HELP.to_string()),
+    }
+}
+
+async fn sell_everywhere(
+    config: &Config,
+    sender: &Sender,
+    mint: &str,
+    percent: u8,
+) -> Result<String> {
+    let mut answer = format!("Sell {}% of {}", percent, mint);
+    for keypair in config.signers()? {
+        let result = super::sell::sell_with_wallet(config, sender, &keypair, mint, percent).await;
+        write_result(&mut answer, &keypair.pubkey().to_string(), result);
+    }
+    Ok(answer)
+}
+
+async fn positions(config: &Config, sender: &Sender) -> Result<String> {
+    let client = sender.client();
+    // Curve values are only shown when the program is known
+    let program = match config.program_id() {
+        Ok(program_id) => Some((program_id, Global::fetch(client, &program_id).await?)),
+        Err(_) => None,
+    };
+
+    let mut answer = String::new();
+    for keypair in config.signers()? {
+        let owner = keypair.pubkey();
+        let lamports = client.get_balance(&owner).await?;
+        writeln!(answer, "{}: {} SOL", owner, lamports_to_sol(lamports))?;
+        for holding in wallet::holdings(client, &owner).await? {
+            write!(answer, "  {}: {}", holding.mint, holding.ui_amount())?;
+            if let Some((program_id, global)) = &program {
+                if let Ok(curve) = BondingCurve::fetch(client, program_id, &holding.mint).await {
+                    let value = curve.sell_quote(holding.amount, global.fee_basis_points);
+                    write!(answer, " (~{:.4} SOL)", lamports_to_sol(value))?;
+                }
+            }
+            answer.push('\n');
+        }
+    }
+    Ok(answer)
+}
+
+// Sell the whole balance of every token in every wallet
+async fn panic_sell(config: &Config, sender: &Sender) -> Result<String> {
+    let mut answer = "Panic sell".to_string();
+    for keypair in config.signers()? {
+        let owner = keypair.pubkey();
+        for holding in wallet::holdings(sender.client(), &owner).await? {
+            let mint = holding.mint.to_string();
+            let result = super::sell::sell_with_wallet(config, sender, &keypair, &mint, 100).await;
+            write_result(&mut answer, &format!("{} {}", owner, mint), result);
+        }
+    }
+    Ok(answer)
+}
+
+fn write_result<T: std::fmt::Display>(answer: &mut String, label: &str, result: Result<T>) {
+    match result {
+        Ok(signature) => answer.push_str(&format!("\n✔ {}: {}", label, signature)),
+        Err(e) => answer.push_str(&format!("\n✘ {}: {}", label, e)),
+    }
+}
@@ -0,0 +1,134 @@
+use colored::*;
+use log::{info, warn};
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    signature::Signer,
+    system_instruction,
+};
+
+use super::buy;
+use crate::config::Config;
+use crate::journal::Journal;
+use crate::transaction::send_instructions;
+use crate::{wallet, BotError, Result};
+
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+// Send `amount` SOL, or whole tokens of `mint`, from the trading wallet to
+// `to`, creating the recipient's token account when it has none yet. The
+// transfer is recorded in the journal.
+pub async fn run(
+    config: &Config,
+    to: &str,
+    amount: f64,
+    mint: Option<&str>,
+    confirm: bool,
+) -> Result<()> {
+    let signer = config.signer()?;
+    let client = config.rpc_client()?;
+    let owner = signer.pubkey();
+    let recipient = wallet::parse_pubkey(to)?;
+    if recipient == owner {
+        return Err("The recipient is the wallet itself"
+            .red()
+            .to_string()
+            .into());
+    }
+    let mint = mint.map(wallet::parse_mint).transpose()?;
+
+    let (instructions, raw_amount, label) = match mint {
+        None => {
+            let lamports = sol_to_lamports(amount);
+            let needed = lamports + SIGNATURE_FEE_LAMPORTS;
+            let available = client.get_balance(&owner).await?;
+            if needed > available {
+                return Err(BotError::InsufficientFunds {
+                    wallet: owner,
+                    needed,
+                    available,
+                });
+            }
+            (
+                vec![system_instruction::transfer(&owner, &recipient, lamports)],
+                lamports,
+                format!("{} SOL", lamports_to_sol(lamports)),
+            )
+        }
+        Some(mint) => {
+            // The fullest account of the mint, the associated one in practice
+            let source = wallet::token_accounts(&client, &owner)
+                .await?
+                .into_iter()
+                .filter(|account| account.mint == mint)
+                .max_by_key(|account| account.amount)
+                .ok_or_else(|| {
+                    format!("Wallet {} holds no {}", owner, mint)
+                        .red()
+                        .to_string()
+                })?;
+            let raw = (amount * 10f64.powi(source.decimals as i32)).round() as u64;
+            if raw == 0 || raw > source.amount {
+                return Err(format!(
+                    "Cannot send {} tokens, the wallet holds {}",
+                    amount,
+                    source.amount as f64 / 10f64.powi(source.decimals as i32)
+                )
+                .red()
+                .to_string()
+                .into());
+            }
+            let destination = wallet::associated_token_address_with_program(
+                &recipient,
+                &mint,
+                &source.token_program,
+            );
+            (
+                vec![
+                    wallet::create_associated_token_account_idempotent_with_program(
+                        &owner,
+                        &recipient,
+                        &mint,
+                        &source.token_program,
+                    ),
+                    wallet::transfer_checked(
+                        &source.token_program,
+                        &source.address,
+                        &mint,
+                        &destination,
+                        &owner,
+                        raw,
+                        source.decimals,
+                    ),
+                ],
+                raw,
+                format!("{} tokens of {}", amount, mint),
+            )
+        }
+    };
+
+    info!(
+        "Sending {} from {} to {}",
+        label.yellow(),
+        owner.to_string().bright_green(),
+        recipient.to_string().bright_cyan()
+    );
+    if confirm {
+        buy::ask_to_proceed(format!("Send {} to {}?", label, recipient))?;
+    }
+
+    let Some(signature) =
+        send_instructions(&client, &signer, &instructions, config.dry_run).await?
+    else {
+        info!("Dry run, transfer simulated and not sent");
+        return Ok(());
+    };
+    info!("{} {}: {}", "Sent".green(), label, signature);
+    // The transfer landed, a journal that can't be written doesn't undo it
+    let recorded = Journal::open(&config.journal_path).and_then(|journal| {
+        journal.record_transfer(&owner, &recipient, mint.as_ref(), raw_amount, &signature)
+    });
+    if let Err(e) = recorded {
+        warn!("Could not journal {}: {}", signature, e);
+    }
+    Ok(())
+}
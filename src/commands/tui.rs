@@ -0,0 +1,385 @@
+use log::info;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::Frame;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::orders;
+use crate::config::Config;
+use crate::curve::BondingCurve;
+use crate::journal::{Journal, LimitOrder, RecordedTrade};
+use crate::metadata::TokenMetadata;
+use crate::monitor::{PriceHistory, SPARKLINE_POINTS};
+use crate::notify::strip_ansi;
+use crate::{logging, shutdown, wallet, Result};
+
+// How often the panes are redrawn, logs and input included
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+// How often positions, orders and trades are read again
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+const INPUT_POLL: Duration = Duration::from_millis(100);
+const RECENT_TRADES: usize = 20;
+
+struct PositionRow {
+    wallet: Pubkey,
+    mint: Pubkey,
+    symbol: Option<String>,
+    tokens: f64,
+    price: Option<f64>,
+    entry_price: Option<f64>,
+    trend: String,
+}
+
+impl PositionRow {
+    fn change_pct(&self) -> Option<f64> {
+        match (self.price, self.entry_price) {
+            (Some(price), Some(entry)) if entry > 0.0 => Some((price / entry - 1.0) * 100.0),
+            _ => None,
+        }
+    }
+}
+
+// What the panes show besides the log tail, read every refresh
+#[derive(Default)]
+struct Snapshot {
+    positions: Vec<PositionRow>,
+    orders: Vec<LimitOrder>,
+    trades: Vec<RecordedTrade>,
+    updated_at: Option<String>,
+    error: Option<String>,
+}
+
+// A full screen view of the open positions with live prices, the pending
+// limit orders, the latest trades and the log, filling orders with `orders`
+pub async fn run(config: Arc<Config>, fill_orders: bool) -> Result<()> {
+    // Keystore passphrases are asked for before the screen is taken over
+    let wallets: Vec<Pubkey> = config
+        .signers()?
+        .iter()
+        .map(|signer| signer.pubkey())
+        .collect();
+    let journal = Journal::open(&config.journal_path)?;
+
+    let snapshot = RefCell::new(Snapshot::default());
+    let done = AtomicBool::new(false);
+    let mut terminal = ratatui::try_init()?;
+    logging::capture(true);
+    info!("Press q to quit");
+
+    let orders = async {
+        if fill_orders {
+            orders::watch(config.clone()).await
+        } else {
+            Ok(())
+        }
+    };
+    let refresh = async {
+        let mut symbols = HashMap::new();
+        let mut histories = HashMap::new();
+        while !done.load(Ordering::Relaxed) && !shutdown::requested() {
+            match refresh(&config, &journal, &wallets, &mut symbols, &mut histories).await {
+                Ok(read) => *snapshot.borrow_mut() = read,
+                Err(e) => snapshot.borrow_mut().error = Some(strip_ansi(&e.to_string())),
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+                _ = shutdown::reached(shutdown::State::Draining) => {}
+            }
+        }
+    };
+    let screen = async {
+        let (keys, mut pressed) = mpsc::unbounded_channel();
+        // Reading terminal events blocks, so it gets a thread of its own
+        let reader = tokio::task::spawn_blocking(move || {
+            while !keys.is_closed() {
+                match event::poll(INPUT_POLL) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(_) => return,
+                }
+                if let Ok(Event::Key(key)) = event::read() {
+                    if key.kind == KeyEventKind::Press && keys.send(key).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        let mut redraw = tokio::time::interval(REDRAW_INTERVAL);
+        let result = loop {
+            tokio::select! {
+                _ = redraw.tick() => {}
+                Some(key) = pressed.recv() => {
+                    let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                        || (key.code == KeyCode::Char('c')
+                            && key.modifiers.contains(KeyModifiers::CONTROL));
+                    if quit {
+                        // Raw mode turns Ctrl-C into a key, not a signal
+                        shutdown::request();
+                    }
+                }
+            }
+            if shutdown::requested() {
+                break Ok(());
+            }
+            if let Err(e) = terminal.draw(|frame| draw(frame, &snapshot.borrow())) {
+                break Err(e);
+            }
+        };
+        done.store(true, Ordering::Relaxed);
+        drop(pressed);
+        let _ = reader.await;
+        // Logs of orders still draining go back to the console
+        ratatui::restore();
+        logging::capture(false);
+        result
+    };
+
+    let (orders, (), screen) = tokio::join!(orders, refresh, screen);
+    screen?;
+    orders
+}
+
+async fn refresh(
+    config: &Config,
+    journal: &Journal,
+    wallets: &[Pubkey],
+    symbols: &mut HashMap<Pubkey, Option<String>>,
+    histories: &mut HashMap<Pubkey, PriceHistory>,
+) -> Result<Snapshot> {
+    let client = config.rpc_client()?;
+    let program_id = config.program_id().ok();
+    let market = config.price_provider()?;
+    // Priced once per mint however many wallets hold it
+    let mut prices = HashMap::new();
+
+    let mut positions = Vec::new();
+    for owner in wallets {
+        let holdings = wallet::holdings(&client, owner).await?;
+        let unknown: Vec<_> = holdings
+            .iter()
+            .map(|holding| holding.mint)
+            .filter(|mint| !symbols.contains_key(mint))
+            .collect();
+        if !unknown.is_empty() {
+            let metadata = TokenMetadata::fetch_many(&client, &unknown).await?;
+            for (mint, metadata) in unknown.into_iter().zip(metadata) {
+                symbols.insert(mint, metadata.map(|metadata| metadata.symbol));
+            }
+        }
+
+        for holding in holdings {
+            let price = match prices.get(&holding.mint) {
+                Some(price) => *price,
+                None => {
+                    // Priced off the curve until it completes, from the market after
+                    let mut price = None;
+                    if let Some(program_id) = &program_id {
+                        if let Ok(curve) =
+                            BondingCurve::fetch(&client, program_id, &holding.mint).await
+                        {
+                            if !curve.complete {
+                                price = Some(curve.price_sol(holding.decimals));
+                            }
+                        }
+                    }
+                    if price.is_none() {
+                        price = market.price_sol(&holding.mint).await.ok();
+                    }
+                    if let Some(price) = price {
+                        histories.entry(holding.mint).or_default().push(price);
+                    }
+                    *prices.entry(holding.mint).or_insert(price)
+                }
+            };
+            positions.push(PositionRow {
+                wallet: *owner,
+                mint: holding.mint,
+                symbol: symbols.get(&holding.mint).cloned().flatten(),
+                tokens: holding.ui_amount(),
+                price,
+                entry_price: journal.entry_price(&holding.mint)?,
+                trend: histories
+                    .get(&holding.mint)
+                    .map(PriceHistory::sparkline)
+                    .unwrap_or_default(),
+            });
+        }
+    }
+
+    Ok(Snapshot {
+        positions,
+        orders: journal.limit_orders(false)?,
+        trades: journal.recent_trades(RECENT_TRADES)?,
+        updated_at: Some(chrono::Local::now().format("%H:%M:%S").to_string()),
+        error: None,
+    })
+}
+
+fn draw(frame: &mut Frame, snapshot: &Snapshot) {
+    let [top, trades, logs] = Layout::vertical([
+        Constraint::Percentage(35),
+        Constraint::Percentage(30),
+        Constraint::Fill(1),
+    ])
+    .areas(frame.area());
+    let [positions, orders] =
+        Layout::horizontal([Constraint::Percentage(65), Constraint::Fill(1)]).areas(top);
+
+    let header = Style::new().add_modifier(Modifier::BOLD);
+    let mut title = " Positions ".to_string();
+    if let Some(updated_at) = &snapshot.updated_at {
+        title.push_str(&format!("(updated {}) ", updated_at));
+    }
+    let mut block = Block::bordered().title(title);
+    if let Some(error) = &snapshot.error {
+        block = block.title_bottom(Line::from(format!(" {} ", error)).red());
+    }
+    let rows = snapshot.positions.iter().map(|position| {
+        let change = match position.change_pct() {
+            Some(pct) if pct >= 0.0 => Line::from(format!("+{:.1}%", pct)).green(),
+            Some(pct) => Line::from(format!("{:.1}%", pct)).red(),
+            None => Line::from("-"),
+        };
+        Row::new(vec![
+            Line::from(short(&position.wallet.to_string())),
+            Line::from(
+                position
+                    .symbol
+                    .clone()
+                    .unwrap_or_else(|| short(&position.mint.to_string())),
+            ),
+            Line::from(format!("{:.2}", position.tokens)),
+            Line::from(price(position.price)),
+            Line::from(price(position.entry_price)),
+            change,
+            Line::from(position.trend.clone()),
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Fill(1),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Length(9),
+            Constraint::Length(SPARKLINE_POINTS as u16),
+        ],
+    )
+    .header(
+        Row::new([
+            "Wallet", "Token", "Amount", "Price", "Entry", "Change", "Trend",
+        ])
+        .style(header),
+    )
+    .block(block);
+    frame.render_widget(table, positions);
+
+    let rows = snapshot.orders.iter().map(|order| {
+        let trigger = match (order.below_price, order.below_market_cap) {
+            (Some(price), _) => format!("price < {}", price),
+            (_, Some(market_cap)) => format!("mcap < {} SOL", market_cap),
+            (None, None) => "-".to_string(),
+        };
+        Row::new(vec![
+            format!("#{}", order.id),
+            short(&order.mint),
+            format!("{} SOL", lamports_to_sol(order.lamports)),
+            trigger,
+        ])
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Length(11),
+            Constraint::Length(12),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(Row::new(["Id", "Token", "Amount", "Below"]).style(header))
+    .block(Block::bordered().title(" Pending orders "));
+    frame.render_widget(table, orders);
+
+    let rows = snapshot.trades.iter().map(|trade| {
+        let status = match trade.status.as_str() {
+            "confirmed" => Color::Green,
+            "failed" => Color::Red,
+            _ => Color::Yellow,
+        };
+        Row::new(vec![
+            trade.updated_at.clone(),
+            trade.side.clone(),
+            short(&trade.mint),
+            short(&trade.wallet),
+            trade
+                .sol_amount
+                .map(|sol| format!("{:.4} SOL", sol))
+                .unwrap_or_default(),
+            trade.status.clone(),
+            short(&trade.signature),
+        ])
+        .style(Style::new().fg(status))
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(19),
+            Constraint::Length(4),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new([
+            "Time",
+            "Side",
+            "Token",
+            "Wallet",
+            "SOL",
+            "Status",
+            "Signature",
+        ])
+        .style(header),
+    )
+    .block(Block::bordered().title(" Recent trades "));
+    frame.render_widget(table, trades);
+
+    // As many of the latest lines as fit inside the borders
+    let height = logs.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = logging::tail(height).into_iter().map(Line::from).collect();
+    let log = Paragraph::new(lines).block(Block::bordered().title(" Log (q to quit) "));
+    frame.render_widget(log, logs);
+}
+
+fn short(address: &str) -> String {
+    match (
+        address.get(..4),
+        address.get(address.len().saturating_sub(4)..),
+    ) {
+        (Some(start), Some(end)) if address.len() > 11 => format!("{}..{}", start, end),
+        _ => address.to_string(),
+    }
+}
+
+fn price(price: Option<f64>) -> String {
+    match price {
+        Some(price) => format!("{:.10}", price),
+        None => "-".to_string(),
+    }
+}
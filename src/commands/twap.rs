@@ -0,0 +1,159 @@
+use clap::ValueEnum;
+use colored::*;
+use log::{error, info};
+use rand::Rng;
+use std::time::Duration;
+
+use super::buy::{self, BuyAmount, Prompt};
+use super::sell;
+use crate::config::Config;
+use crate::monitor::ExitRules;
+use crate::{shutdown, wallet, Result};
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+// A large order split in child orders spread over `duration`
+pub struct TwapOrder {
+    pub side: Side,
+    // SOL to buy, or percent of the position to sell
+    pub size: f64,
+    pub slices: u32,
+    pub duration: Duration,
+    // How much a child order may differ from an even split, in percent
+    pub jitter_pct: f64,
+}
+
+// Work a buy or sell as randomized child orders at even intervals, keeping
+// each one small against the curve. A failed child is carried into the next.
+pub async fn run(config: &Config, mint: &str, order: TwapOrder, confirm: bool) -> Result<()> {
+    let mint_pubkey = wallet::parse_mint(mint)?;
+    if order.size <= 0.0 || order.slices == 0 {
+        return Err("The TWAP size and slices must be positive"
+            .red()
+            .to_string()
+            .into());
+    }
+    if order.side == Side::Sell && order.size > 100.0 {
+        return Err("Can't sell more than 100% of the position"
+            .red()
+            .to_string()
+            .into());
+    }
+    let interval = order.duration / order.slices;
+    let (what, unit) = match order.side {
+        Side::Buy => ("buy", " SOL"),
+        Side::Sell => ("sell", "% of the position"),
+    };
+    if confirm {
+        buy::ask_to_proceed(format!(
+            "You are about to {} {}{} of {} in {} orders over {}s. Proceed?",
+            what,
+            order.size,
+            unit,
+            mint_pubkey,
+            order.slices,
+            order.duration.as_secs()
+        ))?;
+    }
+
+    let shares = shares(order.slices, order.jitter_pct);
+    let keypair = config.signer()?;
+    let sender = config.sender()?;
+    // Share of the starting position still held, sells are sized against it
+    let mut held = 100.0;
+    let mut carry = 0.0;
+    for (i, share) in shares.iter().enumerate() {
+        if i > 0 {
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = shutdown::reached(shutdown::State::Draining) => {}
+            }
+        }
+        if shutdown::requested() {
+            return Err(format!(
+                "Shutting down with {} of {} TWAP orders done",
+                i, order.slices
+            )
+            .yellow()
+            .to_string()
+            .into());
+        }
+
+        let size = order.size * share + carry;
+        info!(
+            "TWAP order {} of {}: {} {}{}",
+            (i + 1).to_string().yellow(),
+            order.slices,
+            what,
+            format!("{:.4}", size)
+                .trim_end_matches('0')
+                .trim_end_matches('.'),
+            unit
+        );
+        let result = match order.side {
+            Side::Buy => {
+                buy::run(
+                    config,
+                    mint,
+                    Some(BuyAmount::Sol(size)),
+                    ExitRules::default(),
+                    None,
+                    // Every slice buys the token again
+                    Prompt {
+                        force: true,
+                        ..Prompt::default()
+                    },
+                )
+                .await
+            }
+            Side::Sell => {
+                // The last order of a full exit sells whatever is left
+                let percent = if i + 1 == shares.len() && order.size >= 100.0 {
+                    100.0
+                } else {
+                    (size / held * 100.0).min(100.0)
+                };
+                sell::sell_portion(config, &sender, &keypair, mint, percent)
+                    .await
+                    .map(|_| ())
+            }
+        };
+        match result {
+            Ok(()) => {
+                held -= size;
+                carry = 0.0;
+            }
+            Err(e) => {
+                error!("TWAP order failed, carried into the next one: {}", e);
+                carry = size;
+            }
+        }
+    }
+    if carry > 0.0 {
+        return Err(format!(
+            "The last TWAP order failed, {:.4}{} left to {}",
+            carry, unit, what
+        )
+        .red()
+        .to_string()
+        .into());
+    }
+    info!("{} {} orders done", "TWAP done,".green(), order.slices);
+    Ok(())
+}
+
+// Random fractions of the order summing to one, each within `jitter_pct` of
+// an even split
+pub fn shares(slices: u32, jitter_pct: f64) -> Vec<f64> {
+    let jitter = (jitter_pct / 100.0).clamp(0.0, 0.99);
+    let mut rng = rand::thread_rng();
+    let weights: Vec<f64> = (0..slices)
+        .map(|_| 1.0 + rng.gen_range(-jitter..=jitter))
+        .collect();
+    let total: f64 = weights.iter().sum();
+    weights.iter().map(|w| w / total).collect()
+}
@@ -0,0 +1,95 @@
+use colored::*;
+use log::info;
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    signature::Signer,
+};
+
+use crate::config::Config;
+use crate::jupiter::WSOL_MINT;
+use crate::transaction::send_instructions;
+use crate::{wallet, BotError, Result};
+
+const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+
+// Move `sol` of the wallet's SOL into its wrapped SOL account, creating the
+// account when it doesn't exist
+pub async fn wrap(config: &Config, sol: f64) -> Result<()> {
+    let signer = config.signer()?;
+    let client = config.rpc_client()?;
+    let owner = signer.pubkey();
+    let account = wallet::associated_token_address(&owner, &WSOL_MINT);
+    let lamports = sol_to_lamports(sol);
+
+    let existing = client
+        .get_account_with_commitment(&account, client.commitment())
+        .await?
+        .value;
+    let rent = match existing {
+        Some(_) => 0,
+        None => {
+            client
+                .get_minimum_balance_for_rent_exemption(wallet::TOKEN_ACCOUNT_LEN)
+                .await?
+        }
+    };
+    let needed = lamports + rent + SIGNATURE_FEE_LAMPORTS;
+    let available = client.get_balance(&owner).await?;
+    if needed > available {
+        return Err(BotError::InsufficientFunds {
+            wallet: owner,
+            needed,
+            available,
+        });
+    }
+
+    let instructions = wallet::wrap_sol(&owner, lamports);
+    match send_instructions(&client, &signer, &instructions, config.dry_run).await? {
+        Some(signature) => info!(
+            "{} {} SOL into {}: {}",
+            "Wrapped".green(),
+            lamports_to_sol(lamports).to_string().yellow(),
+            account,
+            signature
+        ),
+        None => info!(
+            "Dry run, wrapping {} SOL simulated",
+            lamports_to_sol(lamports)
+        ),
+    }
+    Ok(())
+}
+
+// Close the wallet's wrapped SOL account, its whole balance and rent coming
+// back as SOL
+pub async fn unwrap(config: &Config) -> Result<()> {
+    let signer = config.signer()?;
+    let client = config.rpc_client()?;
+    let owner = signer.pubkey();
+    let account = wallet::associated_token_address(&owner, &WSOL_MINT);
+    let Some(wrapped) = client
+        .get_account_with_commitment(&account, client.commitment())
+        .await?
+        .value
+    else {
+        return Err(format!("Wallet {} has no wrapped SOL", owner)
+            .red()
+            .to_string()
+            .into());
+    };
+
+    let close = wallet::close_token_account(&wallet::TOKEN_PROGRAM_ID, &account, &owner, &owner);
+    match send_instructions(&client, &signer, &[close], config.dry_run).await? {
+        Some(signature) => info!(
+            "{} {} SOL: {}",
+            "Unwrapped".green(),
+            lamports_to_sol(wrapped.lamports).to_string().yellow(),
+            signature
+        ),
+        None => info!(
+            "Dry run, unwrapping {} SOL simulated",
+            lamports_to_sol(wrapped.lamports)
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,790 @@
+use clap::Parser;
+use colored::*;
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig, native_token::sol_to_lamports, pubkey::Pubkey,
+    signature::Keypair,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::breaker::{CircuitBreaker, DEFAULT_BREAKER_COOLOFF, DEFAULT_BREAKER_WINDOW};
+use crate::cli::Cli;
+use crate::fees::FeeOracle;
+use crate::geyser::Geyser;
+use crate::holders::DEFAULT_TOP_HOLDERS;
+use crate::jito::{DynamicTip, JitoClient, DEFAULT_BLOCK_ENGINE_URL, DEFAULT_TIP_CAP_LAMPORTS};
+use crate::journal::{Journal, DEFAULT_JOURNAL_PATH};
+use crate::keychain;
+use crate::keystore::{self, Keystore};
+use crate::kill_switch::KillSwitch;
+use crate::lock;
+use crate::logging::{LogFileConfig, DEFAULT_LOG_KEEP, DEFAULT_LOG_MAX_MB};
+use crate::market::{Birdeye, DexScreener, PriceProvider, PriceProviderKind, Raydium};
+use crate::monitor::{BuyRung, ExitRules, MigrationAction, SellRung};
+use crate::notify::{Discord, Notifier, Telegram};
+use crate::price::PriceOracle;
+use crate::risk::RiskLimits;
+use crate::rpc::RpcPool;
+use crate::rugcheck::RugCheckMode;
+use crate::schedule::DEFAULT_NTP_SERVER;
+use crate::shutdown::DEFAULT_SHUTDOWN_GRACE;
+use crate::signer::{Ledger, WalletSigner};
+use crate::simulate::DEFAULT_DEBIT_TOLERANCE_LAMPORTS;
+use crate::slots::SlotClock;
+use crate::source::TxSource;
+use crate::tpu::TpuSender;
+use crate::transaction::{Commitment, ComputeBudget, FeeEscalation, SendMode, SendOptions, Sender};
+use crate::venue::{
+    CurveVenue, Moonshot, Venue, VenueKind, MOONSHOT_PROGRAM_ID, PUMP_FUN_PROGRAM_ID,
+};
+use crate::{wallet, Result};
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+pub const DEFAULT_KEYRING_ENTRY: &str = "default";
+const DEFAULT_JITO_TIP_LAMPORTS: u64 = 10_000;
+const DEFAULT_PRICE_POLL_MS: u64 = 2_000;
+const DEFAULT_RPC_TIMEOUT_MS: u64 = 10_000;
+const DEFAULT_TPU_FANOUT_SLOTS: u64 = 12;
+const DEFAULT_REBROADCAST_SLOTS: u64 = 2;
+// SOL left in each wallet by `buy --amount-pct`
+const DEFAULT_SOL_RESERVE: f64 = 0.01;
+const DEFAULT_SWEEP_KEEP_SOL: f64 = 0.002;
+const DEFAULT_PRIORITY_FEE_CAP: u64 = 1_000_000;
+const DEFAULT_PRIORITY_FEE_RETRY_MS: u64 = 5_000;
+const DEFAULT_PANIC_UNIT_PRICE: u64 = 1_000_000;
+
+// Settings read from config.toml, every key is optional
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileConfig {
+    pub rpc_url: Option<String>,
+    pub rpc_urls: Vec<String>,
+    pub rpc_timeout_ms: Option<u64>,
+    pub private_key: Option<String>,
+    pub keypair_path: Option<String>,
+    pub mnemonic: Option<String>,
+    pub derivation_path: Option<String>,
+    pub keystore_path: Option<String>,
+    pub keyring_entry: Option<String>,
+    pub ledger: Option<String>,
+    pub wallets: Vec<String>,
+    pub buy_amount: Option<f64>,
+    pub sol_reserve: Option<f64>,
+    pub sell_percent: Option<u8>,
+    pub send_mode: Option<SendMode>,
+    pub skip_preflight: Option<bool>,
+    pub preflight_commitment: Option<Commitment>,
+    pub confirm_commitment: Option<Commitment>,
+    pub jito_tip_lamports: Option<u64>,
+    pub jito_tip_percentile: Option<u8>,
+    pub jito_tip_cap_lamports: Option<u64>,
+    pub debit_tolerance_lamports: Option<u64>,
+    pub jito_block_engine_url: Option<String>,
+    pub compute_unit_price: Option<u64>,
+    pub compute_unit_limit: Option<u32>,
+    pub panic_unit_price: Option<u64>,
+    pub priority_fee_percentile: Option<u8>,
+    pub fee_oracle: Option<FeeOracle>,
+    pub helius_url: Option<String>,
+    pub price_oracle: Option<PriceOracle>,
+    pub price_provider: Option<PriceProviderKind>,
+    pub birdeye_api_key: Option<String>,
+    pub log_file: Option<String>,
+    pub otlp_endpoint: Option<String>,
+    pub log_max_mb: Option<u64>,
+    pub log_daily: Option<bool>,
+    pub log_keep: Option<usize>,
+    pub priority_fee_step: Option<u64>,
+    pub priority_fee_cap: Option<u64>,
+    pub priority_fee_retry_ms: Option<u64>,
+    pub program_id: Option<String>,
+    pub take_profit_pct: Option<f64>,
+    pub stop_loss_pct: Option<f64>,
+    pub trailing_stop_pct: Option<f64>,
+    pub on_migration: Option<MigrationAction>,
+    pub buy_ladder: Option<Vec<BuyRung>>,
+    pub sell_ladder: Option<Vec<SellRung>>,
+    pub moon_bag_pct: Option<f64>,
+    pub creator_sell_pct: Option<f64>,
+    pub progress_exit_pct: Option<f64>,
+    pub price_alerts: Option<Vec<f64>>,
+    pub alert_new_high: Option<bool>,
+    pub price_poll_ms: Option<u64>,
+    pub ws_url: Option<String>,
+    pub geyser_url: Option<String>,
+    pub geyser_x_token: Option<String>,
+    pub tx_source: Option<TxSource>,
+    pub venue: Option<VenueKind>,
+    pub tx_fallback: Option<bool>,
+    pub jupiter_sells: Option<bool>,
+    pub race_jito: Option<bool>,
+    pub tpu_fanout_slots: Option<u64>,
+    pub slot_clock: Option<bool>,
+    pub rebroadcast_slots: Option<u64>,
+    pub slippage_bps: Option<u64>,
+    pub patch_slippage: Option<bool>,
+    pub show_tx: Option<bool>,
+    pub allowed_programs: Vec<String>,
+    pub nonce_account: Option<String>,
+    pub rug_check: Option<RugCheckMode>,
+    pub top_holders: Option<usize>,
+    pub max_top_holders_pct: Option<f64>,
+    pub min_creator_score: Option<u32>,
+    pub ntp_server: Option<String>,
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub discord_webhook_url: Option<String>,
+    pub journal_path: Option<String>,
+    pub shutdown_grace_secs: Option<u64>,
+    pub lock_dir: Option<PathBuf>,
+    pub kill_switch_file: Option<PathBuf>,
+    pub kill_switch_url: Option<String>,
+    pub kill_switch_flatten: Option<bool>,
+    pub risk_limits: RiskLimits,
+    pub circuit_breaker_failures: Option<u32>,
+    pub circuit_breaker_window_secs: Option<u64>,
+    pub circuit_breaker_cooloff_secs: Option<u64>,
+    pub sweep_keep_sol: Option<f64>,
+}
+
+impl FileConfig {
+    // Load the file given with --config, or config.toml when it exists. The
+    // keys of the selected [profiles.<name>] table override the top level ones.
+    pub fn load(path: Option<&str>, profile: Option<&str>) -> Result<FileConfig> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => DEFAULT_CONFIG_PATH,
+            None if profile.is_some() => {
+                return Err(format!(
+                    "Profiles are read from {}, which does not exist",
+                    DEFAULT_CONFIG_PATH
+                )
+                .red()
+                .to_string()
+                .into())
+            }
+            None => return Ok(FileConfig::default()),
+        };
+        let contents = fs::read_to_string(path).map_err(|e| {
+            format!("Could not read config file {}: {}", path, e)
+                .red()
+                .to_string()
+        })?;
+        let invalid = |e: &dyn std::fmt::Display| {
+            format!("Invalid config file {}: {}", path, e)
+                .red()
+                .to_string()
+        };
+        let mut table: toml::Table = toml::from_str(&contents).map_err(|e| invalid(&e))?;
+
+        let profiles = match table.remove("profiles") {
+            Some(toml::Value::Table(profiles)) => profiles,
+            Some(_) => return Err(invalid(&"profiles must be a table").into()),
+            None => toml::Table::new(),
+        };
+        if let Some(name) = profile {
+            let overrides = match profiles.get(name) {
+                Some(toml::Value::Table(overrides)) => overrides,
+                _ => {
+                    let names: Vec<&str> = profiles.keys().map(|name| name.as_str()).collect();
+                    return Err(format!(
+                        "No profile {} in {}, available: {}",
+                        name,
+                        path,
+                        if names.is_empty() {
+                            "none".to_string()
+                        } else {
+                            names.join(", ")
+                        }
+                    )
+                    .red()
+                    .to_string()
+                    .into());
+                }
+            };
+            table.extend(overrides.clone());
+        }
+        table.try_into().map_err(|e| invalid(&e).into())
+    }
+}
+
+// Settings resolved with precedence CLI > env > config file
+#[derive(Clone)]
+pub struct Config {
+    pub rpc_urls: Vec<String>,
+    pub rpc_timeout: Duration,
+    pub private_key: Option<String>,
+    pub keypair_path: Option<String>,
+    pub mnemonic: Option<String>,
+    pub derivation_path: Option<String>,
+    pub keystore_path: Option<String>,
+    pub keyring_entry: Option<String>,
+    pub ledger: Option<String>,
+    pub key_password: Option<String>,
+    // Keystore secret once decrypted, so the passphrase is asked for only once
+    unlocked: Arc<Mutex<Option<Vec<u8>>>>,
+    // Shared by every sender so QUIC connections outlive a single trade
+    tpu: Arc<OnceLock<Arc<TpuSender>>>,
+    pub wallets: Vec<String>,
+    pub buy_amount: Option<f64>,
+    pub sol_reserve_lamports: u64,
+    pub sell_percent: u8,
+    pub send_mode: SendMode,
+    pub send_options: SendOptions,
+    pub jito_tip_lamports: u64,
+    pub jito_dynamic_tip: Option<DynamicTip>,
+    pub debit_tolerance_lamports: u64,
+    pub jito_block_engine_url: String,
+    pub compute_budget: ComputeBudget,
+    // Unit price `panic` sells pay at least, in micro-lamports
+    pub panic_unit_price: u64,
+    pub priority_fee_percentile: Option<u8>,
+    pub fee_oracle: Option<FeeOracle>,
+    pub helius_url: Option<String>,
+    pub price_oracle: PriceOracle,
+    pub price_provider: PriceProviderKind,
+    pub birdeye_api_key: Option<String>,
+    pub log_file: Option<LogFileConfig>,
+    pub otlp_endpoint: Option<String>,
+    pub fee_escalation: Option<FeeEscalation>,
+    pub program_id: Option<String>,
+    pub exit_rules: ExitRules,
+    pub price_poll_interval: Duration,
+    pub ws_url: Option<String>,
+    pub geyser_url: Option<String>,
+    pub geyser_x_token: Option<String>,
+    pub dry_run: bool,
+    pub tx_source: TxSource,
+    pub venue: VenueKind,
+    pub tx_fallback: bool,
+    pub jupiter_sells: bool,
+    pub race_jito: bool,
+    pub tpu_fanout_slots: u64,
+    pub slot_clock: bool,
+    pub rebroadcast_slots: u64,
+    pub slippage_bps: Option<u64>,
+    pub patch_slippage: bool,
+    pub show_tx: bool,
+    pub allowed_programs: Vec<String>,
+    pub nonce_account: Option<String>,
+    pub rug_check: RugCheckMode,
+    pub top_holders: usize,
+    pub max_top_holders_pct: Option<f64>,
+    pub min_creator_score: Option<u32>,
+    pub ntp_server: String,
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    pub discord_webhook_url: Option<String>,
+    pub journal_path: String,
+    pub shutdown_grace: Duration,
+    // Holds a lock file per wallet of a running daemon or watcher
+    pub lock_dir: PathBuf,
+    pub kill_switch: KillSwitch,
+    pub risk_limits: RiskLimits,
+    pub circuit_breaker: Option<CircuitBreaker>,
+    // SOL `sweep` leaves in every sub-wallet for fees
+    pub sweep_keep_lamports: u64,
+}
+
+impl Config {
+    pub fn load(cli: &Cli) -> Result<Config> {
+        let file = FileConfig::load(cli.config.as_deref(), cli.profile.as_deref())?;
+        let sell_percent = file.sell_percent.unwrap_or(100);
+        if !(1..=100).contains(&sell_percent) {
+            return Err("sell_percent must be between 1 and 100"
+                .red()
+                .to_string()
+                .into());
+        }
+        if file.slippage_bps.is_some_and(|bps| bps > 10_000) {
+            return Err("slippage_bps must be between 0 and 10000"
+                .red()
+                .to_string()
+                .into());
+        }
+        if file
+            .take_profit_pct
+            .is_some_and(|pct| pct.is_nan() || pct <= 0.0)
+        {
+            return Err("take_profit_pct must be above 0".red().to_string().into());
+        }
+        for (name, pct) in [
+            ("stop_loss_pct", file.stop_loss_pct),
+            ("trailing_stop_pct", file.trailing_stop_pct),
+            ("creator_sell_pct", file.creator_sell_pct),
+            (
+                "max_top_holders_pct",
+                cli.max_top_holders_pct.or(file.max_top_holders_pct),
+            ),
+        ] {
+            if pct.is_some_and(|pct| !(pct > 0.0 && pct <= 100.0)) {
+                return Err(format!("{} must be above 0 and at most 100", name)
+                    .red()
+                    .to_string()
+                    .into());
+            }
+        }
+
+        Ok(Config {
+            rpc_urls: if !cli.rpc_url.is_empty() {
+                cli.rpc_url.clone()
+            } else if !file.rpc_urls.is_empty() {
+                file.rpc_urls
+            } else {
+                file.rpc_url.into_iter().collect()
+            },
+            rpc_timeout: Duration::from_millis(
+                file.rpc_timeout_ms.unwrap_or(DEFAULT_RPC_TIMEOUT_MS),
+            ),
+            private_key: cli.private_key.clone().or(file.private_key),
+            keypair_path: cli.keypair.clone().or(file.keypair_path),
+            mnemonic: cli.mnemonic.clone().or(file.mnemonic),
+            derivation_path: cli.derivation_path.clone().or(file.derivation_path),
+            keystore_path: cli.keystore.clone().or(file.keystore_path),
+            keyring_entry: cli.keyring_entry.clone().or(file.keyring_entry),
+            ledger: cli.ledger.clone().or(file.ledger),
+            key_password: cli.key_password.clone(),
+            unlocked: Arc::default(),
+            tpu: Arc::default(),
+            wallets: if cli.private_keys.is_empty() {
+                file.wallets
+            } else {
+                cli.private_keys.clone()
+            },
+            buy_amount: file.buy_amount,
+            sol_reserve_lamports: sol_to_lamports(file.sol_reserve.unwrap_or(DEFAULT_SOL_RESERVE)),
+            sell_percent,
+            send_mode: cli.send_mode.or(file.send_mode).unwrap_or(SendMode::Rpc),
+            send_options: SendOptions {
+                skip_preflight: cli.skip_preflight || file.skip_preflight.unwrap_or(false),
+                preflight_commitment: cli
+                    .preflight_commitment
+                    .or(file.preflight_commitment)
+                    .unwrap_or(Commitment::Confirmed),
+                confirm_commitment: cli
+                    .confirm_commitment
+                    .or(file.confirm_commitment)
+                    .unwrap_or(Commitment::Confirmed),
+            },
+            jito_tip_lamports: cli
+                .jito_tip_lamports
+                .or(file.jito_tip_lamports)
+                .unwrap_or(DEFAULT_JITO_TIP_LAMPORTS),
+            jito_dynamic_tip: cli.jito_tip_percentile.or(file.jito_tip_percentile).map(
+                |percentile| DynamicTip {
+                    percentile,
+                    cap_lamports: cli
+                        .jito_tip_cap_lamports
+                        .or(file.jito_tip_cap_lamports)
+                        .unwrap_or(DEFAULT_TIP_CAP_LAMPORTS),
+                },
+            ),
+            debit_tolerance_lamports: cli
+                .debit_tolerance_lamports
+                .or(file.debit_tolerance_lamports)
+                .unwrap_or(DEFAULT_DEBIT_TOLERANCE_LAMPORTS),
+            jito_block_engine_url: cli
+                .jito_block_engine_url
+                .clone()
+                .or(file.jito_block_engine_url)
+                .unwrap_or_else(|| DEFAULT_BLOCK_ENGINE_URL.to_string()),
+            compute_budget: ComputeBudget {
+                unit_price: cli.compute_unit_price.or(file.compute_unit_price),
+                unit_limit: cli.compute_unit_limit.or(file.compute_unit_limit),
+            },
+            panic_unit_price: file.panic_unit_price.unwrap_or(DEFAULT_PANIC_UNIT_PRICE),
+            priority_fee_percentile: cli.priority_fee_percentile.or(file.priority_fee_percentile),
+            fee_oracle: cli.fee_oracle.or(file.fee_oracle),
+            helius_url: cli.helius_url.clone().or(file.helius_url),
+            price_oracle: cli.price_oracle.or(file.price_oracle).unwrap_or_default(),
+            price_provider: cli
+                .price_provider
+                .or(file.price_provider)
+                .unwrap_or_default(),
+            birdeye_api_key: cli.birdeye_api_key.clone().or(file.birdeye_api_key),
+            log_file: cli
+                .log_file
+                .clone()
+                .or(file.log_file)
+                .map(|path| LogFileConfig {
+                    path: path.into(),
+                    // 0 turns off rotation by size
+                    max_bytes: Some(file.log_max_mb.unwrap_or(DEFAULT_LOG_MAX_MB))
+                        .filter(|mb| *mb > 0)
+                        .map(|mb| mb * 1024 * 1024),
+                    daily: file.log_daily.unwrap_or(false),
+                    keep: file.log_keep.unwrap_or(DEFAULT_LOG_KEEP),
+                }),
+            otlp_endpoint: cli.otlp_endpoint.clone().or(file.otlp_endpoint),
+            fee_escalation: cli
+                .priority_fee_step
+                .or(file.priority_fee_step)
+                .map(|step| FeeEscalation {
+                    step,
+                    cap: cli
+                        .priority_fee_cap
+                        .or(file.priority_fee_cap)
+                        .unwrap_or(DEFAULT_PRIORITY_FEE_CAP),
+                    window: Duration::from_millis(
+                        file.priority_fee_retry_ms
+                            .unwrap_or(DEFAULT_PRIORITY_FEE_RETRY_MS),
+                    ),
+                }),
+            program_id: cli.program_id.clone().or(file.program_id),
+            exit_rules: ExitRules {
+                take_profit_pct: file.take_profit_pct,
+                stop_loss_pct: file.stop_loss_pct,
+                trailing_stop_pct: file.trailing_stop_pct,
+                on_migration: file.on_migration,
+                buy_ladder: file.buy_ladder.unwrap_or_default(),
+                sell_ladder: file.sell_ladder.unwrap_or_default(),
+                moon_bag_pct: file.moon_bag_pct,
+                creator_sell_pct: file.creator_sell_pct,
+                progress_exit_pct: file.progress_exit_pct,
+                price_alerts: file.price_alerts.unwrap_or_default(),
+                alert_new_high: file.alert_new_high.unwrap_or(false),
+            },
+            price_poll_interval: Duration::from_millis(
+                file.price_poll_ms.unwrap_or(DEFAULT_PRICE_POLL_MS),
+            ),
+            ws_url: cli.ws_url.clone().or(file.ws_url),
+            geyser_url: cli.geyser_url.clone().or(file.geyser_url),
+            geyser_x_token: cli.geyser_x_token.clone().or(file.geyser_x_token),
+            dry_run: cli.dry_run,
+            tx_source: cli.tx_source.or(file.tx_source).unwrap_or(TxSource::Api),
+            tx_fallback: file.tx_fallback.unwrap_or(true),
+            jupiter_sells: file.jupiter_sells.unwrap_or(true),
+            venue: cli.venue.or(file.venue).unwrap_or(VenueKind::Degen),
+            race_jito: cli.race_jito || file.race_jito.unwrap_or(false),
+            tpu_fanout_slots: file.tpu_fanout_slots.unwrap_or(DEFAULT_TPU_FANOUT_SLOTS),
+            slot_clock: cli.slot_clock || file.slot_clock.unwrap_or(false),
+            rebroadcast_slots: file
+                .rebroadcast_slots
+                .unwrap_or(DEFAULT_REBROADCAST_SLOTS)
+                .max(1),
+            slippage_bps: cli.slippage_bps.or(file.slippage_bps),
+            patch_slippage: cli.patch_slippage || file.patch_slippage.unwrap_or(false),
+            show_tx: cli.show_tx || file.show_tx.unwrap_or(false),
+            allowed_programs: file.allowed_programs,
+            nonce_account: cli.nonce_account.clone().or(file.nonce_account),
+            rug_check: cli
+                .rug_check
+                .or(file.rug_check)
+                .unwrap_or(RugCheckMode::Off),
+            top_holders: file.top_holders.unwrap_or(DEFAULT_TOP_HOLDERS),
+            max_top_holders_pct: cli.max_top_holders_pct.or(file.max_top_holders_pct),
+            min_creator_score: cli.min_creator_score.or(file.min_creator_score),
+            ntp_server: cli
+                .ntp_server
+                .clone()
+                .or(file.ntp_server)
+                .unwrap_or_else(|| DEFAULT_NTP_SERVER.to_string()),
+            telegram_bot_token: cli.telegram_bot_token.clone().or(file.telegram_bot_token),
+            telegram_chat_id: cli.telegram_chat_id.clone().or(file.telegram_chat_id),
+            discord_webhook_url: cli.discord_webhook_url.clone().or(file.discord_webhook_url),
+            journal_path: cli
+                .journal
+                .clone()
+                .or(file.journal_path)
+                .unwrap_or_else(|| DEFAULT_JOURNAL_PATH.to_string()),
+            shutdown_grace: file
+                .shutdown_grace_secs
+                .map_or(DEFAULT_SHUTDOWN_GRACE, Duration::from_secs),
+            lock_dir: file.lock_dir.unwrap_or_else(lock::default_lock_dir),
+            kill_switch: KillSwitch {
+                file: cli.kill_switch_file.clone().or(file.kill_switch_file),
+                url: cli.kill_switch_url.clone().or(file.kill_switch_url),
+                flatten: cli.kill_switch_flatten || file.kill_switch_flatten.unwrap_or(false),
+            },
+            risk_limits: RiskLimits {
+                max_trade_sol: cli.max_trade_sol.or(file.risk_limits.max_trade_sol),
+                max_daily_sol: cli.max_daily_sol.or(file.risk_limits.max_daily_sol),
+                max_open_positions: cli
+                    .max_open_positions
+                    .or(file.risk_limits.max_open_positions),
+                max_position_sol: cli.max_position_sol.or(file.risk_limits.max_position_sol),
+                buy_cooldown_secs: cli.buy_cooldown_secs.or(file.risk_limits.buy_cooldown_secs),
+                creator_cooldown_secs: cli
+                    .creator_cooldown_secs
+                    .or(file.risk_limits.creator_cooldown_secs),
+            },
+            circuit_breaker: cli
+                .circuit_breaker_failures
+                .or(file.circuit_breaker_failures)
+                .filter(|failures| *failures > 0)
+                .map(|failures| CircuitBreaker {
+                    failures,
+                    window: file
+                        .circuit_breaker_window_secs
+                        .map_or(DEFAULT_BREAKER_WINDOW, Duration::from_secs),
+                    cooloff: file
+                        .circuit_breaker_cooloff_secs
+                        .map_or(DEFAULT_BREAKER_COOLOFF, Duration::from_secs),
+                }),
+            sweep_keep_lamports: sol_to_lamports(
+                file.sweep_keep_sol.unwrap_or(DEFAULT_SWEEP_KEEP_SOL),
+            ),
+        })
+    }
+
+    // Settings from the environment and config file alone, resolved the way
+    // the binary does without flags. For programs embedding the bot.
+    pub fn from_env() -> Result<Config> {
+        let cli = Cli::try_parse_from(["degen-fund-bot", "balance"])?;
+        Config::load(&cli)
+    }
+
+    // The single trading wallet, falling back to the first of the wallet list
+    pub fn keypair(&self) -> Result<Keypair> {
+        // The example config ships an empty private_key
+        if let Some(private_key) = self.private_key.as_deref().filter(|key| !key.is_empty()) {
+            return wallet::load_keypair(Some(private_key));
+        }
+        if let Some(path) = &self.keypair_path {
+            return wallet::read_keypair_file(path);
+        }
+        if let Some(mnemonic) = &self.mnemonic {
+            return wallet::keypair_from_mnemonic(mnemonic, self.derivation_path.as_deref());
+        }
+        if let Some(path) = &self.keystore_path {
+            return self.unlock_keystore(path);
+        }
+        if let Some(entry) = &self.keyring_entry {
+            return keychain::load(entry);
+        }
+        wallet::load_keypair(self.wallets.first().map(|k| k.as_str()))
+    }
+
+    // Whether a private key is configured without encryption
+    pub fn has_plaintext_key(&self) -> bool {
+        self.private_key
+            .as_deref()
+            .is_some_and(|key| !key.is_empty())
+            || self.keypair_path.is_some()
+            || self.mnemonic.is_some()
+    }
+
+    fn unlock_keystore(&self, path: &str) -> Result<Keypair> {
+        let mut unlocked = self.unlocked.lock().unwrap();
+        if let Some(secret) = unlocked.as_ref() {
+            return wallet::keypair_from_bytes(secret);
+        }
+        let keystore = Keystore::read(path)?;
+        let passphrase = keystore::passphrase(
+            self.key_password.as_deref(),
+            &format!("Passphrase for {}: ", keystore.pubkey()),
+        )?;
+        let keypair = keystore.decrypt(&passphrase)?;
+        *unlocked = Some(keypair.to_bytes().to_vec());
+        Ok(keypair)
+    }
+
+    // The single trading wallet, a Ledger when one is configured
+    pub fn signer(&self) -> Result<WalletSigner> {
+        match &self.ledger {
+            Some(url) => Ok(WalletSigner::Ledger(Ledger::connect(url)?)),
+            None => Ok(self.keypair()?.into()),
+        }
+    }
+
+    // All trading wallets, the wallet list when configured or else the single
+    // key. A Ledger always trades alone.
+    pub fn signers(&self) -> Result<Vec<WalletSigner>> {
+        if self.wallets.is_empty() || self.ledger.is_some() {
+            return Ok(vec![self.signer()?]);
+        }
+        self.wallets
+            .iter()
+            .map(|key| Ok(wallet::load_keypair(Some(key))?.into()))
+            .collect()
+    }
+
+    pub fn program_id(&self) -> Result<Pubkey> {
+        let program_id = self.program_id.as_deref().ok_or_else(|| {
+            "The degen.fund program id must be given with --program-id, DEGEN_FUND_PROGRAM_ID or program_id in the config file"
+                .red()
+                .to_string()
+        })?;
+        Pubkey::from_str(program_id).map_err(|_| {
+            format!("Invalid program id '{}'", program_id)
+                .red()
+                .to_string()
+                .into()
+        })
+    }
+
+    // The durable nonce account trades are signed against, when configured
+    pub fn nonce_account(&self) -> Result<Option<Pubkey>> {
+        self.nonce_account
+            .as_deref()
+            .map(wallet::parse_pubkey)
+            .transpose()
+    }
+
+    // The launchpad trades are built against
+    pub fn venue(&self) -> Result<Box<dyn Venue>> {
+        Ok(match self.venue {
+            VenueKind::Degen => Box::new(CurveVenue::degen(self.program_id()?)),
+            VenueKind::Pumpfun => Box::new(CurveVenue::pump_fun()),
+            VenueKind::Moonshot => Box::new(Moonshot),
+        })
+    }
+
+    // Program of the configured venue, None for degen.fund without a program id
+    pub fn venue_program_id(&self) -> Option<Pubkey> {
+        match self.venue {
+            VenueKind::Degen => self.program_id().ok(),
+            VenueKind::Pumpfun => Some(PUMP_FUN_PROGRAM_ID),
+            VenueKind::Moonshot => Some(MOONSHOT_PROGRAM_ID),
+        }
+    }
+
+    // Market prices of tokens that left the curve
+    pub fn price_provider(&self) -> Result<Box<dyn PriceProvider>> {
+        Ok(match self.price_provider {
+            PriceProviderKind::Raydium => Box::new(Raydium::default()),
+            PriceProviderKind::Birdeye => {
+                let api_key = self.birdeye_api_key.clone().ok_or_else(|| {
+                    "The birdeye price provider needs --birdeye-api-key, BIRDEYE_API_KEY or birdeye_api_key in the config file"
+                        .red()
+                        .to_string()
+                })?;
+                Box::new(Birdeye::new(api_key))
+            }
+            PriceProviderKind::Dexscreener => Box::new(DexScreener),
+        })
+    }
+
+    pub fn helius_url(&self) -> Result<&str> {
+        self.helius_url
+            .as_deref()
+            .or(self.rpc_urls.first().map(|url| url.as_str()))
+            .ok_or_else(|| {
+                "The helius fee oracle needs --helius-url, HELIUS_URL or helius_url in the config file"
+                    .red()
+                    .to_string()
+                    .into()
+            })
+    }
+
+    // A client for the primary RPC endpoint
+    pub fn rpc_client(&self) -> Result<RpcClient> {
+        let rpc_url = self.rpc_urls.first().ok_or_else(|| {
+            "An RPC endpoint must be given with --rpc-url, SOLANA_RPC_URL or rpc_url in the config file"
+                .red()
+                .to_string()
+        })?;
+        Ok(RpcClient::new_with_timeout_and_commitment(
+            rpc_url.to_string(),
+            self.rpc_timeout,
+            CommitmentConfig::confirmed(),
+        ))
+    }
+
+    // The configured WebSocket endpoint, or the RPC endpoint with a ws scheme
+    pub fn ws_url(&self) -> Result<String> {
+        if let Some(ws_url) = &self.ws_url {
+            return Ok(ws_url.clone());
+        }
+        let rpc_url = self.rpc_urls.first().ok_or_else(|| {
+            "A WebSocket endpoint must be given with --ws-url, SOLANA_WS_URL or ws_url in the config file"
+                .red()
+                .to_string()
+        })?;
+        Ok(rpc_url
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1))
+    }
+
+    fn jito_client(&self) -> JitoClient {
+        let jito = JitoClient::new(&self.jito_block_engine_url, self.jito_tip_lamports);
+        match self.jito_dynamic_tip {
+            Some(tip) => jito.with_dynamic_tip(tip),
+            None => jito,
+        }
+    }
+
+    // The most a Jito tip can come to
+    pub fn max_jito_tip(&self) -> u64 {
+        match self.jito_dynamic_tip {
+            Some(tip) => tip.cap_lamports.max(self.jito_tip_lamports),
+            None => self.jito_tip_lamports,
+        }
+    }
+
+    // The Geyser stream when one is configured
+    pub fn geyser(&self) -> Option<Geyser> {
+        self.geyser_url
+            .as_ref()
+            .map(|url| Geyser::new(url.clone(), self.geyser_x_token.clone()))
+    }
+
+    pub fn tpu(&self) -> Arc<TpuSender> {
+        self.tpu
+            .get_or_init(|| {
+                Arc::new(TpuSender::new(
+                    self.rpc_urls.first().cloned(),
+                    self.ws_url().ok(),
+                    self.tpu_fanout_slots,
+                ))
+            })
+            .clone()
+    }
+
+    pub fn sender(&self) -> Result<Sender> {
+        let sender = Sender::new(
+            RpcPool::new(&self.rpc_urls, self.rpc_timeout)?,
+            self.send_mode,
+            self.jito_client(),
+            self.race_jito,
+            self.tpu(),
+            self.dry_run,
+            self.notifier()?,
+        );
+        let sender = match self.fee_escalation {
+            Some(escalation) => sender.with_fee_escalation(escalation),
+            None => sender,
+        };
+        let sender = match self.circuit_breaker {
+            Some(breaker) => sender.with_circuit_breaker(breaker),
+            None => sender,
+        };
+        let sender = sender
+            .with_send_options(self.send_options)
+            .with_rebuild(self.clone());
+        let sender = if self.slot_clock {
+            sender.with_slot_clock(SlotClock::new(self.ws_url()?), self.rebroadcast_slots)
+        } else {
+            sender
+        };
+        // Simulated trades are not journaled
+        if self.dry_run {
+            return Ok(sender);
+        }
+        Ok(sender.with_journal(Journal::open(&self.journal_path)?))
+    }
+
+    pub fn telegram(&self) -> Result<Option<Telegram>> {
+        match (&self.telegram_bot_token, &self.telegram_chat_id) {
+            (Some(token), Some(chat_id)) => Ok(Some(Telegram::new(token.clone(), chat_id.clone()))),
+            (None, None) => Ok(None),
+            _ => Err(
+                "telegram_bot_token and telegram_chat_id must be configured together"
+                    .red()
+                    .to_string()
+                    .into(),
+            ),
+        }
+    }
+
+    pub fn notifier(&self) -> Result<Notifier> {
+        let telegram = self.telegram()?;
+        let discord = self.discord_webhook_url.clone().map(Discord::new);
+        Ok(Notifier::new(telegram, discord))
+    }
+}
@@ -0,0 +1,342 @@
+use colored::*;
+use futures_util::{stream, StreamExt};
+use log::{info, warn};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcTransactionConfig, RpcTransactionLogsConfig, RpcTransactionLogsFilter},
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{option_serializer::OptionSerializer, UiTransactionEncoding};
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+use crate::config::Config;
+use crate::curve::{BondingCurve, SELL_DISCRIMINATOR};
+use crate::{wallet, BotError, Result};
+
+// Anchor logs the instruction name of every call into the program
+pub const CREATE_LOG: &str = "Program log: Instruction: Create";
+const SELL_LOG: &str = "Program log: Instruction: Sell";
+// Account positions in the curve sell instruction
+const SELL_MINT_INDEX: usize = 2;
+const SELL_USER_INDEX: usize = 6;
+// Recent transactions of the creator scanned for earlier launches
+const HISTORY_SIGNATURES: usize = 200;
+// Pages of 1000 signatures walked back to find a mint's first transaction
+const MAX_MINT_PAGES: usize = 10;
+const FETCH_CONCURRENCY: usize = 10;
+
+pub struct Launch {
+    pub mint: Pubkey,
+    pub creator: Pubkey,
+}
+
+// The launch made by a transaction, None when it doesn't create a token
+pub async fn fetch_launch(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    signature: &Signature,
+) -> Result<Option<Launch>> {
+    let tx = client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let creates = match tx.transaction.meta.as_ref().map(|meta| &meta.log_messages) {
+        Some(OptionSerializer::Some(logs)) => logs.iter().any(|log| log.starts_with(CREATE_LOG)),
+        _ => false,
+    };
+    let Some(decoded) = tx.transaction.transaction.decode() else {
+        return Ok(None);
+    };
+    if !creates {
+        return Ok(None);
+    }
+
+    Ok(launch_from_keys(
+        program_id,
+        decoded.message.static_account_keys(),
+    ))
+}
+
+// The launch of a create transaction from its account keys. The mint is the
+// account whose bonding curve address also appears among them.
+pub fn launch_from_keys(program_id: &Pubkey, keys: &[Pubkey]) -> Option<Launch> {
+    let mint = keys
+        .iter()
+        .find(|key| keys.contains(&BondingCurve::address(program_id, key)))?;
+    Some(Launch {
+        mint: *mint,
+        creator: keys[0],
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    // The curve completed and the token moved to Raydium
+    Migrated,
+    // The creator sold out while the token was still on the curve
+    Rugged,
+    // Still on the curve with the creator holding
+    Dead,
+}
+
+impl Outcome {
+    fn label(&self) -> ColoredString {
+        match self {
+            Outcome::Migrated => "migrated".green(),
+            Outcome::Rugged => "rugged".red(),
+            Outcome::Dead => "dead".dimmed(),
+        }
+    }
+}
+
+pub struct History {
+    pub creator: Pubkey,
+    pub launches: Vec<(Pubkey, Outcome)>,
+}
+
+impl History {
+    // 0 to 100, a migration counts fully, a dead token half and a rug nothing.
+    // None for a first launch.
+    pub fn score(&self) -> Option<u32> {
+        if self.launches.is_empty() {
+            return None;
+        }
+        let points: u32 = self
+            .launches
+            .iter()
+            .map(|(_, outcome)| match outcome {
+                Outcome::Migrated => 100,
+                Outcome::Dead => 50,
+                Outcome::Rugged => 0,
+            })
+            .sum();
+        Some(points / self.launches.len() as u32)
+    }
+
+    pub fn print(&self) {
+        info!(
+            "Creator {} has {} earlier launch(es)",
+            self.creator.to_string().bright_green(),
+            self.launches.len()
+        );
+        for (mint, outcome) in &self.launches {
+            info!("  {} {}", mint.to_string().bright_cyan(), outcome.label());
+        }
+        match self.score() {
+            Some(score) => info!("Creator score {}", format!("{}/100", score).bold()),
+            None => info!("Creator score: first launch"),
+        }
+    }
+}
+
+// The creator of `mint` and how their earlier launches on the program ended
+pub async fn history(client: &RpcClient, program_id: &Pubkey, mint: &Pubkey) -> Result<History> {
+    let launch = find_launch(client, program_id, mint).await?;
+
+    let signatures = client
+        .get_signatures_for_address_with_config(
+            &launch.creator,
+            GetConfirmedSignaturesForAddress2Config {
+                limit: Some(HISTORY_SIGNATURES),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let signatures: Vec<Signature> = signatures
+        .iter()
+        .filter(|status| status.err.is_none())
+        .filter_map(|status| Signature::from_str(&status.signature).ok())
+        .collect();
+    let fetched: Vec<Result<Option<Launch>>> = stream::iter(signatures)
+        .map(|signature| async move { fetch_launch(client, program_id, &signature).await })
+        .buffered(FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut launches = Vec::new();
+    for earlier in fetched.into_iter().flatten().flatten() {
+        let earlier = earlier.mint;
+        if earlier == *mint {
+            continue;
+        }
+        let outcome = match BondingCurve::fetch(client, program_id, &earlier).await {
+            Ok(curve) if curve.complete => Outcome::Migrated,
+            _ => match wallet::token_balance(client, &launch.creator, &earlier).await {
+                Ok((0, _)) => Outcome::Rugged,
+                _ => Outcome::Dead,
+            },
+        };
+        launches.push((earlier, outcome));
+    }
+    Ok(History {
+        creator: launch.creator,
+        launches,
+    })
+}
+
+// The launch of `mint`, from its first transaction
+pub async fn find_launch(client: &RpcClient, program_id: &Pubkey, mint: &Pubkey) -> Result<Launch> {
+    let first = first_signature(client, mint).await?;
+    fetch_launch(client, program_id, &first)
+        .await?
+        .ok_or_else(|| {
+            format!("Could not find the launch of {}", mint)
+                .red()
+                .to_string()
+                .into()
+        })
+}
+
+// Signatures of the creator's curve sells of `mint`, as they land. A logs
+// subscription to the creator's wallet runs in the background until the
+// receiver is dropped.
+pub async fn watch_sells(
+    config: &Config,
+    program_id: &Pubkey,
+    launch: &Launch,
+) -> Result<mpsc::UnboundedReceiver<Signature>> {
+    let pubsub = PubsubClient::new(&config.ws_url()?).await?;
+    let client = config.rpc_client()?;
+    let (sells, receiver) = mpsc::unbounded_channel();
+    let (program_id, creator, mint) = (*program_id, launch.creator, launch.mint);
+    tokio::spawn(async move {
+        let subscription = pubsub
+            .logs_subscribe(
+                RpcTransactionLogsFilter::Mentions(vec![creator.to_string()]),
+                RpcTransactionLogsConfig {
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await;
+        let (mut logs, _unsubscribe) = match subscription {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                warn!("Could not watch the creator's wallet: {}", e);
+                return;
+            }
+        };
+        while let Some(response) = tokio::select! {
+            response = logs.next() => response,
+            _ = sells.closed() => None,
+        } {
+            let response = response.value;
+            if response.err.is_some() || !response.logs.iter().any(|log| log.starts_with(SELL_LOG))
+            {
+                continue;
+            }
+            let Ok(signature) = Signature::from_str(&response.signature) else {
+                continue;
+            };
+            match is_sell(&client, &program_id, &creator, &mint, &signature).await {
+                Ok(true) => {
+                    if sells.send(signature).is_err() {
+                        return;
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => warn!("Could not inspect {}: {}", signature, e),
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+// Whether the transaction sells `mint` on the curve from `seller`
+async fn is_sell(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    signature: &Signature,
+) -> Result<bool> {
+    let tx = client
+        .get_transaction_with_config(
+            signature,
+            RpcTransactionConfig {
+                encoding: Some(UiTransactionEncoding::Base64),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let (Some(decoded), Some(meta)) = (tx.transaction.transaction.decode(), tx.transaction.meta)
+    else {
+        return Ok(false);
+    };
+    let mut keys = decoded.message.static_account_keys().to_vec();
+    if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+        for address in loaded.writable.iter().chain(&loaded.readonly) {
+            keys.push(Pubkey::from_str(address)?);
+        }
+    }
+    Ok(decoded.message.instructions().iter().any(|ix| {
+        let account = |position: usize| {
+            ix.accounts
+                .get(position)
+                .and_then(|index| keys.get(*index as usize))
+        };
+        keys.get(ix.program_id_index as usize) == Some(program_id)
+            && ix.data.starts_with(&SELL_DISCRIMINATOR)
+            && account(SELL_MINT_INDEX) == Some(mint)
+            && account(SELL_USER_INDEX) == Some(seller)
+    }))
+}
+
+// The oldest transaction of an address, walking back a page at a time
+async fn first_signature(client: &RpcClient, address: &Pubkey) -> Result<Signature> {
+    let mut oldest = None;
+    for _ in 0..MAX_MINT_PAGES {
+        let page = client
+            .get_signatures_for_address_with_config(
+                address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before: oldest,
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        oldest = Some(Signature::from_str(&last.signature)?);
+        if page.len() < 1000 {
+            break;
+        }
+    }
+    oldest.ok_or_else(|| {
+        format!("No transactions found for {}", address)
+            .red()
+            .to_string()
+            .into()
+    })
+}
+
+// Refuse the buy when the creator's score is below the configured minimum
+pub async fn gate(config: &Config, client: &RpcClient, mint: &Pubkey) -> Result<()> {
+    let Some(min_score) = config.min_creator_score else {
+        return Ok(());
+    };
+    let history = history(client, &config.program_id()?, mint).await?;
+    match history.score() {
+        Some(score) if score < min_score => Err(BotError::policy(format!(
+            "Refusing to buy, creator {} scores {}/100 (min {})",
+            history.creator, score, min_score
+        ))),
+        Some(score) => {
+            info!("Creator {} scores {}/100", history.creator, score);
+            Ok(())
+        }
+        None => {
+            info!("Creator {} launches for the first time", history.creator);
+            Ok(())
+        }
+    }
+}
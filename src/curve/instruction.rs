@@ -0,0 +1,119 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+    transaction::Transaction,
+};
+
+use super::{event_authority, BondingCurve, Global};
+use crate::wallet::{
+    associated_token_address, create_associated_token_account_idempotent,
+    ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+use crate::Result;
+
+// Anchor instruction discriminators, sha256("global:<name>")[..8]
+pub const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+pub const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+
+// Unsigned buy of `lamports` worth of `mint`, paying at most `slippage_bps` above the quote
+pub async fn build_buy_transaction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    buyer: &Pubkey,
+    mint: &Pubkey,
+    lamports: u64,
+    slippage_bps: u64,
+) -> Result<Transaction> {
+    let global = Global::fetch(client, program_id).await?;
+    let curve = BondingCurve::fetch(client, program_id, mint).await?;
+    if curve.complete {
+        return Err("Bonding curve is complete, the token has migrated".into());
+    }
+
+    let token_amount = curve.buy_quote(lamports, global.fee_basis_points);
+    let cost = curve.buy_cost(token_amount, global.fee_basis_points);
+    let max_sol_cost = cost.saturating_add(cost.saturating_mul(slippage_bps) / 10_000);
+
+    let bonding_curve = BondingCurve::address(program_id, mint);
+    let mut data = BUY_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&max_sol_cost.to_le_bytes());
+    let buy = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(Global::address(program_id), false),
+            AccountMeta::new(global.fee_recipient, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(bonding_curve, false),
+            AccountMeta::new(associated_token_address(&bonding_curve, mint), false),
+            AccountMeta::new(associated_token_address(buyer, mint), false),
+            AccountMeta::new(*buyer, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(event_authority(program_id), false),
+            AccountMeta::new_readonly(*program_id, false),
+        ],
+        data,
+    };
+
+    let instructions = [
+        create_associated_token_account_idempotent(buyer, buyer, mint),
+        buy,
+    ];
+    let blockhash = client.get_latest_blockhash().await?;
+    let mut tx = Transaction::new_with_payer(&instructions, Some(buyer));
+    tx.message.recent_blockhash = blockhash;
+    Ok(tx)
+}
+
+// Unsigned sell of `token_amount` raw units of `mint`, accepting at most
+// `slippage_bps` below the quote
+pub async fn build_sell_transaction(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    token_amount: u64,
+    slippage_bps: u64,
+) -> Result<Transaction> {
+    let global = Global::fetch(client, program_id).await?;
+    let curve = BondingCurve::fetch(client, program_id, mint).await?;
+    if curve.complete {
+        return Err("Bonding curve is complete, the token has migrated".into());
+    }
+
+    let sol_output = curve.sell_quote(token_amount, global.fee_basis_points);
+    let min_sol_output =
+        sol_output.saturating_sub(sol_output.saturating_mul(slippage_bps) / 10_000);
+
+    let bonding_curve = BondingCurve::address(program_id, mint);
+    let mut data = SELL_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&token_amount.to_le_bytes());
+    data.extend_from_slice(&min_sol_output.to_le_bytes());
+    let sell = Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(Global::address(program_id), false),
+            AccountMeta::new(global.fee_recipient, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(bonding_curve, false),
+            AccountMeta::new(associated_token_address(&bonding_curve, mint), false),
+            AccountMeta::new(associated_token_address(seller, mint), false),
+            AccountMeta::new(*seller, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(event_authority(program_id), false),
+            AccountMeta::new_readonly(*program_id, false),
+        ],
+        data,
+    };
+
+    let blockhash = client.get_latest_blockhash().await?;
+    let mut tx = Transaction::new_with_payer(&[sell], Some(seller));
+    tx.message.recent_blockhash = blockhash;
+    Ok(tx)
+}
@@ -0,0 +1,268 @@
+use colored::*;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, pubkey::Pubkey};
+
+use crate::Result;
+
+mod instruction;
+
+pub use instruction::{
+    build_buy_transaction, build_sell_transaction, BUY_DISCRIMINATOR, SELL_DISCRIMINATOR,
+};
+
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+pub const GLOBAL_SEED: &[u8] = b"global";
+pub const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+
+// Program-wide settings, of which only the fee fields are needed to trade
+#[derive(Clone, Debug)]
+pub struct Global {
+    pub fee_recipient: Pubkey,
+    pub initial_real_token_reserves: u64,
+    pub fee_basis_points: u64,
+}
+
+impl Global {
+    pub fn address(program_id: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[GLOBAL_SEED], program_id).0
+    }
+
+    pub async fn fetch(client: &RpcClient, program_id: &Pubkey) -> Result<Global> {
+        let data = client.get_account_data(&Self::address(program_id)).await?;
+        Self::from_account_data(&data)
+    }
+
+    // discriminator, initialized, authority, fee_recipient, the initial
+    // virtual token, virtual SOL and real token reserves, token_total_supply,
+    // then fee_basis_points
+    pub fn from_account_data(data: &[u8]) -> Result<Global> {
+        if data.len() < 113 {
+            return Err("Global account is too short".red().to_string().into());
+        }
+        Ok(Global {
+            fee_recipient: Pubkey::try_from(&data[41..73])?,
+            initial_real_token_reserves: u64::from_le_bytes(data[89..97].try_into()?),
+            fee_basis_points: u64::from_le_bytes(data[105..113].try_into()?),
+        })
+    }
+}
+
+pub fn event_authority(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id).0
+}
+
+// On-chain state of a token's bonding curve, as laid out after the
+// 8 byte Anchor account discriminator
+#[derive(Clone, Debug)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool,
+}
+
+impl BondingCurve {
+    pub fn address(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], program_id).0
+    }
+
+    pub async fn fetch(
+        client: &RpcClient,
+        program_id: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<BondingCurve> {
+        let address = Self::address(program_id, mint);
+        let data = client.get_account_data(&address).await.map_err(|e| {
+            format!("Could not fetch bonding curve {}: {}", address, e)
+                .red()
+                .to_string()
+        })?;
+        Self::from_account_data(&data)
+    }
+
+    pub fn from_account_data(data: &[u8]) -> Result<BondingCurve> {
+        if data.len() < 8 + 5 * 8 + 1 {
+            return Err("Bonding curve account is too short"
+                .red()
+                .to_string()
+                .into());
+        }
+        let u64_at = |index: usize| {
+            let start = 8 + index * 8;
+            u64::from_le_bytes(data[start..start + 8].try_into().unwrap())
+        };
+        // A zeroed account would divide by zero in every quote
+        if u64_at(0) == 0 || u64_at(1) == 0 {
+            return Err("Bonding curve account is not initialised"
+                .red()
+                .to_string()
+                .into());
+        }
+        Ok(BondingCurve {
+            virtual_token_reserves: u64_at(0),
+            virtual_sol_reserves: u64_at(1),
+            real_token_reserves: u64_at(2),
+            // Real SOL reserves sit at 3
+            token_total_supply: u64_at(4),
+            complete: data[8 + 5 * 8] != 0,
+        })
+    }
+
+    // Spot price in SOL per whole token
+    pub fn price_sol(&self, decimals: u8) -> f64 {
+        if self.virtual_token_reserves == 0 {
+            return 0.0;
+        }
+        let lamports_per_raw =
+            self.virtual_sol_reserves as f64 / self.virtual_token_reserves as f64;
+        lamports_per_raw * 10f64.powi(decimals as i32) / LAMPORTS_PER_SOL as f64
+    }
+
+    // Raw tokens received for spending `lamports`, fee included
+    pub fn buy_quote(&self, lamports: u64, fee_basis_points: u64) -> u64 {
+        let sol_in = lamports as u128 * 10_000 / (10_000 + fee_basis_points as u128);
+        let tokens = self.virtual_token_reserves as u128 * sol_in
+            / (self.virtual_sol_reserves as u128 + sol_in);
+        (tokens as u64).min(self.real_token_reserves)
+    }
+
+    // Lamports needed to buy exactly `token_amount` raw tokens, fee included
+    pub fn buy_cost(&self, token_amount: u64, fee_basis_points: u64) -> u64 {
+        let token_amount =
+            (token_amount as u128).min((self.virtual_token_reserves as u128).saturating_sub(1));
+        let cost = (self.virtual_sol_reserves as u128 * token_amount)
+            .div_ceil(self.virtual_token_reserves as u128 - token_amount);
+        // Close to the whole reserve the cost no longer fits
+        u64::try_from(cost + cost * fee_basis_points as u128 / 10_000).unwrap_or(u64::MAX)
+    }
+
+    // Lamports received for selling `token_amount` raw tokens, after the fee
+    pub fn sell_quote(&self, token_amount: u64, fee_basis_points: u64) -> u64 {
+        let sol_out = self.virtual_sol_reserves as u128 * token_amount as u128
+            / (self.virtual_token_reserves as u128 + token_amount as u128);
+        (sol_out - sol_out * fee_basis_points as u128 / 10_000) as u64
+    }
+
+    // Share of the curve's tokens sold so far, 100 once it completes
+    pub fn progress_pct(&self, initial_real_token_reserves: u64) -> f64 {
+        if self.complete || initial_real_token_reserves == 0 {
+            return 100.0;
+        }
+        let sold = initial_real_token_reserves.saturating_sub(self.real_token_reserves);
+        sold as f64 / initial_real_token_reserves as f64 * 100.0
+    }
+
+    // Fully diluted market cap in SOL
+    pub fn market_cap_sol(&self, decimals: u8) -> f64 {
+        self.price_sol(decimals) * self.token_total_supply as f64 / 10f64.powi(decimals as i32)
+    }
+}
+
+pub async fn mint_decimals(client: &RpcClient, mint: &Pubkey) -> Result<u8> {
+    Ok(client.get_token_supply(mint).await?.decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEE_BASIS_POINTS: u64 = 100;
+
+    fn curve() -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        }
+    }
+
+    fn account_data(reserves: [u64; 5], complete: bool) -> Vec<u8> {
+        let mut data = vec![0; 8];
+        for reserve in reserves {
+            data.extend_from_slice(&reserve.to_le_bytes());
+        }
+        data.push(complete as u8);
+        data
+    }
+
+    #[test]
+    fn buy_quote_takes_the_fee_off_first() {
+        assert_eq!(
+            curve().buy_quote(1_000_000_000, FEE_BASIS_POINTS),
+            34_281_150_129_545
+        );
+        assert_eq!(curve().buy_quote(0, FEE_BASIS_POINTS), 0);
+    }
+
+    #[test]
+    fn buy_quote_is_capped_by_the_real_reserves() {
+        assert_eq!(
+            curve().buy_quote(1_000_000_000_000_000, FEE_BASIS_POINTS),
+            793_100_000_000_000
+        );
+    }
+
+    #[test]
+    fn buy_cost_rounds_up_and_adds_the_fee() {
+        assert_eq!(
+            curve().buy_cost(10_000_000_000_000, FEE_BASIS_POINTS),
+            285_042_333
+        );
+        assert_eq!(curve().buy_cost(0, FEE_BASIS_POINTS), 0);
+        // Buying back a quote never costs more than was spent on it
+        assert_eq!(
+            curve().buy_cost(34_281_150_129_545, FEE_BASIS_POINTS),
+            999_999_999
+        );
+    }
+
+    #[test]
+    fn buy_cost_of_the_whole_reserve_saturates() {
+        assert_eq!(
+            curve().buy_cost(2_146_000_000_000_000, FEE_BASIS_POINTS),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn sell_quote_takes_the_fee_off_the_output() {
+        assert_eq!(
+            curve().sell_quote(10_000_000_000_000, FEE_BASIS_POINTS),
+            274_238_227
+        );
+        assert_eq!(curve().sell_quote(10_000_000_000_000, 0), 277_008_310);
+        assert_eq!(curve().sell_quote(0, FEE_BASIS_POINTS), 0);
+    }
+
+    #[test]
+    fn from_account_data_reads_the_reserves() {
+        let data = account_data(
+            [
+                1_073_000_000_000_000,
+                30_000_000_000,
+                793_100_000_000_000,
+                0,
+                1_000_000_000_000_000,
+            ],
+            true,
+        );
+        let curve = BondingCurve::from_account_data(&data).unwrap();
+        assert_eq!(curve.virtual_token_reserves, 1_073_000_000_000_000);
+        assert_eq!(curve.virtual_sol_reserves, 30_000_000_000);
+        assert_eq!(curve.real_token_reserves, 793_100_000_000_000);
+        assert_eq!(curve.token_total_supply, 1_000_000_000_000_000);
+        assert!(curve.complete);
+    }
+
+    #[test]
+    fn from_account_data_refuses_zero_reserves() {
+        let data = account_data([0; 5], false);
+        assert!(BondingCurve::from_account_data(&data).is_err());
+        let data = account_data([1_073_000_000_000_000, 0, 0, 0, 0], false);
+        assert!(BondingCurve::from_account_data(&data).is_err());
+        assert!(BondingCurve::from_account_data(&[0; 40]).is_err());
+    }
+}
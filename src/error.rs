@@ -0,0 +1,225 @@
+use colored::*;
+use solana_client::client_error::ClientError;
+use solana_remote_wallet::remote_wallet::RemoteWalletError;
+use solana_sdk::native_token::lamports_to_sol;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::SignerError;
+use solana_sdk::transaction::TransactionError;
+use std::error::Error;
+
+use crate::transaction::{Abandoned, BlockhashExpired};
+
+// Exit codes besides 0 for success, 2 for bad usage and 130 when a shutdown
+// timed out
+pub const EXIT_OTHER: u8 = 1;
+pub const EXIT_TRANSACTION_FAILED: u8 = 3;
+pub const EXIT_NOT_LANDED: u8 = 4;
+pub const EXIT_POLICY: u8 = 5;
+pub const EXIT_INSUFFICIENT_FUNDS: u8 = 6;
+pub const EXIT_API: u8 = 7;
+pub const EXIT_RPC: u8 = 8;
+pub const EXIT_SIGNING: u8 = 9;
+
+// What went wrong, told apart so callers can retry, report or exit on it
+#[derive(Debug, thiserror::Error)]
+pub enum BotError {
+    // An HTTP API (degen.fund, Jupiter, price feeds...) failed or refused
+    #[error("{}", format!("{} API error: {}", .service, .message).red())]
+    Api {
+        service: String,
+        status: Option<u16>,
+        message: String,
+    },
+    // The Solana RPC failed or rejected a request or transaction, boxed as
+    // the client error is large
+    #[error("{}", format!("RPC error: {}", .0).red())]
+    Rpc(Box<ClientError>),
+    // The wallet could not sign, from a bad key to a Ledger refusing
+    #[error("{}", format!("Signing failed: {}", .0).red())]
+    Signing(Box<SignerError>),
+    // A safety check refused the trade
+    #[error("{}", .0.red())]
+    Policy(String),
+    #[error(
+        "{}",
+        format!(
+            "Insufficient SOL in {}: {:.6} SOL needed, {:.6} SOL held",
+            .wallet,
+            lamports_to_sol(*.needed),
+            lamports_to_sol(*.available)
+        )
+        .red()
+    )]
+    InsufficientFunds {
+        wallet: Pubkey,
+        needed: u64,
+        available: u64,
+    },
+    // A transaction landed but its execution failed
+    #[error(
+        "{}",
+        format!("Transaction {} failed on-chain: {}", .signature, .error).red()
+    )]
+    TransactionFailed {
+        signature: Signature,
+        error: TransactionError,
+    },
+    // Anything else, most often a message already formatted for the user
+    #[error("{0}")]
+    Other(Box<dyn Error + Send + Sync>),
+}
+
+impl BotError {
+    pub fn api(service: &str, status: Option<u16>, message: impl ToString) -> BotError {
+        BotError::Api {
+            service: service.to_string(),
+            status,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn policy(message: impl ToString) -> BotError {
+        BotError::Policy(message.to_string())
+    }
+
+    // A short name of the variant for machine-readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BotError::Api { .. } => "api",
+            BotError::Rpc(_) => "rpc",
+            BotError::Signing(_) => "signing",
+            BotError::Policy(_) => "policy",
+            BotError::InsufficientFunds { .. } => "insufficient_funds",
+            BotError::TransactionFailed { .. } => "transaction_failed",
+            _ if self.is_not_landed() => "not_landed",
+            BotError::Other(_) => "other",
+        }
+    }
+
+    // What the process exits with, for scripts to branch on
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            BotError::Api { .. } => EXIT_API,
+            BotError::Rpc(_) => EXIT_RPC,
+            BotError::Signing(_) => EXIT_SIGNING,
+            BotError::Policy(_) => EXIT_POLICY,
+            BotError::InsufficientFunds { .. } => EXIT_INSUFFICIENT_FUNDS,
+            BotError::TransactionFailed { .. } => EXIT_TRANSACTION_FAILED,
+            _ if self.is_not_landed() => EXIT_NOT_LANDED,
+            BotError::Other(_) => EXIT_OTHER,
+        }
+    }
+
+    // Sent, but no confirmation came before the blockhash expired or the
+    // shutdown gave up on it
+    fn is_not_landed(&self) -> bool {
+        self.is::<BlockhashExpired>() || self.is::<Abandoned>()
+    }
+
+    // The underlying error when it is of type `E`
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        match self {
+            BotError::Rpc(e) => (&**e as &dyn Error).downcast_ref(),
+            BotError::Signing(e) => (&**e as &dyn Error).downcast_ref(),
+            BotError::Other(e) => e.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn is<E: Error + 'static>(&self) -> bool {
+        self.downcast_ref::<E>().is_some()
+    }
+}
+
+impl From<reqwest::Error> for BotError {
+    fn from(error: reqwest::Error) -> BotError {
+        let service = error.url().and_then(|url| url.host_str()).unwrap_or("HTTP");
+        BotError::api(service, error.status().map(|s| s.as_u16()), &error)
+    }
+}
+
+impl From<ClientError> for BotError {
+    fn from(error: ClientError) -> BotError {
+        BotError::Rpc(Box::new(error))
+    }
+}
+
+impl From<SignerError> for BotError {
+    fn from(error: SignerError) -> BotError {
+        BotError::Signing(Box::new(error))
+    }
+}
+
+impl From<RemoteWalletError> for BotError {
+    fn from(error: RemoteWalletError) -> BotError {
+        BotError::Signing(Box::new(error.into()))
+    }
+}
+
+impl From<String> for BotError {
+    fn from(message: String) -> BotError {
+        BotError::Other(message.into())
+    }
+}
+
+impl From<&str> for BotError {
+    fn from(message: &str) -> BotError {
+        BotError::Other(message.into())
+    }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for BotError {
+    fn from(error: Box<dyn Error + Send + Sync>) -> BotError {
+        BotError::Other(error)
+    }
+}
+
+// Errors of libraries with nothing more to tell than their message
+macro_rules! other_errors {
+    ($($error:ty),* $(,)?) => {
+        $(
+            impl From<$error> for BotError {
+                fn from(error: $error) -> BotError {
+                    BotError::Other(Box::new(error))
+                }
+            }
+        )*
+    };
+}
+
+other_errors!(
+    std::io::Error,
+    std::fmt::Error,
+    std::num::ParseIntError,
+    std::num::ParseFloatError,
+    std::string::FromUtf8Error,
+    std::array::TryFromSliceError,
+    chrono::OutOfRangeError,
+    log::SetLoggerError,
+    clap::Error,
+    keyring::Error,
+    serde_json::Error,
+    toml::de::Error,
+    rusqlite::Error,
+    bincode::Error,
+    bs58::decode::Error,
+    base64::DecodeError,
+    solana_sdk::pubkey::ParsePubkeyError,
+    solana_sdk::signature::ParseSignatureError,
+    solana_sdk::program_error::ProgramError,
+    solana_sdk::message::CompileError,
+    solana_sdk::transport::TransportError,
+    solana_client::pubsub_client::PubsubClientError,
+    solana_tpu_client::tpu_client::TpuSenderError,
+    tokio::task::JoinError,
+    tokio::sync::watch::error::RecvError,
+    tokio_tungstenite::tungstenite::Error,
+    tonic::Status,
+    tonic::transport::Error,
+    tonic::metadata::errors::InvalidMetadataValue,
+    tonic::codegen::http::uri::InvalidUri,
+    tracing::subscriber::SetGlobalDefaultError,
+    BlockhashExpired,
+    Abandoned,
+);
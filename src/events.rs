@@ -0,0 +1,34 @@
+use solana_sdk::pubkey::Pubkey;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::notify::Event;
+
+// Updates kept for a slow subscriber before it starts missing them
+const CAPACITY: usize = 1024;
+
+// What is streamed to control plane clients: every notified trade event and
+// each price a monitored position sees
+#[derive(Clone)]
+pub enum Update {
+    Event(Arc<Event>),
+    Position {
+        mint: Pubkey,
+        price: f64,
+        entry_price: f64,
+    },
+}
+
+fn channel() -> &'static broadcast::Sender<Update> {
+    static CHANNEL: OnceLock<broadcast::Sender<Update>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+pub fn publish(update: Update) {
+    // Sending only fails while nobody is subscribed
+    let _ = channel().send(update);
+}
+
+pub fn subscribe() -> broadcast::Receiver<Update> {
+    channel().subscribe()
+}
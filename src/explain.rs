@@ -0,0 +1,163 @@
+use colored::*;
+use log::info;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget, instruction::Instruction, native_token::lamports_to_sol, pubkey::Pubkey,
+    system_program, transaction::VersionedTransaction,
+};
+
+use crate::curve::{BUY_DISCRIMINATOR, SELL_DISCRIMINATOR};
+use crate::jupiter::JUPITER_PROGRAM_ID;
+use crate::venue::{MOONSHOT_PROGRAM_ID, PUMP_FUN_PROGRAM_ID};
+use crate::wallet::{ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::{message, Result};
+
+// Name of a program the bot knows, `program_id` being the degen.fund one
+pub fn program_name(program: &Pubkey, program_id: Option<&Pubkey>) -> Option<&'static str> {
+    Some(match *program {
+        _ if Some(program) == program_id => "degen.fund",
+        p if p == system_program::id() => "System",
+        p if p == compute_budget::id() => "Compute Budget",
+        TOKEN_PROGRAM_ID => "Token",
+        TOKEN_2022_PROGRAM_ID => "Token-2022",
+        ASSOCIATED_TOKEN_PROGRAM_ID => "Associated Token",
+        PUMP_FUN_PROGRAM_ID => "pump.fun",
+        MOONSHOT_PROGRAM_ID => "Moonshot",
+        JUPITER_PROGRAM_ID => "Jupiter",
+        _ => return None,
+    })
+}
+
+// Print every instruction of `tx` with its program, what it does where that
+// can be decoded, and its accounts, marking `wallet`
+pub async fn print(
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+    wallet: &Pubkey,
+    program_id: Option<&Pubkey>,
+) -> Result<()> {
+    let decompiled = message::decompile(client, &tx.message).await?;
+    info!(
+        "Transaction paid by {} with {} instruction(s):",
+        decompiled.payer.to_string().bright_green(),
+        decompiled.instructions.len()
+    );
+    for (i, ix) in decompiled.instructions.iter().enumerate() {
+        let program = match program_name(&ix.program_id, program_id) {
+            Some(name) => name.bold(),
+            None => format!("Unknown program {}", ix.program_id).red().bold(),
+        };
+        info!("{}. {}: {}", i + 1, program, describe(ix, program_id));
+        for account in &ix.accounts {
+            let flags = format!(
+                "{}{}",
+                if account.is_signer { "s" } else { "-" },
+                if account.is_writable { "w" } else { "-" }
+            );
+            let you = if account.pubkey == *wallet {
+                " (your wallet)".yellow().to_string()
+            } else {
+                String::new()
+            };
+            info!("     {} {}{}", flags.dimmed(), account.pubkey, you);
+        }
+    }
+    Ok(())
+}
+
+fn describe(ix: &Instruction, program_id: Option<&Pubkey>) -> String {
+    let data = &ix.data;
+    let u64_at = |offset: usize| {
+        data.get(offset..offset + 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    let account = |index: usize| {
+        ix.accounts
+            .get(index)
+            .map_or_else(|| "?".to_string(), |account| account.pubkey.to_string())
+    };
+
+    if ix.program_id == system_program::id() {
+        return match (data.get(..4), u64_at(4)) {
+            (Some([2, 0, 0, 0]), Some(lamports)) => format!(
+                "transfer {} SOL from {} to {}",
+                lamports_to_sol(lamports).to_string().yellow(),
+                account(0),
+                account(1)
+            ),
+            (Some([0, 0, 0, 0]), Some(lamports)) => format!(
+                "create account {} funded with {} SOL",
+                account(1),
+                lamports_to_sol(lamports).to_string().yellow()
+            ),
+            (Some([4, 0, 0, 0]), _) => format!("advance durable nonce {}", account(0)),
+            _ => format!("instruction {}", data.first().copied().unwrap_or_default()),
+        };
+    }
+    if ix.program_id == compute_budget::id() {
+        return match (data.first(), data.get(1..5), u64_at(1)) {
+            (Some(2), Some(units), _) => format!(
+                "limit {} compute units",
+                u32::from_le_bytes(units.try_into().unwrap())
+            ),
+            (Some(3), _, Some(price)) => format!("priority fee {} micro-lamports per unit", price),
+            _ => "other setting".to_string(),
+        };
+    }
+    if ix.program_id == ASSOCIATED_TOKEN_PROGRAM_ID {
+        return format!(
+            "create token account {} of {} for mint {}",
+            account(1),
+            account(2),
+            account(3)
+        );
+    }
+    if ix.program_id == TOKEN_PROGRAM_ID || ix.program_id == TOKEN_2022_PROGRAM_ID {
+        return match (data.first(), u64_at(1)) {
+            (Some(3), Some(amount)) => format!(
+                "transfer {} raw tokens from {} to {}",
+                amount,
+                account(0),
+                account(1)
+            ),
+            (Some(12), Some(amount)) => format!(
+                "transfer {} raw tokens from {} to {}",
+                amount,
+                account(0),
+                account(2)
+            ),
+            (Some(8), Some(amount)) => format!("burn {} raw tokens of {}", amount, account(0)),
+            (Some(9), _) => format!("close {}, rent to {}", account(0), account(1)),
+            (Some(17), _) => format!("sync wrapped SOL in {}", account(0)),
+            (Some(tag), _) => format!("instruction {}", tag),
+            _ => "empty instruction".to_string(),
+        };
+    }
+    // The degen.fund and pump.fun curves take (token amount, SOL limit),
+    // Moonshot (token amount, SOL amount)
+    let curve = Some(&ix.program_id) == program_id
+        || ix.program_id == PUMP_FUN_PROGRAM_ID
+        || ix.program_id == MOONSHOT_PROGRAM_ID;
+    if curve {
+        let amounts = u64_at(8).zip(u64_at(16));
+        return match (data.get(..8), amounts) {
+            (Some(disc), Some((tokens, lamports))) if disc == BUY_DISCRIMINATOR => format!(
+                "buy {} raw tokens for up to {} SOL",
+                tokens,
+                lamports_to_sol(lamports).to_string().yellow()
+            ),
+            (Some(disc), Some((tokens, lamports))) if disc == SELL_DISCRIMINATOR => format!(
+                "sell {} raw tokens for at least {} SOL",
+                tokens,
+                lamports_to_sol(lamports).to_string().yellow()
+            ),
+            _ => format!("curve instruction, {} data bytes", data.len()),
+        };
+    }
+    if ix.program_id == JUPITER_PROGRAM_ID {
+        return "swap".to_string();
+    }
+    format!("{} data bytes, not decoded", data.len())
+        .red()
+        .to_string()
+}
@@ -0,0 +1,187 @@
+use base64::Engine;
+use clap::ValueEnum;
+use colored::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+
+use crate::config::Config;
+use crate::transaction::ComputeBudget;
+use crate::{message, Result};
+
+// getRecentPrioritizationFees takes at most 128 accounts
+const MAX_FEE_ACCOUNTS: usize = 128;
+pub const DEFAULT_FEE_PERCENTILE: u8 = 75;
+
+// Where the dynamic compute unit price comes from
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeOracle {
+    /// getRecentPrioritizationFees on the trade's write locked accounts
+    Rpc,
+    /// Helius getPriorityFeeEstimate for the exact transaction
+    Helius,
+}
+
+// The compute budget to apply to `tx`: the configured one, with the unit price
+// taken from the fee oracle when one or a percentile is set. A static
+// compute_unit_price then acts as the minimum.
+pub async fn compute_budget(
+    config: &Config,
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+) -> Result<ComputeBudget> {
+    let mut budget = config.compute_budget;
+    let (oracle, percentile) = match (config.fee_oracle, config.priority_fee_percentile) {
+        (None, None) => return Ok(budget),
+        (oracle, percentile) => (
+            oracle.unwrap_or(FeeOracle::Rpc),
+            percentile.unwrap_or(DEFAULT_FEE_PERCENTILE),
+        ),
+    };
+    let price = match oracle {
+        FeeOracle::Rpc => {
+            let accounts = write_locked(client, tx, config.venue_program_id()).await?;
+            recent_unit_price(client, &accounts, percentile).await?
+        }
+        FeeOracle::Helius => {
+            let url = config.helius_url()?;
+            helius_estimate(url, tx, percentile).await?
+        }
+    };
+    budget.unit_price = Some(price.max(budget.unit_price.unwrap_or(0)));
+    Ok(budget)
+}
+
+// Accounts the venue instruction write locks, or every writable account of
+// the transaction when there is none
+async fn write_locked(
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+    program: Option<Pubkey>,
+) -> Result<Vec<Pubkey>> {
+    let decompiled = message::decompile(client, &tx.message).await?;
+    let trades: Vec<_> = decompiled
+        .instructions
+        .iter()
+        .filter(|ix| Some(ix.program_id) == program)
+        .collect();
+    let instructions = if trades.is_empty() {
+        decompiled.instructions.iter().collect()
+    } else {
+        trades
+    };
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for account in instructions.iter().flat_map(|ix| &ix.accounts) {
+        if account.is_writable && !accounts.contains(&account.pubkey) {
+            accounts.push(account.pubkey);
+        }
+    }
+    accounts.truncate(MAX_FEE_ACCOUNTS);
+    Ok(accounts)
+}
+
+// Unit price in micro-lamports at `percentile` of the recent slots' fees
+async fn recent_unit_price(client: &RpcClient, accounts: &[Pubkey], percentile: u8) -> Result<u64> {
+    let fees: Vec<u64> = client
+        .get_recent_prioritization_fees(accounts)
+        .await?
+        .iter()
+        .map(|fee| fee.prioritization_fee)
+        .collect();
+    Ok(at_percentile(fees, percentile))
+}
+
+// The fee at `percentile` of `fees`, rounding down, 0 without any
+fn at_percentile(mut fees: Vec<u64>, percentile: u8) -> u64 {
+    if fees.is_empty() {
+        return 0;
+    }
+    fees.sort_unstable();
+    fees[(fees.len() - 1) * percentile.min(100) as usize / 100]
+}
+
+// Helius priority level for a percentile, its levels being the 0th, 25th,
+// 50th, 75th, 95th and 100th percentiles
+fn helius_level(percentile: u8) -> &'static str {
+    match percentile {
+        0..=12 => "Min",
+        13..=37 => "Low",
+        38..=62 => "Medium",
+        63..=85 => "High",
+        86..=97 => "VeryHigh",
+        _ => "UnsafeMax",
+    }
+}
+
+// Unit price Helius estimates for landing `tx` at the level matching `percentile`
+async fn helius_estimate(url: &str, tx: &VersionedTransaction, percentile: u8) -> Result<u64> {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(tx)?);
+    let response: Value = reqwest::Client::new()
+        .post(url)
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getPriorityFeeEstimate",
+            "params": [{
+                "transaction": encoded,
+                "options": {
+                    "transactionEncoding": "base64",
+                    "priorityLevel": helius_level(percentile),
+                },
+            }],
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    if let Some(error) = response["error"]["message"].as_str() {
+        return Err(format!("Helius fee estimate failed: {}", error)
+            .red()
+            .to_string()
+            .into());
+    }
+    let estimate = response["result"]["priorityFeeEstimate"]
+        .as_f64()
+        .ok_or("Unexpected answer from getPriorityFeeEstimate")?;
+    Ok(estimate.ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_percentile_picks_from_the_sorted_fees() {
+        let fees = vec![500, 100, 400, 200, 300];
+        assert_eq!(at_percentile(fees.clone(), 0), 100);
+        assert_eq!(at_percentile(fees.clone(), 50), 300);
+        assert_eq!(at_percentile(fees.clone(), 75), 400);
+        assert_eq!(at_percentile(fees.clone(), 99), 400);
+        assert_eq!(at_percentile(fees.clone(), 100), 500);
+        assert_eq!(at_percentile(fees, 255), 500);
+    }
+
+    #[test]
+    fn at_percentile_without_fees_is_zero() {
+        assert_eq!(at_percentile(Vec::new(), 75), 0);
+        assert_eq!(at_percentile(vec![42], 75), 42);
+    }
+
+    #[test]
+    fn helius_level_maps_to_the_nearest_level() {
+        assert_eq!(helius_level(0), "Min");
+        assert_eq!(helius_level(12), "Min");
+        assert_eq!(helius_level(13), "Low");
+        assert_eq!(helius_level(25), "Low");
+        assert_eq!(helius_level(50), "Medium");
+        assert_eq!(helius_level(62), "Medium");
+        assert_eq!(helius_level(63), "High");
+        assert_eq!(helius_level(DEFAULT_FEE_PERCENTILE), "High");
+        assert_eq!(helius_level(86), "VeryHigh");
+        assert_eq!(helius_level(95), "VeryHigh");
+        assert_eq!(helius_level(98), "UnsafeMax");
+        assert_eq!(helius_level(100), "UnsafeMax");
+    }
+}
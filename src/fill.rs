@@ -0,0 +1,83 @@
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, native_token::lamports_to_sol, pubkey::Pubkey,
+    signature::Signature,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+
+use crate::Result;
+
+// What a confirmed trade actually exchanged, read back from the ledger
+pub struct Fill {
+    /// SOL paid or received, excluding the transaction fee
+    pub sol: f64,
+    /// Tokens received or sent, in UI units
+    pub tokens: f64,
+    /// Network fee paid, priority fee included
+    pub fee_lamports: u64,
+}
+
+impl Fill {
+    pub async fn fetch(
+        client: &RpcClient,
+        signature: &Signature,
+        owner: &Pubkey,
+        mint: &Pubkey,
+    ) -> Result<Fill> {
+        let tx = client
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await?;
+        let meta = tx
+            .transaction
+            .meta
+            .ok_or("Transaction has no status meta")?;
+
+        // Our transactions are paid by the trading wallet, account 0
+        let (Some(pre), Some(post)) = (meta.pre_balances.first(), meta.post_balances.first())
+        else {
+            return Err("Transaction has no balances".into());
+        };
+        let lamports = (*post as i128 - *pre as i128 + meta.fee as i128).unsigned_abs();
+
+        let owner = owner.to_string();
+        let mint = mint.to_string();
+        let token_amount = |balances: &OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+            let mut raw = 0u128;
+            let mut decimals = 0;
+            if let OptionSerializer::Some(balances) = balances {
+                for balance in balances {
+                    if balance.mint == mint
+                        && balance.owner.as_ref() == OptionSerializer::Some(&owner)
+                    {
+                        raw += balance.ui_token_amount.amount.parse::<u128>().unwrap_or(0);
+                        decimals = balance.ui_token_amount.decimals;
+                    }
+                }
+            }
+            (raw, decimals)
+        };
+        let (pre_tokens, _) = token_amount(&meta.pre_token_balances);
+        let (post_tokens, decimals) = token_amount(&meta.post_token_balances);
+        let raw_delta = post_tokens.abs_diff(pre_tokens);
+
+        Ok(Fill {
+            sol: lamports_to_sol(lamports as u64),
+            tokens: raw_delta as f64 / 10f64.powi(decimals as i32),
+            fee_lamports: meta.fee,
+        })
+    }
+
+    // Effective SOL per token
+    pub fn price(&self) -> Option<f64> {
+        (self.tokens > 0.0).then(|| self.sol / self.tokens)
+    }
+}
@@ -0,0 +1,225 @@
+use colored::*;
+use futures_util::{stream, Stream, StreamExt};
+use solana_sdk::{pubkey::Pubkey, signature::Signature};
+use std::collections::HashMap;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::{Channel, ClientTlsConfig};
+
+use crate::Result;
+
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+const COMMITMENT_CONFIRMED: i32 = 1;
+
+// The subset of the Yellowstone geyser.proto messages the bot reads, with
+// their field tags. prost skips every other field.
+mod proto {
+    use std::collections::HashMap;
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeRequest {
+        #[prost(map = "string, message", tag = "1")]
+        pub accounts: HashMap<String, FilterAccounts>,
+        #[prost(map = "string, message", tag = "3")]
+        pub transactions: HashMap<String, FilterTransactions>,
+        #[prost(int32, optional, tag = "6")]
+        pub commitment: Option<i32>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FilterAccounts {
+        #[prost(string, repeated, tag = "2")]
+        pub account: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct FilterTransactions {
+        #[prost(bool, optional, tag = "1")]
+        pub vote: Option<bool>,
+        #[prost(bool, optional, tag = "2")]
+        pub failed: Option<bool>,
+        #[prost(string, repeated, tag = "3")]
+        pub account_include: Vec<String>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SubscribeUpdate {
+        #[prost(oneof = "Update", tags = "2, 4")]
+        pub update: Option<Update>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Update {
+        #[prost(message, tag = "2")]
+        Account(UpdateAccount),
+        #[prost(message, tag = "4")]
+        Transaction(UpdateTransaction),
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct UpdateAccount {
+        #[prost(message, optional, tag = "1")]
+        pub account: Option<AccountInfo>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct AccountInfo {
+        #[prost(bytes = "vec", tag = "6")]
+        pub data: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct UpdateTransaction {
+        #[prost(message, optional, tag = "1")]
+        pub transaction: Option<TransactionInfo>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TransactionInfo {
+        #[prost(bytes = "vec", tag = "1")]
+        pub signature: Vec<u8>,
+        #[prost(message, optional, tag = "3")]
+        pub transaction: Option<Transaction>,
+        #[prost(message, optional, tag = "4")]
+        pub meta: Option<TransactionMeta>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Transaction {
+        #[prost(message, optional, tag = "2")]
+        pub message: Option<Message>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct Message {
+        #[prost(bytes = "vec", repeated, tag = "2")]
+        pub account_keys: Vec<Vec<u8>>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct TransactionMeta {
+        #[prost(string, repeated, tag = "6")]
+        pub log_messages: Vec<String>,
+    }
+}
+
+// A confirmed, successful transaction from the stream
+pub struct TransactionUpdate {
+    pub signature: Signature,
+    // Static account keys, in message order
+    pub account_keys: Vec<Pubkey>,
+    pub logs: Vec<String>,
+}
+
+// A Yellowstone gRPC endpoint, authenticated with an x-token when it needs one
+pub struct Geyser {
+    url: String,
+    x_token: Option<String>,
+}
+
+impl Geyser {
+    pub fn new(url: String, x_token: Option<String>) -> Geyser {
+        Geyser { url, x_token }
+    }
+
+    async fn subscribe(
+        &self,
+        request: proto::SubscribeRequest,
+    ) -> Result<tonic::Streaming<proto::SubscribeUpdate>> {
+        let mut endpoint = Channel::from_shared(self.url.clone())?;
+        if self.url.starts_with("https://") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new().with_webpki_roots())?;
+        }
+        let channel = endpoint.connect().await.map_err(|e| {
+            format!("Could not connect to Geyser {}: {}", self.url, e)
+                .red()
+                .to_string()
+        })?;
+        let mut grpc =
+            tonic::client::Grpc::new(channel).max_decoding_message_size(MAX_MESSAGE_SIZE);
+        grpc.ready().await?;
+
+        // The server ends the subscription once the request stream closes
+        let requests = stream::iter([request]).chain(stream::pending());
+        let mut request = tonic::Request::new(requests);
+        if let Some(x_token) = &self.x_token {
+            request.metadata_mut().insert("x-token", x_token.parse()?);
+        }
+        let response = grpc
+            .streaming(
+                request,
+                PathAndQuery::from_static(SUBSCRIBE_PATH),
+                ProstCodec::default(),
+            )
+            .await?;
+        Ok(response.into_inner())
+    }
+
+    // Confirmed, successful non-vote transactions that mention `program`
+    pub async fn transactions(
+        &self,
+        program: &Pubkey,
+    ) -> Result<impl Stream<Item = Result<TransactionUpdate>>> {
+        let filter = proto::FilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: vec![program.to_string()],
+        };
+        let updates = self
+            .subscribe(proto::SubscribeRequest {
+                transactions: HashMap::from([("program".to_string(), filter)]),
+                commitment: Some(COMMITMENT_CONFIRMED),
+                ..Default::default()
+            })
+            .await?;
+        Ok(updates.filter_map(|update| async move {
+            let info = match update {
+                Ok(proto::SubscribeUpdate {
+                    update: Some(proto::Update::Transaction(update)),
+                }) => update.transaction?,
+                Ok(_) => return None,
+                Err(status) => return Some(Err(status.into())),
+            };
+            let signature = Signature::try_from(info.signature.as_slice()).ok()?;
+            let account_keys = info
+                .transaction?
+                .message?
+                .account_keys
+                .iter()
+                .filter_map(|key| Pubkey::try_from(key.as_slice()).ok())
+                .collect();
+            Some(Ok(TransactionUpdate {
+                signature,
+                account_keys,
+                logs: info.meta.map(|meta| meta.log_messages).unwrap_or_default(),
+            }))
+        }))
+    }
+
+    // The data of `account` after every confirmed write to it
+    pub async fn account_updates(
+        &self,
+        account: &Pubkey,
+    ) -> Result<impl Stream<Item = Result<Vec<u8>>>> {
+        let filter = proto::FilterAccounts {
+            account: vec![account.to_string()],
+        };
+        let updates = self
+            .subscribe(proto::SubscribeRequest {
+                accounts: HashMap::from([("account".to_string(), filter)]),
+                commitment: Some(COMMITMENT_CONFIRMED),
+                ..Default::default()
+            })
+            .await?;
+        Ok(updates.filter_map(|update| async move {
+            match update {
+                Ok(proto::SubscribeUpdate {
+                    update: Some(proto::Update::Account(update)),
+                }) => update.account.map(|account| Ok(account.data)),
+                Ok(_) => None,
+                Err(status) => Some(Err(status.into())),
+            }
+        }))
+    }
+}
@@ -0,0 +1,107 @@
+use log::{info, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+use crate::config::Config;
+use crate::wallet::parse_token_account;
+use crate::{BotError, Result};
+
+pub const DEFAULT_TOP_HOLDERS: usize = 10;
+
+// The largest holders of a token, by owner wallet
+pub struct Concentration {
+    pub holders: Vec<(Pubkey, u64)>,
+    pub supply: u64,
+    pub decimals: u8,
+}
+
+impl Concentration {
+    // Share of the supply held by the first `top` holders, in percent
+    pub fn top_pct(&self, top: usize) -> f64 {
+        if self.supply == 0 {
+            return 0.0;
+        }
+        let held: u64 = self
+            .holders
+            .iter()
+            .take(top)
+            .map(|(_, amount)| amount)
+            .sum();
+        held as f64 / self.supply as f64 * 100.0
+    }
+}
+
+// Holders from getTokenLargestAccounts (the top 20 token accounts), summed per
+// owner and leaving out `excluded` owners such as the bonding curve
+pub async fn fetch(
+    client: &RpcClient,
+    mint: &Pubkey,
+    excluded: &[Pubkey],
+) -> Result<Concentration> {
+    let supply = client.get_token_supply(mint).await?;
+    let largest = client.get_token_largest_accounts(mint).await?;
+    let addresses = largest
+        .iter()
+        .map(|account| Pubkey::from_str(&account.address))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+
+    let mut holders: Vec<(Pubkey, u64)> = Vec::new();
+    for account in client
+        .get_multiple_accounts(&addresses)
+        .await?
+        .into_iter()
+        .flatten()
+    {
+        let Some(token_account) = parse_token_account(&account) else {
+            continue;
+        };
+        if excluded.contains(&token_account.owner) {
+            continue;
+        }
+        match holders
+            .iter_mut()
+            .find(|(owner, _)| *owner == token_account.owner)
+        {
+            Some((_, amount)) => *amount += token_account.amount,
+            None => holders.push((token_account.owner, token_account.amount)),
+        }
+    }
+    holders.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+    Ok(Concentration {
+        holders,
+        supply: supply.amount.parse()?,
+        decimals: supply.decimals,
+    })
+}
+
+// The venue's curve, left out of the concentration when it can be resolved
+pub fn excluded_owners(config: &Config, mint: &Pubkey) -> Vec<Pubkey> {
+    match config.venue() {
+        Ok(venue) => vec![venue.curve_address(mint)],
+        Err(_) => {
+            warn!("Without program_id the bonding curve counts as a holder");
+            Vec::new()
+        }
+    }
+}
+
+// Refuse the buy when the top holders own more than the configured share
+pub async fn gate(config: &Config, client: &RpcClient, mint: &Pubkey) -> Result<()> {
+    let Some(max_pct) = config.max_top_holders_pct else {
+        return Ok(());
+    };
+    let concentration = fetch(client, mint, &excluded_owners(config, mint)).await?;
+    let pct = concentration.top_pct(config.top_holders);
+    if pct > max_pct {
+        return Err(BotError::policy(format!(
+            "Refusing to buy, the top {} holders own {:.1}% of the supply (max {:.1}%)",
+            config.top_holders, pct, max_pct
+        )));
+    }
+    info!(
+        "Top {} holders own {:.1}% of the supply",
+        config.top_holders, pct
+    );
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use chrono::Utc;
+use colored::*;
+use dialoguer::Select;
+use log::info;
+use std::io::{self, IsTerminal, Read};
+
+use crate::api::{self, Listing};
+use crate::Result;
+
+// Where the mint of a trade comes from: `-` reads it from stdin, `--clipboard`
+// from the system clipboard, anything else is the mint itself
+pub fn mint(arg: Option<&str>, clipboard: bool) -> Result<String> {
+    let raw = if clipboard {
+        from_clipboard()?
+    } else {
+        match arg {
+            Some("-") => from_stdin()?,
+            Some(mint) => return Ok(mint.to_string()),
+            None => return Err("No mint given".red().to_string().into()),
+        }
+    };
+    // Pasted text often carries whitespace or a trailing newline
+    let mint = raw.split_whitespace().next().unwrap_or_default();
+    if mint.is_empty() {
+        let source = if clipboard { "clipboard" } else { "stdin" };
+        return Err(format!("No mint found on {}", source)
+            .red()
+            .to_string()
+            .into());
+    }
+    Ok(mint.to_string())
+}
+
+fn from_stdin() -> Result<String> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err("Pipe the mint into stdin when passing '-'"
+            .red()
+            .to_string()
+            .into());
+    }
+    let mut contents = String::new();
+    stdin.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+fn from_clipboard() -> Result<String> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(|e| {
+            format!("Could not read the clipboard: {}", e)
+                .red()
+                .to_string()
+                .into()
+        })
+}
+
+// Look a ticker up on degen.fund and let the user pick among the tokens using
+// it, exact ticker matches first
+pub async fn mint_by_symbol(symbol: &str) -> Result<String> {
+    let ticker = symbol.trim_start_matches('$');
+    let mut listings = api::search_tokens(ticker).await?;
+    listings.sort_by_key(|listing| !listing.symbol.eq_ignore_ascii_case(ticker));
+    if listings.is_empty() {
+        return Err(format!("No degen.fund token found for '{}'", symbol)
+            .red()
+            .to_string()
+            .into());
+    }
+    if listings.len() == 1 {
+        let listing = &listings[0];
+        info!("Found {}", describe(listing));
+        return Ok(listing.mint.to_string());
+    }
+
+    let items: Vec<String> = listings.iter().map(describe).collect();
+    let picked = Select::new()
+        .with_prompt(format!("Tokens matching '{}'", symbol))
+        .items(&items)
+        .default(0)
+        .interact_opt()
+        .map_err(|e| {
+            format!(
+                "Could not ask which token to buy ({}), pass its mint instead",
+                e
+            )
+            .red()
+            .to_string()
+        })?;
+    match picked {
+        Some(index) => Ok(listings[index].mint.to_string()),
+        None => Err("Buy cancelled".red().to_string().into()),
+    }
+}
+
+// `$TICKER (Name) mint, 12m old, $40.2k market cap`
+fn describe(listing: &Listing) -> String {
+    let age = match listing.created_at {
+        Some(created_at) => {
+            let minutes = (Utc::now() - created_at).num_minutes().max(0);
+            match minutes {
+                0..=59 => format!("{}m old", minutes),
+                60..=2879 => format!("{}h old", minutes / 60),
+                _ => format!("{}d old", minutes / 1440),
+            }
+        }
+        None => "unknown age".to_string(),
+    };
+    let market_cap = match listing.market_cap_usd {
+        Some(usd) if usd >= 1_000_000.0 => format!("${:.1}M market cap", usd / 1_000_000.0),
+        Some(usd) if usd >= 1_000.0 => format!("${:.1}k market cap", usd / 1_000.0),
+        Some(usd) => format!("${:.0} market cap", usd),
+        None => "unknown market cap".to_string(),
+    };
+    format!(
+        "${} ({}) {}, {}, {}",
+        listing.symbol, listing.name, listing.mint, age, market_cap
+    )
+}
@@ -0,0 +1,275 @@
+use colored::*;
+use futures_util::StreamExt;
+use log::{debug, warn};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::sol_to_lamports,
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::signer::WalletSigner;
+use crate::{nonce, Result};
+
+pub const DEFAULT_BLOCK_ENGINE_URL: &str = "https://mainnet.block-engine.jito.wtf";
+pub const DEFAULT_TIP_CAP_LAMPORTS: u64 = 1_000_000;
+const TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+const TIP_STREAM_URL: &str = "wss://bundles.jito.wtf/api/v1/bundles/tip_stream";
+const TIP_STREAM_RECONNECT: Duration = Duration::from_secs(2);
+// Percentiles of landed tips the tip floor reports
+const TIP_PERCENTILES: [u8; 5] = [25, 50, 75, 95, 99];
+
+// A tip sized from recently landed bundles instead of a fixed amount
+#[derive(Clone, Copy)]
+pub struct DynamicTip {
+    pub percentile: u8,
+    pub cap_lamports: u64,
+}
+
+// Landed tips in lamports at each of TIP_PERCENTILES
+type TipFloor = [u64; 5];
+
+// Submits transactions as Jito bundles with a tip transfer appended
+pub struct JitoClient {
+    http: reqwest::Client,
+    bundles_url: String,
+    tip_lamports: u64,
+    tip_accounts: OnceCell<Vec<Pubkey>>,
+    dynamic_tip: Option<DynamicTip>,
+    // Latest tip floor, kept fresh by the tip stream once it is started
+    tip_floor: Arc<Mutex<Option<TipFloor>>>,
+    tip_stream: OnceCell<()>,
+}
+
+impl JitoClient {
+    pub fn new(block_engine_url: &str, tip_lamports: u64) -> JitoClient {
+        JitoClient {
+            http: reqwest::Client::new(),
+            bundles_url: format!("{}/api/v1/bundles", block_engine_url.trim_end_matches('/')),
+            tip_lamports,
+            tip_accounts: OnceCell::new(),
+            dynamic_tip: None,
+            tip_floor: Arc::new(Mutex::new(None)),
+            tip_stream: OnceCell::new(),
+        }
+    }
+
+    // Tip at a percentile of recent landed tips, the fixed tip being the
+    // minimum and `cap_lamports` the maximum
+    pub fn with_dynamic_tip(mut self, dynamic_tip: DynamicTip) -> JitoClient {
+        self.dynamic_tip = Some(dynamic_tip);
+        self
+    }
+
+    async fn tip(&self) -> u64 {
+        let Some(dynamic) = self.dynamic_tip else {
+            return self.tip_lamports;
+        };
+        self.tip_stream
+            .get_or_init(|| async {
+                tokio::spawn(stream_tip_floor(self.tip_floor.clone()));
+            })
+            .await;
+        // Until the stream delivers, ask the REST endpoint
+        let streamed = *self.tip_floor.lock().unwrap();
+        let floor = match streamed {
+            Some(floor) => Some(floor),
+            None => match fetch_tip_floor(&self.http).await {
+                Ok(floor) => {
+                    *self.tip_floor.lock().unwrap() = Some(floor);
+                    Some(floor)
+                }
+                Err(e) => {
+                    warn!(
+                        "Could not read the Jito tip floor, tipping the fixed amount: {}",
+                        e
+                    );
+                    None
+                }
+            },
+        };
+        let Some(floor) = floor else {
+            return self.tip_lamports;
+        };
+        let index = TIP_PERCENTILES
+            .iter()
+            .position(|&percentile| percentile >= dynamic.percentile)
+            .unwrap_or(TIP_PERCENTILES.len() - 1);
+        let tip = floor[index]
+            .max(self.tip_lamports)
+            .min(dynamic.cap_lamports);
+        debug!(
+            "Jito tip {} lamports at the {}th percentile",
+            tip, TIP_PERCENTILES[index]
+        );
+        tip
+    }
+
+    // Send `tx` followed by a tip transaction from `payer` as a single bundle.
+    // Returns the signature of `tx`, the bundle lands or fails as a whole.
+    pub async fn send_bundle(
+        &self,
+        client: &RpcClient,
+        tx: &VersionedTransaction,
+        payer: &WalletSigner,
+    ) -> Result<Signature> {
+        let tip_account = self.tip_account(&tx.signatures[0]).await?;
+        let tip = self.tip().await;
+        // A durable nonce transaction carries the nonce instead of a blockhash
+        let blockhash = match nonce::durable_nonce(&tx.message) {
+            Some(_) => client.get_latest_blockhash().await?,
+            None => *tx.message.recent_blockhash(),
+        };
+        let tip_tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &tip_account,
+                tip,
+            )],
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+
+        let encoded = vec![
+            bs58::encode(bincode::serialize(tx)?).into_string(),
+            bs58::encode(bincode::serialize(&tip_tx)?).into_string(),
+        ];
+        self.call("sendBundle", json!([encoded])).await?;
+
+        Ok(tx.signatures[0])
+    }
+
+    // Pick one of the block engine's tip accounts, spread by signature
+    async fn tip_account(&self, signature: &Signature) -> Result<Pubkey> {
+        let accounts = self
+            .tip_accounts
+            .get_or_try_init(|| async {
+                let result = self.call("getTipAccounts", json!([])).await?;
+                result
+                    .as_array()
+                    .ok_or("Unexpected getTipAccounts response")?
+                    .iter()
+                    .map(|account| Ok(Pubkey::from_str(account.as_str().unwrap_or_default())?))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .await?;
+        if accounts.is_empty() {
+            return Err("Block engine returned no tip accounts"
+                .red()
+                .to_string()
+                .into());
+        }
+        Ok(accounts[signature.as_ref()[0] as usize % accounts.len()])
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response: Value = self
+            .http
+            .post(&self.bundles_url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(format!("Jito {} failed: {}", method, error)
+                .red()
+                .to_string()
+                .into());
+        }
+        Ok(response["result"].clone())
+    }
+}
+
+// The tip floor endpoints answer a one element array of percentiles in SOL
+fn parse_tip_floor(response: &Value) -> Result<TipFloor> {
+    let entry = &response[0];
+    let mut floor = [0; 5];
+    for (tip, percentile) in floor.iter_mut().zip(TIP_PERCENTILES) {
+        let key = format!("landed_tips_{}th_percentile", percentile);
+        let sol = entry[key.as_str()]
+            .as_f64()
+            .ok_or("Unexpected answer from the Jito tip floor")?;
+        *tip = sol_to_lamports(sol);
+    }
+    Ok(floor)
+}
+
+async fn fetch_tip_floor(http: &reqwest::Client) -> Result<TipFloor> {
+    let response: Value = http.get(TIP_FLOOR_URL).send().await?.json().await?;
+    parse_tip_floor(&response)
+}
+
+// Keep `latest` updated from the tip stream for as long as the bot runs,
+// reconnecting whenever the stream drops
+async fn stream_tip_floor(latest: Arc<Mutex<Option<TipFloor>>>) {
+    loop {
+        match tokio_tungstenite::connect_async(TIP_STREAM_URL).await {
+            Ok((mut stream, _)) => {
+                while let Some(message) = stream.next().await {
+                    let text = match message {
+                        Ok(Message::Text(text)) => text,
+                        Ok(_) => continue,
+                        Err(e) => {
+                            debug!("Jito tip stream dropped: {}", e);
+                            break;
+                        }
+                    };
+                    let floor = serde_json::from_str(&text)
+                        .map_err(|e| e.into())
+                        .and_then(|response| parse_tip_floor(&response));
+                    match floor {
+                        Ok(floor) => *latest.lock().unwrap() = Some(floor),
+                        Err(e) => debug!("Unreadable Jito tip stream message: {}", e),
+                    }
+                }
+            }
+            Err(e) => debug!("Could not connect to the Jito tip stream: {}", e),
+        }
+        tokio::time::sleep(TIP_STREAM_RECONNECT).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tip_floor_reads_every_percentile_in_lamports() {
+        let response = json!([{
+            "time": "2024-09-01T12:00:00Z",
+            "landed_tips_25th_percentile": 0.000001,
+            "landed_tips_50th_percentile": 0.00001,
+            "landed_tips_75th_percentile": 0.0001,
+            "landed_tips_95th_percentile": 0.001,
+            "landed_tips_99th_percentile": 0.01,
+            "ema_landed_tips_50th_percentile": 0.00002
+        }]);
+        assert_eq!(
+            parse_tip_floor(&response).unwrap(),
+            [1_000, 10_000, 100_000, 1_000_000, 10_000_000]
+        );
+    }
+
+    #[test]
+    fn parse_tip_floor_refuses_a_missing_percentile() {
+        let response = json!([{
+            "landed_tips_25th_percentile": 0.000001,
+            "landed_tips_50th_percentile": 0.00001,
+            "landed_tips_75th_percentile": 0.0001,
+            "landed_tips_95th_percentile": 0.001
+        }]);
+        assert!(parse_tip_floor(&response).is_err());
+        assert!(parse_tip_floor(&json!([])).is_err());
+        assert!(parse_tip_floor(&json!({"error": "rate limited"})).is_err());
+    }
+}
@@ -0,0 +1,752 @@
+use chrono::{DateTime, Utc};
+use colored::*;
+use rusqlite::{params, Connection};
+use solana_sdk::{
+    clock::Slot, native_token::sol_to_lamports, pubkey::Pubkey, signature::Signature,
+};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::fill::Fill;
+use crate::prepare::Trade;
+use crate::Result;
+
+pub const DEFAULT_JOURNAL_PATH: &str = "trades.db";
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS trades (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    side TEXT NOT NULL,
+    -- Lamports spent for buys, raw token units for sells
+    amount INTEGER NOT NULL,
+    signature TEXT NOT NULL UNIQUE,
+    -- 'sent' or 'pending', then 'confirmed', 'failed' or 'expired' when it
+    -- never landed
+    status TEXT NOT NULL,
+    slot INTEGER,
+    fee_lamports INTEGER,
+    sol_amount REAL,
+    token_amount REAL,
+    price REAL,
+    error TEXT
+);
+CREATE INDEX IF NOT EXISTS trades_mint ON trades (mint);
+-- Every signature a trade went out under, fee escalations and rebuilds
+-- included. The trade row carries the latest one, or the one that landed.
+CREATE TABLE IF NOT EXISTS trade_attempts (
+    signature TEXT PRIMARY KEY,
+    trade_id INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    -- 'sent', then 'landed' or 'superseded' once another attempt replaced it
+    -- or landed
+    status TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS dca_plans (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    tranche_lamports INTEGER NOT NULL,
+    tranches INTEGER NOT NULL,
+    done INTEGER NOT NULL DEFAULT 0,
+    interval_secs INTEGER NOT NULL,
+    max_price REAL,
+    last_tranche_at TEXT,
+    -- 'active', then 'done' once every tranche is bought or 'cancelled'
+    status TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS limit_orders (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    lamports INTEGER NOT NULL,
+    below_price REAL,
+    below_market_cap REAL,
+    -- 'open', 'filling' while bought, then 'filled', 'failed' or 'cancelled'
+    status TEXT NOT NULL,
+    error TEXT
+);
+CREATE TABLE IF NOT EXISTS sell_ladder_fills (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    multiple REAL NOT NULL,
+    pct REAL NOT NULL,
+    UNIQUE (mint, multiple)
+);
+CREATE TABLE IF NOT EXISTS moon_bags (
+    mint TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    pct REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transfers (
+    id INTEGER PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    wallet TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    -- NULL for SOL
+    mint TEXT,
+    -- Lamports for SOL, raw token units otherwise
+    amount INTEGER NOT NULL,
+    signature TEXT NOT NULL UNIQUE
+);
+";
+
+// A confirmed trade whose fill could be read back
+pub struct FilledTrade {
+    pub mint: String,
+    pub side: String,
+    pub sol_amount: f64,
+    pub token_amount: f64,
+    pub fee_lamports: u64,
+}
+
+// A journaled trade as it stands
+pub struct RecordedTrade {
+    pub updated_at: String,
+    pub wallet: String,
+    pub mint: String,
+    pub side: String,
+    pub amount: u64,
+    pub signature: String,
+    pub status: String,
+    pub sol_amount: Option<f64>,
+    pub price: Option<f64>,
+    pub error: Option<String>,
+}
+
+// A DCA buy split in tranches, with how many were bought so far
+pub struct DcaPlan {
+    pub id: i64,
+    pub mint: String,
+    pub tranche_lamports: u64,
+    pub tranches: u32,
+    pub done: u32,
+    pub interval: Duration,
+    pub max_price: Option<f64>,
+    pub last_tranche_at: Option<DateTime<Utc>>,
+}
+
+// A resting buy of `lamports` once the curve price or market cap (in SOL)
+// falls below the limit
+pub struct LimitOrder {
+    pub id: i64,
+    pub created_at: String,
+    pub mint: String,
+    pub lamports: u64,
+    pub below_price: Option<f64>,
+    pub below_market_cap: Option<f64>,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+// Every trade sent or attempted, kept in a local SQLite database
+pub struct Journal {
+    conn: Mutex<Connection>,
+}
+
+impl Journal {
+    pub fn open(path: &str) -> Result<Journal> {
+        let conn = Connection::open(path).map_err(|e| {
+            format!("Could not open trade journal {}: {}", path, e)
+                .red()
+                .to_string()
+        })?;
+        // Several bot processes may share the journal
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Journal {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    pub fn record_sent(&self, wallet: &Pubkey, trade: &Trade, signature: &Signature) -> Result<()> {
+        self.upsert(wallet, trade, signature, "sent", None)
+    }
+
+    // Another attempt at the trade `previous` was sent for, which the trade
+    // row follows from now on. With `superseded` the earlier attempts can no
+    // longer land.
+    pub fn record_resent(
+        &self,
+        previous: &Signature,
+        signature: &Signature,
+        superseded: bool,
+    ) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        if superseded {
+            conn.execute(
+                "UPDATE trade_attempts SET status = 'superseded'
+                 WHERE trade_id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1)",
+                params![previous.to_string()],
+            )?;
+        }
+        conn.execute(
+            "INSERT OR IGNORE INTO trade_attempts (signature, trade_id, created_at, status)
+             SELECT ?2, trade_id, ?3, 'sent' FROM trade_attempts WHERE signature = ?1",
+            params![previous.to_string(), signature.to_string(), now],
+        )?;
+        conn.execute(
+            "UPDATE trades SET signature = ?2, status = 'sent', updated_at = ?3
+             WHERE id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1)",
+            params![previous.to_string(), signature.to_string(), now],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_failed(
+        &self,
+        wallet: &Pubkey,
+        trade: &Trade,
+        signature: &Signature,
+        error: &str,
+    ) -> Result<()> {
+        self.upsert(wallet, trade, signature, "failed", Some(error))
+    }
+
+    // Sent but still landing when the bot stopped, the outcome is unknown
+    pub fn record_pending(&self, signature: &Signature) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE trades SET status = 'pending', updated_at = ?2
+             WHERE signature = ?1
+                OR id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1)",
+            params![signature.to_string(), Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn record_confirmed(
+        &self,
+        signature: &Signature,
+        slot: Slot,
+        fill: Option<&Fill>,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        // Whichever attempt landed, the others of its trade never will
+        conn.execute(
+            "UPDATE trade_attempts
+             SET status = CASE WHEN signature = ?1 THEN 'landed' ELSE 'superseded' END
+             WHERE trade_id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1)",
+            params![signature.to_string()],
+        )?;
+        conn.execute(
+            "UPDATE trades SET signature = ?1, status = 'confirmed', updated_at = ?2, slot = ?3,
+                fee_lamports = ?4, sol_amount = ?5, token_amount = ?6, price = ?7
+             WHERE signature = ?1
+                OR id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1)",
+            params![
+                signature.to_string(),
+                Utc::now().to_rfc3339(),
+                slot as i64,
+                fill.map(|fill| fill.fee_lamports as i64),
+                fill.map(|fill| fill.sol),
+                fill.map(|fill| fill.tokens),
+                fill.and_then(Fill::price),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // A trade that never landed before its blockhash expired
+    pub fn record_expired(&self, signature: &Signature) -> Result<()> {
+        self.settle(signature, "expired", None)
+    }
+
+    // A trade that landed but failed, found after the bot stopped watching it
+    pub fn record_reverted(&self, signature: &Signature, error: &str) -> Result<()> {
+        self.settle(signature, "failed", Some(error))
+    }
+
+    // The signatures of the buys still landing that were last sent before
+    // `before`, every attempt of them
+    pub fn stale_landing(&self, before: DateTime<Utc>) -> Result<Vec<Signature>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT signature FROM trades
+             WHERE side = 'buy' AND status IN ('sent', 'pending') AND updated_at < ?1
+             UNION
+             SELECT a.signature FROM trade_attempts a JOIN trades t ON t.id = a.trade_id
+             WHERE t.side = 'buy' AND t.status IN ('sent', 'pending') AND t.updated_at < ?1",
+        )?;
+        let signatures = statement
+            .query_map(params![before.to_rfc3339()], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(signatures
+            .iter()
+            .filter_map(|signature| signature.parse().ok())
+            .collect())
+    }
+
+    // Whether a buy of `mint` was sent before, whatever became of it since
+    // except a failure
+    pub fn has_bought(&self, mint: &Pubkey) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM trades WHERE mint = ?1 AND side = 'buy' AND status NOT IN ('failed', 'expired')",
+            params![mint.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    // Whether `wallet` sent a buy of `mint` before that did not fail
+    pub fn has_bought_with(&self, wallet: &Pubkey, mint: &Pubkey) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM trades
+             WHERE wallet = ?1 AND mint = ?2 AND side = 'buy' AND status NOT IN ('failed', 'expired')",
+            params![wallet.to_string(), mint.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    // Lamports spent by the buys sent since `since` that did not fail or
+    // expire, every attempt at a buy counted once
+    pub fn spent_since(&self, since: DateTime<Utc>) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let spent: Option<i64> = conn.query_row(
+            "SELECT SUM(amount) FROM trades
+             WHERE side = 'buy' AND status NOT IN ('failed', 'expired') AND created_at >= ?1",
+            params![since.to_rfc3339()],
+            |row| row.get(0),
+        )?;
+        Ok(spent.unwrap_or(0) as u64)
+    }
+
+    // Lamports the buys of `mint` that did not fail spent, less the SOL its
+    // confirmed sells brought back
+    pub fn net_spent(&self, mint: &Pubkey) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let (spent, received): (Option<i64>, Option<f64>) = conn.query_row(
+            "SELECT SUM(CASE WHEN side = 'buy' AND status NOT IN ('failed', 'expired') THEN amount END),
+                SUM(CASE WHEN side = 'sell' AND status = 'confirmed' THEN sol_amount END)
+             FROM trades WHERE mint = ?1",
+            params![mint.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let received = sol_to_lamports(received.unwrap_or(0.0).max(0.0));
+        Ok((spent.unwrap_or(0) as u64).saturating_sub(received))
+    }
+
+    // Each token bought since `since` by a buy that did not fail, with the
+    // time of its latest buy
+    pub fn buys_since(&self, since: DateTime<Utc>) -> Result<Vec<(String, DateTime<Utc>)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT mint, MAX(created_at) FROM trades
+             WHERE side = 'buy' AND status NOT IN ('failed', 'expired') AND created_at >= ?1
+             GROUP BY mint",
+        )?;
+        let buys = statement
+            .query_map(params![since.to_rfc3339()], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(buys
+            .into_iter()
+            .filter_map(|(mint, at)| {
+                let at = DateTime::parse_from_rfc3339(&at).ok()?;
+                Some((mint, at.with_timezone(&Utc)))
+            })
+            .collect())
+    }
+
+    // Lamports of the buys of `mint` sent but not confirmed yet
+    pub fn landing_spend(&self, mint: &Pubkey) -> Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let spent: Option<i64> = conn.query_row(
+            "SELECT SUM(amount) FROM trades
+             WHERE mint = ?1 AND side = 'buy' AND status IN ('sent', 'pending')",
+            params![mint.to_string()],
+            |row| row.get(0),
+        )?;
+        Ok(spent.unwrap_or(0) as u64)
+    }
+
+    // Every (wallet, mint) that sent a buy which did not fail
+    pub fn bought_positions(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT DISTINCT wallet, mint FROM trades
+             WHERE side = 'buy' AND status NOT IN ('failed', 'expired')",
+        )?;
+        let positions = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(positions)
+    }
+
+    // The unfinished DCA plan of `mint`, if one was started
+    pub fn active_dca(&self, mint: &Pubkey) -> Result<Option<DcaPlan>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT id, mint, tranche_lamports, tranches, done, interval_secs, max_price,
+                last_tranche_at
+             FROM dca_plans WHERE mint = ?1 AND status = 'active' ORDER BY id DESC LIMIT 1",
+        )?;
+        let mut plans = statement.query_map(params![mint.to_string()], |row| {
+            Ok(DcaPlan {
+                id: row.get(0)?,
+                mint: row.get(1)?,
+                tranche_lamports: row.get::<_, i64>(2)? as u64,
+                tranches: row.get(3)?,
+                done: row.get(4)?,
+                interval: Duration::from_secs(row.get::<_, i64>(5)? as u64),
+                max_price: row.get(6)?,
+                last_tranche_at: row
+                    .get::<_, Option<String>>(7)?
+                    .and_then(|time| DateTime::parse_from_rfc3339(&time).ok())
+                    .map(|time| time.with_timezone(&Utc)),
+            })
+        })?;
+        Ok(plans.next().transpose()?)
+    }
+
+    pub fn start_dca(
+        &self,
+        mint: &Pubkey,
+        tranche_lamports: u64,
+        tranches: u32,
+        interval: Duration,
+        max_price: Option<f64>,
+    ) -> Result<DcaPlan> {
+        let now = Utc::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO dca_plans (created_at, updated_at, mint, tranche_lamports, tranches,
+                interval_secs, max_price, status)
+             VALUES (?1, ?1, ?2, ?3, ?4, ?5, ?6, 'active')",
+            params![
+                now,
+                mint.to_string(),
+                tranche_lamports as i64,
+                tranches,
+                interval.as_secs() as i64,
+                max_price,
+            ],
+        )?;
+        Ok(DcaPlan {
+            id: conn.last_insert_rowid(),
+            mint: mint.to_string(),
+            tranche_lamports,
+            tranches,
+            done: 0,
+            interval,
+            max_price,
+            last_tranche_at: None,
+        })
+    }
+
+    // Count one more tranche bought, finishing the plan with the last one
+    pub fn record_tranche(&self, plan: &mut DcaPlan) -> Result<()> {
+        plan.done += 1;
+        let now = Utc::now();
+        plan.last_tranche_at = Some(now);
+        let status = if plan.done >= plan.tranches {
+            "done"
+        } else {
+            "active"
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dca_plans SET done = ?2, last_tranche_at = ?3, updated_at = ?3, status = ?4
+             WHERE id = ?1",
+            params![plan.id, plan.done, now.to_rfc3339(), status],
+        )?;
+        Ok(())
+    }
+
+    pub fn cancel_dca(&self, plan: &DcaPlan) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE dca_plans SET status = 'cancelled', updated_at = ?2 WHERE id = ?1",
+            params![plan.id, Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    pub fn add_limit_order(
+        &self,
+        mint: &Pubkey,
+        lamports: u64,
+        below_price: Option<f64>,
+        below_market_cap: Option<f64>,
+    ) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO limit_orders (created_at, updated_at, mint, lamports, below_price,
+                below_market_cap, status)
+             VALUES (?1, ?1, ?2, ?3, ?4, ?5, 'open')",
+            params![
+                Utc::now().to_rfc3339(),
+                mint.to_string(),
+                lamports as i64,
+                below_price,
+                below_market_cap,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    // Limit orders, only the open ones unless `all`, oldest first
+    pub fn limit_orders(&self, all: bool) -> Result<Vec<LimitOrder>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT id, created_at, mint, lamports, below_price, below_market_cap, status, error
+             FROM limit_orders WHERE ?1 OR status = 'open' ORDER BY id",
+        )?;
+        let orders = statement
+            .query_map(params![all], |row| {
+                Ok(LimitOrder {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    mint: row.get(2)?,
+                    lamports: row.get::<_, i64>(3)? as u64,
+                    below_price: row.get(4)?,
+                    below_market_cap: row.get(5)?,
+                    status: row.get(6)?,
+                    error: row.get(7)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(orders)
+    }
+
+    // Move an order from status `from` to `to`, returning false when it was
+    // not in `from` anymore, taken by another process or cancelled meanwhile
+    pub fn move_limit_order(
+        &self,
+        id: i64,
+        from: &str,
+        to: &str,
+        error: Option<&str>,
+    ) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let changed = conn.execute(
+            "UPDATE limit_orders SET status = ?3, error = ?4, updated_at = ?5
+             WHERE id = ?1 AND status = ?2",
+            params![id, from, to, error, Utc::now().to_rfc3339()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    // Multiples of the sell ladder already sold for `mint`, with their percent
+    pub fn sell_ladder_fills(&self, mint: &Pubkey) -> Result<Vec<(f64, f64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement =
+            conn.prepare("SELECT multiple, pct FROM sell_ladder_fills WHERE mint = ?1")?;
+        let fills = statement
+            .query_map(params![mint.to_string()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(fills)
+    }
+
+    pub fn record_sell_ladder_fill(&self, mint: &Pubkey, multiple: f64, pct: f64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR IGNORE INTO sell_ladder_fills (created_at, mint, multiple, pct)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![Utc::now().to_rfc3339(), mint.to_string(), multiple, pct],
+        )?;
+        Ok(())
+    }
+
+    // Forget the sell ladder fills and moon bag of `mint`, as a new position starts
+    pub fn clear_position(&self, mint: &Pubkey) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM sell_ladder_fills WHERE mint = ?1",
+            params![mint.to_string()],
+        )?;
+        conn.execute(
+            "DELETE FROM moon_bags WHERE mint = ?1",
+            params![mint.to_string()],
+        )?;
+        Ok(())
+    }
+
+    // SOL, or a token when `mint` is given, sent out of the wallet
+    pub fn record_transfer(
+        &self,
+        wallet: &Pubkey,
+        recipient: &Pubkey,
+        mint: Option<&Pubkey>,
+        amount: u64,
+        signature: &Signature,
+    ) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO transfers (created_at, wallet, recipient, mint, amount, signature)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                Utc::now().to_rfc3339(),
+                wallet.to_string(),
+                recipient.to_string(),
+                mint.map(|mint| mint.to_string()),
+                amount as i64,
+                signature.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // The position of `mint` was sold down to a moon bag of `pct` percent
+    pub fn record_moon_bag(&self, mint: &Pubkey, pct: f64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO moon_bags (mint, created_at, pct) VALUES (?1, ?2, ?3)",
+            params![mint.to_string(), Utc::now().to_rfc3339(), pct],
+        )?;
+        Ok(())
+    }
+
+    // Percent kept as a moon bag when the position of `mint` is down to one
+    pub fn moon_bag(&self, mint: &str) -> Result<Option<f64>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare("SELECT pct FROM moon_bags WHERE mint = ?1")?;
+        let mut bags = statement.query_map(params![mint], |row| row.get(0))?;
+        Ok(bags.next().transpose()?)
+    }
+
+    // Average price paid per token over the confirmed buys of `mint`
+    pub fn entry_price(&self, mint: &Pubkey) -> Result<Option<f64>> {
+        let conn = self.conn.lock().unwrap();
+        let (sol, tokens): (Option<f64>, Option<f64>) = conn.query_row(
+            "SELECT SUM(sol_amount), SUM(token_amount) FROM trades
+             WHERE mint = ?1 AND side = 'buy' AND status = 'confirmed'
+                AND sol_amount IS NOT NULL AND token_amount IS NOT NULL",
+            params![mint.to_string()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        Ok(match (sol, tokens) {
+            (Some(sol), Some(tokens)) if tokens > 0.0 => Some(sol / tokens),
+            _ => None,
+        })
+    }
+
+    // Confirmed trades with a known fill, oldest first
+    pub fn filled_trades(&self) -> Result<Vec<FilledTrade>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT mint, side, sol_amount, token_amount, fee_lamports FROM trades
+             WHERE status = 'confirmed' AND sol_amount IS NOT NULL AND token_amount IS NOT NULL
+             ORDER BY slot, id",
+        )?;
+        let trades = statement
+            .query_map([], |row| {
+                Ok(FilledTrade {
+                    mint: row.get(0)?,
+                    side: row.get(1)?,
+                    sol_amount: row.get(2)?,
+                    token_amount: row.get(3)?,
+                    fee_lamports: row.get::<_, Option<i64>>(4)?.unwrap_or(0) as u64,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(trades)
+    }
+
+    // The latest `limit` trades, newest first
+    pub fn recent_trades(&self, limit: usize) -> Result<Vec<RecordedTrade>> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(
+            "SELECT updated_at, wallet, mint, side, amount, signature, status, sol_amount, price,
+                    error
+             FROM trades ORDER BY id DESC LIMIT ?1",
+        )?;
+        let trades = statement
+            .query_map(params![limit as i64], |row| {
+                Ok(RecordedTrade {
+                    updated_at: row.get(0)?,
+                    wallet: row.get(1)?,
+                    mint: row.get(2)?,
+                    side: row.get(3)?,
+                    amount: row.get::<_, i64>(4)? as u64,
+                    signature: row.get(5)?,
+                    status: row.get(6)?,
+                    sol_amount: row.get(7)?,
+                    price: row.get(8)?,
+                    error: row.get(9)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(trades)
+    }
+
+    // Settle a trade still landing, leaving one settled since alone
+    fn settle(&self, signature: &Signature, status: &str, error: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE trades SET status = ?2, error = ?3, updated_at = ?4
+             WHERE status IN ('sent', 'pending')
+                AND (signature = ?1
+                    OR id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1))",
+            params![
+                signature.to_string(),
+                status,
+                error,
+                Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn upsert(
+        &self,
+        wallet: &Pubkey,
+        trade: &Trade,
+        signature: &Signature,
+        status: &str,
+        error: Option<&str>,
+    ) -> Result<()> {
+        let (side, amount) = match trade {
+            Trade::Buy { lamports, .. } => ("buy", *lamports),
+            Trade::Sell { token_amount, .. } => ("sell", *token_amount),
+        };
+        let now = Utc::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        // Any attempt's signature stands for its trade
+        let updated = conn.execute(
+            "UPDATE trades SET updated_at = ?2, status = ?3, error = ?4
+             WHERE id = (SELECT trade_id FROM trade_attempts WHERE signature = ?1)",
+            params![signature.to_string(), now, status, error],
+        )?;
+        if updated > 0 {
+            return Ok(());
+        }
+        conn.execute(
+            "INSERT INTO trades (created_at, updated_at, wallet, mint, side, amount, signature, status, error)
+             VALUES (?1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT (signature) DO UPDATE SET
+                updated_at = excluded.updated_at, status = excluded.status, error = excluded.error",
+            params![
+                now,
+                wallet.to_string(),
+                trade.mint().to_string(),
+                side,
+                amount as i64,
+                signature.to_string(),
+                status,
+                error,
+            ],
+        )?;
+        conn.execute(
+            "INSERT OR IGNORE INTO trade_attempts (signature, trade_id, created_at, status)
+             SELECT signature, id, ?2, 'sent' FROM trades WHERE signature = ?1",
+            params![signature.to_string(), now],
+        )?;
+        Ok(())
+    }
+}
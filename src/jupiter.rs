@@ -0,0 +1,82 @@
+use base64::Engine;
+use serde_json::{json, Value};
+use solana_sdk::{hash::Hash, pubkey, pubkey::Pubkey, transaction::VersionedTransaction};
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+use crate::{BotError, Result};
+
+pub const JUPITER_PROGRAM_ID: Pubkey = pubkey!("JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4");
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+const QUOTE_URL: &str = "https://lite-api.jup.ag/swap/v1/quote";
+const SWAP_URL: &str = "https://lite-api.jup.ag/swap/v1/swap";
+
+// A Jupiter route, kept whole since the swap endpoint takes it back as is
+pub struct Quote {
+    pub out_amount: u64,
+    response: Value,
+}
+
+pub async fn quote(
+    input_mint: &Pubkey,
+    output_mint: &Pubkey,
+    amount: u64,
+    slippage_bps: u64,
+) -> Result<Quote> {
+    let url = format!(
+        "{}?inputMint={}&outputMint={}&amount={}&slippageBps={}",
+        QUOTE_URL, input_mint, output_mint, amount, slippage_bps
+    );
+    let response: Value = reqwest::get(&url).await?.json().await?;
+    if let Some(error) = response["error"].as_str() {
+        return Err(BotError::api(
+            "Jupiter",
+            None,
+            format!("no route: {}", error),
+        ));
+    }
+    let out_amount = response["outAmount"]
+        .as_str()
+        .and_then(|amount| amount.parse().ok())
+        .ok_or_else(|| BotError::api("Jupiter", None, "unexpected answer from the quote API"))?;
+    Ok(Quote {
+        out_amount,
+        response,
+    })
+}
+
+// Unsigned swap transaction for `quote`, wrapping and unwrapping SOL as needed
+pub async fn swap_transaction(quote: &Quote, user: &Pubkey) -> Result<VersionedTransaction> {
+    let response: Value = reqwest::Client::new()
+        .post(SWAP_URL)
+        .json(&json!({
+            "quoteResponse": quote.response,
+            "userPublicKey": user.to_string(),
+            "wrapAndUnwrapSol": true,
+            "dynamicComputeUnitLimit": true,
+        }))
+        .send()
+        .await?
+        .json()
+        .await?;
+    let encoded = response["swapTransaction"]
+        .as_str()
+        .ok_or("Unexpected answer from the Jupiter swap API")?;
+    let bytes = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+    let tx: VersionedTransaction = bincode::deserialize(&bytes)?;
+    swaps().lock().unwrap().insert(tx.message.hash());
+    Ok(tx)
+}
+
+// Messages of the swaps built above, so a Jupiter route is only trusted in a
+// sell the bot asked Jupiter for itself
+fn swaps() -> &'static Mutex<HashSet<Hash>> {
+    static SWAPS: OnceLock<Mutex<HashSet<Hash>>> = OnceLock::new();
+    SWAPS.get_or_init(Default::default)
+}
+
+// Whether `tx` is a swap built by swap_transaction, untouched since. Each one
+// is vouched for once.
+pub fn take_swap(tx: &VersionedTransaction) -> bool {
+    swaps().lock().unwrap().remove(&tx.message.hash())
+}
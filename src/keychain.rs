@@ -0,0 +1,37 @@
+use colored::*;
+use keyring::Entry;
+use solana_sdk::signature::Keypair;
+
+use crate::{wallet, Result};
+
+// Service name the private keys are filed under in the OS keychain
+const SERVICE: &str = "degen-fund-bot";
+
+fn entry(name: &str) -> Result<Entry> {
+    Entry::new(SERVICE, name).map_err(|e| {
+        format!("Could not open keychain entry '{}': {}", name, e)
+            .red()
+            .to_string()
+            .into()
+    })
+}
+
+// Store the key base58 encoded, the same format as PRIVATE_KEY_BASE58
+pub fn store(name: &str, keypair: &Keypair) -> Result<()> {
+    entry(name)?.set_password(&keypair.to_base58_string())?;
+    Ok(())
+}
+
+pub fn load(name: &str) -> Result<Keypair> {
+    let secret = entry(name)?.get_password().map_err(|e| {
+        format!("Could not read keychain entry '{}': {}", name, e)
+            .red()
+            .to_string()
+    })?;
+    wallet::load_keypair(Some(&secret))
+}
+
+pub fn delete(name: &str) -> Result<()> {
+    entry(name)?.delete_credential()?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use argon2::{password_hash::SaltString, Argon2};
+use base64::Engine;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::{Keypair, Signer};
+use std::fs;
+
+use crate::{wallet, Result};
+
+const VERSION: u32 = 1;
+const KDF: &str = "argon2id";
+
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+// A private key encrypted with AES-256-GCM under an Argon2id derived key
+#[derive(Serialize, Deserialize)]
+pub struct Keystore {
+    version: u32,
+    kdf: String,
+    /// Public key, readable without the passphrase
+    pubkey: String,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl Keystore {
+    pub fn encrypt(keypair: &Keypair, passphrase: &str) -> Result<Keystore> {
+        let salt = SaltString::generate(&mut OsRng);
+        let key = derive_key(passphrase, salt.as_str())?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(&key)
+            .encrypt(&nonce, keypair.to_bytes().as_ref())
+            .map_err(|_| "Encryption failed")?;
+        Ok(Keystore {
+            version: VERSION,
+            kdf: KDF.to_string(),
+            pubkey: keypair.pubkey().to_string(),
+            salt: salt.as_str().to_string(),
+            nonce: b64().encode(nonce),
+            ciphertext: b64().encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair> {
+        if self.version != VERSION || self.kdf != KDF {
+            return Err(format!(
+                "Unsupported keystore version {} ({})",
+                self.version, self.kdf
+            )
+            .red()
+            .to_string()
+            .into());
+        }
+        let key = derive_key(passphrase, &self.salt)?;
+        let nonce = b64().decode(&self.nonce)?;
+        if nonce.len() != 12 {
+            return Err("Keystore nonce is corrupt".red().to_string().into());
+        }
+        let secret = Aes256Gcm::new(&key)
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                b64().decode(&self.ciphertext)?.as_ref(),
+            )
+            .map_err(|_| "Wrong passphrase or corrupt keystore".red().to_string())?;
+        wallet::keypair_from_bytes(&secret)
+    }
+
+    pub fn pubkey(&self) -> &str {
+        &self.pubkey
+    }
+
+    pub fn read(path: &str) -> Result<Keystore> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            format!("Could not read keystore {}: {}", path, e)
+                .red()
+                .to_string()
+        })?;
+        serde_json::from_str(&contents).map_err(|e| {
+            format!("Invalid keystore {}: {}", path, e)
+                .red()
+                .to_string()
+                .into()
+        })
+    }
+
+    // Written readable by the owner only
+    pub fn write(&self, path: &str) -> Result<()> {
+        let contents = serde_json::to_string_pretty(self)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path).map_err(|e| {
+            format!("Could not create keystore {}: {}", path, e)
+                .red()
+                .to_string()
+        })?;
+        std::io::Write::write_all(&mut file, contents.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &str) -> Result<Key<Aes256Gcm>> {
+    let mut key = Key::<Aes256Gcm>::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt.as_bytes(), &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+// The passphrase from KEY_PASSWORD when set, otherwise asked for on the terminal
+pub fn passphrase(from_env: Option<&str>, prompt: &str) -> Result<String> {
+    if let Some(passphrase) = from_env {
+        return Ok(passphrase.to_string());
+    }
+    Ok(rpassword::prompt_password(prompt)?)
+}
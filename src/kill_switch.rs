@@ -0,0 +1,150 @@
+use colored::*;
+use log::{error, info, warn};
+use reqwest::StatusCode;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::commands::panic;
+use crate::config::Config;
+use crate::prepare::Trade;
+use crate::{shutdown, BotError, Result};
+
+// How often a running daemon or watcher looks at the switch
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const FLAG_TIMEOUT: Duration = Duration::from_secs(3);
+// A reading of the flag URL is reused for this long, so a burst of buys
+// waits on it once
+const FLAG_TTL: Duration = Duration::from_secs(1);
+
+// An emergency brake on buying, engaged by a local file or a remote flag
+#[derive(Clone, Debug, Default)]
+pub struct KillSwitch {
+    // Engaged while this file exists
+    pub file: Option<PathBuf>,
+    // Engaged while this URL answers with anything but 404 or an empty, 0,
+    // false or off body. An unreachable URL counts as engaged.
+    pub url: Option<String>,
+    // Sell every position when it engages
+    pub flatten: bool,
+}
+
+impl KillSwitch {
+    pub fn is_configured(&self) -> bool {
+        self.file.is_some() || self.url.is_some()
+    }
+
+    // Why trading is halted, none when it isn't
+    pub async fn engaged(&self) -> Option<String> {
+        if let Some(file) = &self.file {
+            if file.exists() {
+                return Some(format!("{} exists", file.display()));
+            }
+        }
+        match &self.url {
+            Some(url) => cached_flag(url).await,
+            None => None,
+        }
+    }
+}
+
+struct FlagReading {
+    url: String,
+    read_at: Instant,
+    reason: Option<String>,
+}
+
+fn last_flag() -> &'static Mutex<Option<FlagReading>> {
+    static LAST_FLAG: OnceLock<Mutex<Option<FlagReading>>> = OnceLock::new();
+    LAST_FLAG.get_or_init(Default::default)
+}
+
+async fn cached_flag(url: &str) -> Option<String> {
+    if let Some(last) = &*last_flag().lock().unwrap() {
+        if last.url == url && last.read_at.elapsed() < FLAG_TTL {
+            return last.reason.clone();
+        }
+    }
+    let reason = read_flag(url).await;
+    *last_flag().lock().unwrap() = Some(FlagReading {
+        url: url.to_string(),
+        read_at: Instant::now(),
+        reason: reason.clone(),
+    });
+    reason
+}
+
+async fn read_flag(url: &str) -> Option<String> {
+    let response = match reqwest::Client::new()
+        .get(url)
+        .timeout(FLAG_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => return Some(format!("{} is unreachable: {}", url, e)),
+    };
+    let status = response.status();
+    if status == StatusCode::NOT_FOUND {
+        return None;
+    }
+    if !status.is_success() {
+        return Some(format!("{} answered {}", url, status));
+    }
+    match response.text().await {
+        Ok(body) => match body.trim().to_ascii_lowercase().as_str() {
+            "" | "0" | "false" | "off" => None,
+            _ => Some(format!("{} is set", url)),
+        },
+        Err(e) => Some(format!("{} is unreadable: {}", url, e)),
+    }
+}
+
+// Refuse a buy while the switch is engaged. Sells still go through, to get
+// out of positions.
+pub async fn check(config: &Config, trade: &Trade) -> Result<()> {
+    if !matches!(trade, Trade::Buy { .. }) {
+        return Ok(());
+    }
+    match config.kill_switch.engaged().await {
+        Some(reason) => Err(BotError::policy(format!(
+            "Kill switch engaged ({}), refusing to buy",
+            reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+// Watch the switch for as long as a daemon or watcher runs, telling when it
+// flips and, with `flatten`, selling every position each time it engages
+pub async fn watch(config: Arc<Config>) {
+    let mut engaged = false;
+    while !shutdown::requested() {
+        match config.kill_switch.engaged().await {
+            Some(reason) if !engaged => {
+                engaged = true;
+                warn!(
+                    "{}",
+                    format!("Kill switch engaged ({}), refusing new buys", reason)
+                        .red()
+                        .bold()
+                );
+                if config.kill_switch.flatten {
+                    info!("Flattening every position");
+                    if let Err(e) = panic::run(&config, None, false).await {
+                        error!("Flattening the positions failed: {}", e);
+                    }
+                }
+            }
+            None if engaged => {
+                engaged = false;
+                info!("{}", "Kill switch cleared, buying again".green());
+            }
+            _ => {}
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = shutdown::reached(shutdown::State::Draining) => {}
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Trading pipeline of the degen.fund bot. `DegenFundClient` fetches, signs,
+//! sends and confirms trades for programs that embed the bot, configured from
+//! the same environment and config.toml as the binary via `Config::from_env`.
+
+mod api;
+pub mod breaker;
+pub mod cli;
+pub mod client;
+pub mod commands;
+pub mod config;
+mod creator;
+pub mod curve;
+pub mod error;
+mod events;
+mod explain;
+pub mod fees;
+pub mod fill;
+pub mod geyser;
+mod holders;
+pub mod input;
+mod jito;
+mod journal;
+mod jupiter;
+mod keychain;
+mod keystore;
+pub mod kill_switch;
+pub mod lock;
+pub mod logging;
+pub mod market;
+mod message;
+mod metadata;
+mod metrics;
+pub mod monitor;
+mod nonce;
+pub mod notify;
+pub mod output;
+mod policy;
+mod preflight;
+pub mod prepare;
+pub mod price;
+mod raydium;
+pub mod risk;
+mod rpc;
+mod rugcheck;
+mod schedule;
+pub mod shutdown;
+pub mod signer;
+mod simulate;
+mod slippage;
+pub mod slots;
+pub mod source;
+pub mod telemetry;
+mod token_list;
+mod tpu;
+pub mod transaction;
+pub mod venue;
+pub mod wallet;
+
+pub use client::DegenFundClient;
+pub use error::BotError;
+
+pub type Result<T> = std::result::Result<T, BotError>;
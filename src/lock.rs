@@ -0,0 +1,76 @@
+use colored::*;
+use log::debug;
+use solana_sdk::signature::Signer;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::Result;
+
+// Where the lock files go unless lock_dir says otherwise
+pub fn default_lock_dir() -> PathBuf {
+    std::env::temp_dir().join("degen-fund-bot")
+}
+
+// Exclusive locks on the configured wallets, one file per wallet holding the
+// pid of the owner. The OS drops them when the process exits, crashed or not.
+pub struct InstanceLock {
+    _files: Vec<File>,
+}
+
+impl InstanceLock {
+    // Lock every wallet of the profile, refusing when another instance
+    // already runs with one of them
+    pub fn acquire(config: &Config) -> Result<InstanceLock> {
+        fs::create_dir_all(&config.lock_dir).map_err(|e| {
+            format!(
+                "Could not create the lock directory {}: {}",
+                config.lock_dir.display(),
+                e
+            )
+            .red()
+            .to_string()
+        })?;
+        let mut files = Vec::new();
+        for signer in config.signers()? {
+            let wallet = signer.pubkey().to_string();
+            let path = config.lock_dir.join(format!("{}.lock", wallet));
+            files.push(lock(&path, &wallet)?);
+        }
+        Ok(InstanceLock { _files: files })
+    }
+}
+
+fn lock(path: &Path, wallet: &str) -> Result<File> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let mut owner = String::new();
+            let _ = file.read_to_string(&mut owner);
+            let owner = match owner.trim() {
+                "" => String::new(),
+                pid => format!(" (pid {})", pid),
+            };
+            return Err(format!(
+                "Another instance{} is already running with wallet {}, stop it first",
+                owner, wallet
+            )
+            .red()
+            .to_string()
+            .into());
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+    file.set_len(0)?;
+    file.rewind()?;
+    write!(file, "{}", std::process::id())?;
+    debug!("Locked {}", path.display());
+    Ok(file)
+}
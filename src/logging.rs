@@ -0,0 +1,275 @@
+use chrono::{Local, NaiveDate};
+use clap::ValueEnum;
+use colored::*;
+use env_logger::Builder;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::notify::strip_ansi;
+use crate::Result;
+
+pub const DEFAULT_LOG_MAX_MB: u64 = 10;
+pub const DEFAULT_LOG_KEEP: usize = 5;
+
+// Lines kept while the console is captured
+const TAIL_LINES: usize = 500;
+
+// How log lines are written
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Colored lines for a terminal
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+// Colored lines on stderr, or one JSON object per line for collectors, and
+// the same lines without colors to the log file when there is one
+pub fn init(format: LogFormat, file: Option<LogFileConfig>) -> Result<()> {
+    let mut builder = Builder::new();
+    match format {
+        LogFormat::Text => {
+            builder.format(|buf, record| {
+                let level = match record.level() {
+                    log::Level::Error => "ERROR".red(),
+                    log::Level::Warn => "WARN".yellow(),
+                    log::Level::Info => "INFO".green(),
+                    log::Level::Debug => "DEBUG".blue(),
+                    log::Level::Trace => "TRACE".cyan(),
+                };
+                writeln!(
+                    buf,
+                    "{} [{}] - {}",
+                    Local::now().format("%Y-%m-%d %H:%M:%S").to_string().blue(),
+                    level,
+                    record.args()
+                )
+            });
+        }
+        LogFormat::Json => {
+            // Messages are colored as they are built, so colors are turned off
+            colored::control::set_override(false);
+            builder.format(|buf, record| writeln!(buf, "{}", json_line(record)));
+        }
+    }
+    let console = builder.filter(None, LevelFilter::Info).build();
+    let file = match file {
+        Some(config) => {
+            let path = config.path.display().to_string();
+            let file = RotatingFile::open(config).map_err(|e| {
+                format!("Could not open the log file {}: {}", path, e)
+                    .red()
+                    .to_string()
+            })?;
+            Some(Mutex::new(file))
+        }
+        None => None,
+    };
+    log::set_max_level(console.filter());
+    log::set_boxed_logger(Box::new(Logger {
+        console,
+        file,
+        format,
+    }))?;
+    Ok(())
+}
+
+fn captured() -> &'static AtomicBool {
+    static CAPTURED: AtomicBool = AtomicBool::new(false);
+    &CAPTURED
+}
+
+fn tail_lines() -> &'static Mutex<VecDeque<String>> {
+    static TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+    &TAIL
+}
+
+// Keep log lines in memory for `tail` instead of writing them to the console,
+// while a full screen interface owns the terminal
+pub fn capture(on: bool) {
+    captured().store(on, Ordering::Relaxed);
+}
+
+pub fn is_captured() -> bool {
+    captured().load(Ordering::Relaxed)
+}
+
+// The latest captured lines, oldest first
+pub fn tail(lines: usize) -> Vec<String> {
+    let tail = tail_lines().lock().unwrap();
+    tail.iter()
+        .skip(tail.len().saturating_sub(lines))
+        .cloned()
+        .collect()
+}
+
+fn plain_line(record: &Record) -> String {
+    format!(
+        "{} [{}] {} - {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        record.level(),
+        record.target(),
+        strip_ansi(&record.args().to_string())
+    )
+}
+
+fn json_line(record: &Record) -> String {
+    serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": strip_ansi(&record.args().to_string()),
+    })
+    .to_string()
+}
+
+struct Logger {
+    console: env_logger::Logger,
+    file: Option<Mutex<RotatingFile>>,
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.console.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.console.matches(record) {
+            return;
+        }
+        if is_captured() {
+            let mut tail = tail_lines().lock().unwrap();
+            if tail.len() == TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(format!(
+                "{} {:<5} {}",
+                Local::now().format("%H:%M:%S"),
+                record.level(),
+                strip_ansi(&record.args().to_string())
+            ));
+        } else {
+            self.console.log(record);
+        }
+        let Some(file) = &self.file else {
+            return;
+        };
+        let line = match self.format {
+            LogFormat::Text => plain_line(record),
+            LogFormat::Json => json_line(record),
+        };
+        // A log line that can't be written has nowhere to be reported
+        let _ = writeln!(file.lock().unwrap(), "{}", line);
+    }
+
+    fn flush(&self) {
+        self.console.flush();
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+// Where the log file goes and when it is rotated
+#[derive(Clone)]
+pub struct LogFileConfig {
+    pub path: PathBuf,
+    // Rotate once the file would grow past this, never when None
+    pub max_bytes: Option<u64>,
+    // Rotate when the local date changes
+    pub daily: bool,
+    // Rotated files kept as path.1 (newest) to path.N
+    pub keep: usize,
+}
+
+// An append-only log file rotated by size and date. Rotated files shift up
+// one number and the oldest beyond `keep` is deleted.
+pub struct RotatingFile {
+    config: LogFileConfig,
+    file: File,
+    size: u64,
+    opened_on: NaiveDate,
+}
+
+impl RotatingFile {
+    pub fn open(config: LogFileConfig) -> io::Result<RotatingFile> {
+        if let Some(dir) = config
+            .path
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&config.path)?;
+        let metadata = file.metadata()?;
+        // A file left by an earlier day counts as that day's
+        let opened_on = metadata
+            .modified()
+            .map(|modified| chrono::DateTime::<Local>::from(modified).date_naive())
+            .unwrap_or_else(|_| Local::now().date_naive());
+        Ok(RotatingFile {
+            size: metadata.len(),
+            config,
+            file,
+            opened_on,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.config.keep == 0 {
+            fs::remove_file(&self.config.path)?;
+        } else {
+            let _ = fs::remove_file(numbered(&self.config.path, self.config.keep));
+            for n in (1..self.config.keep).rev() {
+                let from = numbered(&self.config.path, n);
+                if from.exists() {
+                    fs::rename(&from, numbered(&self.config.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.config.path, numbered(&self.config.path, 1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)?;
+        self.size = 0;
+        self.opened_on = Local::now().date_naive();
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let too_big = self
+            .config
+            .max_bytes
+            .is_some_and(|max| self.size > 0 && self.size + buf.len() as u64 > max);
+        let new_day = self.config.daily && Local::now().date_naive() != self.opened_on;
+        if too_big || new_day {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
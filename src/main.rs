@@ -1,140 +1,331 @@
-use base64::{self, Engine};
-use bs58;
+use clap::Parser;
 use colored::*;
 use dotenv::dotenv;
-use env_logger::Builder;
-use indicatif::{ProgressBar, ProgressStyle};
-use log::{info, LevelFilter};
-use reqwest;
-use solana_client::rpc_client::RpcClient;
-use solana_sdk::{
-    commitment_config::CommitmentConfig,
-    signature::{Keypair, Signature, Signer},
-    transaction::Transaction,
-};
-use std::env;
-use std::io::Write;
+use log::info;
+use solana_sdk::{native_token::sol_to_lamports, pubkey::Pubkey};
+use std::process::ExitCode;
+use std::sync::Arc;
 use std::time::Duration;
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Logger configuration with colors
-    Builder::new()
-        .format(|buf, record| {
-            let level = match record.level() {
-                log::Level::Error => "ERROR".red(),
-                log::Level::Warn => "WARN".yellow(),
-                log::Level::Info => "INFO".green(),
-                log::Level::Debug => "DEBUG".blue(),
-                log::Level::Trace => "TRACE".cyan(),
-            };
-            writeln!(
-                buf,
-                "{} [{}] - {}",
-                chrono::Local::now()
-                    .format("%Y-%m-%d %H:%M:%S")
-                    .to_string()
-                    .blue(),
-                level,
-                record.args()
-            )
-        })
-        .filter(None, LevelFilter::Info)
-        .init();
+use degen_fund_bot::cli::{Cli, Command};
+use degen_fund_bot::commands::{
+    self,
+    buy::{BuyAmount, Prompt},
+    copy::CopySizing,
+    daemon::{CopyFeed, Feeds},
+    dca::DcaOrder,
+    disperse::DisperseAmount,
+    sell::SellAmount,
+    snipe::SnipeFilters,
+    twap::{Side, TwapOrder},
+};
+use degen_fund_bot::config::{Config, DEFAULT_KEYRING_ENTRY};
+use degen_fund_bot::lock::InstanceLock;
+use degen_fund_bot::telemetry::Telemetry;
+use degen_fund_bot::{input, kill_switch, logging, output, price, shutdown, wallet, Result};
 
-    info!("{}", "Starting Degen Fund Bot".bold());
+// Exits with the code of the error, see BotError::exit_code
+#[tokio::main]
+async fn main() -> ExitCode {
+    match start().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
 
+async fn start() -> Result<()> {
     dotenv().ok();
 
-    // Reading environment variables
-    let rpc_url = env::var("SOLANA_RPC_URL").expect("SOLANA_RPC_URL must be set in .env");
-    let private_key_base58 =
-        env::var("PRIVATE_KEY_BASE58").expect("PRIVATE_KEY_BASE58 must be set in .env");
-    let buy_amount = env::var("BUY_AMOUNT").expect("BUY_AMOUNT must be set in .env");
-    let token_to_buy = env::var("TOKEN_TO_BUY").expect("TOKEN_TO_BUY must be set in .env");
-
-    // Decode base58 private key
-    let private_key = bs58::decode(private_key_base58).into_vec()?;
-    let keypair = Keypair::from_bytes(&private_key)?;
-    let buyer = keypair.pubkey().to_string();
-
-    info!(
-        "Buying {} tokens using wallet {}",
-        buy_amount.yellow(),
-        buyer.bright_green()
-    );
-
-    let client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
-
-    // Configure transaction URL
-    let transaction_url = format!("https://www.degen.fund/api/antibot/{}", token_to_buy);
-    let url = format!(
-        "{}?&buy-amount={}&buyer={}",
-        transaction_url, buy_amount, buyer
-    );
-
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message("Preparing transaction...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
+    // Flags and environment take precedence over the config file
+    let cli = Cli::parse();
+    let config = Config::load(&cli)?;
+    logging::init(cli.log_format, config.log_file.clone())?;
 
-    let response = reqwest::get(&url).await?.text().await?;
-    let byte_tx = base64::engine::general_purpose::STANDARD.decode(&response)?;
-    let mut tx: Transaction = bincode::deserialize(&byte_tx)?;
-
-    // Sign the transaction
-    let our_pubkey = keypair.pubkey();
-    let our_signature_index = tx
-        .message
-        .account_keys
-        .iter()
-        .position(|&pubkey| pubkey == our_pubkey);
-
-    if let Some(index) = our_signature_index {
-        if tx.signatures[index] == Signature::default() {
-            let message_data = tx.message_data();
-            let signature = keypair.sign_message(&message_data);
-            tx.signatures[index] = signature;
-        }
+    info!("{}", "Starting Degen Fund Bot".bold());
+    let telemetry = match &config.otlp_endpoint {
+        Some(endpoint) => Some(Telemetry::init(endpoint)?),
+        None => None,
+    };
+    if let Some(profile) = &cli.profile {
+        info!("Using profile {}", profile.bright_cyan());
+    }
+    if cli.command.trades() {
+        shutdown::listen(config.shutdown_grace);
+    }
+    // Two daemons on one wallet would fill the same orders twice
+    let locks_wallets = cli.command.locks_wallets(&config.exit_rules);
+    let _lock = if locks_wallets {
+        Some(InstanceLock::acquire(&config)?)
     } else {
-        return Err("Our public key is not in the list of signers"
-            .red()
-            .to_string()
-            .into());
+        None
+    };
+    if locks_wallets && config.kill_switch.is_configured() {
+        tokio::spawn(kill_switch::watch(Arc::new(config.clone())));
     }
 
-    spinner.finish_with_message("Transaction prepared successfully!".green().to_string());
-
-    // Send the transaction
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message("Sending transaction...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
-
-    let signature = client.send_transaction(&tx)?;
-
-    spinner.finish_with_message("Transaction sent successfully!".green().to_string());
+    output::init(cli.output);
+    let result = run(&cli, config).await;
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown().await;
+    }
+    output::finish(&result);
+    result
+}
 
-    info!(
-        "Transaction signature: {}",
-        signature.to_string().bright_green()
-    );
+async fn run(cli: &Cli, config: Config) -> Result<()> {
+    match &cli.command {
+        Command::Buy {
+            mint,
+            clipboard,
+            symbol,
+            tokens,
+            concurrency,
+            amount,
+            amount_usd,
+            amount_pct,
+            exit,
+            at,
+            no_preview,
+            yes,
+            force,
+        } => {
+            if let Some(tokens) = tokens {
+                // A token list totals its spend in SOL up front
+                let amount = match amount_usd {
+                    Some(usd) => Some(
+                        price::usd_to_sol(config.price_oracle, &config.rpc_client()?, *usd).await?,
+                    ),
+                    None => *amount,
+                };
+                return commands::batch::run(
+                    &config,
+                    tokens,
+                    amount,
+                    *concurrency as usize,
+                    !yes,
+                    *force,
+                )
+                .await;
+            }
+            let rules = exit.rules(&config.exit_rules);
+            let prompt = Prompt {
+                preview: !no_preview,
+                confirm: !yes,
+                force: *force,
+            };
+            let mint = match symbol {
+                Some(symbol) => input::mint_by_symbol(symbol).await?,
+                None => input::mint(mint.as_deref(), *clipboard)?,
+            };
+            let amount = match (amount_pct, amount_usd) {
+                (Some(pct), _) => Some(BuyAmount::BalancePct(*pct)),
+                (None, Some(usd)) => Some(BuyAmount::Usd(*usd)),
+                (None, None) => amount.map(BuyAmount::Sol),
+            };
+            commands::buy::run(&config, &mint, amount, rules, *at, prompt).await
+        }
+        Command::Monitor { mint, exit } => {
+            commands::monitor::run(&config, mint, exit.rules(&config.exit_rules)).await
+        }
+        Command::Sell {
+            mint,
+            percent,
+            tokens,
+        } => {
+            let amount = match tokens {
+                Some(tokens) => Some(SellAmount::Tokens(*tokens)),
+                None => percent.map(SellAmount::Percent),
+            };
+            commands::sell::run(&config, mint, amount).await
+        }
+        Command::Panic { unit_price, yes } => {
+            commands::panic::run(&config, *unit_price, !yes).await
+        }
+        Command::Snipe {
+            amount,
+            creators,
+            max_market_cap,
+            max_buys,
+        } => {
+            let filters = SnipeFilters {
+                creators: parse_pubkeys(creators)?,
+                max_market_cap_sol: *max_market_cap,
+                max_buys: *max_buys,
+            };
+            commands::snipe::run(Arc::new(config), *amount, filters).await
+        }
+        Command::Dca {
+            mint,
+            total,
+            tranches,
+            interval_secs,
+            max_price,
+            restart,
+            yes,
+        } => {
+            let order = DcaOrder {
+                total: *total,
+                tranches: *tranches,
+                interval: Duration::from_secs(*interval_secs),
+                max_price: *max_price,
+            };
+            commands::dca::run(&config, mint, order, *restart, !yes).await
+        }
+        Command::Twap {
+            mint,
+            side,
+            amount,
+            percent,
+            slices,
+            duration_secs,
+            jitter,
+            yes,
+        } => {
+            let size = match side {
+                Side::Buy => amount.or(config.buy_amount).ok_or_else(|| {
+                    "A buy amount must be given with --amount or buy_amount in the config file"
+                        .red()
+                        .to_string()
+                })?,
+                Side::Sell => percent.unwrap_or(config.sell_percent) as f64,
+            };
+            let order = TwapOrder {
+                side: *side,
+                size,
+                slices: *slices,
+                duration: Duration::from_secs(*duration_secs),
+                jitter_pct: *jitter,
+            };
+            commands::twap::run(&config, mint, order, !yes).await
+        }
+        Command::Orders { action } => commands::orders::run(Arc::new(config), action).await,
+        Command::Copy {
+            targets,
+            amount,
+            ratio,
+            max_amount,
+        } => {
+            let targets = parse_pubkeys(targets)?;
+            let sizing = copy_sizing(&config, *amount, *ratio)?;
+            commands::copy::run(Arc::new(config), targets, sizing, *max_amount).await
+        }
+        Command::Daemon {
+            watchlist,
+            snipe,
+            creators,
+            max_market_cap,
+            copy_targets,
+            copy_ratio,
+            max_copy_amount,
+            telegram,
+            orders,
+            metrics_addr,
+            dashboard_addr,
+            api_token,
+            amount,
+        } => {
+            let copy = if copy_targets.is_empty() {
+                None
+            } else {
+                Some(CopyFeed {
+                    targets: parse_pubkeys(copy_targets)?,
+                    sizing: copy_sizing(&config, *amount, *copy_ratio)?,
+                    max_amount: *max_copy_amount,
+                })
+            };
+            let snipe = if *snipe {
+                Some(SnipeFilters {
+                    creators: parse_pubkeys(creators)?,
+                    max_market_cap_sol: *max_market_cap,
+                    max_buys: None,
+                })
+            } else {
+                None
+            };
+            let feeds = Feeds {
+                watchlist: watchlist.clone(),
+                snipe,
+                copy,
+                telegram: *telegram,
+                orders: *orders,
+            };
+            let dashboard = dashboard_addr.zip(api_token.clone());
+            commands::daemon::run(Arc::new(config), *amount, feeds, *metrics_addr, dashboard).await
+        }
+        Command::Telegram => commands::telegram::run(Arc::new(config)).await,
+        Command::Serve {
+            listen,
+            grpc_listen,
+            token,
+        } => commands::serve::run(Arc::new(config), *listen, *grpc_listen, token.clone()).await,
+        Command::Tui { orders } => commands::tui::run(Arc::new(config), *orders).await,
+        Command::Pnl => commands::pnl::run(&config).await,
+        Command::Portfolio => commands::portfolio::run(&config).await,
+        Command::ImportKey { output } => commands::import_key::run(&config, output),
+        Command::Keyring { action } => commands::keyring::run(
+            &config,
+            action,
+            config
+                .keyring_entry
+                .as_deref()
+                .unwrap_or(DEFAULT_KEYRING_ENTRY),
+        ),
+        Command::Check { mint } => commands::check::run(&config, mint).await,
+        Command::Holders { mint, top } => commands::holders::run(&config, mint, *top).await,
+        Command::Nonce { action } => commands::nonce::run(&config, action).await,
+        Command::Disperse {
+            targets,
+            amount,
+            total,
+            jitter,
+            yes,
+        } => {
+            // Clap requires one of the two
+            let amount = match total {
+                Some(total) => DisperseAmount::Total(*total),
+                None => DisperseAmount::Each(amount.unwrap_or_default()),
+            };
+            commands::disperse::run(&config, targets, amount, *jitter, !yes).await
+        }
+        Command::Sweep { to, keep_sol, yes } => {
+            let keep = keep_sol.map_or(config.sweep_keep_lamports, sol_to_lamports);
+            commands::sweep::run(&config, to.as_deref(), keep, !yes).await
+        }
+        Command::Cleanup {
+            burn_below_usd,
+            yes,
+        } => commands::cleanup::run(&config, *burn_below_usd, !yes).await,
+        Command::Transfer {
+            to,
+            amount,
+            mint,
+            yes,
+        } => commands::transfer::run(&config, to, *amount, mint.as_deref(), !yes).await,
+        Command::Wrap { amount } => commands::wrap::wrap(&config, *amount).await,
+        Command::Unwrap => commands::wrap::unwrap(&config).await,
+        Command::Balance { mint } => commands::balance::run(&config, mint.as_deref()).await,
+    }
+}
 
-    let solscan_url = format!("https://solscan.io/tx/{}", signature);
-    info!(
-        "View transaction on Solscan: {}",
-        solscan_url.bright_blue().underline()
-    );
+fn parse_pubkeys(addresses: &[String]) -> Result<Vec<Pubkey>> {
+    addresses
+        .iter()
+        .map(|address| wallet::parse_pubkey(address))
+        .collect()
+}
 
-    Ok(())
+fn copy_sizing(config: &Config, amount: Option<f64>, ratio: Option<f64>) -> Result<CopySizing> {
+    match (ratio, amount.or(config.buy_amount)) {
+        (Some(ratio), _) => Ok(CopySizing::Ratio(ratio)),
+        (None, Some(amount)) => Ok(CopySizing::Fixed(amount)),
+        (None, None) => Err(
+            "A copy size must be given with --amount, --ratio or buy_amount in the config file"
+                .red()
+                .to_string()
+                .into(),
+        ),
+    }
 }
@@ -0,0 +1,52 @@
+use futures_util::future::{BoxFuture, FutureExt};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use super::PriceProvider;
+use crate::jupiter::WSOL_MINT;
+use crate::Result;
+
+const MULTI_PRICE_URL: &str = "https://public-api.birdeye.so/defi/multi_price";
+
+pub struct Birdeye {
+    api_key: String,
+}
+
+impl Birdeye {
+    pub fn new(api_key: String) -> Birdeye {
+        Birdeye { api_key }
+    }
+}
+
+impl PriceProvider for Birdeye {
+    fn name(&self) -> &'static str {
+        "Birdeye"
+    }
+
+    // Birdeye quotes USD, so the token is priced against wrapped SOL in the
+    // same request
+    fn price_sol<'a>(&'a self, mint: &'a Pubkey) -> BoxFuture<'a, Result<f64>> {
+        async move {
+            let url = format!("{}?list_address={},{}", MULTI_PRICE_URL, mint, WSOL_MINT);
+            let response: Value = reqwest::Client::new()
+                .get(&url)
+                .header("X-API-KEY", &self.api_key)
+                .header("x-chain", "solana")
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let usd = |address: &Pubkey| {
+                response["data"][address.to_string()]["value"]
+                    .as_f64()
+                    .filter(|price| *price > 0.0)
+            };
+            match (usd(mint), usd(&WSOL_MINT)) {
+                (Some(token), Some(sol)) => Ok(token / sol),
+                _ => Err(format!("Birdeye has no price for {}", mint).into()),
+            }
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,43 @@
+use futures_util::future::{BoxFuture, FutureExt};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use super::PriceProvider;
+use crate::jupiter::WSOL_MINT;
+use crate::Result;
+
+const TOKEN_PAIRS_URL: &str = "https://api.dexscreener.com/latest/dex/tokens";
+
+pub struct DexScreener;
+
+impl PriceProvider for DexScreener {
+    fn name(&self) -> &'static str {
+        "DexScreener"
+    }
+
+    // The native price of the most liquid pair quoted in SOL
+    fn price_sol<'a>(&'a self, mint: &'a Pubkey) -> BoxFuture<'a, Result<f64>> {
+        async move {
+            let url = format!("{}/{}", TOKEN_PAIRS_URL, mint);
+            let response: Value = reqwest::get(&url).await?.error_for_status()?.json().await?;
+            let mint = mint.to_string();
+            let wsol = WSOL_MINT.to_string();
+            let price = response["pairs"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|pair| {
+                    pair["baseToken"]["address"].as_str() == Some(&mint)
+                        && pair["quoteToken"]["address"].as_str() == Some(&wsol)
+                })
+                .max_by(|a, b| {
+                    let liquidity = |pair: &Value| pair["liquidity"]["usd"].as_f64().unwrap_or(0.0);
+                    liquidity(a).total_cmp(&liquidity(b))
+                })
+                .and_then(|pair| pair["priceNative"].as_str()?.parse::<f64>().ok())
+                .filter(|price| *price > 0.0);
+            price.ok_or_else(|| format!("DexScreener has no SOL pair for {}", mint).into())
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,36 @@
+use clap::ValueEnum;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::Result;
+
+mod birdeye;
+mod dexscreener;
+mod raydium;
+
+pub use birdeye::Birdeye;
+pub use dexscreener::DexScreener;
+pub use raydium::Raydium;
+
+// Where the market price of a token that left its curve comes from
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceProviderKind {
+    /// The deepest Raydium SOL pool
+    #[default]
+    Raydium,
+    /// Birdeye's aggregated price, needs birdeye_api_key
+    Birdeye,
+    /// DexScreener's most liquid SOL pair
+    Dexscreener,
+}
+
+// A market price feed for tokens trading off the bonding curve, after the
+// migration
+pub trait PriceProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Current price in SOL per whole token
+    fn price_sol<'a>(&'a self, mint: &'a Pubkey) -> BoxFuture<'a, Result<f64>>;
+}
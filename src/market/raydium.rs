@@ -0,0 +1,37 @@
+use futures_util::future::{BoxFuture, FutureExt};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::PriceProvider;
+use crate::raydium::Pool;
+use crate::Result;
+
+// Raydium pool prices, with each mint's pool looked up once
+#[derive(Default)]
+pub struct Raydium {
+    pools: Mutex<HashMap<Pubkey, String>>,
+}
+
+impl PriceProvider for Raydium {
+    fn name(&self) -> &'static str {
+        "Raydium"
+    }
+
+    fn price_sol<'a>(&'a self, mint: &'a Pubkey) -> BoxFuture<'a, Result<f64>> {
+        async move {
+            let known = self.pools.lock().unwrap().get(mint).cloned();
+            let pool = match known {
+                Some(id) => Pool::with_id(id, *mint),
+                // The pool may take a moment to be indexed after the migration
+                None => {
+                    let pool = Pool::find(mint).await?;
+                    self.pools.lock().unwrap().insert(*mint, pool.id.clone());
+                    pool
+                }
+            };
+            pool.price_sol().await
+        }
+        .boxed()
+    }
+}
@@ -0,0 +1,114 @@
+use colored::*;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::{state::AddressLookupTable, AddressLookupTableAccount},
+    hash::Hash,
+    instruction::{AccountMeta, Instruction},
+    message::{v0, Message, VersionedMessage},
+    pubkey::Pubkey,
+};
+
+use crate::Result;
+
+// A transaction message expanded back into instructions, with any address
+// lookup tables it uses resolved
+pub struct Decompiled {
+    pub payer: Pubkey,
+    pub instructions: Vec<Instruction>,
+    pub lookup_tables: Vec<AddressLookupTableAccount>,
+    pub blockhash: Hash,
+    versioned: bool,
+}
+
+pub async fn decompile(client: &RpcClient, message: &VersionedMessage) -> Result<Decompiled> {
+    let static_keys = message.static_account_keys();
+    let mut lookup_tables = Vec::new();
+    let mut writable_loaded = Vec::new();
+    let mut readonly_loaded = Vec::new();
+    for lookup in message.address_table_lookups().unwrap_or_default() {
+        let data = client.get_account_data(&lookup.account_key).await?;
+        let table = AddressLookupTable::deserialize(&data).map_err(|e| {
+            format!("Invalid lookup table {}: {}", lookup.account_key, e)
+                .red()
+                .to_string()
+        })?;
+        let resolve = |indexes: &[u8]| {
+            indexes
+                .iter()
+                .map(|&i| {
+                    table.addresses.get(i as usize).copied().ok_or_else(|| {
+                        format!("Lookup table {} has no index {}", lookup.account_key, i)
+                    })
+                })
+                .collect::<std::result::Result<Vec<_>, _>>()
+        };
+        writable_loaded.extend(resolve(&lookup.writable_indexes)?);
+        readonly_loaded.extend(resolve(&lookup.readonly_indexes)?);
+        lookup_tables.push(AddressLookupTableAccount {
+            key: lookup.account_key,
+            addresses: table.addresses.to_vec(),
+        });
+    }
+
+    // Static keys come first, then loaded writable and loaded readonly addresses
+    let account_meta = |index: usize| -> Result<AccountMeta> {
+        if index < static_keys.len() {
+            return Ok(AccountMeta {
+                pubkey: static_keys[index],
+                is_signer: message.is_signer(index),
+                is_writable: message.is_maybe_writable(index, None),
+            });
+        }
+        let loaded = index - static_keys.len();
+        if loaded < writable_loaded.len() {
+            return Ok(AccountMeta::new(writable_loaded[loaded], false));
+        }
+        readonly_loaded
+            .get(loaded - writable_loaded.len())
+            .map(|pubkey| AccountMeta::new_readonly(*pubkey, false))
+            .ok_or_else(|| format!("Account index {} out of range", index).into())
+    };
+
+    let mut instructions = Vec::new();
+    for ix in message.instructions() {
+        let program_id = account_meta(ix.program_id_index as usize)?.pubkey;
+        let accounts = ix
+            .accounts
+            .iter()
+            .map(|&index| account_meta(index as usize))
+            .collect::<Result<Vec<_>>>()?;
+        instructions.push(Instruction {
+            program_id,
+            accounts,
+            data: ix.data.clone(),
+        });
+    }
+
+    Ok(Decompiled {
+        payer: static_keys[0],
+        instructions,
+        lookup_tables,
+        blockhash: *message.recent_blockhash(),
+        versioned: matches!(message, VersionedMessage::V0(_)),
+    })
+}
+
+impl Decompiled {
+    // Compile back into a message of the same version
+    pub fn compile(&self) -> Result<VersionedMessage> {
+        if self.versioned {
+            Ok(VersionedMessage::V0(v0::Message::try_compile(
+                &self.payer,
+                &self.instructions,
+                &self.lookup_tables,
+                self.blockhash,
+            )?))
+        } else {
+            Ok(VersionedMessage::Legacy(Message::new_with_blockhash(
+                &self.instructions,
+                Some(&self.payer),
+                &self.blockhash,
+            )))
+        }
+    }
+}
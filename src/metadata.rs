@@ -0,0 +1,113 @@
+use serde_json::Value;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+use crate::Result;
+
+pub const METADATA_PROGRAM_ID: Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+// Social links looked up in the off-chain JSON, at the top level as
+// launchpads write them or under `extensions`
+const SOCIAL_KEYS: [&str; 3] = ["website", "twitter", "telegram"];
+
+// Name, symbol and uri from a token's Metaplex metadata account
+pub struct TokenMetadata {
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    pub is_mutable: bool,
+}
+
+// What the launchpad uploaded to the metadata uri
+pub struct OffChainMetadata {
+    pub image: Option<String>,
+    pub description: Option<String>,
+    pub socials: Vec<(&'static str, String)>,
+}
+
+impl OffChainMetadata {
+    pub async fn fetch(uri: &str) -> Result<OffChainMetadata> {
+        let json: Value = reqwest::get(uri).await?.json().await?;
+        let text = |value: &Value| {
+            value
+                .as_str()
+                .map(str::trim)
+                .filter(|text| !text.is_empty())
+                .map(str::to_string)
+        };
+        let socials = SOCIAL_KEYS
+            .iter()
+            .filter_map(|key| {
+                text(&json[key])
+                    .or_else(|| text(&json["extensions"][key]))
+                    .map(|link| (*key, link))
+            })
+            .collect();
+        Ok(OffChainMetadata {
+            image: text(&json["image"]),
+            description: text(&json["description"]),
+            socials,
+        })
+    }
+}
+
+impl TokenMetadata {
+    pub fn address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"metadata", METADATA_PROGRAM_ID.as_ref(), mint.as_ref()],
+            &METADATA_PROGRAM_ID,
+        )
+        .0
+    }
+
+    // Metadata of every mint, None where a mint has no metadata account
+    pub async fn fetch_many(
+        client: &RpcClient,
+        mints: &[Pubkey],
+    ) -> Result<Vec<Option<TokenMetadata>>> {
+        let addresses: Vec<Pubkey> = mints.iter().map(Self::address).collect();
+        let mut metadata = Vec::with_capacity(mints.len());
+        // getMultipleAccounts takes at most 100 keys
+        for chunk in addresses.chunks(100) {
+            for account in client.get_multiple_accounts(chunk).await? {
+                metadata.push(account.and_then(|account| Self::from_account_data(&account.data)));
+            }
+        }
+        Ok(metadata)
+    }
+
+    // Borsh layout: key (1), update authority (32), mint (32), the length
+    // prefixed, null padded name, symbol and uri strings, seller fee (2), an
+    // optional creator list, primary sale flag (1) and the mutable flag (1)
+    pub fn from_account_data(data: &[u8]) -> Option<TokenMetadata> {
+        let mut offset = 1 + 32 + 32;
+        let string = |offset: &mut usize| {
+            let len = u32::from_le_bytes(data.get(*offset..*offset + 4)?.try_into().ok()?) as usize;
+            let bytes = data.get(*offset + 4..*offset + 4 + len)?;
+            *offset += 4 + len;
+            Some(
+                String::from_utf8_lossy(bytes)
+                    .trim_end_matches('\0')
+                    .trim()
+                    .to_string(),
+            )
+        };
+        let name = string(&mut offset)?;
+        let symbol = string(&mut offset)?;
+        let uri = string(&mut offset)?;
+        offset += 2;
+        if *data.get(offset)? == 1 {
+            // address (32), verified (1), share (1) per creator
+            let creators =
+                u32::from_le_bytes(data.get(offset + 1..offset + 5)?.try_into().ok()?) as usize;
+            offset += 4 + creators * 34;
+        }
+        offset += 1 + 1;
+        Some(TokenMetadata {
+            name,
+            symbol,
+            uri,
+            is_mutable: *data.get(offset)? != 0,
+        })
+    }
+}
@@ -0,0 +1,167 @@
+use axum::{routing::get, Router};
+use colored::*;
+use log::info;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
+    TextEncoder,
+};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::prepare::Trade;
+use crate::{shutdown, Result};
+
+// From a fast landing to a trade that waited out a blockhash
+const LATENCY_BUCKETS: &[f64] = &[0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+
+struct Metrics {
+    registry: Registry,
+    attempted: IntCounterVec,
+    landed: IntCounterVec,
+    latency: HistogramVec,
+    rpc_errors: IntCounterVec,
+    fees: IntCounter,
+    open_positions: IntGauge,
+}
+
+fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(|| {
+        let attempted = IntCounterVec::new(
+            Opts::new("degen_trades_attempted_total", "Trades sent, by side"),
+            &["side"],
+        )
+        .unwrap();
+        let landed = IntCounterVec::new(
+            Opts::new(
+                "degen_trades_landed_total",
+                "Trades confirmed on chain, by side",
+            ),
+            &["side"],
+        )
+        .unwrap();
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "degen_trade_latency_seconds",
+                "Time from sending a trade to its confirmation, by side",
+            )
+            .buckets(LATENCY_BUCKETS.to_vec()),
+            &["side"],
+        )
+        .unwrap();
+        let rpc_errors = IntCounterVec::new(
+            Opts::new(
+                "degen_rpc_errors_total",
+                "RPC calls and sends that failed, by endpoint",
+            ),
+            &["endpoint"],
+        )
+        .unwrap();
+        let fees = IntCounter::new(
+            "degen_fees_paid_lamports_total",
+            "Transaction fees, base and priority, paid by confirmed trades",
+        )
+        .unwrap();
+        let open_positions =
+            IntGauge::new("degen_open_positions", "Positions being monitored").unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(attempted.clone())).unwrap();
+        registry.register(Box::new(landed.clone())).unwrap();
+        registry.register(Box::new(latency.clone())).unwrap();
+        registry.register(Box::new(rpc_errors.clone())).unwrap();
+        registry.register(Box::new(fees.clone())).unwrap();
+        registry.register(Box::new(open_positions.clone())).unwrap();
+        Metrics {
+            registry,
+            attempted,
+            landed,
+            latency,
+            rpc_errors,
+            fees,
+            open_positions,
+        }
+    })
+}
+
+fn served() -> &'static AtomicBool {
+    static SERVED: AtomicBool = AtomicBool::new(false);
+    &SERVED
+}
+
+// Whether anything scrapes the metrics, so fills are only read for them then
+pub fn is_enabled() -> bool {
+    served().load(Ordering::Relaxed)
+}
+
+fn side(trade: &Trade) -> &'static str {
+    match trade {
+        Trade::Buy { .. } => "buy",
+        Trade::Sell { .. } => "sell",
+    }
+}
+
+pub fn attempted(trade: &Trade) {
+    metrics().attempted.with_label_values(&[side(trade)]).inc();
+}
+
+pub fn landed(trade: &Trade, latency: Duration) {
+    let metrics = metrics();
+    metrics.landed.with_label_values(&[side(trade)]).inc();
+    metrics
+        .latency
+        .with_label_values(&[side(trade)])
+        .observe(latency.as_secs_f64());
+}
+
+pub fn rpc_error(endpoint: &str) {
+    metrics().rpc_errors.with_label_values(&[endpoint]).inc();
+}
+
+pub fn fee_paid(lamports: u64) {
+    metrics().fees.inc_by(lamports);
+}
+
+// Counts a position as open for as long as it is held
+pub struct OpenPosition;
+
+impl OpenPosition {
+    pub fn open() -> OpenPosition {
+        metrics().open_positions.inc();
+        OpenPosition
+    }
+}
+
+impl Drop for OpenPosition {
+    fn drop(&mut self) {
+        metrics().open_positions.dec();
+    }
+}
+
+fn render() -> String {
+    let mut buffer = Vec::new();
+    // Encoding into memory only fails on malformed metric families
+    let _ = TextEncoder::new().encode(&metrics().registry.gather(), &mut buffer);
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+// Serve the Prometheus text format on /metrics until shutdown
+pub async fn serve(addr: SocketAddr) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await.map_err(|e| {
+        format!("Could not listen for metrics on {}: {}", addr, e)
+            .red()
+            .to_string()
+    })?;
+    served().store(true, Ordering::Relaxed);
+    info!(
+        "Serving metrics on {}",
+        format!("http://{}/metrics", addr).bright_blue()
+    );
+    let app = Router::new().route("/metrics", get(|| async { render() }));
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown::reached(shutdown::State::Draining))
+        .await?;
+    Ok(())
+}
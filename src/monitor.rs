@@ -0,0 +1,661 @@
+use clap::ValueEnum;
+use colored::*;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use log::{info, warn};
+use serde::Deserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{nonblocking::pubsub_client::PubsubClient, rpc_config::RpcAccountInfoConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::time::Interval;
+
+use crate::geyser::Geyser;
+use crate::market::PriceProvider;
+use crate::transaction::Sender;
+use crate::venue::Venue;
+use crate::{shutdown, Result};
+
+// Least time between two new high alerts, a rally setting one every tick
+const NEW_HIGH_ALERT_INTERVAL: Duration = Duration::from_secs(60);
+// Prices drawn in the sparkline of the price lines
+pub const SPARKLINE_POINTS: usize = 30;
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// What to do once the bonding curve completes and the token moves to Raydium
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationAction {
+    /// Sell the whole position
+    Sell,
+    /// Notify and stop monitoring, keeping the tokens
+    Alert,
+    /// Keep applying the thresholds to the market price from the price provider
+    Follow,
+}
+
+// Exit thresholds relative to the entry price, and the buys added on the way down
+#[derive(Clone, Default)]
+pub struct ExitRules {
+    pub take_profit_pct: Option<f64>,
+    pub stop_loss_pct: Option<f64>,
+    // Percent below the highest price seen since the entry
+    pub trailing_stop_pct: Option<f64>,
+    pub on_migration: Option<MigrationAction>,
+    pub buy_ladder: Vec<BuyRung>,
+    pub sell_ladder: Vec<SellRung>,
+    // Percent of the position take profit, trailing and ladder sells leave held
+    pub moon_bag_pct: Option<f64>,
+    // Percent of the position sold as soon as the creator sells any
+    pub creator_sell_pct: Option<f64>,
+    // Sell everything once this much of the curve is sold, ahead of migration
+    pub progress_exit_pct: Option<f64>,
+    // Percents from the entry to notify at, without trading
+    pub price_alerts: Vec<f64>,
+    pub alert_new_high: bool,
+}
+
+impl ExitRules {
+    pub fn is_empty(&self) -> bool {
+        self.take_profit_pct.is_none()
+            && self.stop_loss_pct.is_none()
+            && self.trailing_stop_pct.is_none()
+            && self.on_migration.is_none()
+            && self.buy_ladder.is_empty()
+            && self.sell_ladder.is_empty()
+            && self.creator_sell_pct.is_none()
+            && self.progress_exit_pct.is_none()
+            && self.price_alerts.is_empty()
+            && !self.alert_new_high
+    }
+}
+
+// Buy `amount` more SOL once the price is `drop_pct` below the entry, written
+// `DROP_PCT:AMOUNT` as in `20:0.3`
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct BuyRung {
+    pub drop_pct: f64,
+    pub amount: f64,
+}
+
+impl FromStr for BuyRung {
+    type Err = String;
+
+    fn from_str(rung: &str) -> std::result::Result<BuyRung, String> {
+        let invalid = || format!("Invalid ladder rung '{}', expected DROP_PCT:AMOUNT", rung);
+        let (drop_pct, amount) = rung.split_once(':').ok_or_else(invalid)?;
+        let drop_pct: f64 = drop_pct
+            .trim()
+            .trim_start_matches('-')
+            .parse()
+            .map_err(|_| invalid())?;
+        let amount: f64 = amount.trim().parse().map_err(|_| invalid())?;
+        if !(0.0..100.0).contains(&drop_pct) || amount <= 0.0 {
+            return Err(invalid());
+        }
+        Ok(BuyRung { drop_pct, amount })
+    }
+}
+
+impl TryFrom<String> for BuyRung {
+    type Error = String;
+
+    fn try_from(rung: String) -> std::result::Result<BuyRung, String> {
+        rung.parse()
+    }
+}
+
+// Sell `pct` of the position as bought once the price is `multiple` times the
+// entry, written `MULTIPLE:PCT` as in `2x:25`
+#[derive(Clone, Copy, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SellRung {
+    pub multiple: f64,
+    pub pct: f64,
+}
+
+impl FromStr for SellRung {
+    type Err = String;
+
+    fn from_str(rung: &str) -> std::result::Result<SellRung, String> {
+        let invalid = || format!("Invalid sell rung '{}', expected MULTIPLE:PCT", rung);
+        let (multiple, pct) = rung.split_once(':').ok_or_else(invalid)?;
+        let multiple: f64 = multiple
+            .trim()
+            .trim_end_matches(['x', 'X'])
+            .parse()
+            .map_err(|_| invalid())?;
+        let pct: f64 = pct
+            .trim()
+            .trim_end_matches('%')
+            .parse()
+            .map_err(|_| invalid())?;
+        if multiple <= 1.0 || pct <= 0.0 || pct > 100.0 {
+            return Err(invalid());
+        }
+        Ok(SellRung { multiple, pct })
+    }
+}
+
+impl TryFrom<String> for SellRung {
+    type Error = String;
+
+    fn try_from(rung: String) -> std::result::Result<SellRung, String> {
+        rung.parse()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Exit {
+    TakeProfit(f64),
+    StopLoss(f64),
+    // The price that retraced from the peak
+    TrailingStop(f64),
+    // The last curve price before the token migrated
+    Migrated(f64),
+    // The last price seen when the creator sold
+    CreatorSold(f64),
+    // The price at which the curve's progress crossed the exit threshold
+    NearMigration(f64),
+}
+
+impl Exit {
+    pub fn price(&self) -> f64 {
+        match self {
+            Exit::TakeProfit(price)
+            | Exit::StopLoss(price)
+            | Exit::TrailingStop(price)
+            | Exit::Migrated(price)
+            | Exit::CreatorSold(price)
+            | Exit::NearMigration(price) => *price,
+        }
+    }
+}
+
+// A price update of a monitored token
+pub enum Tick {
+    Price(f64),
+    // The curve completed and the migration action ends the watch, with the
+    // last curve price
+    Migrated(f64),
+}
+
+// Follows the price on the venue's curve, polled or streamed from Geyser, and
+// the market price once the token migrated when the action is to follow it
+pub struct PriceWatch<'a> {
+    sender: &'a Sender,
+    venue: Box<dyn Venue>,
+    mint: Pubkey,
+    decimals: u8,
+    entry_price: f64,
+    on_migration: Option<MigrationAction>,
+    updates: Option<BoxStream<'static, Result<Vec<u8>>>>,
+    ticker: Interval,
+    market: Box<dyn PriceProvider>,
+    following: bool,
+    last_price: f64,
+    // Tokens the curve starts with, to tell its progress
+    initial_curve_tokens: Option<u64>,
+    progress: Option<f64>,
+    history: PriceHistory,
+}
+
+impl<'a> PriceWatch<'a> {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn new(
+        sender: &'a Sender,
+        geyser: Option<&Geyser>,
+        venue: Box<dyn Venue>,
+        mint: &Pubkey,
+        decimals: u8,
+        entry_price: f64,
+        on_migration: Option<MigrationAction>,
+        interval: Duration,
+        ws_url: Option<&str>,
+        market: Box<dyn PriceProvider>,
+    ) -> Result<PriceWatch<'a>> {
+        let address = venue.curve_address(mint);
+        let updates = match (geyser, ws_url) {
+            (Some(geyser), _) => Some(geyser.account_updates(&address).await?.boxed()),
+            (None, Some(ws_url)) => match account_updates(ws_url, address).await {
+                Ok(updates) => Some(updates),
+                Err(e) => {
+                    warn!("Could not subscribe to the curve, polling instead: {}", e);
+                    None
+                }
+            },
+            (None, None) => None,
+        };
+        let initial_curve_tokens = match venue.initial_curve_tokens(sender.client()).await {
+            Ok(initial) => initial,
+            Err(e) => {
+                warn!(
+                    "Could not read the curve's initial tokens, its progress is unknown: {}",
+                    e
+                );
+                None
+            }
+        };
+        Ok(PriceWatch {
+            sender,
+            venue,
+            mint: *mint,
+            decimals,
+            entry_price,
+            on_migration,
+            updates,
+            ticker: tokio::time::interval(interval),
+            market,
+            following: false,
+            last_price: entry_price,
+            initial_curve_tokens,
+            progress: None,
+            history: PriceHistory::default(),
+        })
+    }
+
+    pub fn last_price(&self) -> f64 {
+        self.last_price
+    }
+
+    // Percent of the curve's tokens sold at the last update
+    pub fn progress(&self) -> Option<f64> {
+        self.progress
+    }
+
+    // The next price, failed reads being logged and retried
+    pub async fn next(&mut self) -> Result<Tick> {
+        loop {
+            if shutdown::requested() {
+                return Err(
+                    "Shutting down, the position is kept but no longer monitored"
+                        .yellow()
+                        .to_string()
+                        .into(),
+                );
+            }
+            if self.following || self.updates.is_none() {
+                self.ticker.tick().await;
+            }
+            if self.following {
+                // The pool may take a moment to be indexed after the migration
+                let price = match self.market.price_sol(&self.mint).await {
+                    Ok(price) => price,
+                    Err(e) => {
+                        warn!("{} price poll failed: {}", self.market.name(), e);
+                        continue;
+                    }
+                };
+                self.history.push(price);
+                info!(
+                    "{} price {:.10} SOL ({:+.2}%) {}",
+                    self.market.name(),
+                    price,
+                    (price / self.entry_price - 1.0) * 100.0,
+                    self.history.colored()
+                );
+                self.last_price = price;
+                return Ok(Tick::Price(price));
+            }
+
+            let curve = match &mut self.updates {
+                Some(stream) => match tokio::select! {
+                    update = stream.next() => update,
+                    _ = shutdown::reached(shutdown::State::Draining) => continue,
+                } {
+                    Some(Ok(data)) => self.venue.decode_curve(&data, self.decimals),
+                    Some(Err(e)) => {
+                        warn!("Curve stream failed, polling instead: {}", e);
+                        self.updates = None;
+                        continue;
+                    }
+                    None => {
+                        warn!("Curve stream ended, polling instead");
+                        self.updates = None;
+                        continue;
+                    }
+                },
+                None => {
+                    self.venue
+                        .curve(self.sender.client(), &self.mint, self.decimals)
+                        .await
+                }
+            };
+            let curve = match curve {
+                Ok(curve) => curve,
+                Err(e) => {
+                    warn!("Price poll failed: {}", e);
+                    continue;
+                }
+            };
+            if curve.complete {
+                warn!("Bonding curve is complete, the token has migrated to Raydium");
+                match self.on_migration {
+                    None => return Err("Cannot monitor a migrated token".red().to_string().into()),
+                    Some(MigrationAction::Sell | MigrationAction::Alert) => {
+                        return Ok(Tick::Migrated(self.last_price))
+                    }
+                    Some(MigrationAction::Follow) => {
+                        info!("Following the {} price", self.market.name().bright_blue());
+                        self.following = true;
+                        continue;
+                    }
+                }
+            }
+            let price = curve.price_sol;
+            self.progress = self
+                .initial_curve_tokens
+                .map(|initial| curve.progress_pct(initial));
+            self.history.push(price);
+            info!(
+                "Price {:.10} SOL ({:+.2}%), market cap {:.2} SOL, curve {} {}",
+                price,
+                (price / self.entry_price - 1.0) * 100.0,
+                curve.market_cap_sol,
+                self.progress
+                    .map_or("-".to_string(), |progress| format!("{:.1}%", progress)),
+                self.history.colored()
+            );
+            self.last_price = price;
+            return Ok(Tick::Price(price));
+        }
+    }
+}
+
+// The data of `address` after every confirmed write to it, from a WebSocket
+// accountSubscribe kept in a background task while the stream is alive
+async fn account_updates(
+    ws_url: &str,
+    address: Pubkey,
+) -> Result<BoxStream<'static, Result<Vec<u8>>>> {
+    let pubsub = PubsubClient::new(ws_url).await?;
+    let (updates, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let subscription = pubsub
+            .account_subscribe(
+                &address,
+                Some(RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    ..Default::default()
+                }),
+            )
+            .await;
+        let (mut accounts, _unsubscribe) = match subscription {
+            Ok(subscription) => subscription,
+            Err(e) => {
+                let _ = updates.send(Err(e.to_string().into()));
+                return;
+            }
+        };
+        while let Some(response) = tokio::select! {
+            response = accounts.next() => response,
+            _ = updates.closed() => None,
+        } {
+            let data = response
+                .value
+                .data
+                .decode()
+                .ok_or_else(|| "Undecodable account data".into());
+            if updates.send(data).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|update| (update, receiver))
+    })
+    .boxed())
+}
+
+// The take-profit and stop-loss prices of a position, and the trailing stop
+// following its high-water price
+pub struct Thresholds {
+    pub take_profit: Option<f64>,
+    pub stop_loss: Option<f64>,
+    pub trailing_stop_pct: Option<f64>,
+    pub peak: f64,
+}
+
+impl Thresholds {
+    pub fn new(rules: &ExitRules, entry_price: f64) -> Thresholds {
+        Thresholds {
+            take_profit: rules
+                .take_profit_pct
+                .map(|pct| entry_price * (1.0 + pct / 100.0)),
+            stop_loss: rules
+                .stop_loss_pct
+                .map(|pct| entry_price * (1.0 - pct / 100.0)),
+            trailing_stop_pct: rules.trailing_stop_pct,
+            peak: entry_price,
+        }
+    }
+
+    // Whether any threshold is still set
+    pub fn is_armed(&self) -> bool {
+        self.take_profit.is_some() || self.stop_loss.is_some() || self.trailing_stop_pct.is_some()
+    }
+
+    // Drop every threshold at once, the take profit and stops forming one
+    // order that cancels together
+    pub fn cancel(&mut self) {
+        self.take_profit = None;
+        self.stop_loss = None;
+        self.trailing_stop_pct = None;
+    }
+
+    // Where the trailing stop currently sits
+    pub fn trailing_stop(&self) -> Option<f64> {
+        self.trailing_stop_pct
+            .map(|pct| self.peak * (1.0 - pct / 100.0))
+    }
+
+    pub fn check(&mut self, price: f64) -> Option<Exit> {
+        if self.take_profit.is_some_and(|tp| price >= tp) {
+            return Some(Exit::TakeProfit(price));
+        }
+        if self.stop_loss.is_some_and(|sl| price <= sl) {
+            return Some(Exit::StopLoss(price));
+        }
+        if self.trailing_stop().is_some_and(|stop| price <= stop) {
+            return Some(Exit::TrailingStop(price));
+        }
+        if price > self.peak {
+            self.peak = price;
+            if let Some(stop) = self.trailing_stop() {
+                info!("New high, trailing stop raised to {:.10} SOL", stop);
+            }
+        }
+        None
+    }
+}
+
+// A price level worth a notification, leaving the decision to the user
+#[derive(Clone, Copy)]
+pub enum Alert {
+    // Percent from the entry, negative below it
+    Change(f64),
+    // Highest price since the entry
+    NewHigh,
+}
+
+// The alert levels not reached yet, and the high-water price for new highs
+pub struct Alerts {
+    entry_price: f64,
+    levels: Vec<f64>,
+    new_high: bool,
+    high: f64,
+    high_alerted_at: Option<Instant>,
+}
+
+impl Alerts {
+    pub fn new(rules: &ExitRules, entry_price: f64) -> Alerts {
+        Alerts {
+            entry_price,
+            levels: rules.price_alerts.clone(),
+            new_high: rules.alert_new_high,
+            high: entry_price,
+            high_alerted_at: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty() && !self.new_high
+    }
+
+    // The alerts the price sets off, each level once and new highs at most
+    // once per NEW_HIGH_ALERT_INTERVAL
+    pub fn check(&mut self, price: f64) -> Vec<Alert> {
+        let change = (price / self.entry_price - 1.0) * 100.0;
+        let (reached, left): (Vec<f64>, Vec<f64>) = self.levels.iter().partition(|level| {
+            (**level >= 0.0 && change >= **level) || (**level < 0.0 && change <= **level)
+        });
+        self.levels = left;
+        let mut alerts: Vec<Alert> = reached.into_iter().map(Alert::Change).collect();
+        if price > self.high {
+            self.high = price;
+            let due = self
+                .high_alerted_at
+                .is_none_or(|at| at.elapsed() >= NEW_HIGH_ALERT_INTERVAL);
+            if self.new_high && due {
+                self.high_alerted_at = Some(Instant::now());
+                alerts.push(Alert::NewHigh);
+            }
+        }
+        alerts
+    }
+}
+
+// The latest prices of a token, drawn as a sparkline to show the trend
+#[derive(Default)]
+pub struct PriceHistory {
+    prices: VecDeque<f64>,
+}
+
+impl PriceHistory {
+    pub fn push(&mut self, price: f64) {
+        if self.prices.len() == SPARKLINE_POINTS {
+            self.prices.pop_front();
+        }
+        self.prices.push_back(price);
+    }
+
+    // One block per price, scaled between the lowest and highest kept
+    pub fn sparkline(&self) -> String {
+        let low = self.prices.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = self
+            .prices
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        self.prices
+            .iter()
+            .map(|price| {
+                let level = match high - low {
+                    range if range > 0.0 => {
+                        ((price - low) / range * (SPARKS.len() - 1) as f64).round() as usize
+                    }
+                    _ => 0,
+                };
+                SPARKS[level]
+            })
+            .collect()
+    }
+
+    // The sparkline in green when the price rose over it, red when it fell
+    pub fn colored(&self) -> ColoredString {
+        let sparkline = self.sparkline();
+        match (self.prices.front(), self.prices.back()) {
+            (Some(first), Some(last)) if last < first => sparkline.red(),
+            _ => sparkline.green(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buy_rung_reads_the_drop_and_amount() {
+        let rung: BuyRung = "20:0.3".parse().unwrap();
+        assert_eq!(rung.drop_pct, 20.0);
+        assert_eq!(rung.amount, 0.3);
+        let rung: BuyRung = " -35 : 1.5 ".parse().unwrap();
+        assert_eq!(rung.drop_pct, 35.0);
+        assert_eq!(rung.amount, 1.5);
+    }
+
+    #[test]
+    fn buy_rung_out_of_range_is_refused() {
+        for rung in ["20", "20:", "x:0.3", "100:0.3", "20:0", "20:-1"] {
+            assert!(rung.parse::<BuyRung>().is_err(), "{}", rung);
+        }
+    }
+
+    #[test]
+    fn sell_rung_reads_the_multiple_and_percent() {
+        let rung: SellRung = "2x:25".parse().unwrap();
+        assert_eq!(rung.multiple, 2.0);
+        assert_eq!(rung.pct, 25.0);
+        let rung: SellRung = " 3.5X : 100% ".parse().unwrap();
+        assert_eq!(rung.multiple, 3.5);
+        assert_eq!(rung.pct, 100.0);
+    }
+
+    #[test]
+    fn sell_rung_out_of_range_is_refused() {
+        for rung in ["2x", "2x:", "y:25", "1x:25", "0.5x:25", "2x:0", "2x:101"] {
+            assert!(rung.parse::<SellRung>().is_err(), "{}", rung);
+        }
+    }
+
+    fn thresholds(take_profit: f64, stop_loss: f64, trailing: Option<f64>) -> Thresholds {
+        let rules = ExitRules {
+            take_profit_pct: Some(take_profit),
+            stop_loss_pct: Some(stop_loss),
+            trailing_stop_pct: trailing,
+            ..ExitRules::default()
+        };
+        Thresholds::new(&rules, 1.0)
+    }
+
+    #[test]
+    fn take_profit_and_stop_loss_sit_around_the_entry() {
+        let mut thresholds = thresholds(50.0, 20.0, None);
+        assert_eq!(thresholds.take_profit, Some(1.5));
+        assert_eq!(thresholds.stop_loss, Some(0.8));
+        assert!(thresholds.check(1.2).is_none());
+        assert!(matches!(thresholds.check(0.8), Some(Exit::StopLoss(_))));
+        assert!(matches!(thresholds.check(1.6), Some(Exit::TakeProfit(_))));
+    }
+
+    #[test]
+    fn trailing_stop_follows_the_high() {
+        let mut thresholds = thresholds(400.0, 90.0, Some(10.0));
+        assert_eq!(thresholds.trailing_stop(), Some(0.9));
+        assert!(thresholds.check(2.0).is_none());
+        assert_eq!(thresholds.trailing_stop(), Some(1.8));
+        // A lower price leaves the stop where the high put it
+        assert!(thresholds.check(1.9).is_none());
+        assert_eq!(thresholds.trailing_stop(), Some(1.8));
+        assert!(matches!(
+            thresholds.check(1.8),
+            Some(Exit::TrailingStop(price)) if price == 1.8
+        ));
+    }
+
+    #[test]
+    fn cancel_drops_every_threshold_together() {
+        let mut thresholds = thresholds(50.0, 20.0, Some(10.0));
+        assert!(thresholds.is_armed());
+        thresholds.cancel();
+        assert!(!thresholds.is_armed());
+        assert!(thresholds.check(10.0).is_none());
+        assert!(thresholds.check(0.01).is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use colored::*;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::VersionedMessage,
+    nonce::state::{State, Versions},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::signer::WalletSigner;
+use crate::transaction::unsigned;
+use crate::{message, Result};
+
+// SystemInstruction::AdvanceNonceAccount
+const ADVANCE_NONCE_TAG: [u8; 4] = [4, 0, 0, 0];
+
+// The stored nonce and the authority allowed to advance it
+pub struct Nonce {
+    pub blockhash: Hash,
+    pub authority: Pubkey,
+    pub lamports: u64,
+}
+
+pub async fn fetch(client: &RpcClient, account: &Pubkey) -> Result<Nonce> {
+    let account_info = client.get_account(account).await?;
+    if account_info.owner != system_program::id() {
+        return Err(format!("{} is not a nonce account", account)
+            .red()
+            .to_string()
+            .into());
+    }
+    let versions: Versions = bincode::deserialize(&account_info.data)?;
+    match versions.state() {
+        State::Initialized(data) => Ok(Nonce {
+            blockhash: data.blockhash(),
+            authority: data.authority,
+            lamports: account_info.lamports,
+        }),
+        State::Uninitialized => Err(format!("Nonce account {} is not initialized", account)
+            .red()
+            .to_string()
+            .into()),
+    }
+}
+
+// The nonce account a transaction advances, when it is built against one.
+// The advance has to be its first instruction.
+pub fn durable_nonce(message: &VersionedMessage) -> Option<Pubkey> {
+    let ix = message.instructions().first()?;
+    let keys = message.static_account_keys();
+    let program = keys.get(ix.program_id_index as usize)?;
+    if *program != system_program::id() || !ix.data.starts_with(&ADVANCE_NONCE_TAG) {
+        return None;
+    }
+    keys.get(*ix.accounts.first()? as usize).copied()
+}
+
+// Rebuild an unsigned transaction against the durable nonce: the stored nonce
+// replaces the blockhash and an advance of the account comes first. Signed
+// with it, the transaction stays valid until the nonce is advanced.
+pub async fn apply(
+    client: &RpcClient,
+    tx: &mut VersionedTransaction,
+    account: &Pubkey,
+    authority: &Pubkey,
+) -> Result<()> {
+    if tx.signatures.iter().any(|s| *s != Signature::default()) {
+        return Err(
+            "Transaction is already partially signed, it can't use a durable nonce"
+                .red()
+                .to_string()
+                .into(),
+        );
+    }
+    let nonce = fetch(client, account).await?;
+    if nonce.authority != *authority {
+        return Err(format!(
+            "Nonce account {} is controlled by {}, not the wallet {}",
+            account, nonce.authority, authority
+        )
+        .red()
+        .to_string()
+        .into());
+    }
+
+    let mut decompiled = message::decompile(client, &tx.message).await?;
+    let mut instructions = vec![system_instruction::advance_nonce_account(
+        account, authority,
+    )];
+    instructions.extend(
+        decompiled
+            .instructions
+            .drain(..)
+            .filter(|ix| !is_advance(ix)),
+    );
+    decompiled.instructions = instructions;
+    decompiled.blockhash = nonce.blockhash;
+    *tx = unsigned(decompiled.compile()?);
+    Ok(())
+}
+
+pub fn is_advance(ix: &Instruction) -> bool {
+    ix.program_id == system_program::id() && ix.data.starts_with(&ADVANCE_NONCE_TAG)
+}
+
+// Create and fund a new nonce account controlled by the wallet
+pub async fn create(client: &RpcClient, payer: &WalletSigner) -> Result<Pubkey> {
+    let account = Keypair::new();
+    let lamports = client
+        .get_minimum_balance_for_rent_exemption(State::size())
+        .await?;
+    let instructions = system_instruction::create_nonce_account(
+        &payer.pubkey(),
+        &account.pubkey(),
+        &payer.pubkey(),
+        lamports,
+    );
+    let blockhash = client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer as &dyn Signer, &account],
+        blockhash,
+    );
+    client.send_and_confirm_transaction(&tx).await?;
+    Ok(account.pubkey())
+}
+
+// Advance the nonce, invalidating every transaction signed against the old one
+pub async fn advance(client: &RpcClient, payer: &WalletSigner, account: &Pubkey) -> Result<Hash> {
+    let ix = system_instruction::advance_nonce_account(account, &payer.pubkey());
+    send(client, payer, &[ix]).await?;
+    Ok(fetch(client, account).await?.blockhash)
+}
+
+// Withdraw the whole balance back to the wallet, which closes the account
+pub async fn close(client: &RpcClient, payer: &WalletSigner, account: &Pubkey) -> Result<u64> {
+    let nonce = fetch(client, account).await?;
+    let ix = system_instruction::withdraw_nonce_account(
+        account,
+        &payer.pubkey(),
+        &payer.pubkey(),
+        nonce.lamports,
+    );
+    send(client, payer, &[ix]).await?;
+    Ok(nonce.lamports)
+}
+
+async fn send(
+    client: &RpcClient,
+    payer: &WalletSigner,
+    instructions: &[Instruction],
+) -> Result<()> {
+    let blockhash = client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &[payer],
+        blockhash,
+    );
+    client.send_and_confirm_transaction(&tx).await?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use serde_json::json;
+use std::time::Duration;
+
+use super::Event;
+use crate::{BotError, Result};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+const COLOR_SUCCESS: u32 = 0x2ecc71;
+const COLOR_FAILURE: u32 = 0xe74c3c;
+
+// Rich embeds posted to a Discord channel webhook
+#[derive(Clone)]
+pub struct Discord {
+    http: reqwest::Client,
+    webhook_url: String,
+}
+
+impl Discord {
+    pub fn new(webhook_url: String) -> Discord {
+        Discord {
+            http: reqwest::Client::new(),
+            webhook_url,
+        }
+    }
+
+    pub async fn send(&self, event: &Event) -> Result<()> {
+        let fields: Vec<_> = event
+            .fields()
+            .into_iter()
+            .map(|(name, value)| {
+                json!({
+                    "name": name,
+                    "value": value,
+                    // Long values get a row of their own
+                    "inline": value.len() <= 24,
+                })
+            })
+            .collect();
+        let color = if event.is_failure() {
+            COLOR_FAILURE
+        } else {
+            COLOR_SUCCESS
+        };
+        let response = self
+            .http
+            .post(&self.webhook_url)
+            .timeout(TIMEOUT)
+            .json(&json!({
+                "embeds": [{
+                    "title": event.title(),
+                    "color": color,
+                    "fields": fields,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                }],
+            }))
+            .send()
+            .await
+            // The webhook URL is a secret
+            .map_err(|e| e.without_url())?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(BotError::api("Discord", Some(status.as_u16()), status));
+        }
+        Ok(())
+    }
+}
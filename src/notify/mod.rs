@@ -0,0 +1,241 @@
+mod discord;
+mod telegram;
+
+use log::{debug, warn};
+use solana_sdk::{
+    clock::Slot, native_token::lamports_to_sol, pubkey::Pubkey, signature::Signature,
+};
+use std::sync::Arc;
+
+pub use discord::Discord;
+pub use telegram::Telegram;
+
+use crate::breaker::Trip;
+use crate::events::{self, Update};
+use crate::fill::Fill;
+use crate::monitor::{Alert, Exit};
+use crate::prepare::Trade;
+
+// Trade lifecycle events worth telling the user about when nobody watches the logs
+pub enum Event {
+    Sent {
+        trade: Trade,
+        signature: Signature,
+    },
+    Confirmed {
+        trade: Trade,
+        signature: Signature,
+        slot: Slot,
+        fill: Option<Fill>,
+    },
+    Failed {
+        trade: Trade,
+        error: String,
+    },
+    // Refused before signing, by the risk limits
+    Refused {
+        trade: Trade,
+        reason: String,
+    },
+    Alert {
+        mint: Pubkey,
+        alert: Alert,
+        price: f64,
+        change_pct: f64,
+    },
+    Exit {
+        mint: Pubkey,
+        exit: Exit,
+    },
+    // The circuit breaker paused trading
+    Paused(Trip),
+}
+
+impl Event {
+    pub fn title(&self) -> String {
+        match self {
+            Event::Sent { trade, .. } => format!("{} sent", trade_name(trade)),
+            Event::Confirmed { trade, .. } => format!("{} confirmed", trade_name(trade)),
+            Event::Failed { trade, .. } => format!("{} failed", trade_name(trade)),
+            Event::Refused { trade, .. } => format!("{} refused", trade_name(trade)),
+            Event::Alert {
+                alert: Alert::Change(pct),
+                ..
+            } => format!("Price {:+}% from entry", pct),
+            Event::Alert {
+                alert: Alert::NewHigh,
+                ..
+            } => "New high since entry".to_string(),
+            Event::Exit {
+                exit: Exit::TakeProfit(_),
+                ..
+            } => "Take profit hit".to_string(),
+            Event::Exit {
+                exit: Exit::StopLoss(_),
+                ..
+            } => "Stop loss hit".to_string(),
+            Event::Exit {
+                exit: Exit::TrailingStop(_),
+                ..
+            } => "Trailing stop hit".to_string(),
+            Event::Exit {
+                exit: Exit::Migrated(_),
+                ..
+            } => "Token migrated to Raydium".to_string(),
+            Event::Exit {
+                exit: Exit::CreatorSold(_),
+                ..
+            } => "Creator sold".to_string(),
+            Event::Exit {
+                exit: Exit::NearMigration(_),
+                ..
+            } => "Curve near migration".to_string(),
+            Event::Paused(_) => "Buying paused".to_string(),
+        }
+    }
+
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            Event::Failed { .. }
+                | Event::Refused { .. }
+                | Event::Paused(_)
+                | Event::Exit {
+                    exit: Exit::StopLoss(_) | Exit::TrailingStop(_) | Exit::CreatorSold(_),
+                    ..
+                }
+        )
+    }
+
+    // (name, value) pairs describing the event
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        match self {
+            Event::Sent { trade, signature } => {
+                push_trade(&mut fields, trade);
+                fields.push(("Signature", solscan_url(signature)));
+            }
+            Event::Confirmed {
+                trade,
+                signature,
+                slot,
+                fill,
+            } => {
+                push_trade(&mut fields, trade);
+                if let Some(price) = fill.as_ref().and_then(Fill::price) {
+                    fields.push(("Price", format!("{:.10} SOL", price)));
+                }
+                fields.push(("Slot", slot.to_string()));
+                fields.push(("Signature", solscan_url(signature)));
+            }
+            Event::Failed { trade, error } => {
+                push_trade(&mut fields, trade);
+                fields.push(("Error", strip_ansi(error)));
+            }
+            Event::Refused { trade, reason } => {
+                push_trade(&mut fields, trade);
+                fields.push(("Reason", strip_ansi(reason)));
+            }
+            Event::Alert {
+                mint,
+                price,
+                change_pct,
+                ..
+            } => {
+                fields.push(("Mint", mint.to_string()));
+                fields.push(("Price", format!("{:.10} SOL", price)));
+                fields.push(("Change", format!("{:+.2}%", change_pct)));
+            }
+            Event::Exit { mint, exit } => {
+                fields.push(("Mint", mint.to_string()));
+                fields.push(("Price", format!("{:.10} SOL", exit.price())));
+            }
+            Event::Paused(trip) => {
+                fields.push(("Failures in a row", trip.failures.to_string()));
+                fields.push(("Resumes at", trip.resumes_at.clone()));
+            }
+        }
+        fields
+    }
+
+    // Plain text rendering, the title followed by one field per line
+    pub fn text(&self) -> String {
+        let mut text = self.title();
+        for (name, value) in self.fields() {
+            text.push_str(&format!("\n{}: {}", name, value));
+        }
+        text
+    }
+}
+
+fn trade_name(trade: &Trade) -> &'static str {
+    match trade {
+        Trade::Buy { .. } => "Buy",
+        Trade::Sell { .. } => "Sell",
+    }
+}
+
+fn push_trade(fields: &mut Vec<(&'static str, String)>, trade: &Trade) {
+    fields.push(("Mint", trade.mint().to_string()));
+    let amount = match trade {
+        Trade::Buy { lamports, .. } => format!("{} SOL", lamports_to_sol(*lamports)),
+        Trade::Sell { token_amount, .. } => format!("{} raw units", token_amount),
+    };
+    fields.push(("Amount", amount));
+}
+
+// Error messages are colored for the terminal, chat apps want plain text
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the CSI sequence up to its final letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+pub fn solscan_url(signature: &Signature) -> String {
+    format!("https://solscan.io/tx/{}", signature)
+}
+
+// Fans events out to every configured channel. Delivery problems are logged
+// and never fail the trade.
+#[derive(Clone, Default)]
+pub struct Notifier {
+    telegram: Option<Telegram>,
+    discord: Option<Discord>,
+}
+
+impl Notifier {
+    pub fn new(telegram: Option<Telegram>, discord: Option<Discord>) -> Notifier {
+        Notifier { telegram, discord }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.telegram.is_some() || self.discord.is_some()
+    }
+
+    pub async fn notify(&self, event: Event) {
+        if let Some(telegram) = &self.telegram {
+            if let Err(e) = telegram.send(&event.text()).await {
+                warn!("Telegram notification failed: {}", e);
+            }
+        }
+        if let Some(discord) = &self.discord {
+            if let Err(e) = discord.send(&event).await {
+                warn!("Discord notification failed: {}", e);
+            }
+        }
+        debug!("Notified: {}", event.title());
+        events::publish(Update::Event(Arc::new(event)));
+    }
+}
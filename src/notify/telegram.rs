@@ -0,0 +1,109 @@
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+use crate::{BotError, Result};
+
+const TELEGRAM_API_URL: &str = "https://api.telegram.org";
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct Response<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    pub message: Option<Message>,
+}
+
+#[derive(Deserialize)]
+pub struct Message {
+    pub chat: Chat,
+    pub text: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Chat {
+    pub id: i64,
+}
+
+// Messages sent through a Telegram bot to a single chat
+#[derive(Clone)]
+pub struct Telegram {
+    http: reqwest::Client,
+    bot_token: String,
+    chat_id: String,
+}
+
+impl Telegram {
+    pub fn new(bot_token: String, chat_id: String) -> Telegram {
+        Telegram {
+            http: reqwest::Client::new(),
+            bot_token,
+            chat_id,
+        }
+    }
+
+    pub async fn send(&self, text: &str) -> Result<()> {
+        let response = self
+            .http
+            .post(format!(
+                "{}/bot{}/sendMessage",
+                TELEGRAM_API_URL, self.bot_token
+            ))
+            .timeout(TIMEOUT)
+            .json(&json!({
+                "chat_id": self.chat_id,
+                "text": text,
+                "disable_web_page_preview": true,
+            }))
+            .send()
+            .await
+            // The URL carries the bot token
+            .map_err(|e| e.without_url())?;
+        if !response.status().is_success() {
+            let status = response.status();
+            return Err(BotError::api("Telegram", Some(status.as_u16()), status));
+        }
+        Ok(())
+    }
+
+    // The configured chat, the only one whose commands are accepted
+    pub fn chat_id(&self) -> &str {
+        &self.chat_id
+    }
+
+    // Long poll for updates from `offset` on, waiting up to `wait_secs`
+    pub async fn updates(&self, offset: i64, wait_secs: u64) -> Result<Vec<Update>> {
+        let response: Response<Vec<Update>> = self
+            .http
+            .post(format!(
+                "{}/bot{}/getUpdates",
+                TELEGRAM_API_URL, self.bot_token
+            ))
+            .timeout(TIMEOUT + Duration::from_secs(wait_secs))
+            .json(&json!({
+                "offset": offset,
+                "timeout": wait_secs,
+                "allowed_updates": ["message"],
+            }))
+            .send()
+            .await
+            .map_err(|e| e.without_url())?
+            .json()
+            .await
+            .map_err(|e| e.without_url())?;
+        match response.result {
+            Some(updates) if response.ok => Ok(updates),
+            _ => Err(format!(
+                "Telegram getUpdates failed: {}",
+                response.description.unwrap_or_default()
+            )
+            .into()),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use std::sync::{Mutex, OnceLock};
+
+use crate::fill::Fill;
+use crate::notify::strip_ansi;
+use crate::prepare::Trade;
+use crate::Result;
+
+// How the outcome of a command is printed
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Logs only, for people
+    Text,
+    /// A JSON result object on stdout once the command ends, logs staying on
+    /// stderr
+    Json,
+}
+
+// One trade as the command left it
+#[derive(Serialize)]
+struct TradeResult {
+    wallet: String,
+    mint: String,
+    side: &'static str,
+    // Lamports spent for a buy, raw tokens for a sell
+    amount: u64,
+    signature: String,
+    // "sent", "confirmed" or "failed"
+    status: &'static str,
+    slot: Option<Slot>,
+    // SOL per whole token, fee excluded
+    price: Option<f64>,
+    sol: Option<f64>,
+    tokens: Option<f64>,
+    fee_lamports: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CommandResult<'a> {
+    ok: bool,
+    error: Option<String>,
+    // "api", "rpc", "signing", "policy", "insufficient_funds" or "other"
+    error_kind: Option<&'static str>,
+    trades: &'a [TradeResult],
+}
+
+fn format() -> &'static OnceLock<OutputFormat> {
+    static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+    &FORMAT
+}
+
+fn trades() -> &'static Mutex<Vec<TradeResult>> {
+    static TRADES: Mutex<Vec<TradeResult>> = Mutex::new(Vec::new());
+    &TRADES
+}
+
+pub fn init(output: OutputFormat) {
+    let _ = format().set(output);
+}
+
+pub fn is_json() -> bool {
+    format().get() == Some(&OutputFormat::Json)
+}
+
+pub(crate) fn sent(trade: &Trade, wallet: &Pubkey, signature: &Signature) {
+    if !is_json() {
+        return;
+    }
+    let (side, amount) = match trade {
+        Trade::Buy { lamports, .. } => ("buy", *lamports),
+        Trade::Sell { token_amount, .. } => ("sell", *token_amount),
+    };
+    trades().lock().unwrap().push(TradeResult {
+        wallet: wallet.to_string(),
+        mint: trade.mint().to_string(),
+        side,
+        amount,
+        signature: signature.to_string(),
+        status: "sent",
+        slot: None,
+        price: None,
+        sol: None,
+        tokens: None,
+        fee_lamports: None,
+        error: None,
+    });
+}
+
+pub(crate) fn confirmed(signature: &Signature, slot: Slot, fill: Option<&Fill>) {
+    update(signature, |result| {
+        result.status = "confirmed";
+        result.slot = Some(slot);
+        if let Some(fill) = fill {
+            result.price = fill.price();
+            result.sol = Some(fill.sol);
+            result.tokens = Some(fill.tokens);
+            result.fee_lamports = Some(fill.fee_lamports);
+        }
+    });
+}
+
+// A trade that failed to send is recorded as well, under the signature it
+// would have had
+pub(crate) fn failed(trade: &Trade, wallet: &Pubkey, signature: &Signature, error: &str) {
+    if !is_json() {
+        return;
+    }
+    let known = trades()
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|result| result.signature == signature.to_string());
+    if !known {
+        sent(trade, wallet, signature);
+    }
+    update(signature, |result| {
+        result.status = "failed";
+        result.error = Some(strip_ansi(error));
+    });
+}
+
+fn update(signature: &Signature, apply: impl FnOnce(&mut TradeResult)) {
+    if !is_json() {
+        return;
+    }
+    let signature = signature.to_string();
+    let mut trades = trades().lock().unwrap();
+    if let Some(result) = trades
+        .iter_mut()
+        .rev()
+        .find(|result| result.signature == signature)
+    {
+        apply(result);
+    }
+}
+
+// Print the result object of the command on stdout, in JSON mode
+pub fn finish(result: &Result<()>) {
+    if !is_json() {
+        return;
+    }
+    let trades = trades().lock().unwrap();
+    let result = CommandResult {
+        ok: result.is_ok(),
+        error: result.as_ref().err().map(|e| strip_ansi(&e.to_string())),
+        error_kind: result.as_ref().err().map(|e| e.kind()),
+        trades: &trades,
+    };
+    match serde_json::to_string(&result) {
+        Ok(json) => println!("{}", json),
+        Err(e) => log::error!("Could not print the JSON result: {}", e),
+    }
+}
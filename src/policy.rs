@@ -0,0 +1,68 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget, pubkey::Pubkey, system_program, transaction::VersionedTransaction,
+};
+
+use crate::config::Config;
+use crate::jupiter::JUPITER_PROGRAM_ID;
+use crate::wallet::{self, ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::{explain, message, BotError, Result};
+
+// Programs a transaction may call before the bot signs it. Anything else in
+// a transaction, most likely from a compromised or spoofed API, is refused.
+pub struct Policy {
+    allowed: Vec<Pubkey>,
+}
+
+impl Policy {
+    // The programs a trade needs on the configured venue, plus Jupiter for a
+    // sell the bot built through it and any extra `allowed_programs`
+    pub fn from_config(config: &Config, jupiter_sell: bool) -> Result<Policy> {
+        let mut allowed = vec![
+            system_program::id(),
+            compute_budget::id(),
+            TOKEN_PROGRAM_ID,
+            TOKEN_2022_PROGRAM_ID,
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+        ];
+        allowed.extend(config.venue_program_id());
+        if config.jupiter_sells && jupiter_sell {
+            allowed.push(JUPITER_PROGRAM_ID);
+        }
+        for program in &config.allowed_programs {
+            allowed.push(wallet::parse_pubkey(program)?);
+        }
+        Ok(Policy { allowed })
+    }
+
+    pub async fn check(
+        &self,
+        client: &RpcClient,
+        tx: &VersionedTransaction,
+        program_id: Option<&Pubkey>,
+    ) -> Result<()> {
+        let decompiled = message::decompile(client, &tx.message).await?;
+        for (i, ix) in decompiled.instructions.iter().enumerate() {
+            if self.allowed.contains(&ix.program_id) {
+                continue;
+            }
+            let program = match explain::program_name(&ix.program_id, program_id) {
+                Some(name) => format!("{} ({})", ix.program_id, name),
+                None => ix.program_id.to_string(),
+            };
+            // Without the program id the degen.fund curve itself is unknown
+            let hint = if program_id.is_none() {
+                "Set program_id to the degen.fund program, or add it to allowed_programs"
+            } else {
+                "Add it to allowed_programs in the config file if it is expected"
+            };
+            return Err(BotError::policy(format!(
+                "Refusing to sign, instruction {} calls program {} which is not allowed. {}",
+                i + 1,
+                program,
+                hint
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use log::{debug, warn};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    compute_budget, native_token::lamports_to_sol, pubkey::Pubkey, system_program,
+    transaction::VersionedTransaction,
+};
+
+use crate::config::Config;
+use crate::curve::BUY_DISCRIMINATOR;
+use crate::prepare::Trade;
+use crate::transaction::SendMode;
+use crate::wallet::ASSOCIATED_TOKEN_PROGRAM_ID;
+use crate::{message, BotError, Result};
+
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+// Compute units a transaction gets per instruction without a SetComputeUnitLimit
+const DEFAULT_UNITS_PER_INSTRUCTION: u64 = 200_000;
+const MAX_COMPUTE_UNITS: u64 = 1_400_000;
+const TOKEN_ACCOUNT_LEN: usize = 165;
+const SYSTEM_TRANSFER_TAG: [u8; 4] = [2, 0, 0, 0];
+
+// Refuse to sign when the payer can't cover everything the transaction may
+// debit: the buy limit, network and priority fees, new token account rent and
+// the Jito tip, while staying rent exempt
+pub async fn check_balance(
+    config: &Config,
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+    payer: &Pubkey,
+    trade: &Trade,
+) -> Result<()> {
+    let decompiled = message::decompile(client, &tx.message).await?;
+    let program_id = config.program_id().ok();
+
+    let mut unit_limit = None;
+    let mut unit_price = 0u64;
+    let mut spend = 0u64;
+    let mut new_accounts = 0usize;
+    let mut found_buy = false;
+    for ix in &decompiled.instructions {
+        if ix.program_id == compute_budget::id() {
+            match ix.data.first() {
+                Some(2) if ix.data.len() >= 5 => {
+                    unit_limit = Some(u32::from_le_bytes(ix.data[1..5].try_into()?) as u64)
+                }
+                Some(3) if ix.data.len() >= 9 => {
+                    unit_price = u64::from_le_bytes(ix.data[1..9].try_into()?)
+                }
+                _ => {}
+            }
+        } else if Some(ix.program_id) == program_id
+            && ix.data.starts_with(&BUY_DISCRIMINATOR)
+            && ix.data.len() >= 24
+        {
+            // The most SOL the buy may take
+            spend += u64::from_le_bytes(ix.data[16..24].try_into()?);
+            found_buy = true;
+        } else if ix.program_id == system_program::id()
+            && ix.data.len() >= 12
+            && ix.data[..4] == SYSTEM_TRANSFER_TAG
+            && ix.accounts.first().map(|account| account.pubkey) == Some(*payer)
+        {
+            // Plain SOL transfers out of the wallet, e.g. a service fee
+            spend += u64::from_le_bytes(ix.data[4..12].try_into()?);
+        } else if ix.program_id == ASSOCIATED_TOKEN_PROGRAM_ID {
+            if let Some(account) = ix.accounts.get(1) {
+                if client.get_account(&account.pubkey).await.is_err() {
+                    new_accounts += 1;
+                }
+            }
+        }
+    }
+    // Without the program id the curve instruction can't be read, fall back
+    // to the amount we asked to spend
+    if let (false, Trade::Buy { lamports, .. }) = (found_buy, trade) {
+        spend += *lamports;
+    }
+
+    let units = unit_limit.unwrap_or(
+        (DEFAULT_UNITS_PER_INSTRUCTION * decompiled.instructions.len() as u64)
+            .min(MAX_COMPUTE_UNITS),
+    );
+    let priority_fee = (unit_price as u128 * units as u128).div_ceil(1_000_000) as u64;
+    let network_fee = LAMPORTS_PER_SIGNATURE * tx.message.header().num_required_signatures as u64;
+    let rent = if new_accounts > 0 {
+        client
+            .get_minimum_balance_for_rent_exemption(TOKEN_ACCOUNT_LEN)
+            .await?
+            * new_accounts as u64
+    } else {
+        0
+    };
+    let tip = match config.send_mode {
+        SendMode::Jito => config.max_jito_tip(),
+        SendMode::Race if config.race_jito => config.max_jito_tip(),
+        _ => 0,
+    };
+    // A wallet left with a non-zero balance must stay rent exempt
+    let reserve = client.get_minimum_balance_for_rent_exemption(0).await?;
+
+    let required = spend + network_fee + priority_fee + rent + tip + reserve;
+    let balance = client.get_balance(payer).await?;
+    debug!(
+        "Preflight: spend {} + fees {} + priority {} + rent {} + tip {} + reserve {} = {} of {} lamports",
+        spend, network_fee, priority_fee, rent, tip, reserve, required, balance
+    );
+    if balance < required {
+        warn!(
+            "The transaction needs up to {:.6} SOL trade, {:.6} SOL fees, {:.6} SOL token account \
+             rent, {:.6} SOL tip and {:.6} SOL rent reserve",
+            lamports_to_sol(spend),
+            lamports_to_sol(network_fee + priority_fee),
+            lamports_to_sol(rent),
+            lamports_to_sol(tip),
+            lamports_to_sol(reserve)
+        );
+        return Err(BotError::InsufficientFunds {
+            wallet: *payer,
+            needed: required,
+            available: balance,
+        });
+    }
+    Ok(())
+}
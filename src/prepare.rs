@@ -0,0 +1,157 @@
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::lamports_to_sol, pubkey::Pubkey, signature::Signer,
+    transaction::VersionedTransaction,
+};
+use tracing::instrument;
+
+use crate::config::Config;
+use crate::policy::Policy;
+use crate::signer::WalletSigner;
+use crate::transaction::{apply_compute_budget, sign_transaction};
+use crate::venue::VenueKind;
+use crate::{
+    breaker, explain, fees, jupiter, kill_switch, nonce, preflight, risk, simulate, slippage,
+    source, Result,
+};
+
+// What a transaction is expected to do, used to check it before signing
+#[derive(Clone, Copy)]
+pub enum Trade {
+    Buy { mint: Pubkey, lamports: u64 },
+    Sell { mint: Pubkey, token_amount: u64 },
+}
+
+impl Trade {
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            Trade::Buy { mint, .. } | Trade::Sell { mint, .. } => *mint,
+        }
+    }
+}
+
+// Everything between receiving an unsigned transaction and sending it
+pub async fn prepare_transaction(
+    config: &Config,
+    client: &RpcClient,
+    tx: &mut VersionedTransaction,
+    keypair: &WalletSigner,
+    trade: &Trade,
+) -> Result<()> {
+    prepare(config, client, tx, keypair, trade, Purpose::Send).await
+}
+
+// The same for a buy signed ahead of a launch and held until T0, which can't
+// be simulated before the token exists
+pub async fn prepare_for_launch(
+    config: &Config,
+    client: &RpcClient,
+    tx: &mut VersionedTransaction,
+    keypair: &WalletSigner,
+    trade: &Trade,
+) -> Result<()> {
+    prepare(config, client, tx, keypair, trade, Purpose::Launch).await
+}
+
+// What a transaction is prepared for
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Purpose {
+    Send,
+    // Held until a launch, its curve may not exist yet
+    Launch,
+    // Replacing an expired transaction of the same trade, which already
+    // counts toward the limits through its reservation or journal row
+    Rebuild,
+}
+
+#[instrument(name = "sign", skip_all)]
+async fn prepare(
+    config: &Config,
+    client: &RpcClient,
+    tx: &mut VersionedTransaction,
+    keypair: &WalletSigner,
+    trade: &Trade,
+    purpose: Purpose,
+) -> Result<()> {
+    kill_switch::check(config, trade).await?;
+    breaker::check(trade)?;
+    // Before the transaction is touched, or it no longer matches the swap
+    let jupiter_sell = matches!(trade, Trade::Sell { .. }) && jupiter::take_swap(tx);
+    let program_id = config.program_id().ok();
+    Policy::from_config(config, jupiter_sell)?
+        .check(client, tx, program_id.as_ref())
+        .await?;
+    let budget = fees::compute_budget(config, client, tx).await?;
+    apply_compute_budget(client, tx, &budget).await?;
+    if let Some(account) = config.nonce_account()? {
+        nonce::apply(client, tx, &account, &keypair.pubkey()).await?;
+    }
+    // Transactions built for other venues already carry the slippage limit
+    if let (Some(slippage_bps), VenueKind::Degen) = (config.slippage_bps, config.venue) {
+        let program_id = config.program_id()?;
+        slippage::enforce(
+            client,
+            &program_id,
+            tx,
+            trade,
+            slippage_bps,
+            config.patch_slippage,
+            jupiter_sell,
+        )
+        .await?;
+    }
+    preflight::check_balance(config, client, tx, &keypair.pubkey(), trade).await?;
+    if config.show_tx {
+        explain::print(client, tx, &keypair.pubkey(), program_id.as_ref()).await?;
+    }
+    sign_transaction(tx, keypair)?;
+    // A dry run simulates anyway and sends nothing
+    if !config.dry_run {
+        let launch_curve = match purpose {
+            Purpose::Launch => Some(launch_curve(config, trade)?),
+            _ => None,
+        };
+        simulate::check_debit(
+            client,
+            tx,
+            &keypair.pubkey(),
+            trade,
+            config.debit_tolerance_lamports,
+            launch_curve.as_ref(),
+        )
+        .await?;
+    }
+    // Last, so only buys about to be sent count toward the limits
+    if purpose != Purpose::Rebuild {
+        risk::check(config, client, &keypair.pubkey(), trade).await?;
+    }
+    Ok(())
+}
+
+// The curve a buy held for a launch trades against, which may not exist yet
+pub fn launch_curve(config: &Config, trade: &Trade) -> Result<Pubkey> {
+    Ok(config.venue()?.curve_address(&trade.mint()))
+}
+
+// Fetch the transaction for a trade again from its source and prepare it,
+// replacing one whose blockhash expired. The limits and cooldowns are not
+// checked again, the trade was let through once already.
+pub async fn rebuild(
+    config: &Config,
+    client: &RpcClient,
+    keypair: &WalletSigner,
+    trade: &Trade,
+) -> Result<VersionedTransaction> {
+    let owner = keypair.pubkey();
+    let mut tx = match *trade {
+        Trade::Buy { mint, lamports } => {
+            source::buy_transaction(config, client, &owner, &mint, lamports_to_sol(lamports))
+                .await?
+        }
+        Trade::Sell { mint, token_amount } => {
+            source::sell_transaction(config, client, &owner, &mint, token_amount).await?
+        }
+    };
+    prepare(config, client, &mut tx, keypair, trade, Purpose::Rebuild).await?;
+    Ok(tx)
+}
@@ -0,0 +1,133 @@
+use clap::ValueEnum;
+use colored::*;
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+use std::collections::HashMap;
+
+use crate::jupiter::WSOL_MINT;
+use crate::Result;
+
+const COINGECKO_SOL_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=solana&vs_currencies=usd";
+const JUPITER_PRICE_URL: &str = "https://lite-api.jup.ag/price/v2";
+const PYTH_PRICE_URL: &str = "https://hermes.pyth.network/v2/updates/price/latest";
+// Pyth's SOL/USD price feed
+const PYTH_SOL_USD_FEED: &str =
+    "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
+
+// Pyth's sponsored SOL/USD price update account (shard 0)
+const PYTH_SOL_USD_ACCOUNT: Pubkey = pubkey!("7UVimffxr9ow1uXYxsr4LHAcV58mLzhmwaeKvJ1pjLiE");
+// Older on-chain prices are refused
+const PYTH_MAX_AGE_SECS: i64 = 60;
+
+// Where the SOL/USD price comes from
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PriceOracle {
+    /// CoinGecko's simple price API
+    #[default]
+    Coingecko,
+    /// Jupiter's price of wrapped SOL
+    Jupiter,
+    /// Pyth's SOL/USD feed through Hermes
+    Pyth,
+    /// Pyth's SOL/USD price account, read from the RPC
+    PythAccount,
+}
+
+// Current SOL price in USD
+pub async fn sol_usd(oracle: PriceOracle, client: &RpcClient) -> Result<f64> {
+    let price = match oracle {
+        PriceOracle::PythAccount => Some(pyth_account_price(client).await?),
+        PriceOracle::Coingecko => {
+            let response: Value = reqwest::get(COINGECKO_SOL_URL).await?.json().await?;
+            response["solana"]["usd"].as_f64()
+        }
+        PriceOracle::Jupiter => token_usd(&[WSOL_MINT]).await?.get(&WSOL_MINT).copied(),
+        PriceOracle::Pyth => {
+            let url = format!("{}?ids[]={}", PYTH_PRICE_URL, PYTH_SOL_USD_FEED);
+            let response: Value = reqwest::get(&url).await?.json().await?;
+            let price = &response["parsed"][0]["price"];
+            match (price["price"].as_str(), price["expo"].as_i64()) {
+                (Some(mantissa), Some(expo)) => mantissa
+                    .parse::<f64>()
+                    .ok()
+                    .map(|mantissa| mantissa * 10f64.powi(expo as i32)),
+                _ => None,
+            }
+        }
+    };
+    price
+        .filter(|price| *price > 0.0)
+        .ok_or_else(|| "Unexpected answer from the SOL price API".into())
+}
+
+// The price in a Pyth PriceUpdateV2 account: after the 8 byte discriminator
+// and the write authority, a Borsh verification level (one byte, two when
+// partial) and the price message starting with the feed id
+async fn pyth_account_price(client: &RpcClient) -> Result<f64> {
+    let data = client.get_account_data(&PYTH_SOL_USD_ACCOUNT).await?;
+    let level = *data.get(40).ok_or("Pyth price account is too short")?;
+    let message = if level == 0 { 42 } else { 41 };
+    let bytes = |at: usize, len: usize| {
+        data.get(message + at..message + at + len)
+            .ok_or("Pyth price account is too short")
+    };
+    let feed_id = hex_feed_id(bytes(0, 32)?);
+    if feed_id != PYTH_SOL_USD_FEED {
+        return Err(format!("Pyth account holds feed {}, not SOL/USD", feed_id).into());
+    }
+    let price = i64::from_le_bytes(bytes(32, 8)?.try_into()?);
+    let exponent = i32::from_le_bytes(bytes(48, 4)?.try_into()?);
+    let publish_time = i64::from_le_bytes(bytes(52, 8)?.try_into()?);
+    let age = chrono::Utc::now().timestamp() - publish_time;
+    if age > PYTH_MAX_AGE_SECS {
+        return Err(format!("The on-chain Pyth price is {}s old", age).into());
+    }
+    Ok(price as f64 * 10f64.powi(exponent))
+}
+
+fn hex_feed_id(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", hex)
+}
+
+// SOL worth `usd` at the current price
+pub async fn usd_to_sol(oracle: PriceOracle, client: &RpcClient, usd: f64) -> Result<f64> {
+    let sol_usd = sol_usd(oracle, client).await.map_err(|e| {
+        format!("Could not fetch the SOL price to size the buy: {}", e)
+            .red()
+            .to_string()
+    })?;
+    let sol = usd / sol_usd;
+    info!(
+        "${} is {} SOL at ${:.2}",
+        usd,
+        format!("{:.6}", sol).yellow(),
+        sol_usd
+    );
+    Ok(sol)
+}
+
+// USD prices of SPL tokens from Jupiter, mints without a price are left out
+pub async fn token_usd(mints: &[Pubkey]) -> Result<HashMap<Pubkey, f64>> {
+    let mut prices = HashMap::new();
+    // The price API takes at most 100 ids per request
+    for chunk in mints.chunks(100) {
+        let ids: Vec<String> = chunk.iter().map(|mint| mint.to_string()).collect();
+        let url = format!("{}?ids={}", JUPITER_PRICE_URL, ids.join(","));
+        let response: Value = reqwest::get(&url).await?.json().await?;
+        for mint in chunk {
+            let price = response["data"][mint.to_string()]["price"]
+                .as_str()
+                .and_then(|price| price.parse::<f64>().ok());
+            if let Some(price) = price {
+                prices.insert(*mint, price);
+            }
+        }
+    }
+    Ok(prices)
+}
@@ -0,0 +1,57 @@
+use colored::*;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::jupiter::WSOL_MINT;
+use crate::Result;
+
+const POOLS_BY_MINT_URL: &str = "https://api-v3.raydium.io/pools/info/mint";
+const POOLS_BY_ID_URL: &str = "https://api-v3.raydium.io/pools/info/ids";
+
+// The SOL pool a token migrated to
+pub struct Pool {
+    pub id: String,
+    mint: Pubkey,
+}
+
+impl Pool {
+    // The deepest SOL pool of `mint`
+    pub async fn find(mint: &Pubkey) -> Result<Pool> {
+        let url = format!(
+            "{}?mint1={}&mint2={}&poolType=all&poolSortField=liquidity&sortType=desc&pageSize=1&page=1",
+            POOLS_BY_MINT_URL, mint, WSOL_MINT
+        );
+        let response: Value = reqwest::get(&url).await?.json().await?;
+        let id = response["data"]["data"][0]["id"].as_str().ok_or_else(|| {
+            format!("No Raydium pool found for {}", mint)
+                .red()
+                .to_string()
+        })?;
+        Ok(Pool {
+            id: id.to_string(),
+            mint: *mint,
+        })
+    }
+
+    // A pool already looked up
+    pub fn with_id(id: String, mint: Pubkey) -> Pool {
+        Pool { id, mint }
+    }
+
+    // Current price in SOL per whole token
+    pub async fn price_sol(&self) -> Result<f64> {
+        let url = format!("{}?ids={}", POOLS_BY_ID_URL, self.id);
+        let response: Value = reqwest::get(&url).await?.json().await?;
+        let pool = &response["data"][0];
+        // Raydium quotes mint B per mint A
+        let price = pool["price"]
+            .as_f64()
+            .filter(|price| *price > 0.0)
+            .ok_or("Unexpected answer from the Raydium pool API")?;
+        if pool["mintA"]["address"].as_str() == Some(&self.mint.to_string()) {
+            Ok(price)
+        } else {
+            Ok(1.0 / price)
+        }
+    }
+}
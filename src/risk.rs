@@ -0,0 +1,357 @@
+use chrono::Utc;
+use log::warn;
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::native_token::{lamports_to_sol, sol_to_lamports};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::journal::Journal;
+use crate::notify::Event;
+use crate::prepare::Trade;
+use crate::{creator, wallet, BotError, Result};
+
+// How long a checked buy counts toward the limits before it is journaled.
+// Buys prepared but never sent drop out after it.
+const RESERVATION_TTL: Duration = Duration::from_secs(60);
+// A buy still landing this long after it was last sent is past its blockhash,
+// which lasts about a minute, so the bot stopped watching it
+const STALE_LANDING_SECS: i64 = 180;
+// Signatures getSignatureStatuses takes at once
+const MAX_STATUSES: usize = 256;
+
+// Limits every buy is held to, whichever mode sends it
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RiskLimits {
+    // SOL a single buy may spend
+    pub max_trade_sol: Option<f64>,
+    // SOL every buy together may spend per UTC day
+    pub max_daily_sol: Option<f64>,
+    // Tokens held at once, a buy of another one is refused
+    pub max_open_positions: Option<usize>,
+    // SOL committed to a single token across every wallet and strategy
+    pub max_position_sol: Option<f64>,
+    // Seconds after buying a token before another one may be bought
+    pub buy_cooldown_secs: Option<u64>,
+    // Seconds after buying a token before another one of its creator's may
+    // be bought
+    pub creator_cooldown_secs: Option<u64>,
+}
+
+impl RiskLimits {
+    pub fn is_empty(&self) -> bool {
+        self.max_trade_sol.is_none()
+            && self.max_daily_sol.is_none()
+            && self.max_open_positions.is_none()
+            && self.max_position_sol.is_none()
+            && self.buy_cooldown_secs.is_none()
+            && self.creator_cooldown_secs.is_none()
+    }
+
+    // Why a buy of `lamports` of `mint` breaks a limit, none when it doesn't
+    async fn violation(
+        &self,
+        config: &Config,
+        client: &RpcClient,
+        mint: &Pubkey,
+        lamports: u64,
+        reserved: &[Reservation],
+    ) -> Result<Option<String>> {
+        if let Some(max) = self.max_trade_sol {
+            if lamports > sol_to_lamports(max) {
+                return Ok(Some(format!(
+                    "{} SOL is over max_trade_sol of {} SOL",
+                    lamports_to_sol(lamports),
+                    max
+                )));
+            }
+        }
+        let journal = Journal::open(&config.journal_path)?;
+        if self.max_daily_sol.is_some() || self.max_position_sol.is_some() {
+            reconcile(client, &journal).await?;
+        }
+        if let Some(max) = self.max_daily_sol {
+            let today = Utc::now()
+                .date_naive()
+                .and_hms_opt(0, 0, 0)
+                .unwrap()
+                .and_utc();
+            let spent =
+                journal.spent_since(today)? + reserved.iter().map(|r| r.lamports).sum::<u64>();
+            if spent + lamports > sol_to_lamports(max) {
+                return Ok(Some(format!(
+                    "{:.4} SOL spent today (UTC), another {} SOL would go over max_daily_sol of {} SOL",
+                    lamports_to_sol(spent),
+                    lamports_to_sol(lamports),
+                    max
+                )));
+            }
+        }
+        if let Some(max) = self.max_open_positions {
+            let mut open = open_positions(client, &journal).await?;
+            open.extend(reserved.iter().map(|r| r.mint));
+            if !open.contains(mint) && open.len() >= max {
+                return Ok(Some(format!(
+                    "{} positions open, max_open_positions is {}",
+                    open.len(),
+                    max
+                )));
+            }
+        }
+        if let Some(max) = self.max_position_sol {
+            let committed = committed(client, &journal, mint).await?
+                + reserved
+                    .iter()
+                    .filter(|r| r.mint == *mint)
+                    .map(|r| r.lamports)
+                    .sum::<u64>();
+            if committed + lamports > sol_to_lamports(max) {
+                return Ok(Some(format!(
+                    "{:.4} SOL already in the position, another {} SOL would go over max_position_sol of {} SOL",
+                    lamports_to_sol(committed),
+                    lamports_to_sol(lamports),
+                    max
+                )));
+            }
+        }
+        if let Some(secs) = self.buy_cooldown_secs {
+            let recent = recent_buys(&journal, secs, reserved)?;
+            if let Some((other, ago)) = recent.iter().find(|(other, _)| other != mint) {
+                return Ok(Some(format!(
+                    "{} was bought {}s ago, buy_cooldown_secs is {}",
+                    other, ago, secs
+                )));
+            }
+        }
+        if let Some(secs) = self.creator_cooldown_secs {
+            let program_id = config.program_id()?;
+            let creator = creator_of(client, &program_id, mint).await?;
+            for (other, ago) in recent_buys(&journal, secs, reserved)? {
+                if other != *mint && creator_of(client, &program_id, &other).await? == creator {
+                    return Ok(Some(format!(
+                        "{} of the same creator {} was bought {}s ago, creator_cooldown_secs is {}",
+                        other, creator, ago, secs
+                    )));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+// Settle the buys journaled as still landing that are past their blockhash
+// from what the chain knows of them, so a send the bot crashed or gave up on
+// doesn't count as landing forever
+async fn reconcile(client: &RpcClient, journal: &Journal) -> Result<()> {
+    let stale =
+        journal.stale_landing(Utc::now() - chrono::Duration::seconds(STALE_LANDING_SECS))?;
+    for signatures in stale.chunks(MAX_STATUSES) {
+        let statuses = client
+            .get_signature_statuses_with_history(signatures)
+            .await?
+            .value;
+        for (signature, status) in signatures.iter().zip(statuses) {
+            match status {
+                Some(status) => match status.err {
+                    Some(error) => journal.record_reverted(signature, &error.to_string())?,
+                    None => journal.record_confirmed(signature, status.slot, None)?,
+                },
+                None => journal.record_expired(signature)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+// The tokens bought in the last `secs` seconds, with how many seconds ago
+fn recent_buys(
+    journal: &Journal,
+    secs: u64,
+    reserved: &[Reservation],
+) -> Result<Vec<(Pubkey, i64)>> {
+    let now = Utc::now();
+    let mut recent = Vec::new();
+    for (mint, at) in journal.buys_since(now - chrono::Duration::seconds(secs as i64))? {
+        recent.push((wallet::parse_pubkey(&mint)?, (now - at).num_seconds()));
+    }
+    recent.extend(
+        reserved
+            .iter()
+            .map(|r| (r.mint, r.at.elapsed().as_secs() as i64)),
+    );
+    Ok(recent)
+}
+
+// The creator of `mint`, looked up once per token
+async fn creator_of(client: &RpcClient, program_id: &Pubkey, mint: &Pubkey) -> Result<Pubkey> {
+    static CREATORS: OnceLock<Mutex<HashMap<Pubkey, Pubkey>>> = OnceLock::new();
+    let creators = CREATORS.get_or_init(Default::default);
+    if let Some(creator) = creators.lock().unwrap().get(mint) {
+        return Ok(*creator);
+    }
+    let creator = creator::find_launch(client, program_id, mint)
+        .await?
+        .creator;
+    creators.lock().unwrap().insert(*mint, creator);
+    Ok(creator)
+}
+
+// Lamports in the position of `mint`: the tokens the wallets that bought it
+// hold at the average entry price, plus the buys still landing
+async fn committed(client: &RpcClient, journal: &Journal, mint: &Pubkey) -> Result<u64> {
+    let address = mint.to_string();
+    let mut held = 0.0;
+    for (owner, _) in journal
+        .bought_positions()?
+        .iter()
+        .filter(|(_, bought)| *bought == address)
+    {
+        let (balance, decimals) =
+            wallet::token_balance(client, &wallet::parse_pubkey(owner)?, mint).await?;
+        held += balance as f64 / 10f64.powi(decimals as i32);
+    }
+    let basis = match journal.entry_price(mint)? {
+        Some(price) => sol_to_lamports(held * price),
+        // No fill could be read back, what the buys spent less the sells
+        None if held > 0.0 => journal.net_spent(mint)?,
+        None => 0,
+    };
+    Ok(basis + journal.landing_spend(mint)?)
+}
+
+// Tokens bought through the journal that a wallet which bought them still holds
+async fn open_positions(client: &RpcClient, journal: &Journal) -> Result<HashSet<Pubkey>> {
+    let bought = journal.bought_positions()?;
+    let wallets: HashSet<&str> = bought.iter().map(|(wallet, _)| wallet.as_str()).collect();
+    let mut open = HashSet::new();
+    for owner in wallets {
+        let held: HashSet<String> = wallet::holdings(client, &wallet::parse_pubkey(owner)?)
+            .await?
+            .iter()
+            .map(|holding| holding.mint.to_string())
+            .collect();
+        for (_, mint) in bought
+            .iter()
+            .filter(|(w, m)| w == owner && held.contains(m))
+        {
+            open.insert(wallet::parse_pubkey(mint)?);
+        }
+    }
+    Ok(open)
+}
+
+// A buy that passed the limits and isn't journaled yet, so concurrent buys
+// can't each pass the daily cap on their own
+#[derive(Clone, Copy)]
+struct Reservation {
+    wallet: Pubkey,
+    mint: Pubkey,
+    lamports: u64,
+    at: Instant,
+}
+
+fn reservations() -> &'static Mutex<Vec<Reservation>> {
+    static RESERVATIONS: OnceLock<Mutex<Vec<Reservation>>> = OnceLock::new();
+    RESERVATIONS.get_or_init(Default::default)
+}
+
+// Checks run one at a time so two buys can't pass on the same headroom
+fn checking() -> &'static tokio::sync::Mutex<()> {
+    static CHECKING: OnceLock<tokio::sync::Mutex<()>> = OnceLock::new();
+    CHECKING.get_or_init(Default::default)
+}
+
+fn reserved() -> Vec<Reservation> {
+    let mut reservations = reservations().lock().unwrap();
+    reservations.retain(|r| r.at.elapsed() < RESERVATION_TTL);
+    reservations.clone()
+}
+
+// Refuse a buy breaking the risk limits, logging and notifying why. Sells
+// are never limited.
+pub async fn check(
+    config: &Config,
+    client: &RpcClient,
+    wallet: &Pubkey,
+    trade: &Trade,
+) -> Result<()> {
+    let Trade::Buy { mint, lamports } = *trade else {
+        return Ok(());
+    };
+    if config.risk_limits.is_empty() {
+        return Ok(());
+    }
+    let _checking = checking().lock().await;
+    let reserved = reserved();
+    if let Some(reason) = config
+        .risk_limits
+        .violation(config, client, &mint, lamports, &reserved)
+        .await?
+    {
+        let reason = format!("Risk limit hit, refusing to buy {}: {}", mint, reason);
+        warn!("{}", reason);
+        config
+            .notifier()?
+            .notify(Event::Refused {
+                trade: *trade,
+                reason: reason.clone(),
+            })
+            .await;
+        return Err(BotError::policy(reason));
+    }
+    reservations().lock().unwrap().push(Reservation {
+        wallet: *wallet,
+        mint,
+        lamports,
+        at: Instant::now(),
+    });
+    Ok(())
+}
+
+// Keep the reservation of a buy held before sending from expiring, checking
+// the limits again if it already has
+pub async fn renew(
+    config: &Config,
+    client: &RpcClient,
+    wallet: &Pubkey,
+    trade: &Trade,
+) -> Result<()> {
+    let Trade::Buy { mint, lamports } = *trade else {
+        return Ok(());
+    };
+    if config.risk_limits.is_empty() {
+        return Ok(());
+    }
+    {
+        let _checking = checking().lock().await;
+        let mut reservations = reservations().lock().unwrap();
+        if let Some(reservation) = reservations
+            .iter_mut()
+            .find(|r| r.wallet == *wallet && r.mint == mint && r.lamports == lamports)
+        {
+            if reservation.at.elapsed() < RESERVATION_TTL {
+                reservation.at = Instant::now();
+                return Ok(());
+            }
+        }
+    }
+    check(config, client, wallet, trade).await
+}
+
+// The buy is journaled (or failed), the journal accounts for it from now on
+pub fn release(wallet: &Pubkey, trade: &Trade) {
+    let Trade::Buy { mint, lamports } = *trade else {
+        return;
+    };
+    let mut reservations = reservations().lock().unwrap();
+    if let Some(i) = reservations
+        .iter()
+        .position(|r| r.wallet == *wallet && r.mint == mint && r.lamports == lamports)
+    {
+        reservations.remove(i);
+    }
+}
@@ -0,0 +1,142 @@
+use colored::*;
+use futures_util::{future::BoxFuture, stream::FuturesUnordered, StreamExt};
+use log::warn;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcSendTransactionConfig,
+    rpc_request::RpcError,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Signature, transaction::VersionedTransaction,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::{metrics, BotError, Result};
+
+pub struct Endpoint {
+    pub url: String,
+    pub client: RpcClient,
+}
+
+// RPC endpoints in priority order, failing over to the next one when the
+// active endpoint errors or times out
+pub struct RpcPool {
+    endpoints: Vec<Endpoint>,
+    active: AtomicUsize,
+}
+
+impl RpcPool {
+    pub fn new(urls: &[String], timeout: Duration) -> Result<RpcPool> {
+        if urls.is_empty() {
+            return Err("An RPC endpoint must be given with --rpc-url, SOLANA_RPC_URL or rpc_url in the config file"
+                .red()
+                .to_string()
+                .into());
+        }
+        let endpoints = urls
+            .iter()
+            .map(|url| Endpoint {
+                url: url.clone(),
+                client: RpcClient::new_with_timeout_and_commitment(
+                    url.clone(),
+                    timeout,
+                    CommitmentConfig::confirmed(),
+                ),
+            })
+            .collect();
+        Ok(RpcPool {
+            endpoints,
+            active: AtomicUsize::new(0),
+        })
+    }
+
+    // The endpoint currently in use
+    pub fn client(&self) -> &RpcClient {
+        &self.endpoints[self.active.load(Ordering::Relaxed)].client
+    }
+
+    // URL of the endpoint currently in use, without query parameters (API keys)
+    pub fn endpoint(&self) -> String {
+        redact(&self.endpoints[self.active.load(Ordering::Relaxed)].url)
+    }
+
+    // Send to every endpoint at once, returning each endpoint's outcome and
+    // how long it took to answer
+    pub async fn broadcast(
+        &self,
+        tx: &VersionedTransaction,
+        config: RpcSendTransactionConfig,
+    ) -> Vec<(String, Duration, Result<Signature>)> {
+        let started = Instant::now();
+        let mut sends: FuturesUnordered<_> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| async move {
+                let result = endpoint
+                    .client
+                    .send_transaction_with_config(tx, config)
+                    .await;
+                if result.is_err() {
+                    metrics::rpc_error(&redact(&endpoint.url));
+                }
+                (
+                    redact(&endpoint.url),
+                    started.elapsed(),
+                    result.map_err(Into::into),
+                )
+            })
+            .collect();
+
+        let mut outcomes = Vec::new();
+        while let Some(outcome) = sends.next().await {
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+
+    // Run `call` against the active endpoint, trying the others in order when
+    // it fails for reasons that have nothing to do with the request itself
+    pub async fn call<'a, T>(
+        &'a self,
+        call: impl Fn(&'a RpcClient) -> BoxFuture<'a, Result<T>>,
+    ) -> Result<T> {
+        let start = self.active.load(Ordering::Relaxed);
+        let mut last_error = None;
+        for offset in 0..self.endpoints.len() {
+            let index = (start + offset) % self.endpoints.len();
+            let endpoint = &self.endpoints[index];
+            match call(&endpoint.client).await {
+                Ok(value) => {
+                    if offset > 0 {
+                        warn!("Failed over to RPC {}", redact(&endpoint.url).yellow());
+                        self.active.store(index, Ordering::Relaxed);
+                    }
+                    return Ok(value);
+                }
+                Err(e) if is_endpoint_failure(&e) => {
+                    warn!("RPC {} failed: {}", redact(&endpoint.url), e);
+                    metrics::rpc_error(&redact(&endpoint.url));
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| "No RPC endpoint available".into()))
+    }
+}
+
+// Transport problems and server errors are worth retrying elsewhere, an RPC
+// rejecting the request (e.g. a failed preflight) is not
+fn is_endpoint_failure(error: &BotError) -> bool {
+    !matches!(
+        error.downcast_ref::<ClientError>().map(|e| e.kind()),
+        Some(ClientErrorKind::RpcError(RpcError::RpcResponseError { .. }))
+            | Some(ClientErrorKind::TransactionError(_))
+    )
+}
+
+fn redact(url: &str) -> String {
+    url.split('?').next().unwrap_or(url).to_string()
+}
@@ -0,0 +1,178 @@
+use clap::ValueEnum;
+use colored::*;
+use log::{info, warn};
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::metadata::TokenMetadata;
+use crate::wallet::{TOKEN_2022_PROGRAM_ID, TOKEN_PROGRAM_ID};
+use crate::{BotError, Result};
+
+// Launchpad tokens are minted with a fixed one billion supply and 6 decimals
+const STANDARD_DECIMALS: u8 = 6;
+const STANDARD_SUPPLY: u64 = 1_000_000_000 * 10u64.pow(STANDARD_DECIMALS as u32);
+
+// How the pre-buy safety checks gate a buy
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RugCheckMode {
+    /// Don't check
+    Off,
+    /// Log the risks and buy anyway
+    Warn,
+    /// Refuse to buy a token with any risk
+    Block,
+}
+
+// The SPL mint fields the checks look at
+struct Mint {
+    mint_authority: Option<Pubkey>,
+    supply: u64,
+    decimals: u8,
+    freeze_authority: Option<Pubkey>,
+}
+
+impl Mint {
+    // COption authorities are a 4 byte tag followed by the key
+    fn from_account_data(data: &[u8]) -> Result<Mint> {
+        if data.len() < 82 {
+            return Err("Mint account is too short".red().to_string().into());
+        }
+        let authority = |offset: usize| -> Result<Option<Pubkey>> {
+            Ok(match data[offset] {
+                0 => None,
+                _ => Some(Pubkey::try_from(&data[offset + 4..offset + 36])?),
+            })
+        };
+        Ok(Mint {
+            mint_authority: authority(0)?,
+            supply: u64::from_le_bytes(data[36..44].try_into()?),
+            decimals: data[44],
+            freeze_authority: authority(46)?,
+        })
+    }
+}
+
+// One safety check and its outcome
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+pub struct Report {
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn risks(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|check| !check.passed)
+    }
+
+    pub fn print(&self) {
+        for check in &self.checks {
+            let mark = if check.passed {
+                "✔".green()
+            } else {
+                "✘".red()
+            };
+            info!("{} {}: {}", mark, check.name.bold(), check.detail);
+        }
+    }
+
+    fn push(&mut self, name: &'static str, passed: bool, detail: String) {
+        self.checks.push(Check {
+            name,
+            passed,
+            detail,
+        });
+    }
+}
+
+// Authorities, metadata mutability and supply of `mint`
+pub async fn check(client: &RpcClient, mint: &Pubkey) -> Result<Report> {
+    let account = client.get_account(mint).await.map_err(|e| {
+        format!("Could not fetch mint {}: {}", mint, e)
+            .red()
+            .to_string()
+    })?;
+    if account.owner != TOKEN_PROGRAM_ID && account.owner != TOKEN_2022_PROGRAM_ID {
+        return Err(format!("{} is not a token mint", mint)
+            .red()
+            .to_string()
+            .into());
+    }
+    let state = Mint::from_account_data(&account.data)?;
+    let mut report = Report { checks: Vec::new() };
+
+    report.push(
+        "Mint authority",
+        state.mint_authority.is_none(),
+        match state.mint_authority {
+            Some(authority) => format!("{} can mint more tokens", authority),
+            None => "revoked".to_string(),
+        },
+    );
+    report.push(
+        "Freeze authority",
+        state.freeze_authority.is_none(),
+        match state.freeze_authority {
+            Some(authority) => format!("{} can freeze holders' accounts", authority),
+            None => "revoked".to_string(),
+        },
+    );
+
+    let metadata = TokenMetadata::fetch_many(client, &[*mint]).await?.remove(0);
+    match metadata {
+        Some(metadata) => report.push(
+            "Metadata",
+            !metadata.is_mutable,
+            if metadata.is_mutable {
+                "mutable, name and image can still be changed".to_string()
+            } else {
+                "immutable".to_string()
+            },
+        ),
+        None => report.push("Metadata", false, "no metadata account".to_string()),
+    }
+
+    let standard = state.decimals == STANDARD_DECIMALS && state.supply == STANDARD_SUPPLY;
+    report.push(
+        "Supply",
+        standard,
+        format!(
+            "{} tokens with {} decimals{}",
+            state.supply as f64 / 10f64.powi(state.decimals as i32),
+            state.decimals,
+            if standard {
+                ""
+            } else {
+                ", not the launchpad standard"
+            }
+        ),
+    );
+    Ok(report)
+}
+
+// Run the checks before a buy according to `mode`
+pub async fn gate(client: &RpcClient, mint: &Pubkey, mode: RugCheckMode) -> Result<()> {
+    if mode == RugCheckMode::Off {
+        return Ok(());
+    }
+    let report = check(client, mint).await?;
+    let risks: Vec<&Check> = report.risks().collect();
+    if risks.is_empty() {
+        return Ok(());
+    }
+    for risk in &risks {
+        warn!("{}: {}", risk.name, risk.detail);
+    }
+    if mode == RugCheckMode::Block {
+        return Err(BotError::policy(format!(
+            "Refusing to buy, {} safety check(s) failed",
+            risks.len()
+        )));
+    }
+    Ok(())
+}
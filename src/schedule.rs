@@ -0,0 +1,338 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use colored::*;
+use log::{info, warn};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Signature, Signer},
+    transaction::VersionedTransaction,
+};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+use crate::config::Config;
+use crate::prepare::Trade;
+use crate::signer::WalletSigner;
+use crate::transaction::{new_spinner, sign_transaction, Sender};
+use crate::{breaker, kill_switch, nonce, prepare, risk, shutdown, simulate, Result};
+
+pub const DEFAULT_NTP_SERVER: &str = "pool.ntp.org:123";
+
+const NTP_TIMEOUT: Duration = Duration::from_secs(3);
+// Seconds between the NTP epoch (1900) and the Unix epoch
+const NTP_UNIX_OFFSET: i64 = 2_208_988_800;
+const BLOCKHASH_REFRESH_INTERVAL: Duration = Duration::from_secs(20);
+// Roughly how long a blockhash stays valid (150 slots)
+const BLOCKHASH_LIFETIME: TimeDelta = TimeDelta::seconds(60);
+const COUNTDOWN_TICK: Duration = Duration::from_millis(100);
+// Sleep until this close to T0, then spin for the last stretch
+const SPIN_WINDOW: TimeDelta = TimeDelta::milliseconds(20);
+// Longest wait for the first slot after T0 before firing anyway
+const FIRST_SLOT_TIMEOUT: Duration = Duration::from_millis(800);
+// Connect to the leaders this long before T0, they rotate every 4 slots
+const WARM_UP_WINDOW: TimeDelta = TimeDelta::seconds(2);
+// How often a held buy looks for its curve to check its debit against
+const CURVE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// A send scheduled for an exact UTC time, measured against an NTP corrected clock
+pub struct Launch {
+    at: DateTime<Utc>,
+    clock_offset: TimeDelta,
+}
+
+impl Launch {
+    pub async fn new(at: DateTime<Utc>, ntp_server: &str) -> Result<Launch> {
+        let clock_offset = match ntp_offset(ntp_server).await {
+            Ok(offset) => {
+                info!(
+                    "Local clock is off by {}ms according to {}",
+                    offset.num_milliseconds(),
+                    ntp_server
+                );
+                offset
+            }
+            Err(e) => {
+                warn!(
+                    "NTP sync with {} failed, using the local clock: {}",
+                    ntp_server, e
+                );
+                TimeDelta::zero()
+            }
+        };
+        let launch = Launch { at, clock_offset };
+        if launch.remaining() <= TimeDelta::zero() {
+            return Err(format!("Launch time {} is in the past", at)
+                .red()
+                .to_string()
+                .into());
+        }
+        Ok(launch)
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        Utc::now() + self.clock_offset
+    }
+
+    fn remaining(&self) -> TimeDelta {
+        self.at - self.now()
+    }
+
+    // Wait until `lead` before T0, for a buy built and signed only then. A
+    // shutdown cancels the wait.
+    pub async fn approach(&self, lead: TimeDelta) -> Result<()> {
+        let wait = self.remaining() - lead;
+        if wait <= TimeDelta::zero() {
+            return Ok(());
+        }
+        info!(
+            "Waiting until {}s before launch to size the buy",
+            lead.num_seconds()
+        );
+        tokio::select! {
+            _ = tokio::time::sleep(wait.to_std()?) => Ok(()),
+            _ = shutdown::reached(shutdown::State::Draining) => {
+                Err("Launch cancelled, shutting down".red().to_string().into())
+            }
+        }
+    }
+
+    // Hold a signed transaction until T0, re-signing it with a fresh blockhash
+    // while waiting so it is still valid when it is fired. With a slot clock
+    // it is held on until the first slot that starts after T0. The kill switch
+    // and circuit breaker are checked all along and once more before firing,
+    // they may have engaged since the buy was signed. The SOL debit, which
+    // can't be simulated before the curve exists, is checked once it does, or
+    // again before firing. A failed check refuses to fire.
+    pub async fn hold(
+        &self,
+        config: &Config,
+        sender: &Sender,
+        tx: &mut VersionedTransaction,
+        keypair: &WalletSigner,
+        trade: &Trade,
+        countdown: bool,
+    ) -> Result<()> {
+        // Transactions co-signed by the API can't be re-signed by us, and a
+        // Ledger would ask for a confirmation on every refresh. Signed against
+        // a durable nonce nothing expires.
+        let durable = nonce::durable_nonce(&tx.message).is_some();
+        let refreshable = tx.signatures.len() == 1 && !keypair.is_interactive() && !durable;
+        if !refreshable && !durable && self.remaining() > BLOCKHASH_LIFETIME {
+            if keypair.is_interactive() {
+                warn!("Transaction is signed on a Ledger, its blockhash may expire before launch");
+            } else {
+                warn!(
+                    "Transaction is co-signed by the API, its blockhash may expire before launch"
+                );
+            }
+        }
+
+        // Subscribed up front so the stream is live by T0
+        let mut ticker = match sender.slot_clock() {
+            Some(clock) => Some(clock.ticker().await),
+            None => None,
+        };
+        let spinner = countdown.then(|| new_spinner("Waiting for launch..."));
+        // A dry run simulates anyway and sends nothing
+        let curve = match config.dry_run {
+            true => None,
+            false => Some(prepare::launch_curve(config, trade)?),
+        };
+        let mut verified = curve.is_none();
+        let mut last_curve_poll = tokio::time::Instant::now();
+        let mut last_refresh = tokio::time::Instant::now();
+        let mut warmed = false;
+        loop {
+            let remaining = self.remaining();
+            if remaining <= SPIN_WINDOW {
+                break;
+            }
+            if shutdown::requested() {
+                if let Some(spinner) = &spinner {
+                    spinner.finish_and_clear();
+                }
+                return Err("Launch cancelled, shutting down".red().to_string().into());
+            }
+            if let Err(e) = still_allowed(config, sender, keypair, trade).await {
+                if let Some(spinner) = &spinner {
+                    spinner.finish_and_clear();
+                }
+                return Err(e);
+            }
+            if !verified && last_curve_poll.elapsed() >= CURVE_POLL_INTERVAL {
+                last_curve_poll = tokio::time::Instant::now();
+                match verify_debit(config, sender, tx, keypair, trade, curve.as_ref()).await {
+                    Ok(done) => verified = done,
+                    Err(e) => {
+                        if let Some(spinner) = &spinner {
+                            spinner.finish_and_clear();
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+            if !warmed && remaining <= WARM_UP_WINDOW {
+                sender.warm_up().await;
+                warmed = true;
+                continue;
+            }
+            if let Some(spinner) = &spinner {
+                spinner.set_message(format!(
+                    "Launch in {} at {}",
+                    format_remaining(remaining).yellow(),
+                    self.at.format("%H:%M:%S%.3f UTC")
+                ));
+            }
+            if refreshable && last_refresh.elapsed() >= BLOCKHASH_REFRESH_INTERVAL {
+                refresh_blockhash(sender, tx, keypair).await?;
+                last_refresh = tokio::time::Instant::now();
+            }
+            let sleep = (remaining - SPIN_WINDOW).to_std()?.min(COUNTDOWN_TICK);
+            tokio::time::sleep(sleep).await;
+        }
+        // Yield rather than spin, the other wallets' launches share the workers
+        while self.remaining() > TimeDelta::zero() {
+            tokio::task::yield_now().await;
+        }
+        let slot = match &mut ticker {
+            Some(ticker) => match tokio::time::timeout(FIRST_SLOT_TIMEOUT, ticker.tick()).await {
+                Ok(Ok(slot)) => Some(slot),
+                _ => {
+                    warn!("No slot arrived after launch time, firing anyway");
+                    None
+                }
+            },
+            None => None,
+        };
+        let allowed = match still_allowed(config, sender, keypair, trade).await {
+            // Only a curve that still doesn't exist is let through unverified
+            Ok(()) if !verified => {
+                simulate::check_debit(
+                    sender.client(),
+                    tx,
+                    &keypair.pubkey(),
+                    trade,
+                    config.debit_tolerance_lamports,
+                    curve.as_ref(),
+                )
+                .await
+            }
+            allowed => allowed,
+        };
+        if let Err(e) = allowed {
+            if let Some(spinner) = &spinner {
+                spinner.finish_and_clear();
+            }
+            return Err(e);
+        }
+
+        if let Some(spinner) = spinner {
+            let at_slot = slot.map_or_else(String::new, |slot| format!(" in slot {}", slot));
+            spinner.finish_with_message(
+                format!(
+                    "Launch time reached, firing at {}{}",
+                    self.now().format("%H:%M:%S%.3f"),
+                    at_slot
+                )
+                .green()
+                .to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+// Keeps the buy's risk reservation alive too, it would expire during a
+// long hold and let other commands spend the same headroom
+async fn still_allowed(
+    config: &Config,
+    sender: &Sender,
+    keypair: &WalletSigner,
+    trade: &Trade,
+) -> Result<()> {
+    kill_switch::check(config, trade).await?;
+    breaker::check(trade)?;
+    risk::renew(config, sender.client(), &keypair.pubkey(), trade).await
+}
+
+// Check the debit of the held buy once its curve exists, true once checked
+async fn verify_debit(
+    config: &Config,
+    sender: &Sender,
+    tx: &VersionedTransaction,
+    keypair: &WalletSigner,
+    trade: &Trade,
+    curve: Option<&Pubkey>,
+) -> Result<bool> {
+    if let Some(curve) = curve {
+        if !simulate::account_exists(sender.client(), curve).await? {
+            return Ok(false);
+        }
+    }
+    simulate::check_debit(
+        sender.client(),
+        tx,
+        &keypair.pubkey(),
+        trade,
+        config.debit_tolerance_lamports,
+        None,
+    )
+    .await?;
+    Ok(true)
+}
+
+async fn refresh_blockhash(
+    sender: &Sender,
+    tx: &mut VersionedTransaction,
+    keypair: &WalletSigner,
+) -> Result<()> {
+    let blockhash = sender
+        .rpc()
+        .call(|client| Box::pin(async move { Ok(client.get_latest_blockhash().await?) }))
+        .await?;
+    tx.message.set_recent_blockhash(blockhash);
+    tx.signatures.fill(Signature::default());
+    sign_transaction(tx, keypair)
+}
+
+fn format_remaining(remaining: TimeDelta) -> String {
+    let millis = remaining.num_milliseconds();
+    format!(
+        "{:02}:{:02}:{:02}.{}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1_000 % 60,
+        millis / 100 % 10
+    )
+}
+
+// SNTP query returning how far the local clock is behind the server's
+async fn ntp_offset(server: &str) -> Result<TimeDelta> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    // LI = 0, version 3, mode 3 (client)
+    let mut request = [0u8; 48];
+    request[0] = 0x1b;
+    let sent_at = Utc::now();
+    socket.send(&request).await?;
+
+    let mut response = [0u8; 48];
+    let len = tokio::time::timeout(NTP_TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| "NTP server did not answer")??;
+    let received_at = Utc::now();
+    if len < response.len() {
+        return Err("Short NTP response".into());
+    }
+
+    let server_received = ntp_timestamp(&response[32..40]).ok_or("Invalid NTP timestamp")?;
+    let server_sent = ntp_timestamp(&response[40..48]).ok_or("Invalid NTP timestamp")?;
+    Ok(((server_received - sent_at) + (server_sent - received_at)) / 2)
+}
+
+fn ntp_timestamp(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let seconds = u32::from_be_bytes(bytes[..4].try_into().ok()?) as i64;
+    let fraction = u32::from_be_bytes(bytes[4..].try_into().ok()?) as i64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+    DateTime::from_timestamp(seconds - NTP_UNIX_OFFSET, nanos as u32)
+}
@@ -0,0 +1,97 @@
+use colored::*;
+use log::{error, warn};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::watch;
+
+// How long in-flight trades get to land after Ctrl-C before they are given up
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(90);
+// Time left to journal abandoned trades before the process exits regardless
+const ABANDON_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum State {
+    Running,
+    // No new work is taken, sent trades are still confirmed
+    Draining,
+    // Trades still landing are journaled as pending and dropped
+    Abandoning,
+}
+
+fn state() -> &'static watch::Sender<State> {
+    static STATE: OnceLock<watch::Sender<State>> = OnceLock::new();
+    STATE.get_or_init(|| watch::channel(State::Running).0)
+}
+
+// Turn Ctrl-C and SIGTERM into a graceful shutdown: the first signal drains,
+// a second one or the end of `grace` abandons what is still in flight
+pub fn listen(grace: Duration) {
+    tokio::spawn(async move {
+        signal().await;
+        warn!(
+            "{}",
+            "Shutting down, finishing in-flight trades (press Ctrl-C again to stop now)".yellow()
+        );
+        state().send_replace(State::Draining);
+
+        tokio::select! {
+            _ = signal() => {}
+            _ = tokio::time::sleep(grace) => {}
+        }
+        warn!("Giving up on trades still landing, they are journaled as pending");
+        state().send_replace(State::Abandoning);
+
+        tokio::time::sleep(ABANDON_TIMEOUT).await;
+        error!("Shutdown timed out, exiting");
+        std::process::exit(130);
+    });
+}
+
+// Start draining as a first Ctrl-C would, for when it arrives as a key press
+pub fn request() {
+    state().send_if_modified(|state| {
+        let running = *state == State::Running;
+        if running {
+            *state = State::Draining;
+        }
+        running
+    });
+}
+
+pub fn current() -> State {
+    *state().borrow()
+}
+
+// Whether new work should no longer be started
+pub fn requested() -> bool {
+    current() >= State::Draining
+}
+
+// Resolves once the shutdown reaches `at`
+pub async fn reached(at: State) {
+    let mut receiver = state().subscribe();
+    // The sender lives in a static, so the channel never closes
+    let _ = receiver.wait_for(|state| *state >= at).await;
+}
+
+async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
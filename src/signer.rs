@@ -0,0 +1,127 @@
+use colored::*;
+use log::info;
+use solana_remote_wallet::{
+    locator::Locator,
+    remote_keypair::{generate_remote_keypair, RemoteKeypair},
+    remote_wallet::initialize_wallet_manager,
+};
+use solana_sdk::{
+    derivation_path::DerivationPath,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer, SignerError},
+};
+
+use crate::Result;
+
+// A trading wallet, either a key held in memory or a Ledger that signs on the
+// device after the user confirms
+pub enum WalletSigner {
+    Keypair(Keypair),
+    Ledger(Ledger),
+}
+
+impl From<Keypair> for WalletSigner {
+    fn from(keypair: Keypair) -> Self {
+        WalletSigner::Keypair(keypair)
+    }
+}
+
+impl Signer for WalletSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        match self {
+            WalletSigner::Keypair(keypair) => keypair.try_pubkey(),
+            WalletSigner::Ledger(ledger) => Ok(ledger.pubkey),
+        }
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        match self {
+            WalletSigner::Keypair(keypair) => keypair.try_sign_message(message),
+            WalletSigner::Ledger(ledger) => ledger.sign_message(message),
+        }
+    }
+
+    fn is_interactive(&self) -> bool {
+        matches!(self, WalletSigner::Ledger(_))
+    }
+}
+
+// A Ledger account addressed like the Solana CLI does, e.g. usb://ledger?key=0.
+// The device connection isn't Send, so it is reopened for every signature.
+pub struct Ledger {
+    locator: Locator,
+    derivation_path: DerivationPath,
+    pubkey: Pubkey,
+}
+
+impl Ledger {
+    pub fn connect(url: &str) -> Result<Ledger> {
+        let (path, key) = match url.split_once('?') {
+            Some((path, query)) => (path, query.strip_prefix("key=")),
+            None => (url, None),
+        };
+        let locator = Locator::new_from_path(path).map_err(|e| {
+            format!("Invalid Ledger url {}: {}", url, e)
+                .red()
+                .to_string()
+        })?;
+        let derivation_path = match key {
+            Some(key) => DerivationPath::from_key_str(key).map_err(|e| {
+                format!("Invalid Ledger key {}: {}", key, e)
+                    .red()
+                    .to_string()
+            })?,
+            None => DerivationPath::default(),
+        };
+        let device = open(&locator, &derivation_path)?;
+        info!(
+            "Using Ledger {} account {}",
+            device.path.bright_blue(),
+            device.pubkey.to_string().bright_green()
+        );
+        Ok(Ledger {
+            pubkey: device.pubkey,
+            locator,
+            derivation_path,
+        })
+    }
+
+    fn sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        let device = open(&self.locator, &self.derivation_path)
+            .map_err(|e| SignerError::Connection(e.to_string()))?;
+        if device.pubkey != self.pubkey {
+            return Err(SignerError::Connection(
+                "A different Ledger account is connected".to_string(),
+            ));
+        }
+        info!("{}", "Confirm the transaction on your Ledger...".yellow());
+        device.try_sign_message(message)
+    }
+}
+
+fn open(locator: &Locator, derivation_path: &DerivationPath) -> Result<RemoteKeypair> {
+    let manager = initialize_wallet_manager().map_err(|e| {
+        let hint = if cfg!(feature = "ledger") {
+            ""
+        } else {
+            ", build with --features ledger for device support"
+        };
+        format!("Could not open the Ledger: {}{}", e, hint)
+            .red()
+            .to_string()
+    })?;
+    manager.update_devices()?;
+    generate_remote_keypair(
+        locator.clone(),
+        derivation_path.clone(),
+        &manager,
+        false,
+        "ledger",
+    )
+    .map_err(|e| {
+        format!("Could not open the Ledger: {}", e)
+            .red()
+            .to_string()
+            .into()
+    })
+}
@@ -0,0 +1,188 @@
+use colored::*;
+use log::{debug, error, info, warn};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+};
+use solana_sdk::{
+    account::Account, commitment_config::CommitmentConfig, native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey, transaction::VersionedTransaction,
+};
+
+use crate::prepare::Trade;
+use crate::wallet::parse_token_account;
+use crate::{BotError, Result};
+
+// SOL a trade may take beyond its amount, for network and priority fees and
+// new token account rent
+pub const DEFAULT_DEBIT_TOLERANCE_LAMPORTS: u64 = 10_000_000;
+
+// Balance changes of `owner` in a simulated transaction
+pub struct Simulation {
+    pub units_consumed: Option<u64>,
+    pub lamports_delta: i128,
+    /// (mint, raw amount delta) for every token account of `owner` the tx writes
+    pub token_deltas: Vec<(Pubkey, i128)>,
+    pub logs: Vec<String>,
+}
+
+// Simulate a signed transaction and diff the writable accounts owned by `owner`
+pub async fn simulate(
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+    owner: &Pubkey,
+) -> Result<Simulation> {
+    let message = &tx.message;
+    let keys = message.static_account_keys();
+    let writable: Vec<Pubkey> = (0..keys.len())
+        .filter(|&i| message.is_maybe_writable(i, None))
+        .map(|i| keys[i])
+        .collect();
+
+    let pre_accounts = client.get_multiple_accounts(&writable).await?;
+    let result = client
+        .simulate_transaction_with_config(
+            tx,
+            RpcSimulateTransactionConfig {
+                sig_verify: false,
+                commitment: Some(CommitmentConfig::confirmed()),
+                accounts: Some(RpcSimulateTransactionAccountsConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    addresses: writable.iter().map(|k| k.to_string()).collect(),
+                }),
+                ..RpcSimulateTransactionConfig::default()
+            },
+        )
+        .await?
+        .value;
+
+    let logs = result.logs.unwrap_or_default();
+    if let Some(err) = result.err {
+        for log in &logs {
+            error!("  {}", log);
+        }
+        return Err(format!("Simulation failed: {}", err)
+            .red()
+            .to_string()
+            .into());
+    }
+
+    let post_accounts: Vec<Option<Account>> = result
+        .accounts
+        .unwrap_or_default()
+        .into_iter()
+        .map(|account| account.and_then(|account| account.decode()))
+        .collect();
+
+    let mut simulation = Simulation {
+        units_consumed: result.units_consumed,
+        lamports_delta: 0,
+        token_deltas: Vec::new(),
+        logs,
+    };
+    for (i, address) in writable.iter().enumerate() {
+        let pre = pre_accounts.get(i).cloned().flatten();
+        let post = post_accounts.get(i).cloned().flatten();
+        if address == owner {
+            let lamports = |account: &Option<Account>| account.as_ref().map_or(0, |a| a.lamports);
+            simulation.lamports_delta = lamports(&post) as i128 - lamports(&pre) as i128;
+            continue;
+        }
+
+        let pre_token = pre.as_ref().and_then(parse_token_account);
+        let post_token = post.as_ref().and_then(parse_token_account);
+        let Some((mint, token_owner)) = post_token.or(pre_token).map(|t| (t.mint, t.owner)) else {
+            continue;
+        };
+        if token_owner != *owner {
+            continue;
+        }
+        let amount = |token: Option<crate::wallet::TokenAccount>| token.map_or(0, |t| t.amount);
+        simulation
+            .token_deltas
+            .push((mint, amount(post_token) as i128 - amount(pre_token) as i128));
+    }
+    Ok(simulation)
+}
+
+// Refuse to send when simulating the signed transaction takes more SOL from
+// `payer` than the trade spends plus `tolerance`, or when it can't be
+// simulated at all. A buy signed ahead of a launch passes its `launch_curve`,
+// and is let through unverified only while that account doesn't exist yet.
+pub async fn check_debit(
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+    payer: &Pubkey,
+    trade: &Trade,
+    tolerance: u64,
+    launch_curve: Option<&Pubkey>,
+) -> Result<()> {
+    let simulation = match simulate(client, tx, payer).await {
+        Ok(simulation) => simulation,
+        Err(e) => match launch_curve {
+            Some(curve) if !account_exists(client, curve).await? => {
+                warn!(
+                    "Could not verify the SOL debit of the transaction before its curve exists: {}",
+                    e
+                );
+                return Ok(());
+            }
+            _ => return Err(e),
+        },
+    };
+    let spend = match trade {
+        Trade::Buy { lamports, .. } => *lamports,
+        Trade::Sell { .. } => 0,
+    };
+    let allowed = spend as i128 + tolerance as i128;
+    let debit = -simulation.lamports_delta;
+    debug!("Simulated debit {} of at most {} lamports", debit, allowed);
+    if debit > allowed {
+        return Err(BotError::policy(format!(
+            "Refusing to send, the transaction takes {:.9} SOL from the wallet but at most \
+             {:.9} SOL was expected. Raise debit_tolerance_lamports if fees explain it",
+            debit as f64 / LAMPORTS_PER_SOL as f64,
+            allowed as f64 / LAMPORTS_PER_SOL as f64
+        )));
+    }
+    Ok(())
+}
+
+pub async fn account_exists(client: &RpcClient, address: &Pubkey) -> Result<bool> {
+    Ok(client
+        .get_account_with_commitment(address, CommitmentConfig::processed())
+        .await?
+        .value
+        .is_some())
+}
+
+impl Simulation {
+    pub fn print(&self) {
+        info!(
+            "{}",
+            "Simulation succeeded, nothing was broadcast".green().bold()
+        );
+        if let Some(units) = self.units_consumed {
+            info!("Compute units consumed: {}", units.to_string().yellow());
+        }
+        info!(
+            "SOL balance change: {}",
+            format!(
+                "{:+.9}",
+                self.lamports_delta as f64 / LAMPORTS_PER_SOL as f64
+            )
+            .yellow()
+        );
+        for log in &self.logs {
+            debug!("  {}", log);
+        }
+        for (mint, delta) in &self.token_deltas {
+            info!(
+                "Token {} change: {} raw units",
+                mint.to_string().bright_cyan(),
+                format!("{:+}", delta).yellow()
+            );
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use colored::*;
+use log::warn;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+
+use crate::curve::{BondingCurve, Global, BUY_DISCRIMINATOR, SELL_DISCRIMINATOR};
+use crate::jupiter::JUPITER_PROGRAM_ID;
+use crate::prepare::Trade;
+use crate::transaction::unsigned;
+use crate::{message, BotError, Result};
+
+// Check the limit argument of the curve instruction against a fresh quote.
+// A limit looser than `slippage_bps` is tightened when `patch` is set and the
+// transaction can still be rebuilt, otherwise the transaction is refused.
+// `jupiter_sell` is set for a sell the bot built through Jupiter itself.
+pub async fn enforce(
+    client: &RpcClient,
+    program_id: &Pubkey,
+    tx: &mut VersionedTransaction,
+    trade: &Trade,
+    slippage_bps: u64,
+    patch: bool,
+    jupiter_sell: bool,
+) -> Result<()> {
+    let mut decompiled = message::decompile(client, &tx.message).await?;
+    if decompiled
+        .instructions
+        .iter()
+        .any(|ix| ix.program_id == JUPITER_PROGRAM_ID)
+    {
+        // Our own Jupiter sells carry the slippage limit of their quote,
+        // anything else routing through Jupiter can't be checked
+        if jupiter_sell && matches!(trade, Trade::Sell { .. }) {
+            return Ok(());
+        }
+        return Err(BotError::policy(
+            "Transaction routes through Jupiter but is not a sell built by the bot, refusing to sign",
+        ));
+    }
+    let discriminator = match trade {
+        Trade::Buy { .. } => BUY_DISCRIMINATOR,
+        Trade::Sell { .. } => SELL_DISCRIMINATOR,
+    };
+    let Some(ix) = decompiled
+        .instructions
+        .iter_mut()
+        .find(|ix| ix.program_id == *program_id && ix.data.starts_with(&discriminator))
+    else {
+        return Err("Could not find the curve instruction to check slippage"
+            .red()
+            .to_string()
+            .into());
+    };
+    if ix.data.len() < 24 {
+        return Err("Curve instruction data is too short"
+            .red()
+            .to_string()
+            .into());
+    }
+    let token_amount = u64::from_le_bytes(ix.data[8..16].try_into()?);
+    let limit = u64::from_le_bytes(ix.data[16..24].try_into()?);
+
+    let global = Global::fetch(client, program_id).await?;
+    let curve = BondingCurve::fetch(client, program_id, &trade.mint()).await?;
+    let allowed = allowed_limit(
+        &curve,
+        global.fee_basis_points,
+        trade,
+        token_amount,
+        slippage_bps,
+    )?;
+    let acceptable = match trade {
+        Trade::Buy { .. } => limit <= allowed,
+        Trade::Sell { .. } => limit >= allowed,
+    };
+    if acceptable {
+        return Ok(());
+    }
+
+    let partially_signed = tx.signatures.iter().any(|s| *s != Signature::default());
+    if !patch || partially_signed {
+        return Err(BotError::policy(format!(
+            "Transaction limit of {} lamports allows worse execution than {} bps slippage ({} lamports), refusing to sign",
+            limit, slippage_bps, allowed
+        )));
+    }
+
+    warn!(
+        "Tightening the transaction limit from {} to {} lamports",
+        limit, allowed
+    );
+    ix.data[16..24].copy_from_slice(&allowed.to_le_bytes());
+    *tx = unsigned(decompiled.compile()?);
+    Ok(())
+}
+
+// The loosest limit `slippage_bps` allows on a curve instruction trading
+// `token_amount`
+fn allowed_limit(
+    curve: &BondingCurve,
+    fee_basis_points: u64,
+    trade: &Trade,
+    token_amount: u64,
+    slippage_bps: u64,
+) -> Result<u64> {
+    match trade {
+        // Buy limit is the most SOL we may pay for `token_amount`, and never
+        // much more than the amount we asked to spend
+        Trade::Buy { lamports, .. } => {
+            let cost = curve
+                .buy_cost(token_amount, fee_basis_points)
+                .min(*lamports);
+            Ok(cost.saturating_add(cost.saturating_mul(slippage_bps) / 10_000))
+        }
+        // Sell limit is the least SOL we accept for `token_amount`
+        Trade::Sell {
+            token_amount: requested,
+            ..
+        } => {
+            if token_amount > *requested {
+                return Err(BotError::policy(format!(
+                    "Transaction sells {} raw units but only {} were requested, refusing to sign",
+                    token_amount, requested
+                )));
+            }
+            let output = curve.sell_quote(token_amount, fee_basis_points);
+            Ok(output.saturating_sub(output.saturating_mul(slippage_bps) / 10_000))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEE_BASIS_POINTS: u64 = 100;
+    const TOKENS: u64 = 10_000_000_000_000;
+
+    fn curve() -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+        }
+    }
+
+    fn buy(lamports: u64) -> Trade {
+        Trade::Buy {
+            mint: Pubkey::new_unique(),
+            lamports,
+        }
+    }
+
+    fn sell(token_amount: u64) -> Trade {
+        Trade::Sell {
+            mint: Pubkey::new_unique(),
+            token_amount,
+        }
+    }
+
+    #[test]
+    fn buy_limit_adds_the_slippage_to_the_cost() {
+        let allowed =
+            allowed_limit(&curve(), FEE_BASIS_POINTS, &buy(1_000_000_000), TOKENS, 500).unwrap();
+        assert_eq!(allowed, 299_294_449);
+    }
+
+    #[test]
+    fn buy_limit_is_capped_by_the_amount_to_spend() {
+        let allowed =
+            allowed_limit(&curve(), FEE_BASIS_POINTS, &buy(200_000_000), TOKENS, 100).unwrap();
+        assert_eq!(allowed, 202_000_000);
+    }
+
+    #[test]
+    fn sell_limit_takes_the_slippage_off_the_output() {
+        let trade = sell(TOKENS);
+        let limit = |bps| allowed_limit(&curve(), FEE_BASIS_POINTS, &trade, TOKENS, bps).unwrap();
+        assert_eq!(limit(0), 274_238_227);
+        assert_eq!(limit(500), 260_526_316);
+        assert_eq!(limit(10_000), 0);
+    }
+
+    #[test]
+    fn sell_of_more_than_requested_is_refused() {
+        let trade = sell(TOKENS - 1);
+        assert!(allowed_limit(&curve(), FEE_BASIS_POINTS, &trade, TOKENS, 500).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+use futures_util::StreamExt;
+use log::debug;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_sdk::clock::Slot;
+use std::time::Duration;
+use tokio::sync::{watch, OnceCell};
+
+use crate::Result;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+// The chain's current slot from slotSubscribe, so sends can be timed in slots
+// rather than wall-clock sleeps. The subscription starts on first use and
+// reconnects whenever it drops.
+pub struct SlotClock {
+    ws_url: String,
+    slots: OnceCell<watch::Receiver<Slot>>,
+}
+
+impl SlotClock {
+    pub fn new(ws_url: String) -> SlotClock {
+        SlotClock {
+            ws_url,
+            slots: OnceCell::new(),
+        }
+    }
+
+    pub async fn ticker(&self) -> SlotTicker {
+        let slots = self
+            .slots
+            .get_or_init(|| async {
+                let (sender, receiver) = watch::channel(0);
+                tokio::spawn(follow_slots(self.ws_url.clone(), sender));
+                receiver
+            })
+            .await;
+        SlotTicker {
+            slots: slots.clone(),
+        }
+    }
+}
+
+pub struct SlotTicker {
+    slots: watch::Receiver<Slot>,
+}
+
+impl SlotTicker {
+    // Wait for a slot after the current one and return it
+    pub async fn tick(&mut self) -> Result<Slot> {
+        self.slots.borrow_and_update();
+        self.slots.changed().await?;
+        Ok(*self.slots.borrow_and_update())
+    }
+}
+
+async fn follow_slots(ws_url: String, slots: watch::Sender<Slot>) {
+    loop {
+        let result: Result<()> = async {
+            let pubsub = PubsubClient::new(&ws_url).await?;
+            let (mut updates, _unsubscribe) = pubsub.slot_subscribe().await?;
+            while let Some(update) = updates.next().await {
+                if slots.send(update.slot).is_err() {
+                    // Every ticker is gone
+                    return Ok(());
+                }
+            }
+            Ok(())
+        }
+        .await;
+        if slots.is_closed() {
+            return;
+        }
+        if let Err(e) = result {
+            debug!("Slot subscription dropped: {}", e);
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
@@ -0,0 +1,218 @@
+use clap::ValueEnum;
+use colored::*;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    native_token::{lamports_to_sol, sol_to_lamports},
+    pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+use tracing::instrument;
+
+use crate::config::Config;
+use crate::jupiter::{self, WSOL_MINT};
+use crate::prepare::Trade;
+use crate::venue::VenueKind;
+use crate::{api, Result};
+
+// Slippage allowed on locally built transactions when none is configured
+const DEFAULT_SLIPPAGE_BPS: u64 = 500;
+
+// Where unsigned trade transactions come from
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxSource {
+    /// The degen.fund antibot API
+    Api,
+    /// Built locally against the venue's on-chain program
+    Local,
+}
+
+impl TxSource {
+    fn name(&self) -> &'static str {
+        match self {
+            TxSource::Api => "API",
+            TxSource::Local => "local",
+        }
+    }
+}
+
+// The configured source first, then the other one when fallback is enabled.
+// Venues other than degen.fund have no API.
+fn sources(config: &Config) -> Vec<TxSource> {
+    if config.venue != VenueKind::Degen {
+        return vec![TxSource::Local];
+    }
+    let other = match config.tx_source {
+        TxSource::Api => TxSource::Local,
+        TxSource::Local => TxSource::Api,
+    };
+    if config.tx_fallback {
+        vec![config.tx_source, other]
+    } else {
+        vec![config.tx_source]
+    }
+}
+
+#[instrument(name = "fetch", skip_all)]
+pub async fn buy_transaction(
+    config: &Config,
+    client: &RpcClient,
+    buyer: &Pubkey,
+    mint: &Pubkey,
+    amount_sol: f64,
+) -> Result<VersionedTransaction> {
+    let mut last_error = None;
+    for source in sources(config) {
+        let result = match source {
+            TxSource::Api => {
+                api::fetch_buy_transaction(&mint.to_string(), amount_sol, &buyer.to_string()).await
+            }
+            TxSource::Local => {
+                config
+                    .venue()?
+                    .build_buy(
+                        client,
+                        buyer,
+                        mint,
+                        sol_to_lamports(amount_sol),
+                        config.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS),
+                    )
+                    .await
+            }
+        };
+        match result {
+            Ok(tx) => return Ok(tx),
+            Err(e) => {
+                warn!(
+                    "Building the buy with the {} source failed: {}",
+                    source.name(),
+                    e
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+    Err(last_error.unwrap_or_else(|| "No transaction source available".red().to_string().into()))
+}
+
+#[instrument(name = "fetch", skip_all)]
+pub async fn sell_transaction(
+    config: &Config,
+    client: &RpcClient,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    token_amount: u64,
+) -> Result<VersionedTransaction> {
+    if config.jupiter_sells {
+        if let Some(tx) = jupiter_sell(config, client, seller, mint, token_amount).await? {
+            return Ok(tx);
+        }
+    }
+
+    let mut last_error = None;
+    for source in sources(config) {
+        let result = match source {
+            TxSource::Api => {
+                api::fetch_sell_transaction(&mint.to_string(), token_amount, &seller.to_string())
+                    .await
+            }
+            TxSource::Local => {
+                config
+                    .venue()?
+                    .build_sell(
+                        client,
+                        seller,
+                        mint,
+                        token_amount,
+                        config.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS),
+                    )
+                    .await
+            }
+        };
+        match result {
+            Ok(tx) => return Ok(tx),
+            Err(e) => {
+                warn!(
+                    "Building the sell with the {} source failed: {}",
+                    source.name(),
+                    e
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+    // A token that left the curve may still trade on Jupiter
+    if config.jupiter_sells {
+        let slippage_bps = config.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+        match jupiter::quote(mint, &WSOL_MINT, token_amount, slippage_bps).await {
+            Ok(quote) => {
+                info!(
+                    "Selling through Jupiter for {} SOL",
+                    lamports_to_sol(quote.out_amount).to_string().yellow()
+                );
+                return jupiter::swap_transaction(&quote, seller).await;
+            }
+            Err(e) => debug!("No Jupiter route for the sell: {}", e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| "No transaction source available".red().to_string().into()))
+}
+
+// A Jupiter swap when the venue can't quote the sell (the token migrated) or
+// Jupiter pays more for it, None to sell on the venue
+async fn jupiter_sell(
+    config: &Config,
+    client: &RpcClient,
+    seller: &Pubkey,
+    mint: &Pubkey,
+    token_amount: u64,
+) -> Result<Option<VersionedTransaction>> {
+    let trade = Trade::Sell {
+        mint: *mint,
+        token_amount,
+    };
+    let slippage_bps = config.slippage_bps.unwrap_or(DEFAULT_SLIPPAGE_BPS);
+    // Without the program id there is nothing to compare against, the curve
+    // sources are tried first
+    let venue = match config.venue() {
+        Ok(venue) => venue,
+        Err(_) => return Ok(None),
+    };
+    let (venue_out, jupiter) = tokio::join!(
+        venue.quote(client, &trade),
+        jupiter::quote(mint, &WSOL_MINT, token_amount, slippage_bps)
+    );
+    let quote = match jupiter {
+        Ok(quote) => quote,
+        Err(e) => {
+            debug!("No Jupiter route for the sell: {}", e);
+            return Ok(None);
+        }
+    };
+    match venue_out {
+        Ok(venue_out) if venue_out >= quote.out_amount => {
+            debug!(
+                "{} pays {} lamports, Jupiter {}",
+                venue.name(),
+                venue_out,
+                quote.out_amount
+            );
+            return Ok(None);
+        }
+        Ok(venue_out) => info!(
+            "Selling through Jupiter for {} SOL, {} pays {} SOL",
+            lamports_to_sol(quote.out_amount).to_string().yellow(),
+            venue.name(),
+            lamports_to_sol(venue_out)
+        ),
+        Err(e) => info!(
+            "Selling through Jupiter for {} SOL, {} can't take the sell: {}",
+            lamports_to_sol(quote.out_amount).to_string().yellow(),
+            venue.name(),
+            e
+        ),
+    }
+    Ok(Some(jupiter::swap_transaction(&quote, seller).await?))
+}
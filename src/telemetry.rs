@@ -0,0 +1,54 @@
+use colored::*;
+use log::{info, warn};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+
+use crate::Result;
+
+const SERVICE_NAME: &str = "degen-fund-bot";
+
+// Exports the spans of the trade pipeline (fetch, sign, send, confirm under
+// one span per trade) to an OTLP collector over gRPC
+pub struct Telemetry {
+    provider: TracerProvider,
+}
+
+impl Telemetry {
+    pub fn init(endpoint: &str) -> Result<Telemetry> {
+        let exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|e| {
+                format!("Could not set up the OTLP exporter: {}", e)
+                    .red()
+                    .to_string()
+            })?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new(vec![KeyValue::new(
+                "service.name",
+                SERVICE_NAME,
+            )]))
+            .build();
+        let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME));
+        tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer))?;
+        info!("Exporting traces to {}", endpoint.bright_blue());
+        Ok(Telemetry { provider })
+    }
+
+    // Flush the spans still batched before exiting
+    pub async fn shutdown(self) {
+        let provider = self.provider;
+        // Shutting down blocks until the exporter is done
+        match tokio::task::spawn_blocking(move || provider.shutdown()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!("Could not flush the traces: {}", e),
+            Err(e) => warn!("Could not flush the traces: {}", e),
+        }
+    }
+}
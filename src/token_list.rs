@@ -0,0 +1,46 @@
+use colored::*;
+use solana_sdk::pubkey::Pubkey;
+use std::fs;
+
+use crate::{wallet, Result};
+
+// A token to buy, with its own amount when the line gives one
+pub struct Entry {
+    pub mint: Pubkey,
+    pub amount: Option<f64>,
+}
+
+// One `MINT [AMOUNT]` per line, `#` starting a comment
+pub fn load(path: &str) -> Result<Vec<Entry>> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        format!("Could not read token list {}: {}", path, e)
+            .red()
+            .to_string()
+    })?;
+    parse(&contents)
+}
+
+pub fn parse(contents: &str) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace();
+        let Some(mint) = fields.next() else {
+            continue;
+        };
+        let invalid = |what: String| format!("Line {}: {}", number + 1, what).red().to_string();
+        let mint = wallet::parse_mint(mint).map_err(|e| invalid(e.to_string()))?;
+        let amount = match fields.next() {
+            Some(amount) => Some(
+                amount
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|amount| *amount > 0.0)
+                    .ok_or_else(|| invalid(format!("invalid amount '{}'", amount)))?,
+            ),
+            None => None,
+        };
+        entries.push(Entry { mint, amount });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,122 @@
+use colored::*;
+use futures_util::future::join_all;
+use log::{debug, info, warn};
+use solana_client::{
+    connection_cache::ConnectionCache, nonblocking::rpc_client::RpcClient,
+    tpu_client::TpuClientConfig,
+};
+use solana_connection_cache::nonblocking::client_connection::ClientConnection;
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, signature::Signature, transaction::VersionedTransaction,
+};
+use solana_tpu_client::nonblocking::tpu_client::TpuClient;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use crate::Result;
+
+type QuicTpuClient = TpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+// Leaders rotate every 4 slots, so the warmed set goes stale within seconds
+const WARM_UP_INTERVAL: Duration = Duration::from_secs(2);
+
+// Sends wire transactions over QUIC straight to the current and upcoming
+// leaders' TPU ports, connecting on first use
+pub struct TpuSender {
+    rpc_url: Option<String>,
+    ws_url: Option<String>,
+    fanout_slots: u64,
+    client: OnceCell<QuicTpuClient>,
+}
+
+impl TpuSender {
+    pub fn new(rpc_url: Option<String>, ws_url: Option<String>, fanout_slots: u64) -> TpuSender {
+        TpuSender {
+            rpc_url,
+            ws_url,
+            fanout_slots,
+            client: OnceCell::new(),
+        }
+    }
+
+    pub async fn send(&self, tx: &VersionedTransaction) -> Result<Signature> {
+        let client = self.client().await?;
+        client
+            .try_send_wire_transaction(bincode::serialize(tx)?)
+            .await?;
+        Ok(tx.signatures[0])
+    }
+
+    // Open QUIC connections to the current and upcoming leaders' TPUs from the
+    // leader schedule, so the next send doesn't wait on the handshakes
+    pub async fn warm_up(&self) -> Result<()> {
+        let client = self.client().await?;
+        let leaders = client.get_leader_tpu_service();
+        let slot = leaders.estimated_current_slot();
+        let sockets = leaders.leader_tpu_sockets(self.fanout_slots);
+        let cache = client.get_connection_cache();
+        // Empty data only establishes the connection
+        let results = join_all(sockets.iter().map(|socket| async move {
+            cache
+                .get_nonblocking_connection(socket)
+                .send_data(&[])
+                .await
+        }))
+        .await;
+        let connected = results.iter().filter(|result| result.is_ok()).count();
+        if connected == 0 && !sockets.is_empty() {
+            return Err(
+                format!("Could not connect to any of {} leader TPUs", sockets.len())
+                    .red()
+                    .to_string()
+                    .into(),
+            );
+        }
+        debug!(
+            "Connected to {} of {} leader TPUs from slot {}",
+            connected,
+            sockets.len(),
+            slot
+        );
+        Ok(())
+    }
+
+    // Keep connections to the upcoming leaders open, for as long as the
+    // future is polled
+    pub async fn keep_warm(&self) {
+        loop {
+            if let Err(e) = self.warm_up().await {
+                warn!("Warming up TPU connections failed: {}", e);
+            }
+            tokio::time::sleep(WARM_UP_INTERVAL).await;
+        }
+    }
+
+    async fn client(&self) -> Result<&QuicTpuClient> {
+        self.client
+            .get_or_try_init(|| async {
+                let (Some(rpc_url), Some(ws_url)) = (&self.rpc_url, &self.ws_url) else {
+                    return Err("TPU send mode needs an RPC and WebSocket endpoint"
+                        .red()
+                        .to_string()
+                        .into());
+                };
+                info!("Connecting to leader TPUs...");
+                let rpc = Arc::new(RpcClient::new_with_commitment(
+                    rpc_url.clone(),
+                    CommitmentConfig::confirmed(),
+                ));
+                let config = TpuClientConfig {
+                    fanout_slots: self.fanout_slots,
+                };
+                // The backend client, which exposes its leader service and connections
+                let ConnectionCache::Quic(cache) = ConnectionCache::new("degen-fund-bot") else {
+                    return Err("QUIC connection cache unavailable".into());
+                };
+                Ok(QuicTpuClient::new_with_connection_cache(rpc, ws_url, config, cache).await?)
+            })
+            .await
+    }
+}
@@ -0,0 +1,950 @@
+use clap::ValueEnum;
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use solana_client::{
+    client_error::ClientError, nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcSendTransactionConfig,
+};
+use solana_sdk::{
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    message::VersionedMessage,
+    signature::{Signature, Signer, SignerError},
+    transaction::{Transaction, TransactionError, VersionedTransaction},
+};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::instrument;
+
+use crate::breaker::{self, CircuitBreaker};
+use crate::config::Config;
+use crate::fill::Fill;
+use crate::jito::JitoClient;
+use crate::journal::Journal;
+use crate::notify::{solscan_url, Event, Notifier};
+use crate::prepare::{self, Trade};
+use crate::rpc::RpcPool;
+use crate::signer::WalletSigner;
+use crate::slots::SlotClock;
+use crate::tpu::TpuSender;
+use crate::{logging, message, metrics, nonce, output, risk, shutdown, simulate};
+use crate::{BotError, Result};
+
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const REBROADCAST_INTERVAL: Duration = Duration::from_secs(2);
+// Fall back to the wall clock when no slot arrives within this
+const SLOT_TICK_TIMEOUT: Duration = Duration::from_secs(1);
+// Times a trade is sent again after its blockhash expired before giving up
+const MAX_REBUILDS: usize = 2;
+
+// How signed transactions are submitted
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SendMode {
+    /// Plain sendTransaction to the RPC endpoint
+    Rpc,
+    /// Jito bundle with a tip, sent to the block engine
+    Jito,
+    /// The same transaction sent to every RPC endpoint (and Jito when enabled) at once
+    Race,
+    /// QUIC straight to the upcoming leaders' TPU ports
+    Tpu,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    /// Seen by the leader, may still be dropped
+    Processed,
+    /// Voted on by a supermajority of the cluster
+    Confirmed,
+    /// Rooted, can't be rolled back
+    Finalized,
+}
+
+impl Commitment {
+    pub fn config(self) -> CommitmentConfig {
+        match self {
+            Commitment::Processed => CommitmentConfig::processed(),
+            Commitment::Confirmed => CommitmentConfig::confirmed(),
+            Commitment::Finalized => CommitmentConfig::finalized(),
+        }
+    }
+}
+
+// Preflight and commitment of RPC sends and of the wait for them to land.
+// Snipes usually skip the preflight and confirm at confirmed.
+#[derive(Clone, Copy)]
+pub struct SendOptions {
+    pub skip_preflight: bool,
+    pub preflight_commitment: Commitment,
+    pub confirm_commitment: Commitment,
+}
+
+impl Default for SendOptions {
+    fn default() -> SendOptions {
+        SendOptions {
+            skip_preflight: false,
+            preflight_commitment: Commitment::Confirmed,
+            confirm_commitment: Commitment::Confirmed,
+        }
+    }
+}
+
+impl SendOptions {
+    fn rpc_config(&self) -> RpcSendTransactionConfig {
+        RpcSendTransactionConfig {
+            skip_preflight: self.skip_preflight,
+            preflight_commitment: Some(self.preflight_commitment.config().commitment),
+            ..Default::default()
+        }
+    }
+}
+
+// Re-sending a transaction that hasn't landed with a higher priority fee
+#[derive(Clone, Copy)]
+pub struct FeeEscalation {
+    /// Micro-lamports per compute unit added on every retry
+    pub step: u64,
+    /// Highest unit price a retry goes to
+    pub cap: u64,
+    /// How long each attempt on a durable nonce gets to land before the next
+    /// one is sent
+    pub window: Duration,
+}
+
+// A transaction that can no longer land because its blockhash expired
+#[derive(Debug)]
+pub struct BlockhashExpired(pub Signature);
+
+impl fmt::Display for BlockhashExpired {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = format!(
+            "Transaction {} was not confirmed before its blockhash expired",
+            self.0
+        );
+        write!(f, "{}", message.red())
+    }
+}
+
+impl std::error::Error for BlockhashExpired {}
+
+// An expired blockhash noticed while landing, or refused by the RPC on send
+pub fn is_blockhash_expired(error: &BotError) -> bool {
+    error.is::<BlockhashExpired>()
+        || matches!(
+            error
+                .downcast_ref::<ClientError>()
+                .and_then(|e| e.get_transaction_error()),
+            Some(TransactionError::BlockhashNotFound)
+        )
+}
+
+// A transaction still landing when the shutdown gave up waiting on it
+#[derive(Debug)]
+pub struct Abandoned(pub Signature);
+
+impl fmt::Display for Abandoned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = format!(
+            "Shutting down before transaction {} landed, it is journaled as pending",
+            self.0
+        );
+        write!(f, "{}", message.yellow())
+    }
+}
+
+impl std::error::Error for Abandoned {}
+
+pub struct Sender {
+    rpc: RpcPool,
+    mode: SendMode,
+    jito: JitoClient,
+    race_jito: bool,
+    tpu: Arc<TpuSender>,
+    dry_run: bool,
+    notifier: Notifier,
+    journal: Option<Journal>,
+    escalation: Option<FeeEscalation>,
+    breaker: Option<CircuitBreaker>,
+    slot_clock: Option<SlotClock>,
+    rebroadcast_slots: u64,
+    // Fetches and prepares a trade again when its blockhash expires
+    rebuild: Option<Box<Config>>,
+    options: SendOptions,
+}
+
+impl Sender {
+    pub fn new(
+        rpc: RpcPool,
+        mode: SendMode,
+        jito: JitoClient,
+        race_jito: bool,
+        tpu: Arc<TpuSender>,
+        dry_run: bool,
+        notifier: Notifier,
+    ) -> Sender {
+        Sender {
+            rpc,
+            mode,
+            jito,
+            race_jito,
+            tpu,
+            dry_run,
+            notifier,
+            journal: None,
+            escalation: None,
+            breaker: None,
+            slot_clock: None,
+            rebroadcast_slots: 0,
+            rebuild: None,
+            options: SendOptions::default(),
+        }
+    }
+
+    pub fn with_journal(mut self, journal: Journal) -> Sender {
+        self.journal = Some(journal);
+        self
+    }
+
+    pub fn with_fee_escalation(mut self, escalation: FeeEscalation) -> Sender {
+        self.escalation = Some(escalation);
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Sender {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    // Time confirmation polls by new slots and rebroadcast every `rebroadcast_slots`
+    pub fn with_slot_clock(mut self, slot_clock: SlotClock, rebroadcast_slots: u64) -> Sender {
+        self.slot_clock = Some(slot_clock);
+        self.rebroadcast_slots = rebroadcast_slots;
+        self
+    }
+
+    pub fn with_send_options(mut self, options: SendOptions) -> Sender {
+        self.options = options;
+        self
+    }
+
+    // Replace a trade whose blockhash expired by a freshly fetched one instead
+    // of failing
+    pub fn with_rebuild(mut self, config: Config) -> Sender {
+        self.rebuild = Some(Box::new(config));
+        self
+    }
+
+    pub fn slot_clock(&self) -> Option<&SlotClock> {
+        self.slot_clock.as_ref()
+    }
+
+    // Connect to the upcoming leaders ahead of a planned send in tpu mode
+    pub async fn warm_up(&self) {
+        if self.mode != SendMode::Tpu || self.dry_run {
+            return;
+        }
+        if let Err(e) = self.tpu.warm_up().await {
+            warn!("Warming up TPU connections failed: {}", e);
+        }
+    }
+
+    pub fn client(&self) -> &RpcClient {
+        self.rpc.client()
+    }
+
+    pub fn rpc(&self) -> &RpcPool {
+        &self.rpc
+    }
+
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    // Simulate instead of broadcasting and print the expected outcome
+    pub async fn dry_run(&self, tx: &VersionedTransaction, payer: &WalletSigner) -> Result<()> {
+        let spinner = new_spinner("Simulating transaction...");
+        let simulation = simulate::simulate(self.client(), tx, &payer.pubkey()).await;
+        spinner.finish_and_clear();
+        simulation?.print();
+        Ok(())
+    }
+
+    #[instrument(name = "send", skip_all)]
+    pub async fn send(&self, tx: &VersionedTransaction, payer: &WalletSigner) -> Result<Signature> {
+        match self.mode {
+            SendMode::Rpc => {
+                let config = self.options.rpc_config();
+                self.rpc
+                    .call(|client| {
+                        Box::pin(async move {
+                            Ok(client.send_transaction_with_config(tx, config).await?)
+                        })
+                    })
+                    .await
+            }
+            SendMode::Jito => self.jito.send_bundle(self.client(), tx, payer).await,
+            SendMode::Race => self.race(tx, payer).await,
+            SendMode::Tpu => self.tpu.send(tx).await,
+        }
+    }
+
+    // Broadcast everywhere concurrently and report who accepted it first
+    async fn race(&self, tx: &VersionedTransaction, payer: &WalletSigner) -> Result<Signature> {
+        let started = Instant::now();
+        let jito = async {
+            if self.race_jito {
+                Some((
+                    self.jito.send_bundle(self.client(), tx, payer).await,
+                    started.elapsed(),
+                ))
+            } else {
+                None
+            }
+        };
+        let (mut outcomes, jito) =
+            tokio::join!(self.rpc.broadcast(tx, self.options.rpc_config()), jito);
+        if let Some((result, elapsed)) = jito {
+            outcomes.push(("jito".to_string(), elapsed, result));
+        }
+        outcomes.sort_by_key(|(_, elapsed, _)| *elapsed);
+
+        for (endpoint, elapsed, result) in &outcomes {
+            match result {
+                Ok(_) => debug!("{} accepted in {}ms", endpoint, elapsed.as_millis()),
+                Err(e) => debug!("{} failed in {}ms: {}", endpoint, elapsed.as_millis(), e),
+            }
+        }
+        let winner = outcomes.iter().find_map(|(endpoint, elapsed, result)| {
+            result
+                .as_ref()
+                .ok()
+                .map(|signature| (endpoint, elapsed, *signature))
+        });
+        match winner {
+            Some((endpoint, elapsed, signature)) => {
+                info!(
+                    "Race won by {} in {}ms",
+                    endpoint.bright_blue(),
+                    elapsed.as_millis()
+                );
+                Ok(signature)
+            }
+            None => Err(format!(
+                "Every endpoint rejected the transaction: {}",
+                outcomes
+                    .iter()
+                    .map(|(endpoint, _, result)| format!(
+                        "{}: {}",
+                        endpoint,
+                        result
+                            .as_ref()
+                            .err()
+                            .map(|e| e.to_string())
+                            .unwrap_or_default()
+                    ))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            )
+            .red()
+            .to_string()
+            .into()),
+        }
+    }
+
+    // Send and wait for confirmation, returning the slot it landed in
+    pub async fn send_and_confirm(
+        &self,
+        tx: &VersionedTransaction,
+        payer: &WalletSigner,
+        trade: &Trade,
+    ) -> Result<(Signature, Slot)> {
+        if self.dry_run {
+            risk::release(&payer.pubkey(), trade);
+            self.dry_run(tx, payer).await?;
+            return Ok((tx.signatures[0], 0));
+        }
+        metrics::attempted(trade);
+        let started = Instant::now();
+        // The first attempt journaled, which stands for the trade
+        let mut sent = tx.signatures[0];
+        let result: Result<(Signature, Slot)> = async {
+            let (signature, tx) = self.send_or_rebuild(tx, payer, trade).await?;
+            sent = signature;
+            self.on_sent(trade, payer, &signature).await;
+            self.land(&tx, payer, trade, &signature).await
+        }
+        .await;
+        match &result {
+            Ok((signature, slot)) => {
+                metrics::landed(trade, started.elapsed());
+                self.on_confirmed(trade, payer, signature, *slot).await
+            }
+            Err(e) if e.is::<Abandoned>() => {}
+            Err(e) => self.on_failed(trade, payer, &sent, e.to_string()).await,
+        }
+        result
+    }
+
+    // Send, and when the RPC no longer knows the blockhash send a rebuilt
+    // transaction instead. Returns the transaction that went out.
+    pub async fn send_or_rebuild(
+        &self,
+        tx: &VersionedTransaction,
+        payer: &WalletSigner,
+        trade: &Trade,
+    ) -> Result<(Signature, VersionedTransaction)> {
+        match self.send(tx, payer).await {
+            Ok(signature) => Ok((signature, tx.clone())),
+            Err(e) if is_blockhash_expired(&e) && self.rebuild.is_some() => {
+                warn!("Blockhash of the transaction expired before it was sent, rebuilding it");
+                let rebuilt = self.rebuild(payer, trade).await?;
+                Ok((self.send(&rebuilt, payer).await?, rebuilt))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn rebuild(&self, payer: &WalletSigner, trade: &Trade) -> Result<VersionedTransaction> {
+        let config = self
+            .rebuild
+            .as_deref()
+            .ok_or("No configuration to rebuild from")?;
+        prepare::rebuild(config, self.client(), payer, trade).await
+    }
+
+    // Trade lifecycle hooks, journaling the trade and notifying about it
+
+    pub(crate) async fn on_sent(&self, trade: &Trade, payer: &WalletSigner, signature: &Signature) {
+        output::sent(trade, &payer.pubkey(), signature);
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.record_sent(&payer.pubkey(), trade, signature) {
+                warn!("Could not journal {}: {}", signature, e);
+            }
+        }
+        risk::release(&payer.pubkey(), trade);
+        self.notifier
+            .notify(Event::Sent {
+                trade: *trade,
+                signature: *signature,
+            })
+            .await;
+    }
+
+    // Another attempt at the trade sent as `previous`, journaled under the
+    // same trade so its amount only counts once
+    async fn on_resent(
+        &self,
+        trade: &Trade,
+        payer: &WalletSigner,
+        previous: &Signature,
+        signature: &Signature,
+        superseded: bool,
+    ) {
+        output::sent(trade, &payer.pubkey(), signature);
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.record_resent(previous, signature, superseded) {
+                warn!("Could not journal {}: {}", signature, e);
+            }
+        }
+        self.notifier
+            .notify(Event::Sent {
+                trade: *trade,
+                signature: *signature,
+            })
+            .await;
+    }
+
+    fn on_pending(&self, signature: &Signature) {
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.record_pending(signature) {
+                warn!("Could not journal {}: {}", signature, e);
+            }
+        }
+    }
+
+    pub(crate) async fn on_confirmed(
+        &self,
+        trade: &Trade,
+        payer: &WalletSigner,
+        signature: &Signature,
+        slot: Slot,
+    ) {
+        let fill = if self.notifier.is_enabled()
+            || self.journal.is_some()
+            || output::is_json()
+            || metrics::is_enabled()
+        {
+            match Fill::fetch(self.client(), signature, &payer.pubkey(), &trade.mint()).await {
+                Ok(fill) => Some(fill),
+                Err(e) => {
+                    debug!("Could not read the fill of {}: {}", signature, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        breaker::record_success();
+        output::confirmed(signature, slot, fill.as_ref());
+        if let Some(fill) = &fill {
+            metrics::fee_paid(fill.fee_lamports);
+        }
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.record_confirmed(signature, slot, fill.as_ref()) {
+                warn!("Could not journal {}: {}", signature, e);
+            }
+        }
+        self.notifier
+            .notify(Event::Confirmed {
+                trade: *trade,
+                signature: *signature,
+                slot,
+                fill,
+            })
+            .await;
+    }
+
+    pub(crate) async fn on_failed(
+        &self,
+        trade: &Trade,
+        payer: &WalletSigner,
+        signature: &Signature,
+        error: String,
+    ) {
+        output::failed(trade, &payer.pubkey(), signature, &error);
+        if let Some(journal) = &self.journal {
+            if let Err(e) = journal.record_failed(&payer.pubkey(), trade, signature, &error) {
+                warn!("Could not journal {}: {}", signature, e);
+            }
+        }
+        risk::release(&payer.pubkey(), trade);
+        self.notifier
+            .notify(Event::Failed {
+                trade: *trade,
+                error,
+            })
+            .await;
+        if let Some(trip) = self.breaker.and_then(|breaker| breaker.record_failure()) {
+            self.notifier.notify(Event::Paused(trip)).await;
+        }
+    }
+
+    // Wait for a sent transaction to land, rebroadcasting it until its
+    // blockhash expires
+    #[instrument(name = "confirm", skip_all)]
+    pub async fn confirm(
+        &self,
+        tx: &VersionedTransaction,
+        payer: &WalletSigner,
+        signature: &Signature,
+    ) -> Result<Slot> {
+        let landed = self.watch(&[(*signature, tx)], payer, None).await?;
+        // Without a window the watch only ends once the transaction lands or fails
+        Ok(landed.map_or(0, |(_, slot)| slot))
+    }
+
+    // Like confirm, with fee escalation. On a durable nonce an attempt that
+    // doesn't land within the window is re-signed with a higher unit price and
+    // sent as well, until the cap. The attempts compete for the nonce, so at
+    // most one lands, and whichever does is returned. On a recent blockhash an
+    // attempt is only re-priced once its blockhash expired, when it can no
+    // longer land. An expired trade that can't be re-priced is rebuilt.
+    #[instrument(name = "confirm", skip_all)]
+    pub async fn land(
+        &self,
+        tx: &VersionedTransaction,
+        payer: &WalletSigner,
+        trade: &Trade,
+        signature: &Signature,
+    ) -> Result<(Signature, Slot)> {
+        // Transactions co-signed by the API can't be re-signed by us, and a
+        // Ledger would ask for every retry
+        let mut escalation = self
+            .escalation
+            .filter(|_| tx.signatures.len() == 1 && !payer.is_interactive());
+        let durable = nonce::durable_nonce(&tx.message).is_some();
+        let mut attempts = vec![(*signature, tx.clone())];
+        let mut rebuilds = 0;
+        loop {
+            let watched: Vec<(Signature, &VersionedTransaction)> = attempts
+                .iter()
+                .map(|(signature, tx)| (*signature, tx))
+                .collect();
+            let window = escalation
+                .filter(|_| durable)
+                .map(|escalation| escalation.window);
+            let (previous, latest) = &attempts[attempts.len() - 1];
+            match self.watch(&watched, payer, window).await {
+                Ok(Some(landed)) => return Ok(landed),
+                Ok(None) => {}
+                Err(e) if e.is::<Abandoned>() => {
+                    self.on_pending(previous);
+                    return Err(e);
+                }
+                // Every attempt has expired, so none of them can land anymore
+                Err(e) if is_blockhash_expired(&e) && rebuilds < MAX_REBUILDS => {
+                    let repriced = match escalation.filter(|_| !durable) {
+                        Some(step) => reprice(self.client(), latest, payer, &step).await?,
+                        None => None,
+                    };
+                    let next = match repriced {
+                        Some((next, unit_price)) => {
+                            info!(
+                                "{}, re-signing it with a unit price of {} micro-lamports",
+                                e,
+                                unit_price.to_string().yellow()
+                            );
+                            next
+                        }
+                        None if self.rebuild.is_some() => {
+                            warn!("{}, rebuilding and sending it again", e);
+                            self.rebuild(payer, trade).await?
+                        }
+                        None => return Err(e),
+                    };
+                    rebuilds += 1;
+                    let signature = self.send(&next, payer).await?;
+                    info!(
+                        "Transaction sent again: {}",
+                        signature.to_string().bright_green()
+                    );
+                    self.on_resent(trade, payer, previous, &signature, true)
+                        .await;
+                    attempts = vec![(signature, next)];
+                    continue;
+                }
+                Err(e) => return Err(e),
+            }
+            // Only attempts on a durable nonce have a window to miss
+            let Some(step) = escalation else {
+                continue;
+            };
+
+            match reprice(self.client(), latest, payer, &step).await? {
+                Some((next, unit_price)) => match self.send(&next, payer).await {
+                    Ok(signature) => {
+                        info!(
+                            "Not landed after {}ms, resent with a unit price of {} micro-lamports",
+                            step.window.as_millis(),
+                            unit_price.to_string().yellow()
+                        );
+                        self.on_resent(trade, payer, previous, &signature, false)
+                            .await;
+                        attempts.push((signature, next));
+                    }
+                    Err(e) => {
+                        warn!("Resending with a higher fee failed: {}", e);
+                        escalation = None;
+                    }
+                },
+                None => {
+                    debug!("Priority fee cap reached, waiting on the sent attempts");
+                    escalation = None;
+                }
+            }
+        }
+    }
+
+    // Poll the status of every attempt until one lands, rebroadcasting the
+    // latest until its blockhash expires. None once `window` passes first.
+    // With a slot clock every new slot triggers a poll.
+    async fn watch(
+        &self,
+        attempts: &[(Signature, &VersionedTransaction)],
+        payer: &WalletSigner,
+        window: Option<Duration>,
+    ) -> Result<Option<(Signature, Slot)>> {
+        let Some(&(latest_signature, latest)) = attempts.last() else {
+            return Ok(None);
+        };
+        let signatures: Vec<Signature> = attempts.iter().map(|(signature, _)| *signature).collect();
+        let started = Instant::now();
+        let mut last_broadcast = Instant::now();
+        let mut ticker = match &self.slot_clock {
+            Some(clock) => Some(clock.ticker().await),
+            None => None,
+        };
+        // Slot of the last broadcast, once the slot clock has reported one
+        let mut broadcast_slot = None;
+        loop {
+            if shutdown::current() == shutdown::State::Abandoning {
+                return Err(Abandoned(latest_signature).into());
+            }
+            let slot = match &mut ticker {
+                Some(ticker) => {
+                    match tokio::time::timeout(SLOT_TICK_TIMEOUT, ticker.tick()).await {
+                        Ok(Ok(slot)) => Some(slot),
+                        _ => None,
+                    }
+                }
+                None => {
+                    tokio::time::sleep(CONFIRM_POLL_INTERVAL).await;
+                    None
+                }
+            };
+
+            let signatures = &signatures;
+            let statuses = self
+                .rpc
+                .call(|client| {
+                    Box::pin(async move { Ok(client.get_signature_statuses(signatures).await?) })
+                })
+                .await?
+                .value;
+            let mut processed = false;
+            for (signature, status) in signatures.iter().zip(statuses) {
+                let Some(status) = status else {
+                    continue;
+                };
+                if let Some(error) = status.err {
+                    return Err(BotError::TransactionFailed {
+                        signature: *signature,
+                        error,
+                    });
+                }
+                if status.satisfies_commitment(self.options.confirm_commitment.config()) {
+                    return Ok(Some((*signature, status.slot)));
+                }
+                processed = true;
+            }
+            if processed {
+                continue;
+            }
+
+            // A durable nonce transaction stays valid until the nonce moves on
+            let blockhash = latest.message.recent_blockhash();
+            let durable_nonce = nonce::durable_nonce(&latest.message);
+            let blockhash_valid = self
+                .rpc
+                .call(|client| {
+                    Box::pin(async move {
+                        match durable_nonce {
+                            Some(account) => {
+                                Ok(nonce::fetch(client, &account).await?.blockhash == *blockhash)
+                            }
+                            None => Ok(client
+                                .is_blockhash_valid(blockhash, CommitmentConfig::processed())
+                                .await?),
+                        }
+                    })
+                })
+                .await?;
+            if !blockhash_valid {
+                return Err(BlockhashExpired(latest_signature).into());
+            }
+            if window.is_some_and(|window| started.elapsed() >= window) {
+                return Ok(None);
+            }
+
+            let due = match (slot, broadcast_slot) {
+                (Some(slot), Some(sent)) => slot >= sent + self.rebroadcast_slots,
+                (Some(slot), None) => {
+                    broadcast_slot = Some(slot);
+                    false
+                }
+                (None, _) => last_broadcast.elapsed() >= REBROADCAST_INTERVAL,
+            };
+            if due {
+                if let Err(e) = self.send(latest, payer).await {
+                    warn!("Rebroadcast failed: {}", e);
+                }
+                last_broadcast = Instant::now();
+                broadcast_slot = slot.or(broadcast_slot);
+            }
+        }
+    }
+}
+
+// Tell the user waiting on a trade where to find it once it landed
+pub fn report_landed(signature: &Signature) {
+    info!(
+        "Transaction confirmed: {}",
+        signature.to_string().bright_green()
+    );
+    info!(
+        "View transaction on Solscan: {}",
+        solscan_url(signature).bright_blue().underline()
+    );
+}
+
+pub fn new_spinner(message: &'static str) -> ProgressBar {
+    // Spinners would draw over a full screen interface
+    if logging::is_captured() {
+        return ProgressBar::hidden();
+    }
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ ")
+            .template("{spinner:.green} {msg}")
+            .unwrap(),
+    );
+    spinner.set_message(message);
+    spinner.enable_steady_tick(Duration::from_millis(100));
+    spinner
+}
+
+// Priority fee settings injected into transactions before signing
+#[derive(Clone, Copy, Default)]
+pub struct ComputeBudget {
+    /// Price per compute unit in micro-lamports
+    pub unit_price: Option<u64>,
+    pub unit_limit: Option<u32>,
+}
+
+impl ComputeBudget {
+    pub fn is_empty(&self) -> bool {
+        self.unit_price.is_none() && self.unit_limit.is_none()
+    }
+}
+
+// Rebuild the message of an unsigned transaction with ComputeBudget
+// instructions prepended, replacing any the API already included
+pub async fn apply_compute_budget(
+    client: &RpcClient,
+    tx: &mut VersionedTransaction,
+    budget: &ComputeBudget,
+) -> Result<()> {
+    if budget.is_empty() {
+        return Ok(());
+    }
+    if tx.signatures.iter().any(|s| *s != Signature::default()) {
+        warn!("Transaction is already partially signed, keeping the API compute budget");
+        return Ok(());
+    }
+
+    let mut decompiled = message::decompile(client, &tx.message).await?;
+    let mut instructions = Vec::new();
+    // The advance of a durable nonce has to stay the first instruction
+    if decompiled
+        .instructions
+        .first()
+        .is_some_and(nonce::is_advance)
+    {
+        instructions.push(decompiled.instructions.remove(0));
+    }
+    if let Some(units) = budget.unit_limit {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+    }
+    if let Some(micro_lamports) = budget.unit_price {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
+            micro_lamports,
+        ));
+    }
+    instructions.extend(
+        decompiled
+            .instructions
+            .drain(..)
+            .filter(|ix| ix.program_id != compute_budget::id()),
+    );
+    decompiled.instructions = instructions;
+
+    *tx = unsigned(decompiled.compile()?);
+    Ok(())
+}
+
+pub fn unsigned(message: VersionedMessage) -> VersionedTransaction {
+    VersionedTransaction {
+        signatures: vec![Signature::default(); message.header().num_required_signatures as usize],
+        message,
+    }
+}
+
+// `tx` with its unit price raised by a step, keeping its unit limit, on a
+// fresh blockhash unless it uses a durable nonce, and signed. None once the
+// price is at the cap.
+async fn reprice(
+    client: &RpcClient,
+    tx: &VersionedTransaction,
+    payer: &WalletSigner,
+    escalation: &FeeEscalation,
+) -> Result<Option<(VersionedTransaction, u64)>> {
+    let mut decompiled = message::decompile(client, &tx.message).await?;
+    let mut budget = ComputeBudget::default();
+    for ix in &decompiled.instructions {
+        if ix.program_id != compute_budget::id() {
+            continue;
+        }
+        match ix.data.first() {
+            Some(2) if ix.data.len() >= 5 => {
+                budget.unit_limit = Some(u32::from_le_bytes(ix.data[1..5].try_into()?))
+            }
+            Some(3) if ix.data.len() >= 9 => {
+                budget.unit_price = Some(u64::from_le_bytes(ix.data[1..9].try_into()?))
+            }
+            _ => {}
+        }
+    }
+    let current = budget.unit_price.unwrap_or(0);
+    if current >= escalation.cap {
+        return Ok(None);
+    }
+    let unit_price = (current + escalation.step).min(escalation.cap);
+    budget.unit_price = Some(unit_price);
+
+    // Retries against a durable nonce compete for it, so at most one lands
+    if nonce::durable_nonce(&tx.message).is_none() {
+        decompiled.blockhash = client.get_latest_blockhash().await?;
+    }
+    let mut next = unsigned(decompiled.compile()?);
+    apply_compute_budget(client, &mut next, &budget).await?;
+    sign_transaction(&mut next, payer)?;
+    Ok(Some((next, unit_price)))
+}
+
+// Fill in our signature on a transaction prepared by the API
+pub fn sign_transaction(tx: &mut VersionedTransaction, keypair: &WalletSigner) -> Result<()> {
+    let our_pubkey = keypair.pubkey();
+    let our_signature_index = tx
+        .message
+        .static_account_keys()
+        .iter()
+        .take(tx.signatures.len())
+        .position(|&pubkey| pubkey == our_pubkey);
+
+    if let Some(index) = our_signature_index {
+        if tx.signatures[index] == Signature::default() {
+            let message_data = tx.message.serialize();
+            // A Ledger refusing to sign is an error, not a blank signature
+            tx.signatures[index] = keypair.try_sign_message(&message_data)?;
+        }
+        Ok(())
+    } else {
+        Err(SignerError::Custom("our public key is not in the list of signers".to_string()).into())
+    }
+}
+
+// Sign and send a transaction of `instructions` paid by `payer`, for wallet
+// upkeep rather than trades. Its signature once confirmed, none on a dry run,
+// which only simulates it.
+pub async fn send_instructions(
+    client: &RpcClient,
+    payer: &WalletSigner,
+    instructions: &[Instruction],
+    dry_run: bool,
+) -> Result<Option<Signature>> {
+    let blockhash = client.get_latest_blockhash().await?;
+    let mut tx = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+    tx.try_sign(&[payer], blockhash)?;
+    if dry_run {
+        if let Some(error) = client.simulate_transaction(&tx).await?.value.err {
+            return Err(format!("The transaction would fail: {}", error)
+                .red()
+                .to_string()
+                .into());
+        }
+        return Ok(None);
+    }
+    Ok(Some(client.send_and_confirm_transaction(&tx).await?))
+}
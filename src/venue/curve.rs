@@ -0,0 +1,123 @@
+use futures_util::future::BoxFuture;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey, pubkey::Pubkey, transaction::VersionedTransaction};
+
+use super::{CurveState, Venue};
+use crate::curve::{self, BondingCurve, Global};
+use crate::prepare::Trade;
+use crate::Result;
+
+pub const PUMP_FUN_PROGRAM_ID: Pubkey = pubkey!("6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P");
+
+// degen.fund and pump.fun share the same bonding curve program layout and
+// only differ in the program id
+pub struct CurveVenue {
+    name: &'static str,
+    program_id: Pubkey,
+}
+
+impl CurveVenue {
+    pub fn degen(program_id: Pubkey) -> CurveVenue {
+        CurveVenue {
+            name: "degen.fund",
+            program_id,
+        }
+    }
+
+    pub fn pump_fun() -> CurveVenue {
+        CurveVenue {
+            name: "pump.fun",
+            program_id: PUMP_FUN_PROGRAM_ID,
+        }
+    }
+}
+
+impl Venue for CurveVenue {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn curve_address(&self, mint: &Pubkey) -> Pubkey {
+        BondingCurve::address(&self.program_id, mint)
+    }
+
+    fn quote<'a>(&'a self, client: &'a RpcClient, trade: &'a Trade) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(async move {
+            let global = Global::fetch(client, &self.program_id).await?;
+            let curve = BondingCurve::fetch(client, &self.program_id, &trade.mint()).await?;
+            if curve.complete {
+                return Err("Bonding curve is complete, the token has migrated".into());
+            }
+            Ok(match trade {
+                Trade::Buy { lamports, .. } => curve.buy_quote(*lamports, global.fee_basis_points),
+                Trade::Sell { token_amount, .. } => {
+                    curve.sell_quote(*token_amount, global.fee_basis_points)
+                }
+            })
+        })
+    }
+
+    fn decode_curve(&self, data: &[u8], decimals: u8) -> Result<CurveState> {
+        let curve = BondingCurve::from_account_data(data)?;
+        Ok(CurveState {
+            price_sol: curve.price_sol(decimals),
+            market_cap_sol: curve.market_cap_sol(decimals),
+            complete: curve.complete,
+            tokens_left: curve.real_token_reserves,
+        })
+    }
+
+    fn initial_curve_tokens<'a>(
+        &'a self,
+        client: &'a RpcClient,
+    ) -> BoxFuture<'a, Result<Option<u64>>> {
+        Box::pin(async move {
+            let global = Global::fetch(client, &self.program_id).await?;
+            Ok(Some(global.initial_real_token_reserves))
+        })
+    }
+
+    fn build_buy<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        buyer: &'a Pubkey,
+        mint: &'a Pubkey,
+        lamports: u64,
+        slippage_bps: u64,
+    ) -> BoxFuture<'a, Result<VersionedTransaction>> {
+        Box::pin(async move {
+            let tx = curve::build_buy_transaction(
+                client,
+                &self.program_id,
+                buyer,
+                mint,
+                lamports,
+                slippage_bps,
+            )
+            .await?;
+            Ok(tx.into())
+        })
+    }
+
+    fn build_sell<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        seller: &'a Pubkey,
+        mint: &'a Pubkey,
+        token_amount: u64,
+        slippage_bps: u64,
+    ) -> BoxFuture<'a, Result<VersionedTransaction>> {
+        Box::pin(async move {
+            let tx = curve::build_sell_transaction(
+                client,
+                &self.program_id,
+                seller,
+                mint,
+                token_amount,
+                slippage_bps,
+            )
+            .await?;
+            Ok(tx.into())
+        })
+    }
+}
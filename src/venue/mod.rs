@@ -0,0 +1,110 @@
+use clap::ValueEnum;
+use colored::*;
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+
+use crate::prepare::Trade;
+use crate::Result;
+
+mod curve;
+mod moonshot;
+
+pub use curve::{CurveVenue, PUMP_FUN_PROGRAM_ID};
+pub use moonshot::{Moonshot, MOONSHOT_PROGRAM_ID};
+
+// Launchpad a token trades on
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VenueKind {
+    /// degen.fund bonding curves
+    Degen,
+    /// pump.fun bonding curves
+    Pumpfun,
+    /// Moonshot bonding curves
+    Moonshot,
+}
+
+// A token's curve as its account stands
+pub struct CurveState {
+    // SOL per whole token
+    pub price_sol: f64,
+    // Fully diluted, in SOL
+    pub market_cap_sol: f64,
+    pub complete: bool,
+    // Raw tokens the curve still sells
+    pub tokens_left: u64,
+}
+
+impl CurveState {
+    // Share of the `initial` tokens the curve sold so far, 100 once it completes
+    pub fn progress_pct(&self, initial: u64) -> f64 {
+        if self.complete || initial == 0 {
+            return 100.0;
+        }
+        initial.saturating_sub(self.tokens_left) as f64 / initial as f64 * 100.0
+    }
+}
+
+// A launchpad that quotes and builds unsigned trades against its own program
+pub trait Venue: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // The account holding a token's unsold supply
+    fn curve_address(&self, mint: &Pubkey) -> Pubkey;
+
+    // Expected output at the current state: raw tokens for a buy, lamports
+    // for a sell, fees taken
+    fn quote<'a>(&'a self, client: &'a RpcClient, trade: &'a Trade) -> BoxFuture<'a, Result<u64>>;
+
+    // The state of a curve from the data of its account
+    fn decode_curve(&self, data: &[u8], decimals: u8) -> Result<CurveState>;
+
+    // Raw tokens every curve starts out selling, None when the venue can't
+    // tell its progress that way
+    fn initial_curve_tokens<'a>(
+        &'a self,
+        client: &'a RpcClient,
+    ) -> BoxFuture<'a, Result<Option<u64>>>;
+
+    fn curve<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        mint: &'a Pubkey,
+        decimals: u8,
+    ) -> BoxFuture<'a, Result<CurveState>> {
+        Box::pin(async move {
+            let address = self.curve_address(mint);
+            let data = client.get_account_data(&address).await.map_err(|e| {
+                format!(
+                    "Could not fetch the {} curve {}: {}",
+                    self.name(),
+                    address,
+                    e
+                )
+                .red()
+                .to_string()
+            })?;
+            self.decode_curve(&data, decimals)
+        })
+    }
+
+    fn build_buy<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        buyer: &'a Pubkey,
+        mint: &'a Pubkey,
+        lamports: u64,
+        slippage_bps: u64,
+    ) -> BoxFuture<'a, Result<VersionedTransaction>>;
+
+    fn build_sell<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        seller: &'a Pubkey,
+        mint: &'a Pubkey,
+        token_amount: u64,
+        slippage_bps: u64,
+    ) -> BoxFuture<'a, Result<VersionedTransaction>>;
+}
@@ -0,0 +1,227 @@
+use colored::*;
+use futures_util::future::BoxFuture;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey,
+    pubkey::Pubkey,
+    system_program,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use super::{CurveState, Venue};
+use crate::curve::{BUY_DISCRIMINATOR, SELL_DISCRIMINATOR};
+use crate::prepare::Trade;
+use crate::wallet::{
+    associated_token_address, create_associated_token_account_idempotent,
+    ASSOCIATED_TOKEN_PROGRAM_ID, TOKEN_PROGRAM_ID,
+};
+use crate::Result;
+
+pub const MOONSHOT_PROGRAM_ID: Pubkey = pubkey!("MoonCVVNZFSYkqNXP6bxHLPL6QQJiMagDL3qcqUQTrG");
+const DEX_FEE_ACCOUNT: Pubkey = pubkey!("3udvfL24waJcLhskRAsStNMoNUvtyXdxrWQz4hgi953N");
+const HELIO_FEE_ACCOUNT: Pubkey = pubkey!("5K5RtTWzzLp4P8Npi84ocf7F1vBsAu29N1irG4iiUnzt");
+const CURVE_SEED: &[u8] = b"token";
+const CONFIG_SEED: &[u8] = b"config_account";
+
+// Constant product curve over virtual reserves, as in the Moonshot SDK
+const INITIAL_VIRTUAL_TOKEN_RESERVES: u128 = 1_073_000_000_000_000_000;
+const INITIAL_VIRTUAL_COLLATERAL_RESERVES: u128 = 30_000_000_000;
+const FEE_BASIS_POINTS: u128 = 100;
+// TradeParams.fixed_side, the side whose amount is exact
+const FIXED_SIDE_EXACT_IN: u8 = 0;
+
+// Token amounts of a Moonshot curve account after the Anchor discriminator
+struct CurveAccount {
+    total_supply: u64,
+    curve_amount: u64,
+}
+
+impl CurveAccount {
+    fn address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[CURVE_SEED, mint.as_ref()], &MOONSHOT_PROGRAM_ID).0
+    }
+
+    async fn fetch(client: &RpcClient, mint: &Pubkey) -> Result<CurveAccount> {
+        let address = Self::address(mint);
+        let data = client.get_account_data(&address).await.map_err(|e| {
+            format!("Could not fetch Moonshot curve {}: {}", address, e)
+                .red()
+                .to_string()
+        })?;
+        Self::from_account_data(&data)
+    }
+
+    fn from_account_data(data: &[u8]) -> Result<CurveAccount> {
+        if data.len() < 24 {
+            return Err("Moonshot curve account is too short"
+                .red()
+                .to_string()
+                .into());
+        }
+        Ok(CurveAccount {
+            total_supply: u64::from_le_bytes(data[8..16].try_into()?),
+            curve_amount: u64::from_le_bytes(data[16..24].try_into()?),
+        })
+    }
+
+    // (virtual token, virtual collateral) reserves
+    fn reserves(&self) -> (u128, u128) {
+        let sold = self.total_supply.saturating_sub(self.curve_amount) as u128;
+        let tokens = INITIAL_VIRTUAL_TOKEN_RESERVES.saturating_sub(sold).max(1);
+        let collateral =
+            INITIAL_VIRTUAL_TOKEN_RESERVES * INITIAL_VIRTUAL_COLLATERAL_RESERVES / tokens;
+        (tokens, collateral)
+    }
+
+    fn buy_quote(&self, lamports: u64) -> u64 {
+        let (tokens, collateral) = self.reserves();
+        let sol_in = lamports as u128 * (10_000 - FEE_BASIS_POINTS) / 10_000;
+        let out = tokens - tokens * collateral / (collateral + sol_in);
+        (out as u64).min(self.curve_amount)
+    }
+
+    fn sell_quote(&self, token_amount: u64) -> u64 {
+        let (tokens, collateral) = self.reserves();
+        let out = collateral - tokens * collateral / (tokens + token_amount as u128);
+        (out * (10_000 - FEE_BASIS_POINTS) / 10_000) as u64
+    }
+}
+
+pub struct Moonshot;
+
+impl Moonshot {
+    // Buy and sell take the same accounts
+    fn trade_instruction(
+        trader: &Pubkey,
+        mint: &Pubkey,
+        discriminator: [u8; 8],
+        token_amount: u64,
+        collateral_amount: u64,
+        slippage_bps: u64,
+    ) -> Instruction {
+        let curve = CurveAccount::address(mint);
+        let mut data = discriminator.to_vec();
+        data.extend_from_slice(&token_amount.to_le_bytes());
+        data.extend_from_slice(&collateral_amount.to_le_bytes());
+        data.push(FIXED_SIDE_EXACT_IN);
+        data.extend_from_slice(&slippage_bps.to_le_bytes());
+        Instruction {
+            program_id: MOONSHOT_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(*trader, true),
+                AccountMeta::new(associated_token_address(trader, mint), false),
+                AccountMeta::new(curve, false),
+                AccountMeta::new(associated_token_address(&curve, mint), false),
+                AccountMeta::new(DEX_FEE_ACCOUNT, false),
+                AccountMeta::new(HELIO_FEE_ACCOUNT, false),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(
+                    Pubkey::find_program_address(&[CONFIG_SEED], &MOONSHOT_PROGRAM_ID).0,
+                    false,
+                ),
+                AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(ASSOCIATED_TOKEN_PROGRAM_ID, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data,
+        }
+    }
+}
+
+impl Venue for Moonshot {
+    fn name(&self) -> &'static str {
+        "Moonshot"
+    }
+
+    fn curve_address(&self, mint: &Pubkey) -> Pubkey {
+        CurveAccount::address(mint)
+    }
+
+    fn quote<'a>(&'a self, client: &'a RpcClient, trade: &'a Trade) -> BoxFuture<'a, Result<u64>> {
+        Box::pin(async move {
+            let curve = CurveAccount::fetch(client, &trade.mint()).await?;
+            Ok(match trade {
+                Trade::Buy { lamports, .. } => curve.buy_quote(*lamports),
+                Trade::Sell { token_amount, .. } => curve.sell_quote(*token_amount),
+            })
+        })
+    }
+
+    // The curve migrates once its market cap threshold is reached, which
+    // isn't a share of its tokens, so only a sold out curve reads as complete
+    fn decode_curve(&self, data: &[u8], decimals: u8) -> Result<CurveState> {
+        let curve = CurveAccount::from_account_data(data)?;
+        let (tokens, collateral) = curve.reserves();
+        let price_sol = collateral as f64 / tokens as f64 * 10f64.powi(decimals as i32)
+            / LAMPORTS_PER_SOL as f64;
+        Ok(CurveState {
+            price_sol,
+            market_cap_sol: price_sol * curve.total_supply as f64 / 10f64.powi(decimals as i32),
+            complete: curve.curve_amount == 0,
+            tokens_left: curve.curve_amount,
+        })
+    }
+
+    fn initial_curve_tokens<'a>(
+        &'a self,
+        _client: &'a RpcClient,
+    ) -> BoxFuture<'a, Result<Option<u64>>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    // Spends exactly `lamports`, the program enforces the slippage on the tokens
+    fn build_buy<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        buyer: &'a Pubkey,
+        mint: &'a Pubkey,
+        lamports: u64,
+        slippage_bps: u64,
+    ) -> BoxFuture<'a, Result<VersionedTransaction>> {
+        Box::pin(async move {
+            let curve = CurveAccount::fetch(client, mint).await?;
+            let token_amount = curve.buy_quote(lamports);
+            let instructions = [
+                create_associated_token_account_idempotent(buyer, buyer, mint),
+                Self::trade_instruction(
+                    buyer,
+                    mint,
+                    BUY_DISCRIMINATOR,
+                    token_amount,
+                    lamports,
+                    slippage_bps,
+                ),
+            ];
+            let mut tx = Transaction::new_with_payer(&instructions, Some(buyer));
+            tx.message.recent_blockhash = client.get_latest_blockhash().await?;
+            Ok(tx.into())
+        })
+    }
+
+    fn build_sell<'a>(
+        &'a self,
+        client: &'a RpcClient,
+        seller: &'a Pubkey,
+        mint: &'a Pubkey,
+        token_amount: u64,
+        slippage_bps: u64,
+    ) -> BoxFuture<'a, Result<VersionedTransaction>> {
+        Box::pin(async move {
+            let curve = CurveAccount::fetch(client, mint).await?;
+            let collateral_amount = curve.sell_quote(token_amount);
+            let sell = Self::trade_instruction(
+                seller,
+                mint,
+                SELL_DISCRIMINATOR,
+                token_amount,
+                collateral_amount,
+                slippage_bps,
+            );
+            let mut tx = Transaction::new_with_payer(&[sell], Some(seller));
+            tx.message.recent_blockhash = client.get_latest_blockhash().await?;
+            Ok(tx.into())
+        })
+    }
+}
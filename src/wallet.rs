@@ -0,0 +1,370 @@
+use bip39::{Language, Mnemonic, Seed};
+use colored::*;
+use solana_account_decoder::UiAccountData;
+use solana_client::{nonblocking::rpc_client::RpcClient, rpc_request::TokenAccountsFilter};
+use solana_sdk::{
+    account::Account,
+    derivation_path::DerivationPath,
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::{keypair, SignerError},
+    system_instruction, system_program,
+};
+use std::str::FromStr;
+
+use crate::jupiter::WSOL_MINT;
+use crate::Result;
+
+pub const TOKEN_PROGRAM_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+pub const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+pub const ASSOCIATED_TOKEN_PROGRAM_ID: Pubkey =
+    pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+// Size of an SPL token account without extensions
+pub const TOKEN_ACCOUNT_LEN: usize = 165;
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/501'/0'/0'";
+
+pub fn associated_token_address(owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    associated_token_address_with_program(owner, mint, &TOKEN_PROGRAM_ID)
+}
+
+// The associated account for a mint of either token program
+pub fn associated_token_address_with_program(
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Pubkey {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &ASSOCIATED_TOKEN_PROGRAM_ID,
+    )
+    .0
+}
+
+// Associated token program CreateIdempotent, a no-op when the account exists
+pub fn create_associated_token_account_idempotent(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    create_associated_token_account_idempotent_with_program(payer, owner, mint, &TOKEN_PROGRAM_ID)
+}
+
+pub fn create_associated_token_account_idempotent_with_program(
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ASSOCIATED_TOKEN_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(
+                associated_token_address_with_program(owner, mint, token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(*owner, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*token_program, false),
+        ],
+        data: vec![1],
+    }
+}
+
+// SPL token TransferChecked of `amount` raw units, signed by `owner`
+pub fn transfer_checked(
+    token_program: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut data = vec![12];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data,
+    }
+}
+
+// SPL token SyncNative, crediting lamports sent to a wrapped SOL account as
+// tokens
+pub fn sync_native(account: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: TOKEN_PROGRAM_ID,
+        accounts: vec![AccountMeta::new(*account, false)],
+        data: vec![17],
+    }
+}
+
+// Wrap `lamports` into the owner's wrapped SOL account, created when missing.
+// For a venue taking WSOL to put ahead of its trade.
+pub fn wrap_sol(owner: &Pubkey, lamports: u64) -> Vec<Instruction> {
+    let account = associated_token_address(owner, &WSOL_MINT);
+    vec![
+        create_associated_token_account_idempotent(owner, owner, &WSOL_MINT),
+        system_instruction::transfer(owner, &account, lamports),
+        sync_native(&account),
+    ]
+}
+
+// SPL token BurnChecked of `amount` raw units, signed by `owner`
+pub fn burn_checked(
+    token_program: &Pubkey,
+    account: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    amount: u64,
+    decimals: u8,
+) -> Instruction {
+    let mut data = vec![15];
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.push(decimals);
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*mint, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data,
+    }
+}
+
+// SPL token CloseAccount of an empty account, its rent going to `destination`
+pub fn close_token_account(
+    token_program: &Pubkey,
+    account: &Pubkey,
+    destination: &Pubkey,
+    owner: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *token_program,
+        accounts: vec![
+            AccountMeta::new(*account, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*owner, true),
+        ],
+        data: vec![9],
+    }
+}
+
+// The fields of an SPL token account we care about
+#[derive(Clone, Copy)]
+pub struct TokenAccount {
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+// Decode an SPL token (or Token-2022) account from its raw data
+pub fn parse_token_account(account: &Account) -> Option<TokenAccount> {
+    if account.owner != TOKEN_PROGRAM_ID && account.owner != TOKEN_2022_PROGRAM_ID {
+        return None;
+    }
+    let data = &account.data;
+    if data.len() < TOKEN_ACCOUNT_LEN {
+        return None;
+    }
+    Some(TokenAccount {
+        mint: Pubkey::try_from(&data[0..32]).ok()?,
+        owner: Pubkey::try_from(&data[32..64]).ok()?,
+        amount: u64::from_le_bytes(data[64..72].try_into().ok()?),
+    })
+}
+
+// Decode a base58 private key into a keypair
+pub fn load_keypair(private_key: Option<&str>) -> Result<Keypair> {
+    let private_key = private_key.ok_or_else(|| {
+        "A wallet must be given with --private-key, --keypair, --mnemonic or the matching config file keys"
+            .red()
+            .to_string()
+    })?;
+    let bytes = bs58::decode(private_key).into_vec()?;
+    keypair_from_bytes(&bytes)
+}
+
+// A keypair from its 32 secret and 32 public key bytes
+pub fn keypair_from_bytes(bytes: &[u8]) -> Result<Keypair> {
+    Keypair::from_bytes(bytes).map_err(|e| SignerError::InvalidInput(e.to_string()).into())
+}
+
+// Read a keypair file in the solana-keygen JSON format
+pub fn read_keypair_file(path: &str) -> Result<Keypair> {
+    // Paths are usually copied from the Solana CLI docs, ~/.config/solana/id.json
+    let expanded = match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    };
+    keypair::read_keypair_file(&expanded).map_err(|e| {
+        format!("Could not read keypair file {}: {}", path, e)
+            .red()
+            .to_string()
+            .into()
+    })
+}
+
+// Derive a keypair from a BIP39 mnemonic, by default along the path
+// wallets like Phantom use for their first account
+pub fn keypair_from_mnemonic(phrase: &str, derivation_path: Option<&str>) -> Result<Keypair> {
+    let mnemonic = Mnemonic::from_phrase(phrase.trim(), Language::English)
+        .map_err(|e| format!("Invalid mnemonic: {}", e).red().to_string())?;
+    let path = derivation_path.unwrap_or(DEFAULT_DERIVATION_PATH);
+    let derivation_path = DerivationPath::from_absolute_path_str(path).map_err(|e| {
+        format!("Invalid derivation path '{}': {}", path, e)
+            .red()
+            .to_string()
+    })?;
+    let seed = Seed::new(&mnemonic, "");
+    keypair::keypair_from_seed_and_derivation_path(seed.as_bytes(), Some(derivation_path))
+        .map_err(|e| e.to_string().into())
+}
+
+pub fn parse_pubkey(address: &str) -> Result<Pubkey> {
+    Pubkey::from_str(address).map_err(|_| {
+        format!("Invalid address '{}'", address)
+            .red()
+            .to_string()
+            .into()
+    })
+}
+
+// A mint address, or a degen.fund token or share URL carrying one
+pub fn parse_mint(mint: &str) -> Result<Pubkey> {
+    let mint = mint.trim();
+    if mint.starts_with("http://") || mint.starts_with("https://") {
+        return mint_from_url(mint);
+    }
+    Pubkey::from_str(mint).map_err(|e| {
+        format!("Invalid mint address '{}': {}", mint, e)
+            .red()
+            .to_string()
+            .into()
+    })
+}
+
+// The mint is a path segment (`/token/<mint>`) or a query value of the link
+fn mint_from_url(link: &str) -> Result<Pubkey> {
+    let url = reqwest::Url::parse(link)
+        .map_err(|e| format!("Invalid URL '{}': {}", link, e).red().to_string())?;
+    let segments = url.path_segments().into_iter().flatten();
+    let values = url.query_pairs().map(|(_, value)| value.into_owned());
+    segments
+        .map(str::to_string)
+        .chain(values)
+        .find_map(|candidate| Pubkey::from_str(&candidate).ok())
+        .ok_or_else(|| {
+            format!("No mint address found in the URL '{}'", link)
+                .red()
+                .to_string()
+                .into()
+        })
+}
+
+// A non-zero balance of one mint, summed over the owner's token accounts
+pub struct Holding {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+}
+
+impl Holding {
+    pub fn ui_amount(&self) -> f64 {
+        self.amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+}
+
+// One token account of a wallet, empty ones included
+pub struct OwnedTokenAccount {
+    pub address: Pubkey,
+    pub token_program: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub decimals: u8,
+    // Its rent, given back when it is closed
+    pub lamports: u64,
+}
+
+// Every token account `owner` has, across both token programs
+pub async fn token_accounts(client: &RpcClient, owner: &Pubkey) -> Result<Vec<OwnedTokenAccount>> {
+    let mut owned = Vec::new();
+    for token_program in [TOKEN_PROGRAM_ID, TOKEN_2022_PROGRAM_ID] {
+        let accounts = client
+            .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(token_program))
+            .await?;
+        for account in accounts {
+            // The RPC answers with jsonParsed token accounts
+            let UiAccountData::Json(parsed) = account.account.data else {
+                continue;
+            };
+            let info = &parsed.parsed["info"];
+            let (Some(mint), Some(amount), Some(decimals)) = (
+                info["mint"].as_str(),
+                info["tokenAmount"]["amount"].as_str(),
+                info["tokenAmount"]["decimals"].as_u64(),
+            ) else {
+                continue;
+            };
+            owned.push(OwnedTokenAccount {
+                address: Pubkey::from_str(&account.pubkey)?,
+                token_program,
+                mint: Pubkey::from_str(mint)?,
+                amount: amount.parse::<u64>()?,
+                decimals: decimals as u8,
+                lamports: account.account.lamports,
+            });
+        }
+    }
+    Ok(owned)
+}
+
+// Every token `owner` holds a non-zero balance of, across both token programs
+pub async fn holdings(client: &RpcClient, owner: &Pubkey) -> Result<Vec<Holding>> {
+    let mut holdings: Vec<Holding> = Vec::new();
+    for account in token_accounts(client, owner).await? {
+        if account.amount == 0 {
+            continue;
+        }
+        match holdings
+            .iter_mut()
+            .find(|holding| holding.mint == account.mint)
+        {
+            Some(holding) => holding.amount += account.amount,
+            None => holdings.push(Holding {
+                mint: account.mint,
+                amount: account.amount,
+                decimals: account.decimals,
+            }),
+        }
+    }
+    Ok(holdings)
+}
+
+// Raw token balance held by `owner` for `mint` and the mint's decimals,
+// summed over all its token accounts
+pub async fn token_balance(client: &RpcClient, owner: &Pubkey, mint: &Pubkey) -> Result<(u64, u8)> {
+    let accounts = client
+        .get_token_accounts_by_owner(owner, TokenAccountsFilter::Mint(*mint))
+        .await?;
+    let mut total = 0u64;
+    let mut decimals = 0u8;
+    for account in accounts {
+        let pubkey = Pubkey::from_str(&account.pubkey)?;
+        let balance = client.get_token_account_balance(&pubkey).await?;
+        total += balance.amount.parse::<u64>()?;
+        decimals = balance.decimals;
+    }
+    Ok((total, decimals))
+}